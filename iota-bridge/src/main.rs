@@ -20,6 +20,7 @@ use hyper::{
 use tokio::{
     sync::oneshot,
     task::LocalSet,
+    runtime::Runtime,
 };
 
 use anyhow::Result;
@@ -34,10 +35,23 @@ use streams_tools::{
             run_buffered_message_loop,
             BufferedMessageLoopOptions,
         },
+        retention_loop::{
+            RetentionWorker,
+            RetentionLoopOptions,
+        },
+        lora_wan_node_replication::{
+            LoraWanNodeSyncClient,
+            export_nodes_to_file,
+            import_nodes_from_file,
+        },
+        dao::migrate_database,
         IotaBridgeOptions,
         ErrorHandlingStrategy,
+        ClusterOptions,
     },
     dao_helpers::DbFileBasedDaoManagerOptions,
+    dao_backend::is_postgres_url,
+    threading_helpers::run_background_worker_in_own_thread,
     IotaBridge,
 };
 
@@ -51,6 +65,9 @@ use cli::{
     IotaBridgeCli,
     ARG_KEYS,
     get_arg_matches,
+    get_cluster_options,
+    get_migration_options,
+    get_pending_request_ttl_secs,
     shall_tangle_transport_be_used,
 };
 
@@ -87,31 +104,121 @@ fn main() {
     assert_data_dir_existence(&cli.data_dir).expect(
         format!("Could not create data_dir '{}'", cli.data_dir).as_str());
     let db_connection_opt = DbFileBasedDaoManagerOptions {
-        file_path_and_name: get_data_folder_file_path(&cli.data_dir, "iota-bridge.sqlite3")
+        file_path_and_name: cli.matches.value_of(cli.arg_keys.db_url)
+            .map(|db_url| db_url.to_string())
+            .unwrap_or(get_data_folder_file_path(&cli.data_dir, "iota-bridge.sqlite3"))
     };
 
+    // Needs to be done before any data store is created because data stores
+    // migrate their tables automatically
+    if cli.matches.is_present(cli.arg_keys.migrate) {
+        if let Err(e) = run_migrate_command(&cli, &db_connection_opt) {
+            log::error!("Error on migrating the database: {}", e);
+            // Scripts running the migration need to detect the failure
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if run_lorawan_node_replication_command(&rt, &cli, db_connection_opt.clone()) {
+        return;
+    }
+
+    let cluster_options = get_cluster_options(&cli, &db_connection_opt);
+    if cluster_options.is_some() && !is_postgres_url(db_connection_opt.file_path_and_name.as_str()) {
+        log::warn!("Running in cluster mode using the local SQLite3 database '{}'. Only iota-bridge instances \
+                    running on this host can be members of the cluster. Use --{} to share the database between hosts.",
+                   db_connection_opt.file_path_and_name, cli.arg_keys.db_url);
+    }
+
     run_buffered_message_loop_in_background(
         &local,
         cli.node,
         shall_tangle_transport_be_used(&cli),
-        db_connection_opt.clone()
+        db_connection_opt.clone(),
+        cluster_options.clone(),
     );
-    local.block_on(&rt, run(db_connection_opt.clone(), cli));
+    match get_pending_request_ttl_secs(&cli) {
+        Ok(Some(pending_request_ttl_secs)) => run_retention_loop_in_background(
+            db_connection_opt.clone(),
+            pending_request_ttl_secs,
+        ),
+        Ok(None) => {},
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        }
+    }
+    local.block_on(&rt, run(db_connection_opt.clone(), cluster_options, cli));
 }
 
-fn run_buffered_message_loop_in_background(local: &LocalSet, iota_node: &str, use_tangle_transport: bool, db_connection_opt: DbFileBasedDaoManagerOptions) {
+fn run_migrate_command(cli: &IotaBridgeCli, db_connection_opt: &DbFileBasedDaoManagerOptions) -> Result<()> {
+    let migration_opt = get_migration_options(cli)?;
+    let plans = migrate_database(db_connection_opt, &migration_opt)?;
+    migration_opt.check_target_table_has_been_found(plans.as_slice())?;
+    for plan in plans.iter() {
+        println!("{}", plan);
+    }
+    if migration_opt.dry_run {
+        println!("Dry run: The database has not been changed");
+    }
+    Ok(())
+}
+
+// Returns true if a LoRaWAN node replication command has been specified via the cli
+fn run_lorawan_node_replication_command(rt: &Runtime, cli: &IotaBridgeCli, db_connection_opt: DbFileBasedDaoManagerOptions) -> bool {
+    let lora_wan_node_store = LoraWanNodeDataStore::new(db_connection_opt);
+    if let Some(file_path_and_name) = cli.matches.value_of(cli.arg_keys.export_lorawan_nodes) {
+        match export_nodes_to_file(&lora_wan_node_store, file_path_and_name) {
+            Ok(nodes_cnt) => log::info!("Exported {} LoRaWAN nodes into file '{}'", nodes_cnt, file_path_and_name),
+            Err(e) => log::error!("Error on exporting LoRaWAN nodes into file '{}': {}", file_path_and_name, e),
+        }
+    } else if let Some(file_path_and_name) = cli.matches.value_of(cli.arg_keys.import_lorawan_nodes) {
+        match import_nodes_from_file(&lora_wan_node_store, file_path_and_name) {
+            Ok(import_result) => log::info!("Imported LoRaWAN nodes from file '{}' - {}", file_path_and_name, import_result),
+            Err(e) => log::error!("Error on importing LoRaWAN nodes from file '{}': {}", file_path_and_name, e),
+        }
+    } else if let Some(remote_iota_bridge_url) = cli.matches.value_of(cli.arg_keys.sync_lorawan_nodes) {
+        let sync_client = LoraWanNodeSyncClient::new(remote_iota_bridge_url);
+        match rt.block_on(sync_client.pull(&lora_wan_node_store)) {
+            Ok(import_result) => log::info!("Pulled LoRaWAN nodes from '{}' - {}", remote_iota_bridge_url, import_result),
+            Err(e) => {
+                log::error!("Error on pulling LoRaWAN nodes from '{}': {}", remote_iota_bridge_url, e);
+                return true;
+            },
+        }
+        match rt.block_on(sync_client.push(&lora_wan_node_store)) {
+            Ok(import_result) => log::info!("Pushed LoRaWAN nodes to '{}' - {}", remote_iota_bridge_url, import_result),
+            Err(e) => log::error!("Error on pushing LoRaWAN nodes to '{}': {}", remote_iota_bridge_url, e),
+        }
+    } else {
+        return false;
+    }
+    true
+}
+
+fn run_buffered_message_loop_in_background(local: &LocalSet, iota_node: &str, use_tangle_transport: bool, db_connection_opt: DbFileBasedDaoManagerOptions, cluster_options: Option<ClusterOptions>) {
     let mut options = BufferedMessageLoopOptions::new(
         iota_node,
         move || { BufferedMessageDataStore::new(db_connection_opt.clone()) }
     );
     options.use_tangle_transport = use_tangle_transport;
+    options.cluster = cluster_options;
 
     local.spawn_local(
         run_buffered_message_loop(options)
     );
 }
 
-async fn run<'a>(db_connection_opt: DbFileBasedDaoManagerOptions, cli: IotaBridgeCli<'a>) {
+// The retention loop runs in its own thread so that compacting the database does not
+// block the LocalSet serving the requests of the sensors
+fn run_retention_loop_in_background(db_connection_opt: DbFileBasedDaoManagerOptions, pending_request_ttl_secs: u64) {
+    let _join = run_background_worker_in_own_thread::<RetentionWorker>(
+        RetentionLoopOptions::new(db_connection_opt, pending_request_ttl_secs)
+    );
+}
+
+async fn run<'a>(db_connection_opt: DbFileBasedDaoManagerOptions, cluster_options: Option<ClusterOptions>, cli: IotaBridgeCli<'a>) {
     let lora_wan_node_store = LoraWanNodeDataStore::new(db_connection_opt.clone());
     let pending_request_store = PendingRequestDataStore::new(db_connection_opt.clone());
     let buffered_message_store = BufferedMessageDataStore::new(db_connection_opt);
@@ -131,6 +238,7 @@ async fn run<'a>(db_connection_opt: DbFileBasedDaoManagerOptions, cli: IotaBridg
     );
 
     options.use_tangle_transport = shall_tangle_transport_be_used(&cli);
+    options.cluster = cluster_options;
     log::info!("Using {}", options);
     let client = IotaBridge::new(options, lora_wan_node_store, pending_request_store, buffered_message_store).await;
 
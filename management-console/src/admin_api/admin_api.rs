@@ -0,0 +1,75 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+
+use axum::{
+    Extension,
+    middleware,
+};
+
+use streams_tools::{
+    explorer::auth::ApiTokenDataStore,
+    dao_helpers::DbFileBasedDaoManagerOptions,
+    multi_channel_management::MultiChannelManagerOptions,
+    remote::remote_sensor::RemoteSensorOptions,
+    dao::sensor_initialization::SensorInitializationDataStore,
+    UserDataStore,
+};
+
+use crate::DevEuiHandshakeOptions;
+
+use super::{
+    app_state::AdminApiState,
+    admin_auth::authenticate_admin,
+    jobs::{
+        DevEuiLocks,
+        JobContext,
+        JobRegistry,
+    },
+    router::router,
+};
+
+pub(crate) struct AdminApiOptions {
+    pub(crate) listener_ip_address_port: String,
+    pub(crate) mult_chan_mngr_opt: MultiChannelManagerOptions,
+    pub(crate) remote_sensor_options: RemoteSensorOptions,
+    pub(crate) handshake_opt: DevEuiHandshakeOptions,
+    pub(crate) db_file_name: String,
+    // If true, every request needs an API token having the admin role
+    pub(crate) auth_enabled: bool,
+}
+
+// Runs the admin api server until the process is stopped.
+// Long running provisioning workflows are processed as jobs in their own threads.
+pub(crate) async fn run_admin_api_server(user_store: UserDataStore, init_store: SensorInitializationDataStore, options: AdminApiOptions) -> Result<()> {
+    let addr: SocketAddr = options.listener_ip_address_port.parse()?;
+
+    let api_token_store = if options.auth_enabled {
+        Some(ApiTokenDataStore::new(DbFileBasedDaoManagerOptions {
+            file_path_and_name: options.db_file_name.clone()
+        }))
+    } else {
+        log::warn!("[Admin API] Authentication is disabled. Every client has full access to the admin api");
+        None
+    };
+
+    let job_context = JobContext {
+        jobs: JobRegistry::default(),
+        dev_eui_locks: DevEuiLocks::default(),
+        user_store,
+        init_store,
+        mult_chan_mngr_opt: options.mult_chan_mngr_opt,
+        remote_sensor_options: options.remote_sensor_options,
+        handshake_opt: options.handshake_opt,
+    };
+    let app = router()
+        .layer(middleware::from_fn(authenticate_admin))
+        .layer(Extension(AdminApiState::new(job_context, options.db_file_name, api_token_store)));
+
+    log::info!("[Admin API] Listening on {}", addr);
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    Json,
+    Extension
+};
+
+use streams_tools::{
+    explorer::error::{
+        AppError,
+        Result,
+    },
+    multi_channel_management::{
+        retire_channel,
+        archive_channel,
+        delete_channel,
+    },
+};
+
+use super::{
+    app_state::AdminApiState,
+    jobs::{
+        JobTask,
+        start_job,
+    },
+    admin_api_dto::{
+        Job,
+        Channel,
+        Initialization,
+        CreateChannelRequest,
+        AddSubscriberRequest,
+        ReinitSensorRequest,
+        RetireChannelRequest,
+        ChannelId,
+        DevEui,
+        JobId,
+    },
+};
+
+fn get_bad_request_error(err: anyhow::Error) -> AppError {
+    AppError::GenericWithMessage(StatusCode::BAD_REQUEST, err.to_string())
+}
+
+fn start_job_and_respond(state: &AdminApiState, task: JobTask) -> Result<(StatusCode, Json<Job>)> {
+    Ok((StatusCode::ACCEPTED, Json(start_job(&state.job_context, task))))
+}
+
+/// List all jobs
+///
+/// Lists the jobs started since the admin api server has been started in the order of their creation
+#[utoipa::path(
+    get,
+    operation_id = "jobs_index",
+    path = "/jobs",
+    responses(
+        (status = 200, description = "Successfully responded with list of jobs", body = [Job])
+    ),
+)]
+pub (crate) async fn jobs_index(Extension(state): Extension<AdminApiState>) -> Result<Json<Vec<Job>>> {
+    Ok(Json(state.job_context.jobs.get_all_jobs()))
+}
+
+/// Get a specific job
+///
+/// Use this endpoint to poll the status of a job
+#[utoipa::path(
+    get,
+    operation_id = "jobs_get",
+    path = "/jobs/{job_id}",
+    responses(
+        (status = 200, description = "Successfully responded requested job", body = Job),
+        (status = 404, description = "Job with specified job_id does not exist")
+    ),
+    params(
+        JobId,
+    )
+)]
+pub (crate) async fn jobs_get(Path(id): Path<JobId>, Extension(state): Extension<AdminApiState>) -> Result<Json<Job>> {
+    state.job_context.jobs.get_job(id.job_id.as_str())
+        .map(|job| Json(job))
+        .ok_or_else(|| AppError::GenericWithMessage(StatusCode::NOT_FOUND, format!("The job {} does not exist", id.job_id)))
+}
+
+/// Create a new channel
+///
+/// Same as the '--create-channel' CLI argument. The announcement link of the new channel
+/// is provided by the 'link' field of the finished job.
+#[utoipa::path(
+    post,
+    operation_id = "channels_create",
+    path = "/channels",
+    request_body = CreateChannelRequest,
+    responses(
+        (status = 202, description = "Successfully started the job", body = Job)
+    ),
+)]
+pub (crate) async fn channels_create(Extension(state): Extension<AdminApiState>, request: Option<Json<CreateChannelRequest>>) -> Result<(StatusCode, Json<Job>)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    start_job_and_respond(&state, JobTask::CreateChannel {
+        external_id: request.external_id,
+        name: request.name,
+    })
+}
+
+/// Get a specific channel including its lifecycle status
+#[utoipa::path(
+    get,
+    operation_id = "channels_get",
+    path = "/channels/{channel_id}",
+    responses(
+        (status = 200, description = "Successfully responded requested channel", body = Channel),
+        (status = 400, description = "Channel with specified channel_id does not exist")
+    ),
+    params(
+        ChannelId,
+    )
+)]
+pub (crate) async fn channels_get(Path(id): Path<ChannelId>, Extension(state): Extension<AdminApiState>) -> Result<Json<Channel>> {
+    state.job_context.user_store.get_item_read_only(&id.channel_id)
+        .map(|user| Json(user.into()))
+        .map_err(|_| AppError::ChannelDoesNotExist(id.channel_id))
+}
+
+/// Add a subscriber to a channel
+///
+/// Same as the '--subscription-link' and '--subscription-pub-key' CLI arguments.
+/// The keyload link is provided by the 'link' field of the finished job.
+#[utoipa::path(
+    post,
+    operation_id = "channels_add_subscriber",
+    path = "/channels/{channel_id}/subscribers",
+    request_body = AddSubscriberRequest,
+    responses(
+        (status = 202, description = "Successfully started the job", body = Job),
+        (status = 400, description = "Channel with specified channel_id does not exist")
+    ),
+    params(
+        ChannelId,
+    )
+)]
+pub (crate) async fn channels_add_subscriber(Path(id): Path<ChannelId>, Extension(state): Extension<AdminApiState>, Json(request): Json<AddSubscriberRequest>) -> Result<(StatusCode, Json<Job>)> {
+    if state.job_context.user_store.get_item_read_only(&id.channel_id).is_err() {
+        return Err(AppError::ChannelDoesNotExist(id.channel_id));
+    }
+    start_job_and_respond(&state, JobTask::AddSubscriber {
+        channel_id: id.channel_id,
+        subscription_link: request.subscription_link,
+        pub_key: request.pub_key,
+    })
+}
+
+/// Retire a channel
+///
+/// Same as the '--retire-channel' CLI argument
+#[utoipa::path(
+    post,
+    operation_id = "channels_retire",
+    path = "/channels/{channel_id}/retire",
+    request_body = RetireChannelRequest,
+    responses(
+        (status = 200, description = "Successfully retired the channel", body = Channel),
+        (status = 400, description = "Channel or successor channel does not exist")
+    ),
+    params(
+        ChannelId,
+    )
+)]
+pub (crate) async fn channels_retire(Path(id): Path<ChannelId>, Extension(state): Extension<AdminApiState>, request: Option<Json<RetireChannelRequest>>) -> Result<Json<Channel>> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    retire_channel(&state.job_context.user_store, id.channel_id.as_str(), request.successor_channel_id.as_deref())
+        .map(|user| Json(user.into()))
+        .map_err(get_bad_request_error)
+}
+
+/// Archive a channel
+///
+/// Same as the '--archive-channel' CLI argument
+#[utoipa::path(
+    post,
+    operation_id = "channels_archive",
+    path = "/channels/{channel_id}/archive",
+    responses(
+        (status = 200, description = "Successfully archived the channel", body = Channel),
+        (status = 400, description = "Channel with specified channel_id does not exist")
+    ),
+    params(
+        ChannelId,
+    )
+)]
+pub (crate) async fn channels_archive(Path(id): Path<ChannelId>, Extension(state): Extension<AdminApiState>) -> Result<Json<Channel>> {
+    archive_channel(&state.job_context.user_store, id.channel_id.as_str())
+        .map(|user| Json(user.into()))
+        .map_err(get_bad_request_error)
+}
+
+/// Delete a retired or archived channel
+///
+/// Same as the '--delete-channel' CLI argument
+#[utoipa::path(
+    delete,
+    operation_id = "channels_delete",
+    path = "/channels/{channel_id}",
+    responses(
+        (status = 204, description = "Successfully deleted the channel"),
+        (status = 400, description = "Channel does not exist or is active")
+    ),
+    params(
+        ChannelId,
+    )
+)]
+pub (crate) async fn channels_delete(Path(id): Path<ChannelId>, Extension(state): Extension<AdminApiState>) -> Result<StatusCode> {
+    delete_channel(&state.job_context.user_store, id.channel_id.as_str(), state.db_file_name.as_str())
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(get_bad_request_error)
+}
+
+/// Initialize the next sensor
+///
+/// Same as the '--init-sensor' CLI argument. The sensor is found using a DevEUI-Handshake.
+/// The DevEUI and channel-id of the initialized sensor are provided by the finished job.
+#[utoipa::path(
+    post,
+    operation_id = "initializations_create",
+    path = "/initializations",
+    responses(
+        (status = 202, description = "Successfully started the job", body = Job)
+    ),
+)]
+pub (crate) async fn initializations_create(Extension(state): Extension<AdminApiState>) -> Result<(StatusCode, Json<Job>)> {
+    start_job_and_respond(&state, JobTask::InitSensor)
+}
+
+/// List the state of all sensor initializations
+///
+/// Same as the '--list-initializations' CLI argument
+#[utoipa::path(
+    get,
+    operation_id = "initializations_index",
+    path = "/initializations",
+    responses(
+        (status = 200, description = "Successfully responded with list of initializations", body = [Initialization])
+    ),
+)]
+pub (crate) async fn initializations_index(Extension(state): Extension<AdminApiState>) -> Result<Json<Vec<Initialization>>> {
+    let initializations = state.job_context.init_store.find_all_unlimited()?;
+    Ok(Json(initializations.into_iter().map(|initialization| initialization.into()).collect()))
+}
+
+/// Get the initialization state of a specific sensor
+#[utoipa::path(
+    get,
+    operation_id = "initializations_get",
+    path = "/initializations/{dev_eui}",
+    responses(
+        (status = 200, description = "Successfully responded requested initialization", body = Initialization),
+        (status = 404, description = "No initialization exists for the specified dev_eui")
+    ),
+    params(
+        DevEui,
+    )
+)]
+pub (crate) async fn initializations_get(Path(id): Path<DevEui>, Extension(state): Extension<AdminApiState>) -> Result<Json<Initialization>> {
+    state.job_context.init_store.get_item_read_only(&id.dev_eui)
+        .map(|initialization| Json(initialization.into()))
+        .map_err(|_| AppError::GenericWithMessage(StatusCode::NOT_FOUND, format!("No initialization exists for DevEUI {}", id.dev_eui)))
+}
+
+/// Re-initialize a sensor
+///
+/// Same as the '--reinit-sensor' CLI argument. The channel-id of the new channel
+/// is provided by the finished job.
+#[utoipa::path(
+    post,
+    operation_id = "reinitializations_create",
+    path = "/reinitializations",
+    request_body = ReinitSensorRequest,
+    responses(
+        (status = 202, description = "Successfully started the job", body = Job)
+    ),
+)]
+pub (crate) async fn reinitializations_create(Extension(state): Extension<AdminApiState>, Json(request): Json<ReinitSensorRequest>) -> Result<(StatusCode, Json<Job>)> {
+    start_job_and_respond(&state, JobTask::ReinitSensor {
+        dev_eui: request.dev_eui,
+    })
+}
@@ -0,0 +1,155 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use utoipa::{
+    IntoParams,
+    ToSchema
+};
+
+use streams_tools::dao::{
+    User,
+    sensor_initialization::SensorInitialization,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobType {
+    CreateChannel,
+    AddSubscriber,
+    InitSensor,
+    ReinitSensor,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Asynchronously processed provisioning workflow
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Job {
+    /// Use this ID to poll the job status via the '/jobs/{job_id}' endpoint
+    pub job_id: String,
+    pub job_type: JobType,
+    pub status: JobStatus,
+    /// Unix timestamp (seconds) of the job creation
+    pub created_at_secs: i64,
+    /// Unix timestamp (seconds) when the job has succeeded or failed. 0 if the job has not finished.
+    pub finished_at_secs: i64,
+    /// DevEUI of the initialized sensor. Empty if not applicable or not known yet.
+    pub dev_eui: String,
+    /// Streams channel-id of the created or modified channel. Empty if not known yet.
+    pub channel_id: String,
+    /// Announcement link of a created channel or keyload link of an added subscriber
+    pub link: String,
+    /// Error message of failed jobs
+    pub error: String,
+}
+
+/// Create a new Streams channel
+#[derive(Serialize, Deserialize, Debug, Default, ToSchema)]
+pub struct CreateChannelRequest {
+    /// External id of the channel. Existing channels using the same external id are disambiguated.
+    pub external_id: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Add a subscriber to a channel by sending a keyload message
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct AddSubscriberRequest {
+    /// Subscription message link logged by the sensor
+    pub subscription_link: String,
+    /// Hex encoded public key of the subscriber
+    pub pub_key: String,
+}
+
+/// Re-initialize an already initialized sensor
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ReinitSensorRequest {
+    pub dev_eui: String,
+}
+
+/// Retire a channel
+#[derive(Serialize, Deserialize, Debug, Default, ToSchema)]
+pub struct RetireChannelRequest {
+    /// Streams channel-id of the channel replacing the retired channel
+    pub successor_channel_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct Channel {
+    pub channel_id: String,
+    pub name: String,
+    pub external_id: String,
+    /// Lifecycle status of the channel: active, retired or archived
+    pub status: String,
+    /// Unix timestamp (seconds) of the channel retirement. 0 for active channels.
+    pub retired_at_secs: i64,
+    /// Streams channel-id of the channel replacing this channel. Empty if there is no successor.
+    pub successor_channel_id: String,
+}
+
+impl From<User> for Channel {
+    fn from(value: User) -> Self {
+        Channel {
+            channel_id: value.streams_channel_id,
+            name: value.name,
+            external_id: value.external_id,
+            status: value.status,
+            retired_at_secs: value.retired_at_secs,
+            successor_channel_id: value.successor_channel_id,
+        }
+    }
+}
+
+/// Persisted progress of a sensor initialization
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct Initialization {
+    pub dev_eui: String,
+    /// Possible states are: Handshake, Announced, Subscribed, KeyloadSent and Done
+    pub state: String,
+    pub channel_id: String,
+    /// Unix timestamp (seconds) of the last state change
+    pub updated_at_secs: i64,
+    pub initialization_cnt: u8,
+    pub initialization_epoch: u32,
+}
+
+impl From<SensorInitialization> for Initialization {
+    fn from(value: SensorInitialization) -> Self {
+        Initialization {
+            dev_eui: value.dev_eui,
+            state: value.state,
+            channel_id: value.streams_channel_id,
+            updated_at_secs: value.updated_at_secs,
+            initialization_cnt: value.initialization_cnt,
+            initialization_epoch: value.initialization_epoch,
+        }
+    }
+}
+
+/// Specify the IOTA Streams channel id
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+pub struct ChannelId {
+    /// Streams channel-id
+    #[param(max_length=80, min_length=80, example ="cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000")]
+    pub channel_id: String,
+}
+
+/// Specify the DevEUI of a sensor
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+pub struct DevEui {
+    pub dev_eui: String,
+}
+
+/// Specify the ID of a job
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+pub struct JobId {
+    pub job_id: String,
+}
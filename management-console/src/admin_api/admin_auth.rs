@@ -0,0 +1,85 @@
+use axum::{
+    http::Request,
+    middleware::Next,
+    response::{
+        IntoResponse,
+        Response,
+    },
+};
+
+use streams_tools::explorer::{
+    auth::{
+        ApiRole,
+        AuthContext,
+        authenticate_request,
+        is_public_path,
+    },
+    error::AppError,
+};
+
+use super::app_state::AdminApiState;
+
+// All requests to the admin api need an API token having the admin role
+const REQUIRED_ROLE: ApiRole = ApiRole::Admin;
+
+fn check_required_role(auth_context: &AuthContext) -> Result<(), Response> {
+    if auth_context.has_role(REQUIRED_ROLE) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden(format!(
+            "The admin api needs an API token with role {}. The role of the used token is {}",
+            REQUIRED_ROLE,
+            auth_context.role
+        )).into_response())
+    }
+}
+
+// Authenticates the bearer token of the request using the API tokens managed with
+// --create-api-token. If the authentication is disabled (no ApiTokenDataStore in the
+// AdminApiState), every request is processed.
+pub(crate) async fn authenticate_admin<B>(request: Request<B>, next: Next<B>) -> Response {
+    if is_public_path(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let state = match request.extensions().get::<AdminApiState>() {
+        Some(state) => state.clone(),
+        None => return AppError::InternalServerError("AdminApiState is not available".to_string()).into_response(),
+    };
+
+    if let Some(api_token_store) = state.api_token_store.as_ref() {
+        let auth_context = match authenticate_request(api_token_store, &request) {
+            Ok(auth_context) => auth_context,
+            Err(response) => return response,
+        };
+        if let Err(response) = check_required_role(&auth_context) {
+            return response;
+        }
+    }
+
+    next.run(request).await
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package management-console admin_api::admin_auth::tests
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    fn get_auth_context(role: ApiRole) -> AuthContext {
+        let mut auth_context = AuthContext::unrestricted();
+        auth_context.role = role;
+        auth_context
+    }
+
+    #[test]
+    fn test_check_required_role() {
+        assert!(check_required_role(&get_auth_context(ApiRole::Admin)).is_ok());
+        for role in [ApiRole::ReadOnly, ApiRole::Operator] {
+            let response = check_required_role(&get_auth_context(role)).unwrap_err();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::{
+    sync::Arc,
+    ops::Deref,
+};
+
+use streams_tools::explorer::auth::ApiTokenDataStore;
+
+use super::jobs::JobContext;
+
+pub(crate) struct AdminApiStateInner {
+    pub(crate) job_context: JobContext,
+    // Database containing the cached messages of the channels
+    pub(crate) db_file_name: String,
+    // None if the authentication is disabled
+    pub(crate) api_token_store: Option<ApiTokenDataStore>,
+}
+
+unsafe impl Send for AdminApiStateInner {}
+unsafe impl Sync for AdminApiStateInner {}
+
+pub(crate) struct AdminApiState(Arc<AdminApiStateInner>);
+
+impl Deref for AdminApiState {
+    type Target = Arc<AdminApiStateInner>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Clone for AdminApiState {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl AdminApiState {
+    pub fn new(job_context: JobContext, db_file_name: String, api_token_store: Option<ApiTokenDataStore>) -> AdminApiState {
+        AdminApiState(
+            Arc::new(AdminApiStateInner {
+                job_context,
+                db_file_name,
+                api_token_store,
+            })
+        )
+    }
+}
+
+unsafe impl Send for AdminApiState {}
+unsafe impl Sync for AdminApiState {}
@@ -0,0 +1,389 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::{
+    Result,
+    Error as AnyError,
+    anyhow,
+    bail,
+};
+
+use async_trait::async_trait;
+
+use streams_tools::{
+    threading_helpers::{
+        Worker,
+        run_background_worker_in_own_thread,
+    },
+    multi_channel_management::{
+        MultiChannelManagerOptions,
+        get_initial_channel_manager,
+        get_channel_manager_for_channel_id,
+    },
+    remote::remote_sensor::RemoteSensorOptions,
+    dao::sensor_initialization::SensorInitializationDataStore,
+    helpers::get_channel_id_from_link,
+    wallet::create_seed,
+    UserDataStore,
+};
+
+use crate::{
+    DevEuiHandshakeOptions,
+    create_channel,
+    send_keyload_message,
+    find_sensor_to_initialize,
+    run_sensor_initialization,
+    run_reinit_sensor,
+};
+
+use super::admin_api_dto::{
+    Job,
+    JobType,
+    JobStatus,
+};
+
+const JOB_ID_LENGTH: usize = 16;
+// Finished jobs exceeding this number are removed from the registry, oldest first
+const MAX_FINISHED_JOBS: usize = 1000;
+
+fn get_now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+// In memory registry of the jobs started via the admin api.
+// Jobs are stored in the order of their creation.
+#[derive(Clone, Default)]
+pub(crate) struct JobRegistry {
+    jobs: Arc<Mutex<Vec<Job>>>,
+}
+
+impl JobRegistry {
+    pub fn create_job(&self, job_type: JobType) -> Job {
+        let job = Job {
+            job_id: create_seed()[..JOB_ID_LENGTH].to_lowercase(),
+            job_type,
+            status: JobStatus::Pending,
+            created_at_secs: get_now_secs(),
+            finished_at_secs: 0,
+            dev_eui: String::new(),
+            channel_id: String::new(),
+            link: String::new(),
+            error: String::new(),
+        };
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.push(job.clone());
+        Self::remove_outdated_jobs(&mut jobs);
+        job
+    }
+
+    pub fn get_job(&self, job_id: &str) -> Option<Job> {
+        self.jobs.lock().unwrap().iter().find(|job| job.job_id == job_id).cloned()
+    }
+
+    pub fn get_all_jobs(&self) -> Vec<Job> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    fn update_job<F: FnOnce(&mut Job)>(&self, job_id: &str, update: F) {
+        if let Some(job) = self.jobs.lock().unwrap().iter_mut().find(|job| job.job_id == job_id) {
+            update(job);
+        }
+    }
+
+    fn set_running(&self, job_id: &str) {
+        self.update_job(job_id, |job| job.status = JobStatus::Running);
+    }
+
+    fn set_finished(&self, job_id: &str, result: Result<JobResult>) {
+        self.update_job(job_id, |job| {
+            job.finished_at_secs = get_now_secs();
+            match result {
+                Ok(job_result) => {
+                    job.status = JobStatus::Succeeded;
+                    job.dev_eui = job_result.dev_eui;
+                    job.channel_id = job_result.channel_id;
+                    job.link = job_result.link;
+                },
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = e.to_string();
+                }
+            }
+        });
+    }
+
+    fn remove_outdated_jobs(jobs: &mut Vec<Job>) {
+        let finished_cnt = jobs.iter().filter(|job| job.finished_at_secs > 0).count();
+        let mut to_be_removed = finished_cnt.saturating_sub(MAX_FINISHED_JOBS);
+        jobs.retain(|job| {
+            if to_be_removed > 0 && job.finished_at_secs > 0 {
+                to_be_removed -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+// DevEUIs of the sensors that are currently initialized or re-initialized by a job.
+// Used to prevent that two jobs process the same sensor concurrently, which would
+// corrupt the persisted initialization state of the sensor.
+#[derive(Clone, Default)]
+pub(crate) struct DevEuiLocks {
+    locked: Arc<Mutex<HashSet<String>>>,
+}
+
+impl DevEuiLocks {
+    // Fails if the sensor is already processed by another job.
+    // The lock is released when the returned guard is dropped.
+    pub fn try_lock(&self, dev_eui: &str) -> Result<DevEuiLockGuard> {
+        let dev_eui = dev_eui.to_lowercase();
+        if !self.locked.lock().unwrap().insert(dev_eui.clone()) {
+            bail!("DevEUI: {} - The sensor is currently processed by another job", dev_eui);
+        }
+        Ok(DevEuiLockGuard {
+            locks: self.clone(),
+            dev_eui,
+        })
+    }
+}
+
+pub(crate) struct DevEuiLockGuard {
+    locks: DevEuiLocks,
+    dev_eui: String,
+}
+
+impl Drop for DevEuiLockGuard {
+    fn drop(&mut self) {
+        self.locks.locked.lock().unwrap().remove(&self.dev_eui);
+    }
+}
+
+#[derive(Clone)]
+pub(crate) enum JobTask {
+    CreateChannel {
+        external_id: Option<String>,
+        name: Option<String>,
+    },
+    AddSubscriber {
+        channel_id: String,
+        subscription_link: String,
+        pub_key: String,
+    },
+    InitSensor,
+    ReinitSensor {
+        dev_eui: String,
+    },
+}
+
+impl JobTask {
+    pub fn get_job_type(&self) -> JobType {
+        match self {
+            JobTask::CreateChannel { .. } => JobType::CreateChannel,
+            JobTask::AddSubscriber { .. } => JobType::AddSubscriber,
+            JobTask::InitSensor => JobType::InitSensor,
+            JobTask::ReinitSensor { .. } => JobType::ReinitSensor,
+        }
+    }
+}
+
+#[derive(Default)]
+struct JobResult {
+    dev_eui: String,
+    channel_id: String,
+    link: String,
+}
+
+// Everything needed to process a job in its own thread
+#[derive(Clone)]
+pub(crate) struct JobContext {
+    pub(crate) jobs: JobRegistry,
+    pub(crate) dev_eui_locks: DevEuiLocks,
+    pub(crate) user_store: UserDataStore,
+    pub(crate) init_store: SensorInitializationDataStore,
+    pub(crate) mult_chan_mngr_opt: MultiChannelManagerOptions,
+    pub(crate) remote_sensor_options: RemoteSensorOptions,
+    pub(crate) handshake_opt: DevEuiHandshakeOptions,
+}
+
+unsafe impl Send for JobContext {}
+unsafe impl Sync for JobContext {}
+
+#[derive(Clone)]
+struct JobWorkerOptions {
+    job_id: String,
+    task: JobTask,
+    context: JobContext,
+}
+
+// Creates a job for the task and processes it in its own thread.
+// Returns the created job that can be used to poll the job status.
+pub(crate) fn start_job(context: &JobContext, task: JobTask) -> Job {
+    let job = context.jobs.create_job(task.get_job_type());
+    log::info!("[Admin API] Starting job {} of type {:?}", job.job_id, job.job_type);
+    let _join_handle = run_background_worker_in_own_thread::<JobWorker>(JobWorkerOptions {
+        job_id: job.job_id.clone(),
+        task,
+        context: context.clone(),
+    });
+    job
+}
+
+struct JobWorker;
+
+#[async_trait(?Send)]
+impl Worker for JobWorker {
+    type OptionsType = JobWorkerOptions;
+    type ResultType = ();
+    type ErrorType = AnyError;
+
+    async fn run(opt: JobWorkerOptions) -> Result<()> {
+        opt.context.jobs.set_running(opt.job_id.as_str());
+        let result = process_job_task(&opt.task, &opt.context).await;
+        match &result {
+            Ok(_) => log::info!("[Admin API] Job {} succeeded", opt.job_id),
+            Err(e) => log::error!("[Admin API] Job {} failed: {}", opt.job_id, e),
+        }
+        opt.context.jobs.set_finished(opt.job_id.as_str(), result);
+        Ok(())
+    }
+}
+
+// Uses the same functions as the corresponding Management Console CLI commands
+async fn process_job_task(task: &JobTask, ctx: &JobContext) -> Result<JobResult> {
+    match task {
+        JobTask::CreateChannel { external_id, name } => {
+            let mut channel_manager = get_initial_channel_manager(&ctx.user_store, &ctx.mult_chan_mngr_opt, external_id.clone()).await?;
+            let announcement_link = create_channel(&mut channel_manager).await?;
+            // Dropping the channel_manager stores the User of the new channel in the user_store
+            drop(channel_manager);
+            let channel_id = get_channel_id_from_link(announcement_link.to_string().as_str())
+                .ok_or_else(|| anyhow!("Could not parse channel_id from announcement link {}", announcement_link))?;
+            if let Some(name) = name {
+                let mut user = ctx.user_store.get_item_read_only(&channel_id)?;
+                user.name = name.clone();
+                ctx.user_store.write_item_to_db(&user)?;
+            }
+            Ok(JobResult {
+                channel_id,
+                link: announcement_link.to_string(),
+                ..Default::default()
+            })
+        },
+        JobTask::AddSubscriber { channel_id, subscription_link, pub_key } => {
+            let mut channel_manager = get_channel_manager_for_channel_id(channel_id.as_str(), &ctx.user_store, &ctx.mult_chan_mngr_opt).await?;
+            let keyload_link = send_keyload_message(&mut channel_manager, subscription_link.as_str(), pub_key.as_str()).await?;
+            Ok(JobResult {
+                channel_id: channel_id.clone(),
+                link: keyload_link.to_string(),
+                ..Default::default()
+            })
+        },
+        JobTask::InitSensor => {
+            // Same as run_init_sensor() but the dev_eui is locked after the DevEUI-Handshake
+            let (remote_sensor, dev_eui) = find_sensor_to_initialize(
+                ctx.remote_sensor_options.clone(),
+                &ctx.handshake_opt
+            ).await?;
+            let _dev_eui_lock = ctx.dev_eui_locks.try_lock(dev_eui.as_str())?;
+            run_sensor_initialization(
+                &ctx.user_store,
+                &ctx.init_store,
+                &ctx.mult_chan_mngr_opt,
+                &remote_sensor,
+                dev_eui.as_str(),
+                None,
+            ).await?;
+            let initialization = ctx.init_store.get_item_read_only(&dev_eui)?;
+            Ok(JobResult {
+                dev_eui,
+                channel_id: initialization.streams_channel_id,
+                link: initialization.announcement_link,
+            })
+        },
+        JobTask::ReinitSensor { dev_eui } => {
+            let _dev_eui_lock = ctx.dev_eui_locks.try_lock(dev_eui.as_str())?;
+            let channel_id = run_reinit_sensor(
+                &ctx.user_store,
+                &ctx.init_store,
+                &ctx.mult_chan_mngr_opt,
+                ctx.remote_sensor_options.clone(),
+                dev_eui.as_str()
+            ).await?;
+            let initialization = ctx.init_store.get_item_read_only(dev_eui)?;
+            Ok(JobResult {
+                dev_eui: dev_eui.clone(),
+                channel_id,
+                link: initialization.announcement_link,
+            })
+        },
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package management-console admin_api::jobs::tests
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dev_eui_locks() {
+        let locks = DevEuiLocks::default();
+        let lock = locks.try_lock("0080E1150533AAFF").unwrap();
+        assert!(locks.try_lock("0080E1150533AAFF").is_err());
+        assert!(locks.try_lock("0080e1150533aaff").is_err());
+        let other_lock = locks.try_lock("0080E1150533BBFF").unwrap();
+
+        drop(lock);
+        let _relocked = locks.try_lock("0080E1150533AAFF").unwrap();
+        drop(other_lock);
+        assert!(locks.clone().try_lock("0080E1150533BBFF").is_ok());
+    }
+
+    #[test]
+    fn test_job_registry() {
+        let jobs = JobRegistry::default();
+        let job = jobs.create_job(JobType::ReinitSensor);
+        assert_eq!(jobs.get_job(job.job_id.as_str()).unwrap().status, JobStatus::Pending);
+
+        jobs.set_running(job.job_id.as_str());
+        assert_eq!(jobs.get_job(job.job_id.as_str()).unwrap().status, JobStatus::Running);
+
+        jobs.set_finished(job.job_id.as_str(), Err(anyhow!("sensor not reachable")));
+        let finished = jobs.get_job(job.job_id.as_str()).unwrap();
+        assert_eq!(finished.status, JobStatus::Failed);
+        assert_eq!(finished.error, "sensor not reachable");
+        assert!(finished.finished_at_secs > 0);
+    }
+
+    #[test]
+    fn test_remove_outdated_jobs() {
+        let jobs = JobRegistry::default();
+        let first = jobs.create_job(JobType::InitSensor);
+        jobs.set_finished(first.job_id.as_str(), Ok(JobResult::default()));
+        let pending = jobs.create_job(JobType::InitSensor);
+        for _ in 0..MAX_FINISHED_JOBS {
+            let job = jobs.create_job(JobType::CreateChannel);
+            jobs.set_finished(job.job_id.as_str(), Ok(JobResult::default()));
+        }
+        // Triggers the removal of the oldest finished job
+        let _last = jobs.create_job(JobType::CreateChannel);
+        assert!(jobs.get_job(first.job_id.as_str()).is_none());
+        assert!(jobs.get_job(pending.job_id.as_str()).is_some());
+        assert_eq!(jobs.get_all_jobs().len(), MAX_FINISHED_JOBS + 2);
+    }
+}
@@ -0,0 +1,14 @@
+mod app_state;
+mod jobs;
+mod admin_api_dto;
+mod admin_api_controller;
+mod admin_auth;
+mod router;
+mod admin_api;
+
+pub(crate) use {
+    admin_api::{
+        AdminApiOptions,
+        run_admin_api_server,
+    },
+};
@@ -0,0 +1,91 @@
+use axum::{
+    routing::{
+        get,
+        post,
+    },
+    Router,
+};
+
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use streams_tools::explorer::{
+    error::AppError,
+    SecurityAddon,
+};
+
+use super::{
+    admin_api_controller as controller,
+    admin_api_dto,
+};
+
+pub async fn route_info() -> axum::Json<serde_json::Value> {
+    axum::Json(serde_json::json!({
+        "routes": ["/", "/swagger-ui", "/jobs", "/channels", "/initializations", "/reinitializations"],
+        "routes_info": {
+            "/" : "this route",
+            "/swagger-ui": "OpenAPI documentation",
+            "/jobs": "Poll the status of asynchronously processed jobs",
+            "/channels": "Create channels, add subscribers and manage the channel lifecycle",
+            "/initializations": "Initialize sensors and view their initialization state",
+            "/reinitializations": "Re-initialize sensors",
+        }
+    }))
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .route("/", get(route_info))
+        .route("/jobs", get(controller::jobs_index))
+        .route("/jobs/:job_id", get(controller::jobs_get))
+        .route("/channels", post(controller::channels_create))
+        .route("/channels/:channel_id", get(controller::channels_get).delete(controller::channels_delete))
+        .route("/channels/:channel_id/subscribers", post(controller::channels_add_subscriber))
+        .route("/channels/:channel_id/retire", post(controller::channels_retire))
+        .route("/channels/:channel_id/archive", post(controller::channels_archive))
+        .route("/initializations", get(controller::initializations_index).post(controller::initializations_create))
+        .route("/initializations/:dev_eui", get(controller::initializations_get))
+        .route("/reinitializations", post(controller::reinitializations_create))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    modifiers(&SecurityAddon),
+    info(title="SUSEE Management Console Admin API", description = "Provisioning workflows of the SUSEE Management Console"),
+    paths(
+        controller::jobs_index,
+        controller::jobs_get,
+        controller::channels_create,
+        controller::channels_get,
+        controller::channels_add_subscriber,
+        controller::channels_retire,
+        controller::channels_archive,
+        controller::channels_delete,
+        controller::initializations_create,
+        controller::initializations_index,
+        controller::initializations_get,
+        controller::reinitializations_create,
+    ),
+    components(
+        schemas(
+            admin_api_dto::Job,
+            admin_api_dto::JobType,
+            admin_api_dto::JobStatus,
+            admin_api_dto::Channel,
+            admin_api_dto::Initialization,
+            admin_api_dto::CreateChannelRequest,
+            admin_api_dto::AddSubscriberRequest,
+            admin_api_dto::ReinitSensorRequest,
+            admin_api_dto::RetireChannelRequest,
+            AppError,
+        ),
+    ),
+    tags(
+        (name = "susee management console admin api")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub struct ApiDoc;
@@ -1,5 +1,22 @@
 use core::str::FromStr;
 
+use std::{
+    collections::HashSet,
+    fs::{
+        File,
+        read_to_string,
+    },
+    io::{
+        BufWriter,
+        Write,
+    },
+    path::Path,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
 use anyhow::{
     Result,
     bail,
@@ -16,12 +33,20 @@ use streams::id::{
 
 use streams_tools::{
     channel_manager::SubscriberData,
-    subscriber_manager::println_maximum_initialization_cnt_reached_warning,
-    ChannelManagerPlainTextWallet,
+    subscriber_manager::println_initialization_cnt_wrapped_around_info,
+    ChannelManagerMultiChannelWallet,
     UserDataStore,
     multi_channel_management::{
         MultiChannelManagerOptions,
+        rotate_streams_client_state_password,
+        reencrypt_streams_client_states,
         get_initial_channel_manager,
+        get_user_for_external_id,
+        get_predecessor_user_for_external_id,
+        retire_predecessor_channel,
+        retire_channel,
+        archive_channel,
+        delete_channel,
         get_channel_manager_for_channel_id,
         get_channel_manager_for_channel_starts_with
     },
@@ -30,7 +55,7 @@ use streams_tools::{
         RemoteSensorOptions,
     },
     binary_persist::{
-        INITIALIZATION_CNT_MAX_VALUE,
+        get_full_initialization_cnt,
         DevEuiHandshake,
         KeyloadRegistration,
         Subscription,
@@ -38,15 +63,68 @@ use streams_tools::{
     explorer::{
         run_explorer_api_server,
         ExplorerOptions,
+        DEFAULT_LIVE_SYNC_INTERVAL_SECS,
+        payload::PayloadDecoderRegistry,
+        auth::{
+            ApiRole,
+            ApiToken,
+            ApiTokenDataStore,
+            ApiTokenDaoManager,
+        },
+        sync_channels_loop::DEFAULT_SYNC_CHANNELS_MAX_WORKERS,
+        message_export::{
+            ExportFormat,
+            MessageExportOptions,
+            start_message_export,
+        },
+    },
+    proof_of_inclusion::PoiClientOptions,
+    dao::User,
+    dao::sensor_initialization::{
+        SensorInitialization,
+        SensorInitializationState,
+        SensorInitializationDataStore,
+    },
+    dao::channel_integrity::ChannelIntegrityDataStore,
+    dao::{
+        migrate_database,
+        count_stored_streams_client_states,
+    },
+    backup::{
+        BackupArchive,
+        write_backup_file,
+        read_backup_file,
+        restore_backup_archive,
+    },
+    webhook::WebhookOptions,
+    message_retention::{
+        MessageRetentionPolicy,
+        SECS_PER_DAY,
+        SECS_PER_MONTH,
     },
     helpers::get_channel_id_from_link,
     dao_helpers::DbFileBasedDaoManagerOptions,
+    dao_migration::{
+        MigrationOptions,
+        MigrationTarget,
+        migrate_table,
+    },
+    wallet::{
+        create_seed,
+        MultiChannelWalletType,
+        StrongholdWallet,
+        StrongholdUnlock,
+        migrate_plain_text_wallet,
+    },
 };
 
 
 use susee_tools::{
     SUSEE_CONST_COMMAND_CONFIRM_FETCH_WAIT_SEC,
-    SUSEE_CONST_SECRET_PASSWORD,
+    get_state_password_from_env_or_keyfile,
+    read_or_create_state_password_file,
+    write_state_password_file,
+    read_state_password_file,
     get_wallet_filename,
     assert_data_dir_existence,
     get_data_folder_file_path,
@@ -60,25 +138,206 @@ use cli::{
 };
 
 use crate::multiple_sensor_init::init_sensor_in_own_thread;
+use crate::admin_api::{
+    run_admin_api_server,
+    AdminApiOptions,
+};
+use crate::sensor_provisioning::{
+    provision_sensors,
+    store_manifest_entry,
+    ManifestEntry,
+    ProvisioningOptions,
+    DEFAULT_PROVISIONING_CONCURRENCY,
+    DEFAULT_PROVISIONING_TIMEOUT_SECS,
+    DEFAULT_PROVISIONING_REPORT_FILE_NAME,
+};
 
 mod cli;
+mod admin_api;
 mod multiple_sensor_init;
+mod sensor_provisioning;
 
 fn get_management_console_wallet_filename<'a>(cli: &ManagementConsoleCli<'a>) -> Result<String> {
+    let default_wallet_file_name = if cli.matches.is_present(cli.arg_keys.stronghold_wallet) {
+        "wallet-management-console.stronghold"
+    } else {
+        "wallet-management-console.txt"
+    };
     get_wallet_filename(
         &cli.matches,
         cli.arg_keys.base.wallet_file,
         &cli.data_dir,
-        "wallet-management-console.txt",
+        default_wallet_file_name,
     )
 }
 
+fn get_stronghold_unlock<'a>(cli: &ManagementConsoleCli<'a>) -> Result<StrongholdUnlock> {
+    if let Some(keyfile) = cli.matches.value_of(cli.arg_keys.stronghold_keyfile) {
+        Ok(StrongholdUnlock::KeyFile(keyfile.to_string()))
+    } else {
+        StrongholdUnlock::from_env()
+    }
+}
+
+fn get_wallet_type<'a>(cli: &ManagementConsoleCli<'a>) -> Result<MultiChannelWalletType> {
+    if cli.matches.is_present(cli.arg_keys.stronghold_wallet) {
+        Ok(MultiChannelWalletType::Stronghold(get_stronghold_unlock(cli)?))
+    } else {
+        Ok(MultiChannelWalletType::PlainText)
+    }
+}
+
+fn migrate_wallet_to_stronghold<'a>(cli: &ManagementConsoleCli<'a>) -> Result<()> {
+    let plain_text_wallet_file = cli.matches.value_of(cli.arg_keys.migrate_wallet_to_stronghold).unwrap();
+    let snapshot_file = get_management_console_wallet_filename(cli)?;
+    migrate_plain_text_wallet(plain_text_wallet_file, snapshot_file.as_str(), &get_stronghold_unlock(cli)?)?;
+    println!("[Management Console] Migrated plain text wallet '{}' into stronghold snapshot file '{}'.
+                     Please delete the plain text wallet file in a secure way after you have verified the migration.",
+             plain_text_wallet_file,
+             snapshot_file,
+    );
+    Ok(())
+}
+
+fn get_stronghold_wallet<'a>(cli: &ManagementConsoleCli<'a>) -> Result<StrongholdWallet> {
+    let wallet_filename = get_management_console_wallet_filename(cli)?;
+    StrongholdWallet::new("", Some(wallet_filename.as_str()), None, get_stronghold_unlock(cli)?)
+}
+
+const DEFAULT_STATE_PASSWORD_FILE_NAME: &str = "state-password-management-console.key";
+
+// A new random state password must not be created if the database already contains Streams
+// Client States. These have been encrypted using another password, e.g. the constant password
+// "SUSEE" used by older versions, and could not be decrypted anymore.
+fn assert_no_streams_client_states_exist<'a>(cli: &ManagementConsoleCli<'a>) -> Result<()> {
+    let db_connection_opt = DbFileBasedDaoManagerOptions { file_path_and_name: get_db_file_name(cli) };
+    let stored_states_cnt = count_stored_streams_client_states(&db_connection_opt)?;
+    if stored_states_cnt > 0 {
+        bail!("No Streams client state password has been configured but the database already contains {} Streams client states.\n\
+               If the database has been created by an older version of the Management Console, switch to an installation\n\
+               specific password using:\n\
+               \tSUSEE_STATE_PASSWORD=SUSEE ./management-console --{} ./data/management-console/new-state-password.key\n\
+               \tmv ./data/management-console/new-state-password.key ./data/management-console/{}\n\
+               Otherwise provide the used password via --{} or the environment variable SUSEE_STATE_PASSWORD.",
+            stored_states_cnt,
+            cli.arg_keys.rotate_state_password,
+            DEFAULT_STATE_PASSWORD_FILE_NAME,
+            cli.arg_keys.base.state_password_file,
+        );
+    }
+    Ok(())
+}
+
+// The password used to encrypt the Streams Client States is taken from (in this order):
+// * the environment variable SUSEE_STATE_PASSWORD or the --state-password-file
+// * the stronghold wallet if --stronghold-wallet is used
+// * the default keyfile in the data_dir
+// A new random password is only created if the database does not contain any Streams Client States.
+fn get_state_password<'a>(cli: &ManagementConsoleCli<'a>) -> Result<String> {
+    if let Some(password) = get_state_password_from_env_or_keyfile(&cli.matches, cli.arg_keys.base.state_password_file)? {
+        Ok(password)
+    } else if cli.matches.is_present(cli.arg_keys.stronghold_wallet) {
+        let mut wallet = get_stronghold_wallet(cli)?;
+        if !wallet.has_state_password() {
+            assert_no_streams_client_states_exist(cli)?;
+        }
+        wallet.get_or_create_state_password()
+    } else {
+        let keyfile = get_data_folder_file_path(&cli.data_dir, DEFAULT_STATE_PASSWORD_FILE_NAME);
+        if !Path::new(keyfile.as_str()).exists() {
+            assert_no_streams_client_states_exist(cli)?;
+        }
+        read_or_create_state_password_file(keyfile.as_str())
+    }
+}
+
+async fn rotate_state_password<'a>(user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>, options: &MultiChannelManagerOptions) -> Result<()> {
+    let new_password_file = cli.matches.value_of(cli.arg_keys.rotate_state_password).unwrap();
+    let new_password = read_or_create_state_password_file(new_password_file)?;
+    if new_password == options.streams_user_serialization_password {
+        bail!("The password in '{}' equals the currently used password", new_password_file);
+    }
+    let rotated_cnt = rotate_streams_client_state_password(user_store, options, new_password.as_str()).await?;
+    let db_connection_opt = DbFileBasedDaoManagerOptions { file_path_and_name: get_db_file_name(cli) };
+    let stored_cnt = count_stored_streams_client_states(&db_connection_opt)?;
+    if rotated_cnt != stored_cnt {
+        bail!("Only {} of {} stored Streams client states have been re-encrypted using the password of '{}'. \
+               The currently used password has not been replaced.",
+              rotated_cnt, stored_cnt, new_password_file);
+    }
+    println!("[Management Console] Re-encrypted the Streams client states of {} channels using the password of '{}'",
+             rotated_cnt,
+             new_password_file,
+    );
+
+    if get_state_password_from_env_or_keyfile(&cli.matches, cli.arg_keys.base.state_password_file)?.is_some() {
+        println!("[Management Console] Please use the new password via --{} or the environment variable SUSEE_STATE_PASSWORD from now on",
+                 cli.arg_keys.base.state_password_file,
+        );
+    } else if cli.matches.is_present(cli.arg_keys.stronghold_wallet) {
+        get_stronghold_wallet(cli)?.set_state_password(new_password.as_str())?;
+        println!("[Management Console] Stored the new password in the stronghold wallet");
+    } else {
+        let default_password_file = get_data_folder_file_path(&cli.data_dir, DEFAULT_STATE_PASSWORD_FILE_NAME);
+        write_state_password_file(default_password_file.as_str(), new_password.as_str())?;
+        println!("[Management Console] Stored the new password in '{}'", default_password_file);
+    }
+    Ok(())
+}
+
+const BACKUP_PASSWORD_ENV_VAR: &str = "SUSEE_BACKUP_PASSWORD";
+
+fn get_backup_password<'a>(cli: &ManagementConsoleCli<'a>) -> Result<String> {
+    if let Ok(password) = std::env::var(BACKUP_PASSWORD_ENV_VAR) {
+        Ok(password)
+    } else if let Some(password_file) = cli.matches.value_of(cli.arg_keys.backup_password_file) {
+        read_state_password_file(password_file)
+    } else {
+        bail!("You need to specify the backup password using the environment variable {} or the CLI argument '--{}'",
+            BACKUP_PASSWORD_ENV_VAR, cli.arg_keys.backup_password_file)
+    }
+}
+
+fn backup<'a>(cli: &ManagementConsoleCli<'a>, options: &MultiChannelManagerOptions) -> Result<()> {
+    let backup_file = cli.matches.value_of(cli.arg_keys.backup).unwrap();
+    let db_connection_opt = DbFileBasedDaoManagerOptions { file_path_and_name: get_db_file_name(cli) };
+    let archive = BackupArchive::new(&db_connection_opt, options.wallet_filename.as_str(), options.streams_user_serialization_password.as_str())?;
+    write_backup_file(backup_file, &archive, get_backup_password(cli)?.as_str())?;
+    println!("[Management Console] Wrote backup of {} channels and {} cached messages into '{}'",
+             archive.channels.len(),
+             archive.get_messages_cnt(),
+             backup_file,
+    );
+    Ok(())
+}
+
+async fn restore<'a>(cli: &ManagementConsoleCli<'a>, options: &MultiChannelManagerOptions) -> Result<()> {
+    let backup_file = cli.matches.value_of(cli.arg_keys.restore).unwrap();
+    let mut archive = read_backup_file(backup_file, get_backup_password(cli)?.as_str())?;
+    if archive.streams_user_serialization_password != options.streams_user_serialization_password {
+        log::info!("[Management Console] Re-encrypting the archived Streams client states using the currently used state password");
+        let mut archive_options = options.clone();
+        archive_options.streams_user_serialization_password = archive.streams_user_serialization_password.clone();
+        let users = archive.channels.iter().map(|channel| channel.user.clone()).collect();
+        let reencrypted_users = reencrypt_streams_client_states(users, &archive_options, options.streams_user_serialization_password.as_str()).await?;
+        for (channel, user) in archive.channels.iter_mut().zip(reencrypted_users.into_iter()) {
+            channel.user = user;
+        }
+        archive.streams_user_serialization_password = options.streams_user_serialization_password.clone();
+    }
+    let db_connection_opt = DbFileBasedDaoManagerOptions { file_path_and_name: get_db_file_name(cli) };
+    let result = restore_backup_archive(&db_connection_opt, options.wallet_filename.as_str(), &archive)?;
+    println!("[Management Console] Restored backup '{}': {}", backup_file, result);
+    Ok(())
+}
+
 fn get_multi_channel_manager_options<'a>(cli: &ManagementConsoleCli<'a>) -> Result<MultiChannelManagerOptions> {
     let wallet_filename= get_management_console_wallet_filename(cli)?;
     Ok(MultiChannelManagerOptions{
         iota_node: cli.node.to_string(),
         wallet_filename,
-        streams_user_serialization_password: SUSEE_CONST_SECRET_PASSWORD.to_string(),
+        wallet_type: get_wallet_type(cli)?,
+        streams_user_serialization_password: get_state_password(cli)?,
         message_data_store_for_msg_caching: None,
         inx_collector_access_throttle_sleep_time_millisecs: None
     })
@@ -89,7 +348,7 @@ pub async fn get_channel_manager_for_cli_arg_channel_starts_with<'a>(
     options: &MultiChannelManagerOptions,
     cli: &ManagementConsoleCli<'a>,
     update_user_on_exit: bool
-) -> Result<ChannelManagerPlainTextWallet> {
+) -> Result<ChannelManagerMultiChannelWallet> {
     if let Some(channel_starts_with) = cli.matches.value_of(cli.arg_keys.channel_starts_with) {
         get_channel_manager_for_channel_starts_with(channel_starts_with, user_store, options, update_user_on_exit).await
     } else {
@@ -108,13 +367,13 @@ fn println_announcement_link(link: &Address, comment: &str) {
     );
 }
 
-async fn create_channel(channel_manager: &mut ChannelManagerPlainTextWallet) -> Result<Address>{
+pub(crate) async fn create_channel(channel_manager: &mut ChannelManagerMultiChannelWallet) -> Result<Address>{
     let announcement_link = channel_manager.create_announcement().await?;
     println_announcement_link(&announcement_link, "A channel has been created with the following announcement link");
     Ok(announcement_link)
 }
 
-async fn println_channel_status<'a> (channel_manager: &mut ChannelManagerPlainTextWallet, cli: &ManagementConsoleCli<'a>)
+async fn println_channel_status<'a> (channel_manager: &mut ChannelManagerMultiChannelWallet, user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>)
 {
     let mut channel_exists = false;
     if let Some(author) = &channel_manager.user {
@@ -127,6 +386,10 @@ async fn println_channel_status<'a> (channel_manager: &mut ChannelManagerPlainTe
                     &link,
                     format!("Channel details for channel ID starting with '{}'", starts_with).as_str()
                 );
+                if let Ok(user) = user_store.get_item_read_only(&link.base().to_string()) {
+                    println_channel_lifecycle_status(&user);
+                }
+                println_channel_integrity(link.base().to_string().as_str(), cli);
                 channel_exists = true
             },
             _ => {},
@@ -138,32 +401,504 @@ async fn println_channel_status<'a> (channel_manager: &mut ChannelManagerPlainTe
 }
 
 
-async fn init_sensor<'a> (user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>, options: &MultiChannelManagerOptions) -> Result<()> {
+fn println_channel_lifecycle_status(user: &User) {
+    log::info!(
+        "[Management Console] Channel {}:
+                                Status: {}
+                     Retired at (secs): {}
+                     Successor Channel: {}\n",
+        user.streams_channel_id,
+        user.status,
+        user.retired_at_secs,
+        user.successor_channel_id,
+    );
+}
+
+fn println_channel_integrity<'a>(channel_id: &str, cli: &ManagementConsoleCli<'a>) {
+    let integrity_store = ChannelIntegrityDataStore::new(DbFileBasedDaoManagerOptions {
+        file_path_and_name: get_db_file_name(cli),
+    });
+    let integrity = match integrity_store.get_item_read_only(&channel_id.to_string()) {
+        Ok(integrity) => integrity,
+        Err(_) => {
+            log::info!("[Management Console] The message chain of channel {} has not been checked yet.\n\
+                        Run the Message Explorer to synchronize and check the channel.\n", channel_id);
+            return;
+        }
+    };
+    match integrity.get_report() {
+        Ok(report) => {
+            log::info!(
+                "[Management Console] Message chain integrity of channel {}:
+                            Checked at (secs): {}
+                                    Is intact: {}
+                             Checked messages: {}
+                           Unchecked messages: {}
+                                Missing links: {}
+                                Sequence gaps: {}
+                                   Duplicates: {}
+                        Unexpected publishers: {}\n",
+                channel_id,
+                integrity.checked_at_secs,
+                integrity.is_intact,
+                report.checked_messages_cnt,
+                report.unchecked_messages_cnt,
+                report.missing_links.len(),
+                report.sequence_gaps.len(),
+                report.duplicates.len(),
+                report.unexpected_publishers.len(),
+            );
+            for link in report.missing_links.iter() {
+                log::info!("[Management Console] Message {} links to missing message {}", link.message_id, link.linked_msg_id);
+            }
+            for gap in report.sequence_gaps.iter() {
+                log::info!("[Management Console] Publisher {} has a sequence gap between {} and {}",
+                    gap.publisher, gap.last_sequence_before_gap, gap.first_sequence_after_gap);
+            }
+            for duplicate in report.duplicates.iter() {
+                log::info!("[Management Console] Message {} is a duplicate of {}: {}", duplicate.message_id, duplicate.duplicate_of, duplicate.reason);
+            }
+            for publisher in report.unexpected_publishers.iter() {
+                log::info!("[Management Console] Unexpected publisher {} sent messages {}", publisher.publisher, publisher.message_ids.join(", "));
+            }
+        },
+        Err(e) => log::error!("[Management Console] Could not read integrity report of channel {}. Error: {}", channel_id, e),
+    }
+}
+
+fn retire_or_archive_channel<'a>(user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>) -> Result<()> {
+    let user = if let Some(channel_id) = cli.matches.value_of(cli.arg_keys.retire_channel) {
+        retire_channel(user_store, channel_id, cli.matches.value_of(cli.arg_keys.successor_channel))?
+    } else {
+        archive_channel(user_store, cli.matches.value_of(cli.arg_keys.archive_channel).unwrap())?
+    };
+    println_channel_lifecycle_status(&user);
+    Ok(())
+}
+
+async fn init_sensor<'a> (user_store: &UserDataStore, init_store: &SensorInitializationDataStore, cli: &ManagementConsoleCli<'a>, options: &MultiChannelManagerOptions) -> Result<()> {
+    let handshake_opt = get_dev_eui_handshake_options(cli)?;
+    let _dev_eui = run_init_sensor(
+        user_store,
+        init_store,
+        options,
+        create_remote_sensor_options(cli, None),
+        &handshake_opt
+    ).await?;
+    Ok(())
+}
+
+// Initializes the next sensor found by a DevEUI-Handshake. Returns the dev_eui of the sensor.
+pub(crate) async fn run_init_sensor(
+    user_store: &UserDataStore,
+    init_store: &SensorInitializationDataStore,
+    options: &MultiChannelManagerOptions,
+    remote_sensor_options: RemoteSensorOptions,
+    handshake_opt: &DevEuiHandshakeOptions
+) -> Result<String> {
+    let (remote_sensor, dev_eui) = find_sensor_to_initialize(remote_sensor_options, handshake_opt).await?;
+    run_sensor_initialization(
+        user_store,
+        init_store,
+        options,
+        &remote_sensor,
+        dev_eui.as_str(),
+        None,
+    ).await?;
+    Ok(dev_eui)
+}
+
+// Performs the DevEUI-Handshake to find the next sensor to be initialized.
+// Returns the RemoteSensor that is bound to the dev_eui of the found sensor and its dev_eui.
+pub(crate) async fn find_sensor_to_initialize(
+    remote_sensor_options: RemoteSensorOptions,
+    handshake_opt: &DevEuiHandshakeOptions
+) -> Result<(RemoteSensor, String)> {
     log::info!("Initializing remote sensor");
-    let remote_sensor = RemoteSensor::new(Some(create_remote_sensor_options(cli, None)));
-    let dev_eui_handshake = perform_dev_eui_handshake(&remote_sensor).await?;
+    let remote_sensor = RemoteSensor::new(Some(remote_sensor_options));
+    let dev_eui_handshake = perform_dev_eui_handshake(&remote_sensor, handshake_opt).await?;
     remote_sensor.set_dev_eui(dev_eui_handshake.dev_eui.as_str());
-    let mut channel_manager  = get_initial_channel_manager(
+    Ok((remote_sensor, dev_eui_handshake.dev_eui))
+}
+
+async fn reinit_sensor<'a> (user_store: &UserDataStore, init_store: &SensorInitializationDataStore, cli: &ManagementConsoleCli<'a>, options: &MultiChannelManagerOptions) -> Result<()> {
+    let dev_eui = cli.matches.value_of(cli.arg_keys.dev_eui).unwrap();
+    let _new_channel_id = run_reinit_sensor(
         user_store,
-        &options,
-        Some(dev_eui_handshake.dev_eui.clone())
+        init_store,
+        options,
+        create_remote_sensor_options(cli, Some(dev_eui.to_string())),
+        dev_eui
     ).await?;
-    let announcement_link = create_channel(&mut channel_manager).await?;
-    let subscription = subscribe_remote_sensor_to_channel(&remote_sensor, announcement_link).await?;
-    let _keyload_registration = make_remote_sensor_register_keyload_msg(&mut channel_manager, &remote_sensor, subscription).await?;
     Ok(())
 }
 
-async fn init_multiple_sensors<'a>(user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>) -> Result<()> {
+// Re-initializes an already initialized sensor using a new channel. The streams client state
+// of the sensor is cleared and the initialization process is run again for the dev_eui.
+// The external_id disambiguation done by get_initial_channel_manager() links the new channel
+// to the old one. Finally the LoraWanNode of the sensor managed by the iota-bridge is updated
+// so that messages of the sensor are dispatched using the new channel.
+// The pending LoraWanNode update is persisted together with the initialization state, so that
+// an interrupted re-initialization can be resumed without creating an additional channel.
+// Returns the channel ID of the new channel.
+pub(crate) async fn run_reinit_sensor(
+    user_store: &UserDataStore,
+    init_store: &SensorInitializationDataStore,
+    options: &MultiChannelManagerOptions,
+    remote_sensor_options: RemoteSensorOptions,
+    dev_eui: &str
+) -> Result<String> {
+    log::info!("DevEUI: {} - Re-initializing remote sensor", dev_eui);
+    let mut remote_sensor_options = remote_sensor_options;
+    remote_sensor_options.dev_eui = dev_eui.to_string();
+    let remote_sensor = RemoteSensor::new(Some(remote_sensor_options));
+
+    let needs_initialization = match init_store.get_item_read_only(&dev_eui.to_string()) {
+        Ok(existing) if !existing.is_done() => {
+            log::info!("DevEUI: {} - Resuming interrupted re-initialization", dev_eui);
+            true
+        },
+        Ok(existing) if existing.lorawan_node_update_pending => {
+            log::info!("DevEUI: {} - Resuming interrupted re-initialization. Only the iota-bridge needs to be updated", dev_eui);
+            false
+        },
+        _ => {
+            if get_user_for_external_id(user_store, dev_eui)?.is_none() {
+                bail!("DevEUI: {} - No channel found for this DevEUI. Use --{} to initialize the sensor for the first time",
+                    dev_eui, ARG_KEYS.init_sensor);
+            }
+            let mut initialization = SensorInitialization::new(dev_eui);
+            initialization.lorawan_node_update_pending = true;
+            init_store.write_item_to_db(&initialization)?;
+            let _clear_client_state = remote_sensor.clear_client_state().await?;
+            log::info!("DevEUI: {} - Cleared the streams client state of the sensor", dev_eui);
+            true
+        }
+    };
+
+    if needs_initialization {
+        run_sensor_initialization(user_store, init_store, options, &remote_sensor, dev_eui, None).await?;
+    }
+
+    let mut initialization = init_store.get_item_read_only(&dev_eui.to_string())?;
+    let mut new_user = user_store.get_item_read_only(&initialization.streams_channel_id)?;
+    let old_channel_id = match get_predecessor_user_for_external_id(user_store, dev_eui)? {
+        Some(predecessor) => {
+            new_user.name = predecessor.name.clone();
+            new_user.payload_decoder = predecessor.payload_decoder.clone();
+            new_user.asset_id = predecessor.asset_id.clone();
+            user_store.write_item_to_db(&new_user)?;
+            predecessor.streams_channel_id
+        },
+        None => {
+            log::warn!("DevEUI: {} - Could not find the predecessor channel of the new channel", dev_eui);
+            String::from("unknown")
+        }
+    };
+
+    remote_sensor.update_lorawan_node(
+        initialization.streams_channel_id.as_str(),
+        initialization.initialization_cnt,
+        initialization.initialization_epoch,
+    ).await?;
+    initialization.lorawan_node_update_pending = false;
+    init_store.write_item_to_db(&initialization)?;
+
+    log::info!("DevEUI: {} - Re-initialization finished. Replaced channel {} by channel {}. Initialization count is {}",
+        dev_eui,
+        old_channel_id,
+        initialization.streams_channel_id,
+        get_full_initialization_cnt(initialization.initialization_cnt, initialization.initialization_epoch),
+    );
+    Ok(initialization.streams_channel_id)
+}
+
+// Runs the initialization process for the sensor as state machine which state is persisted
+// in the init_store after each step. If an unfinished initialization exists for the dev_eui,
+// the process is resumed with the step following the persisted state, so that no additional
+// channel is created for the sensor. Otherwise a new initialization is started.
+pub(crate) async fn run_sensor_initialization(
+    user_store: &UserDataStore,
+    init_store: &SensorInitializationDataStore,
+    options: &MultiChannelManagerOptions,
+    remote_sensor: &RemoteSensor,
+    dev_eui: &str,
+    manifest_entry: Option<&ManifestEntry>,
+) -> Result<()> {
+    let mut initialization = match init_store.get_item_read_only(&dev_eui.to_string()) {
+        Ok(existing) if !existing.is_done() => {
+            log::info!("DevEUI: {} - Resuming unfinished initialization in state {}", dev_eui, existing.state);
+            existing
+        },
+        _ => SensorInitialization::new(dev_eui),
+    };
+    init_store.write_item_to_db(&initialization)?;
+
+    loop {
+        match initialization.get_state()? {
+            SensorInitializationState::Handshake => {
+                let mut channel_manager = get_initial_channel_manager(
+                    user_store,
+                    options,
+                    Some(dev_eui.to_string())
+                ).await?;
+                let announcement_link = create_channel(&mut channel_manager).await?;
+                initialization.streams_channel_id = get_channel_id_from_link(announcement_link.to_string().as_str())
+                    .ok_or_else(|| anyhow!("Could not parse channel_id from announcement link {}", announcement_link))?;
+                initialization.announcement_link = announcement_link.to_string();
+                initialization.set_state(SensorInitializationState::Announced);
+                // The new channel is persisted before its User is stored, so that a crash can not
+                // result in a User that is unknown to the initialization (see state Announced)
+                init_store.write_item_to_db(&initialization)?;
+                // Dropping the channel_manager stores the User of the new channel in the user_store
+                drop(channel_manager);
+            },
+            SensorInitializationState::Announced => {
+                if user_store.get_item_read_only(&initialization.streams_channel_id).is_err() {
+                    // The initialization has been interrupted before the User of the channel has
+                    // been stored. The channel can not be used without its Streams client state.
+                    log::warn!("DevEUI: {} - The User of channel {} has not been stored. Restarting the initialization using a new channel",
+                        dev_eui, initialization.streams_channel_id);
+                    let lorawan_node_update_pending = initialization.lorawan_node_update_pending;
+                    initialization = SensorInitialization::new(dev_eui);
+                    initialization.lorawan_node_update_pending = lorawan_node_update_pending;
+                    init_store.write_item_to_db(&initialization)?;
+                    continue;
+                }
+                retire_predecessor_channel(user_store, dev_eui, initialization.streams_channel_id.as_str())?;
+                let announcement_link = Address::from_str(initialization.announcement_link.as_str()).map_err(|e|anyhow!(e))?;
+                let subscription = subscribe_remote_sensor_to_channel(remote_sensor, announcement_link).await?;
+                initialization.subscription_link = subscription.subscription_link;
+                initialization.pub_key = subscription.pup_key;
+                initialization.initialization_cnt = subscription.initialization_cnt;
+                initialization.initialization_epoch = subscription.initialization_epoch;
+                initialization.set_state(SensorInitializationState::Subscribed);
+            },
+            SensorInitializationState::Subscribed => {
+                let mut channel_manager = get_channel_manager_for_channel_id(
+                    initialization.streams_channel_id.as_str(),
+                    user_store,
+                    options
+                ).await?;
+                let keyload_msg_link = send_keyload_message(
+                    &mut channel_manager,
+                    initialization.subscription_link.as_str(),
+                    initialization.pub_key.as_str()
+                ).await?;
+                drop(channel_manager);
+                initialization.keyload_link = keyload_msg_link.to_string();
+                initialization.set_state(SensorInitializationState::KeyloadSent);
+            },
+            SensorInitializationState::KeyloadSent => {
+                let _keyload_registration = make_remote_sensor_register_keyload_msg(
+                    remote_sensor,
+                    initialization.keyload_link.as_str()
+                ).await?;
+                if let Some(entry) = manifest_entry {
+                    store_manifest_entry(user_store, initialization.streams_channel_id.as_str(), entry)?;
+                }
+                initialization.set_state(SensorInitializationState::Done);
+            },
+            SensorInitializationState::Done => {
+                return Ok(());
+            }
+        }
+        init_store.write_item_to_db(&initialization)?;
+        log::debug!("DevEUI: {} - Initialization state is now {}", dev_eui, initialization.state);
+    }
+}
+
+fn get_provisioning_options<'a>(cli: &ManagementConsoleCli<'a>) -> Result<ProvisioningOptions> {
+    let concurrency = match cli.matches.value_of(cli.arg_keys.provision_concurrency) {
+        Some(value) => usize::from_str(value)
+            .map_err(|e| anyhow!("Invalid value for --{}: '{}'. Error: {}", cli.arg_keys.provision_concurrency, value, e))?,
+        None => DEFAULT_PROVISIONING_CONCURRENCY,
+    };
+    if concurrency == 0 {
+        bail!("The value for --{} must be greater than 0", cli.arg_keys.provision_concurrency);
+    }
+    let timeout_secs = match cli.matches.value_of(cli.arg_keys.provision_timeout) {
+        Some(value) => u32::from_str(value)
+            .map_err(|e| anyhow!("Invalid value for --{}: '{}'. Error: {}", cli.arg_keys.provision_timeout, value, e))?,
+        None => DEFAULT_PROVISIONING_TIMEOUT_SECS,
+    };
+    Ok(ProvisioningOptions {
+        manifest_file: cli.matches.value_of(cli.arg_keys.provision_from).unwrap().to_string(),
+        report_file: cli.matches.value_of(cli.arg_keys.provision_report)
+            .map(|report_file| report_file.to_string())
+            .unwrap_or(get_data_folder_file_path(&cli.data_dir, DEFAULT_PROVISIONING_REPORT_FILE_NAME)),
+        concurrency,
+        timeout_secs,
+    })
+}
+
+async fn export_messages<'a>(user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>, options: &MultiChannelManagerOptions) -> Result<()> {
+    let format = match cli.matches.value_of(cli.arg_keys.export_format) {
+        Some(format) => ExportFormat::from_str(format)?,
+        None => ExportFormat::Csv,
+    };
+    let channel_ids: Vec<String> = cli.matches.value_of(cli.arg_keys.export_messages).unwrap()
+        .split(',')
+        .map(|channel_id| channel_id.trim())
+        .filter(|channel_id| !channel_id.is_empty())
+        .map(|channel_id| channel_id.to_string())
+        .collect();
+    let export_file = cli.matches.value_of(cli.arg_keys.export_file)
+        .map(|export_file| export_file.to_string())
+        .unwrap_or(get_data_folder_file_path(&cli.data_dir, match format {
+            ExportFormat::Csv => DEFAULT_EXPORT_CSV_FILE_NAME,
+            ExportFormat::JsonLines => DEFAULT_EXPORT_JSONL_FILE_NAME,
+        }));
+
+    let mut export_options = MessageExportOptions::new(channel_ids, format, options.clone(), get_db_file_name(cli));
+    if let Some(default_payload_decoder) = cli.matches.value_of(cli.arg_keys.default_payload_decoder) {
+        export_options.payload_decoders.validate_decoder_name(default_payload_decoder)?;
+        export_options.default_payload_decoder = Some(default_payload_decoder.to_string());
+    }
+    let mut receiver = start_message_export(user_store, export_options)?;
+
+    let mut writer = BufWriter::new(File::create(export_file.as_str())?);
+    let mut lines_cnt: usize = 0;
+    while let Some(line) = receiver.recv().await {
+        writer.write_all(line?.as_bytes())?;
+        lines_cnt += 1;
+    }
+    writer.flush()?;
+    let messages_cnt = if format == ExportFormat::Csv { lines_cnt.saturating_sub(1) } else { lines_cnt };
+    println!("[Management Console] Exported {} messages to '{}'", messages_cnt, export_file);
+    Ok(())
+}
+
+fn list_initializations(init_store: &SensorInitializationDataStore) -> Result<()> {
+    let initializations = init_store.find_all_unlimited()?;
+    if initializations.is_empty() {
+        println!("[Management Console] No sensor initializations found");
+        return Ok(());
+    }
+    println!("{:<20} {:<12} {:<20} {:<66}", "DevEUI", "State", "Updated at (secs)", "Channel ID");
+    for initialization in initializations {
+        println!("{:<20} {:<12} {:<20} {:<66}",
+                 initialization.dev_eui,
+                 initialization.state,
+                 initialization.updated_at_secs,
+                 initialization.streams_channel_id,
+        );
+    }
+    Ok(())
+}
+
+fn create_api_token<'a>(api_token_store: &ApiTokenDataStore, cli: &ManagementConsoleCli<'a>) -> Result<()> {
+    let role = ApiRole::from_str(cli.matches.value_of(cli.arg_keys.create_api_token).unwrap())?;
+    let external_id_prefix = cli.matches.value_of(cli.arg_keys.api_token_external_id_prefix).unwrap_or("");
+    let description = cli.matches.value_of(cli.arg_keys.api_token_description).unwrap_or("");
+    let (api_token, bearer_token) = ApiToken::new_random(role, external_id_prefix, description)?;
+    api_token_store.write_item_to_db(&api_token)?;
+    println!("[Management Console] Created API token {} having role {}", api_token.token_id, role);
+    println!("[Management Console] Please store the token in a safe place. It can not be printed again:\n\n    {}\n", bearer_token);
+    Ok(())
+}
+
+fn list_api_tokens(api_token_store: &ApiTokenDataStore) -> Result<()> {
+    let api_tokens = api_token_store.find_all_unlimited()?;
+    if api_tokens.is_empty() {
+        println!("[Management Console] No API tokens found");
+        return Ok(());
+    }
+    println!("{:<18} {:<10} {:<20} {:<20} {}", "Token ID", "Role", "External ID Prefix", "Created at (secs)", "Description");
+    for api_token in api_tokens {
+        println!("{:<18} {:<10} {:<20} {:<20} {}",
+                 api_token.token_id,
+                 api_token.role,
+                 api_token.external_id_prefix,
+                 api_token.created_at_secs,
+                 api_token.description,
+        );
+    }
+    Ok(())
+}
+
+fn revoke_api_token(api_token_store: &ApiTokenDataStore, token_id: &str) -> Result<()> {
+    let token_id = token_id.to_string();
+    if api_token_store.get_item_read_only(&token_id).is_err() {
+        bail!("An API token with token ID {} does not exist", token_id);
+    }
+    api_token_store.delete_item_in_db(&token_id)?;
+    println!("[Management Console] Revoked API token {}", token_id);
+    Ok(())
+}
+
+fn get_cors_allowed_origins<'a>(cli: &ManagementConsoleCli<'a>) -> Vec<String> {
+    cli.matches.value_of(cli.arg_keys.cors_allowed_origins)
+        .map(|origins| origins.split(',')
+            .map(|origin| origin.trim())
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| origin.to_string())
+            .collect()
+        )
+        .unwrap_or_default()
+}
+
+fn get_message_retention_policy<'a>(cli: &ManagementConsoleCli<'a>) -> Result<MessageRetentionPolicy> {
+    let get_retention_secs = |arg_key: &str, secs_per_unit: u64| -> Result<Option<u64>> {
+        match cli.matches.value_of(arg_key) {
+            Some(value) => {
+                let units = u64::from_str(value)
+                    .map_err(|e| anyhow!("Invalid value for --{}: '{}'. Error: {}", arg_key, value, e))?;
+                if units == 0 {
+                    bail!("The value for --{} must be greater than 0", arg_key);
+                }
+                // The retention period is subtracted from the current time as i64
+                match units.checked_mul(secs_per_unit) {
+                    Some(secs) if secs <= i64::MAX as u64 => Ok(Some(secs)),
+                    _ => bail!("The value for --{} is too large: '{}'", arg_key, value),
+                }
+            },
+            None => Ok(None),
+        }
+    };
+    Ok(MessageRetentionPolicy {
+        message_retention_secs: get_retention_secs(cli.arg_keys.message_retention_months, SECS_PER_MONTH)?,
+        binary_retention_secs: get_retention_secs(cli.arg_keys.binary_retention_days, SECS_PER_DAY)?,
+    })
+}
+
+fn get_migration_options<'a>(cli: &ManagementConsoleCli<'a>) -> Result<MigrationOptions> {
+    let target = match cli.matches.value_of(cli.arg_keys.migrate_to) {
+        Some(value) => Some(MigrationTarget::from_str(value)
+            .map_err(|e| anyhow!("Invalid value for --{}: {}", cli.arg_keys.migrate_to, e))?),
+        None => None,
+    };
+    Ok(MigrationOptions {
+        dry_run: cli.matches.is_present(cli.arg_keys.dry_run),
+        target,
+    })
+}
+
+fn migrate<'a>(cli: &ManagementConsoleCli<'a>, db_connection_opt: &DbFileBasedDaoManagerOptions) -> Result<()> {
+    let migration_opt = get_migration_options(cli)?;
+    let mut plans = migrate_database(db_connection_opt, &migration_opt)?;
+    plans.extend(migrate_table::<ApiTokenDaoManager>(db_connection_opt.clone(), &migration_opt)?);
+    migration_opt.check_target_table_has_been_found(plans.as_slice())?;
+    for plan in plans.iter() {
+        println!("[Management Console] {}", plan);
+    }
+    if migration_opt.dry_run {
+        println!("[Management Console] Dry run: The database has not been changed");
+    }
+    Ok(())
+}
+
+async fn init_multiple_sensors<'a>(user_store: &UserDataStore, init_store: &SensorInitializationDataStore, cli: &ManagementConsoleCli<'a>) -> Result<()> {
     log::info!("Initializing multiple remote sensors");
+    let handshake_opt = get_dev_eui_handshake_options(cli)?;
     loop {
         let dev_eui = {
             let remote_sensor = RemoteSensor::new(Some(create_remote_sensor_options(cli, None)));
-            let dev_eui_handshake = perform_dev_eui_handshake(&remote_sensor).await?;
+            let dev_eui_handshake = perform_dev_eui_handshake(&remote_sensor, &handshake_opt).await?;
             dev_eui_handshake.dev_eui.clone()
         };
 
-        match init_sensor_in_own_thread(user_store, cli, dev_eui).await{
+        match init_sensor_in_own_thread(user_store, init_store, cli, dev_eui).await{
             Ok(_) => {}
             Err(_) => {
                 break;
@@ -173,51 +908,133 @@ async fn init_multiple_sensors<'a>(user_store: &UserDataStore, cli: &ManagementC
     Ok(())
 }
 
-async fn perform_dev_eui_handshake<'a>(remote_sensor: &RemoteSensor) -> Result<DevEuiHandshake> {
+const PAIRING_CODE_LENGTH: usize = 16;
+const DEFAULT_DEV_EUI_HANDSHAKE_TIMEOUT_SECS: u32 = 600;
+
+// Defines which DevEuiHandshake confirmations are accepted by the Management Console
+#[derive(Clone)]
+pub(crate) struct DevEuiHandshakeOptions {
+    // If specified, only sensors having a DevEUI contained in the allow_list are accepted.
+    // The DevEUIs are stored in lower case.
+    pub allow_list: Option<HashSet<String>>,
+    // If true, every DevEuiHandshakeCmd contains a new one-time pairing code that has to be
+    // echoed back by the sensor
+    pub use_pairing_code: bool,
+    // Overall time to wait for an accepted DevEuiHandshake. Rejected responses do not
+    // extend the deadline.
+    pub timeout_secs: u32,
+}
+
+impl Default for DevEuiHandshakeOptions {
+    fn default() -> Self {
+        DevEuiHandshakeOptions {
+            allow_list: None,
+            use_pairing_code: false,
+            timeout_secs: DEFAULT_DEV_EUI_HANDSHAKE_TIMEOUT_SECS,
+        }
+    }
+}
+
+// Reads the allow-list file containing one DevEUI per line.
+// Empty lines and lines starting with '#' are ignored.
+fn read_dev_eui_allow_list(allow_list_file: &str) -> Result<HashSet<String>> {
+    let content = read_to_string(allow_list_file)
+        .map_err(|e| anyhow!("Could not read DevEUI allow-list file '{}'. Error: {}", allow_list_file, e))?;
+    let allow_list: HashSet<String> = content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|dev_eui| dev_eui.to_lowercase())
+        .collect();
+    if allow_list.is_empty() {
+        bail!("The DevEUI allow-list file '{}' does not contain any DevEUI", allow_list_file);
+    }
+    Ok(allow_list)
+}
+
+pub(crate) fn get_dev_eui_handshake_options<'a>(cli: &ManagementConsoleCli<'a>) -> Result<DevEuiHandshakeOptions> {
+    let allow_list = match cli.matches.value_of(cli.arg_keys.dev_eui_allow_list) {
+        Some(allow_list_file) => {
+            let allow_list = read_dev_eui_allow_list(allow_list_file)?;
+            log::info!("Accepting {} DevEUIs listed in allow-list file '{}'", allow_list.len(), allow_list_file);
+            Some(allow_list)
+        },
+        None => None,
+    };
+    Ok(DevEuiHandshakeOptions {
+        allow_list,
+        use_pairing_code: cli.matches.is_present(cli.arg_keys.require_pairing_code),
+        timeout_secs: DEFAULT_DEV_EUI_HANDSHAKE_TIMEOUT_SECS,
+    })
+}
+
+fn create_pairing_code() -> String {
+    create_seed()[..PAIRING_CODE_LENGTH].to_string()
+}
+
+// Performs DevEUI-Handshakes until a sensor responds that is accepted according to the
+// handshake_opt. Responses of sensors that are not accepted are rejected and logged.
+// Fails if no accepted response has been received within handshake_opt.timeout_secs.
+async fn perform_dev_eui_handshake<'a>(remote_sensor: &RemoteSensor, handshake_opt: &DevEuiHandshakeOptions) -> Result<DevEuiHandshake> {
     log::info!("Using {} as iota-bridge url", remote_sensor.get_proxy_url());
 
-    log::info!("DevEUI: {} - Sending dev_eui_handshake command to remote sensor.", remote_sensor.get_dev_eui_command());
-    let dev_eui_handshake = remote_sensor.dev_eui_handshake().await?;
+    let started_at = Instant::now();
+    loop {
+        if started_at.elapsed() >= Duration::from_secs(handshake_opt.timeout_secs as u64) {
+            bail!("No accepted dev_eui_handshake received within {} secs", handshake_opt.timeout_secs);
+        }
+        let pairing_code = if handshake_opt.use_pairing_code {
+            create_pairing_code()
+        } else {
+            String::new()
+        };
+        log::info!("DevEUI: {} - Sending dev_eui_handshake command to remote sensor.", remote_sensor.get_dev_eui_command());
+        let dev_eui_handshake = remote_sensor.dev_eui_handshake(pairing_code.as_str()).await?;
+
+        if handshake_opt.use_pairing_code && dev_eui_handshake.pairing_code != pairing_code {
+            log::warn!("DevEUI: {} - Rejected dev_eui_handshake: The pairing code does not match the expected pairing code.",
+                dev_eui_handshake.dev_eui);
+            continue;
+        }
+        if let Some(allow_list) = &handshake_opt.allow_list {
+            if !allow_list.contains(&dev_eui_handshake.dev_eui.to_lowercase()) {
+                log::warn!("DevEUI: {} - Rejected dev_eui_handshake: The DevEUI is not contained in the DevEUI allow-list.",
+                    dev_eui_handshake.dev_eui);
+                continue;
+            }
+        }
 
-    log::info!("DevEUI: {} - Received dev_eui_handshake from remote sensor.", dev_eui_handshake.dev_eui);
-    Ok(dev_eui_handshake)
+        log::info!("DevEUI: {} - Received dev_eui_handshake from remote sensor.", dev_eui_handshake.dev_eui);
+        return Ok(dev_eui_handshake);
+    }
 }
 
 async fn subscribe_remote_sensor_to_channel(remote_sensor: &RemoteSensor, announcement_link: Address) -> Result<Subscription> {
     log::info!("DevEUI: {} - Sending subscribe_announcement_link command to remote sensor.", remote_sensor.get_dev_eui_command());
     let subscription_confirm = remote_sensor.subscribe_to_channel(announcement_link.to_string().as_str()).await?;
 
-    if subscription_confirm.initialization_cnt == INITIALIZATION_CNT_MAX_VALUE {
-        println_maximum_initialization_cnt_reached_warning("SManagement Console", subscription_confirm.initialization_cnt);
+    if subscription_confirm.initialization_cnt == 0 && subscription_confirm.initialization_epoch > 0 {
+        println_initialization_cnt_wrapped_around_info("Management Console", subscription_confirm.initialization_epoch);
     }
 
     log::info!("
 DevEUI: {} - Received confirmation for successful Subscription from remote sensor.
-                     Initialization count is {}
+                     Initialization count is {} (epoch {}, full initialization count {})
                      Creating keyload_message for
                             subscription: {}
                             public key: {}\n",
              remote_sensor.get_dev_eui_confirm(),
              subscription_confirm.initialization_cnt,
+             subscription_confirm.initialization_epoch,
+             get_full_initialization_cnt(subscription_confirm.initialization_cnt, subscription_confirm.initialization_epoch),
              subscription_confirm.subscription_link,
              subscription_confirm.pup_key,
     );
     Ok(subscription_confirm)
 }
 
-async fn make_remote_sensor_register_keyload_msg(
-    channel_manager: &mut ChannelManagerPlainTextWallet,
-    remote_sensor: &RemoteSensor,
-    subscription: Subscription
-) -> Result<KeyloadRegistration> {
-    let keyload_msg_link = send_keyload_message(
-        channel_manager,
-        subscription.subscription_link.as_str(),
-        subscription.pup_key.as_str()
-    ).await?;
-
+async fn make_remote_sensor_register_keyload_msg(remote_sensor: &RemoteSensor, keyload_msg_link: &str) -> Result<KeyloadRegistration> {
     log::info!("DevEUI: {} - Sending register_keyload_msg command to remote sensor.", remote_sensor.get_dev_eui_command());
-    let keyload_registration = remote_sensor.register_keyload_msg(keyload_msg_link.to_string().as_str()).await?;
+    let keyload_registration = remote_sensor.register_keyload_msg(keyload_msg_link).await?;
     log::info!("
 DevEUI: {0} - Received confirmation for successful KeyloadRegistration from remote sensor.
                      =========> Sensor {0} has been fully initialized <==========="
@@ -244,17 +1061,18 @@ fn create_remote_sensor_options<'a>(cli: &ManagementConsoleCli<'a>, opt_dev_eu:
     remote_options
 }
 
-async fn send_keyload_message_cli<'a> (channel_manager: &mut ChannelManagerPlainTextWallet, cli: &ManagementConsoleCli<'a>) -> Result<Address>
+async fn send_keyload_message_cli<'a> (channel_manager: &mut ChannelManagerMultiChannelWallet, cli: &ManagementConsoleCli<'a>) -> Result<Address>
 {
     let sub_msg_link_string = cli.matches.value_of(cli.arg_keys.subscription_link).unwrap();
     let pub_key_str = cli.matches.value_of(cli.arg_keys.subscription_pub_key).unwrap();
     send_keyload_message(channel_manager, sub_msg_link_string, pub_key_str).await
 }
 
-async fn send_keyload_message<'a> (channel_manager: &mut ChannelManagerPlainTextWallet, sub_msg_link_string: &str, pub_key_str: &str) -> Result<Address>
+pub(crate) async fn send_keyload_message<'a> (channel_manager: &mut ChannelManagerMultiChannelWallet, sub_msg_link_string: &str, pub_key_str: &str) -> Result<Address>
 {
     let subscription_msg_link = Address::from_str(sub_msg_link_string).map_err(|e|anyhow!(e))?;
-    let pub_key = hex::decode(pub_key_str).unwrap();
+    let pub_key = hex::decode(pub_key_str)
+        .map_err(|e| anyhow!("Could not decode the public key '{}'. Error: {}", pub_key_str, e))?;
     let keyload_msg_link = channel_manager.add_subscribers(&vec![ SubscriberData {
         subscription_link: & subscription_msg_link,
         permissioned_public_key: Permissioned::ReadWrite(pub_key.as_slice(), PermissionDuration::Perpetual)
@@ -272,6 +1090,14 @@ A keyload message has been created with the following keyload link:
 }
 
 const DB_FILE_PATH_AND_NAME: &str = "client-states-management-console.sqlite3";
+const DEFAULT_EXPORT_CSV_FILE_NAME: &str = "messages-export.csv";
+const DEFAULT_EXPORT_JSONL_FILE_NAME: &str = "messages-export.jsonl";
+
+fn get_db_file_name(cli: &ManagementConsoleCli) -> String {
+    cli.matches.value_of(cli.arg_keys.db_url)
+        .map(|db_url| db_url.to_string())
+        .unwrap_or(get_data_folder_file_path(&cli.data_dir, DB_FILE_PATH_AND_NAME))
+}
 
 
 #[tokio::main]
@@ -286,16 +1112,56 @@ async fn main() -> Result<()> {
 
 
     let db_connection_opt = DbFileBasedDaoManagerOptions {
-        file_path_and_name: get_data_folder_file_path(&cli.data_dir, DB_FILE_PATH_AND_NAME)
+        file_path_and_name: get_db_file_name(&cli)
     };
-    let mut user_store = UserDataStore::new(db_connection_opt);
+
+    // Needs to be done before the data stores are created because they
+    // migrate their tables automatically
+    if cli.matches.is_present(cli.arg_keys.migrate) {
+        return migrate(&cli, &db_connection_opt);
+    }
+
+    let mut user_store = UserDataStore::new(db_connection_opt.clone());
+    let init_store = SensorInitializationDataStore::new(db_connection_opt.clone());
+    let api_token_store = ApiTokenDataStore::new(db_connection_opt);
 
     let mut print_usage_help = false;
 
+    // Needs to be done before the stronghold wallet is used by any other function
+    // because the snapshot file must not exist
+    if cli.matches.is_present(cli.arg_keys.migrate_wallet_to_stronghold) {
+        return migrate_wallet_to_stronghold(&cli);
+    }
+
+    // Only reads the init_store so no state password and wallet are needed
+    if cli.matches.is_present(cli.arg_keys.list_initializations) {
+        return list_initializations(&init_store);
+    }
+
+    // API tokens are managed without state password and wallet too
+    if cli.matches.is_present(cli.arg_keys.create_api_token) {
+        return create_api_token(&api_token_store, &cli);
+    }
+    if cli.matches.is_present(cli.arg_keys.list_api_tokens) {
+        return list_api_tokens(&api_token_store);
+    }
+    if let Some(token_id) = cli.matches.value_of(cli.arg_keys.revoke_api_token) {
+        return revoke_api_token(&api_token_store, token_id);
+    }
+
     let options = get_multi_channel_manager_options(&cli)
         .expect("Could not create multi_channel_manager_options");
 
-    if cli.matches.is_present(cli.arg_keys.create_channel) {
+    if cli.matches.is_present(cli.arg_keys.rotate_state_password) {
+        rotate_state_password(&user_store, &cli, &options).await?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.backup) {
+        backup(&cli, &options)?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.restore) {
+        restore(&cli, &options).await?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.create_channel) {
         let mut channel_manager = get_initial_channel_manager(&user_store, &options, None).await.unwrap();
         create_channel(&mut channel_manager).await?;
     }
@@ -312,24 +1178,87 @@ async fn main() -> Result<()> {
         }
     } else if cli.matches.is_present(cli.arg_keys.println_channel_status) {
         let mut channel_manager = get_channel_manager_for_cli_arg_channel_starts_with(&mut user_store, &options, &cli, false).await.unwrap();
-        println_channel_status(&mut channel_manager, &cli ).await;
+        println_channel_status(&mut channel_manager, &user_store, &cli ).await;
     }
     else if cli.matches.is_present(cli.arg_keys.init_sensor) {
-        init_sensor(&user_store, &cli, &options).await?;
+        init_sensor(&user_store, &init_store, &cli, &options).await?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.retire_channel) || cli.matches.is_present(cli.arg_keys.archive_channel) {
+        retire_or_archive_channel(&user_store, &cli)?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.delete_channel) {
+        delete_channel(&user_store, cli.matches.value_of(cli.arg_keys.delete_channel).unwrap(), get_db_file_name(&cli).as_str())?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.reinit_sensor) {
+        reinit_sensor(&user_store, &init_store, &cli, &options).await?;
     }
     else if cli.matches.is_present(cli.arg_keys.init_multiple_sensors) {
-        init_multiple_sensors(&mut user_store, &cli).await?;
+        init_multiple_sensors(&user_store, &init_store, &cli).await?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.provision_from) {
+        let _report = provision_sensors(&user_store, &init_store, &cli, get_provisioning_options(&cli)?).await?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.export_messages) {
+        export_messages(&user_store, &cli, &options).await?;
+    }
+    else if cli.matches.is_present(cli.arg_keys.run_admin_api_server) {
+        let mut remote_sensor_options = create_remote_sensor_options(&cli, None);
+        // The dev_eui is set for each initialization
+        remote_sensor_options.dev_eui = RemoteSensorOptions::default().dev_eui;
+        run_admin_api_server(
+            user_store,
+            init_store,
+            AdminApiOptions {
+                listener_ip_address_port: cli.matches.value_of(cli.arg_keys.run_admin_api_server).unwrap().to_string(),
+                mult_chan_mngr_opt: options.clone(),
+                remote_sensor_options,
+                handshake_opt: get_dev_eui_handshake_options(&cli)?,
+                db_file_name: get_db_file_name(&cli),
+                auth_enabled: !cli.matches.is_present(cli.arg_keys.disable_admin_api_auth),
+            }
+        ).await?;
     }
     else if cli.matches.is_present(cli.arg_keys.run_explorer_api_server) {
         let message_explorer_listener_address = cli.matches.value_of(cli.arg_keys.run_explorer_api_server).unwrap();
+        let poi_client_options = if cli.matches.is_present(cli.arg_keys.fetch_poi) {
+            Some(PoiClientOptions::new(cli.node))
+        } else {
+            None
+        };
+        let webhook_options = cli.matches.value_of(cli.arg_keys.webhook_url).map(|webhook_url| {
+            let mut webhook_options = WebhookOptions::new(webhook_url);
+            webhook_options.secret = cli.matches.value_of(cli.arg_keys.webhook_secret).map(|secret| secret.to_string());
+            webhook_options.include_decrypted_payload = cli.matches.is_present(cli.arg_keys.webhook_decrypted_payload);
+            webhook_options
+        });
+        let live_sync_interval_secs = match cli.matches.value_of(cli.arg_keys.live_sync_interval) {
+            Some(value) => u64::from_str(value)
+                .map_err(|e| anyhow!("Invalid value for --{}: '{}'. Error: {}", cli.arg_keys.live_sync_interval, value, e))?,
+            None => DEFAULT_LIVE_SYNC_INTERVAL_SECS,
+        };
+        let sync_channels_max_workers = match cli.matches.value_of(cli.arg_keys.sync_max_workers) {
+            Some(value) => usize::from_str(value)
+                .map_err(|e| anyhow!("Invalid value for --{}: '{}'. Error: {}", cli.arg_keys.sync_max_workers, value, e))?,
+            None => DEFAULT_SYNC_CHANNELS_MAX_WORKERS,
+        };
         run_explorer_api_server(
             user_store,
             ExplorerOptions {
                 iota_node: cli.node.to_string(),
                 wallet_filename: get_management_console_wallet_filename(&cli)?,
-                db_file_name: get_data_folder_file_path(&cli.data_dir, DB_FILE_PATH_AND_NAME),
+                wallet_type: get_wallet_type(&cli)?,
+                db_file_name: get_db_file_name(&cli),
                 listener_ip_address_port: message_explorer_listener_address.to_string(),
-                streams_user_serialization_password: SUSEE_CONST_SECRET_PASSWORD.to_string()
+                streams_user_serialization_password: options.streams_user_serialization_password.clone(),
+                poi_client_options,
+                webhook_options,
+                payload_decoders: PayloadDecoderRegistry::default(),
+                default_payload_decoder: cli.matches.value_of(cli.arg_keys.default_payload_decoder).map(|decoder| decoder.to_string()),
+                live_sync_interval_secs,
+                sync_channels_max_workers,
+                auth_enabled: !cli.matches.is_present(cli.arg_keys.disable_explorer_auth),
+                cors_allowed_origins: get_cors_allowed_origins(&cli),
+                message_retention: get_message_retention_policy(&cli)?,
             }
         ).await?;
     } else {
@@ -10,29 +10,26 @@ use streams_tools::{
         Worker,
         run_worker_in_own_thread,
     },
-    multi_channel_management::{
-        get_initial_channel_manager,
-        MultiChannelManagerOptions
-    },
+    multi_channel_management::MultiChannelManagerOptions,
     remote::remote_sensor::{
         RemoteSensor,
         RemoteSensorOptions
     },
+    dao::sensor_initialization::SensorInitializationDataStore,
     UserDataStore
 };
 
 use crate::{
     cli::ManagementConsoleCli,
-    create_channel,
     create_remote_sensor_options,
     get_multi_channel_manager_options,
-    make_remote_sensor_register_keyload_msg,
-    subscribe_remote_sensor_to_channel
+    run_sensor_initialization,
 };
 
-pub(crate) async fn init_sensor_in_own_thread<'a>(user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>, dev_eui: String) -> Result<()> {
+pub(crate) async fn init_sensor_in_own_thread<'a>(user_store: &UserDataStore, init_store: &SensorInitializationDataStore, cli: &ManagementConsoleCli<'a>, dev_eui: String) -> Result<()> {
     let init_sensor_opt = InitSensorOptions::new(
         user_store,
+        init_store,
         cli,
         dev_eui,
     )?;
@@ -47,17 +44,19 @@ pub(crate) async fn init_sensor_in_own_thread<'a>(user_store: &UserDataStore, cl
 #[derive(Clone)]
 struct InitSensorOptions {
     user_store: UserDataStore,
+    init_store: SensorInitializationDataStore,
     mult_chan_mngr_opt: MultiChannelManagerOptions,
     remote_sensor_options: RemoteSensorOptions,
     dev_eui: String,
 }
 
 impl InitSensorOptions {
-    pub fn new<'a>(user_store: &UserDataStore, cli: &ManagementConsoleCli<'a>, dev_eui: String) -> Result<InitSensorOptions> {
+    pub fn new<'a>(user_store: &UserDataStore, init_store: &SensorInitializationDataStore, cli: &ManagementConsoleCli<'a>, dev_eui: String) -> Result<InitSensorOptions> {
         let mult_chan_mngr_opt = get_multi_channel_manager_options(cli)?;
         let remote_sensor_options = create_remote_sensor_options(cli, Some(dev_eui.clone()));
         Ok(InitSensorOptions{
             user_store: user_store.clone(),
+            init_store: init_store.clone(),
             mult_chan_mngr_opt,
             remote_sensor_options,
             dev_eui,
@@ -75,20 +74,13 @@ impl Worker for InitSensor {
 
     async fn run(opt: InitSensorOptions) -> Result<()> {
         log::info!("DevEUI: {} - Starting initialization thread", opt.dev_eui);
-        let mut channel_manager  = get_initial_channel_manager(
+        let remote_sensor = RemoteSensor::new(Some(opt.remote_sensor_options));
+        run_sensor_initialization(
             &opt.user_store,
+            &opt.init_store,
             &opt.mult_chan_mngr_opt,
-            Some(opt.dev_eui.clone())
-        ).await?;
-
-        log::debug!("DevEUI: {} - channel_manager is ready - calling create_channel()", opt.dev_eui);
-        let announcement_link = create_channel(&mut channel_manager).await
-            .expect("Could not create_channel");
-        let remote_sensor = RemoteSensor::new(Some(opt.remote_sensor_options));
-        log::debug!("DevEUI: {} - remote_sensor is ready - calling subscribe_remote_sensor_to_channel()", opt.dev_eui);
-        let subscription = subscribe_remote_sensor_to_channel(&remote_sensor, announcement_link).await?;
-        log::debug!("DevEUI: {} - calling make_remote_sensor_register_keyload_msg()", opt.dev_eui);
-        let _keyload_registration = make_remote_sensor_register_keyload_msg(&mut channel_manager, &remote_sensor, subscription).await?;
-        Ok(())
+            &remote_sensor,
+            opt.dev_eui.as_str()
+        ).await
     }
 }
\ No newline at end of file
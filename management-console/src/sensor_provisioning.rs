@@ -0,0 +1,380 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    fs::{
+        read_to_string,
+        write,
+    },
+    thread::{
+        self,
+        JoinHandle,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use anyhow::{
+    Result,
+    Error as AnyError,
+    anyhow,
+    bail,
+};
+
+use streams_tools::{
+    threading_helpers::{
+        Worker,
+        run_background_worker_in_own_thread,
+    },
+    multi_channel_management::MultiChannelManagerOptions,
+    remote::remote_sensor::{
+        RemoteSensor,
+        RemoteSensorOptions
+    },
+    dao::sensor_initialization::SensorInitializationDataStore,
+    UserDataStore
+};
+
+use crate::{
+    cli::ManagementConsoleCli,
+    create_remote_sensor_options,
+    get_multi_channel_manager_options,
+    get_dev_eui_handshake_options,
+    perform_dev_eui_handshake,
+    run_sensor_initialization,
+};
+
+pub(crate) const DEFAULT_PROVISIONING_CONCURRENCY: usize = 4;
+pub(crate) const DEFAULT_PROVISIONING_TIMEOUT_SECS: u32 = 600;
+pub(crate) const DEFAULT_PROVISIONING_REPORT_FILE_NAME: &str = "provisioning-report.csv";
+
+static MANIFEST_HEADER_FIRST_COLUMN: &str = "dev_eui";
+
+// A sensor listed in the provisioning manifest.
+// The name and asset_id are stored in the User of the sensors channel. The external_id of the
+// User is not taken from the manifest, as it always is the DevEUI of the sensor (see
+// multi_channel_management::get_initial_channel_manager()).
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ManifestEntry {
+    pub dev_eui: String,
+    pub name: String,
+    pub asset_id: String,
+}
+
+// Reads the manifest CSV file having the columns 'dev_eui', 'name' and 'asset_id'.
+// The header line is optional. Empty lines and lines starting with '#' are ignored.
+// Field values must not contain commas. Surrounding double quotes are removed.
+pub(crate) fn read_manifest(manifest_file: &str) -> Result<Vec<ManifestEntry>> {
+    let content = read_to_string(manifest_file)
+        .map_err(|e| anyhow!("Could not read manifest file '{}'. Error: {}", manifest_file, e))?;
+    parse_manifest(content.as_str(), manifest_file)
+}
+
+fn parse_manifest(content: &str, manifest_file: &str) -> Result<Vec<ManifestEntry>> {
+    let mut ret_val = Vec::<ManifestEntry>::new();
+    let mut dev_euis = HashSet::<String>::new();
+    let mut is_first_line = true;
+    for (line_idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns: Vec<String> = line.split(',')
+            .map(|column| column.trim().trim_matches('"').trim().to_string())
+            .collect();
+        if is_first_line {
+            is_first_line = false;
+            if columns[0].eq_ignore_ascii_case(MANIFEST_HEADER_FIRST_COLUMN) {
+                continue;
+            }
+        }
+        if columns.len() > 3 || columns[0].is_empty() {
+            bail!("Invalid line {} in manifest file '{}': '{}'. Expected columns: dev_eui,name,asset_id",
+                line_idx + 1, manifest_file, line)
+        }
+        let dev_eui = columns[0].clone();
+        if !dev_euis.insert(dev_eui.clone()) {
+            bail!("DevEUI {} is listed more than once in manifest file '{}'", dev_eui, manifest_file)
+        }
+        let name = columns.get(1).cloned().unwrap_or_default();
+        let asset_id = columns.get(2).cloned().unwrap_or_default();
+        ret_val.push(ManifestEntry { dev_eui, name, asset_id });
+    }
+    if ret_val.is_empty() {
+        bail!("The manifest file '{}' does not contain any sensor", manifest_file)
+    }
+    Ok(ret_val)
+}
+
+// Stores the name and asset_id of the manifest entry in the User of the channel.
+// Is called by run_sensor_initialization() before the Done state is persisted, so that an
+// interrupted provisioning is resumed before the entry has been stored.
+pub(crate) fn store_manifest_entry(user_store: &UserDataStore, streams_channel_id: &str, entry: &ManifestEntry) -> Result<()> {
+    let mut user = user_store.get_item_read_only(&streams_channel_id.to_string())?;
+    user.name = entry.name.clone();
+    user.asset_id = entry.asset_id.clone();
+    user_store.write_item_to_db(&user)?;
+    log::info!("DevEUI: {} - Stored name '{}' and asset_id '{}' for channel {}",
+        entry.dev_eui, user.name, user.asset_id, user.streams_channel_id);
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ProvisioningStatus {
+    Pending,
+    AlreadyInitialized,
+    Done,
+    Failed(String),
+}
+
+impl fmt::Display for ProvisioningStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProvisioningStatus::Pending => write!(f, "Pending"),
+            ProvisioningStatus::AlreadyInitialized => write!(f, "AlreadyInitialized"),
+            ProvisioningStatus::Done => write!(f, "Done"),
+            ProvisioningStatus::Failed(_) => write!(f, "Failed"),
+        }
+    }
+}
+
+pub(crate) struct ProvisioningReport {
+    pub entries: Vec<(ManifestEntry, ProvisioningStatus)>,
+}
+
+impl ProvisioningReport {
+    fn new(manifest: Vec<ManifestEntry>) -> Self {
+        ProvisioningReport {
+            entries: manifest.into_iter()
+                .map(|entry| (entry, ProvisioningStatus::Pending))
+                .collect(),
+        }
+    }
+
+    fn set_status(&mut self, dev_eui: &str, status: ProvisioningStatus) {
+        if let Some(entry) = self.entries.iter_mut().find(|(entry, _)| entry.dev_eui == dev_eui) {
+            entry.1 = status;
+        }
+    }
+
+    fn get_entry(&self, dev_eui: &str) -> Option<&ManifestEntry> {
+        self.entries.iter()
+            .find(|(entry, _)| entry.dev_eui == dev_eui)
+            .map(|(entry, _)| entry)
+    }
+
+    fn count(&self, status_matches: fn(&ProvisioningStatus) -> bool) -> usize {
+        self.entries.iter().filter(|(_, status)| status_matches(status)).count()
+    }
+
+    fn write_to_file(&self, init_store: &SensorInitializationDataStore, report_file: &str) -> Result<()> {
+        let mut content = String::from("dev_eui,name,asset_id,status,streams_channel_id,error\n");
+        for (entry, status) in self.entries.iter() {
+            let streams_channel_id = init_store.get_item_read_only(&entry.dev_eui)
+                .map(|initialization| initialization.streams_channel_id)
+                .unwrap_or_default();
+            let error = match status {
+                ProvisioningStatus::Failed(err) => err.replace(',', ";").replace('\n', " "),
+                _ => String::new(),
+            };
+            content += format!("{},{},{},{},{},{}\n",
+                               entry.dev_eui, entry.name, entry.asset_id, status, streams_channel_id, error).as_str();
+        }
+        write(report_file, content)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProvisioningReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProvisioningReport:\n   sensors: {}\n   done: {}\n   already initialized: {}\n   failed: {}",
+               self.entries.len(),
+               self.count(|status| *status == ProvisioningStatus::Done),
+               self.count(|status| *status == ProvisioningStatus::AlreadyInitialized),
+               self.count(|status| matches!(status, ProvisioningStatus::Failed(_))),
+        )
+    }
+}
+
+pub(crate) struct ProvisioningOptions {
+    pub manifest_file: String,
+    pub report_file: String,
+    pub concurrency: usize,
+    pub timeout_secs: u32,
+}
+
+// Initializes the sensors listed in the manifest file.
+// Sensors are found using DevEUI-Handshakes like --init-multiple-sensors does, but only
+// sensors listed in the manifest are initialized. Sensors that do not respond within
+// timeout_secs are reported as failed. At most options.concurrency sensors are initialized
+// in parallel. Returns after all listed sensors have been initialized or have failed.
+pub(crate) async fn provision_sensors<'a>(
+    user_store: &UserDataStore,
+    init_store: &SensorInitializationDataStore,
+    cli: &ManagementConsoleCli<'a>,
+    options: ProvisioningOptions
+) -> Result<ProvisioningReport> {
+    let manifest = read_manifest(options.manifest_file.as_str())?;
+    log::info!("Provisioning {} sensors listed in manifest file '{}'", manifest.len(), options.manifest_file);
+    let mut report = ProvisioningReport::new(manifest.clone());
+    let mut pending = HashSet::<String>::new();
+    for entry in manifest.iter() {
+        match init_store.get_item_read_only(&entry.dev_eui) {
+            Ok(initialization) if initialization.is_done() => {
+                log::info!("DevEUI: {} - Sensor has already been initialized. Skipping it.", entry.dev_eui);
+                report.set_status(entry.dev_eui.as_str(), ProvisioningStatus::AlreadyInitialized);
+            },
+            _ => { pending.insert(entry.dev_eui.clone()); }
+        }
+    }
+
+    let mult_chan_mngr_opt = get_multi_channel_manager_options(cli)?;
+    let mut handshake_opt = get_dev_eui_handshake_options(cli)?;
+    handshake_opt.timeout_secs = options.timeout_secs;
+    let mut running = Vec::<(String, JoinHandle<Result<()>>)>::new();
+    loop {
+        let mut still_running = Vec::<(String, JoinHandle<Result<()>>)>::new();
+        for (dev_eui, handle) in running.into_iter() {
+            if handle.is_finished() {
+                let status = match handle.join() {
+                    Ok(Ok(())) => ProvisioningStatus::Done,
+                    Ok(Err(e)) => ProvisioningStatus::Failed(e.to_string()),
+                    Err(_) => ProvisioningStatus::Failed("The initialization thread panicked".to_string()),
+                };
+                log::info!("DevEUI: {} - Provisioning finished with status {}", dev_eui, status);
+                report.set_status(dev_eui.as_str(), status);
+            } else {
+                still_running.push((dev_eui, handle));
+            }
+        }
+        running = still_running;
+
+        if pending.is_empty() && running.is_empty() {
+            break;
+        }
+
+        if !pending.is_empty() && running.len() < options.concurrency {
+            let mut remote_sensor_options = create_remote_sensor_options(cli, None);
+            remote_sensor_options.confirm_fetch_timeout_sec = Some(options.timeout_secs);
+            let remote_sensor = RemoteSensor::new(Some(remote_sensor_options.clone()));
+            match perform_dev_eui_handshake(&remote_sensor, &handshake_opt).await {
+                Ok(dev_eui_handshake) => {
+                    let dev_eui = dev_eui_handshake.dev_eui;
+                    if pending.remove(&dev_eui) {
+                        remote_sensor_options.dev_eui = dev_eui.clone();
+                        let worker_opt = ProvisionSensorOptions {
+                            user_store: user_store.clone(),
+                            init_store: init_store.clone(),
+                            mult_chan_mngr_opt: mult_chan_mngr_opt.clone(),
+                            remote_sensor_options,
+                            entry: report.get_entry(dev_eui.as_str()).unwrap().clone(),
+                        };
+                        running.push((dev_eui, run_background_worker_in_own_thread::<ProvisionSensor>(worker_opt)));
+                    } else {
+                        log::warn!("DevEUI: {} - Sensor is not listed in the manifest or is already being provisioned. Ignoring it.", dev_eui);
+                    }
+                },
+                Err(e) => {
+                    log::error!("No further sensor responded to the DevEUI-Handshake. Error: {}", e);
+                    for dev_eui in pending.drain() {
+                        report.set_status(
+                            dev_eui.as_str(),
+                            ProvisioningStatus::Failed(format!("No DevEUI-Handshake received within {} secs", options.timeout_secs))
+                        );
+                    }
+                }
+            }
+        } else {
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    report.write_to_file(init_store, options.report_file.as_str())?;
+    log::info!("{}\n   Report has been written to '{}'", report, options.report_file);
+    Ok(report)
+}
+
+#[derive(Clone)]
+struct ProvisionSensorOptions {
+    user_store: UserDataStore,
+    init_store: SensorInitializationDataStore,
+    mult_chan_mngr_opt: MultiChannelManagerOptions,
+    remote_sensor_options: RemoteSensorOptions,
+    entry: ManifestEntry,
+}
+
+struct ProvisionSensor;
+
+#[async_trait(?Send)]
+impl Worker for ProvisionSensor {
+    type OptionsType = ProvisionSensorOptions;
+    type ResultType = ();
+    type ErrorType = AnyError;
+
+    async fn run(opt: ProvisionSensorOptions) -> Result<()> {
+        log::info!("DevEUI: {} - Starting provisioning thread", opt.entry.dev_eui);
+        let remote_sensor = RemoteSensor::new(Some(opt.remote_sensor_options));
+        run_sensor_initialization(
+            &opt.user_store,
+            &opt.init_store,
+            &opt.mult_chan_mngr_opt,
+            &remote_sensor,
+            opt.entry.dev_eui.as_str(),
+            Some(&opt.entry),
+        ).await
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package management-console sensor_provisioning::tests
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(dev_eui: &str, name: &str, asset_id: &str) -> ManifestEntry {
+        ManifestEntry {
+            dev_eui: dev_eui.to_string(),
+            name: name.to_string(),
+            asset_id: asset_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let content = "dev_eui,name,asset_id\n\
+            4711471147114711,Sensor Hall A,hall-a-01\n\
+            \n\
+            # Comment line\n\
+            4712471247124712, \"Sensor Hall B\" ,\n\
+            4713471347134713\n";
+        assert_eq!(parse_manifest(content, "sensors.csv").unwrap(), vec![
+            entry("4711471147114711", "Sensor Hall A", "hall-a-01"),
+            entry("4712471247124712", "Sensor Hall B", ""),
+            entry("4713471347134713", "", ""),
+        ]);
+        // The header line is optional
+        assert_eq!(parse_manifest("4711471147114711,Sensor,asset", "sensors.csv").unwrap(), vec![
+            entry("4711471147114711", "Sensor", "asset"),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_invalid_manifest() {
+        assert!(parse_manifest("dev_eui,name,asset_id\n", "sensors.csv").is_err());
+        assert!(parse_manifest("", "sensors.csv").is_err());
+        assert!(parse_manifest("4711471147114711,Sensor,asset,unexpected", "sensors.csv").is_err());
+        assert!(parse_manifest(",Sensor,asset", "sensors.csv").is_err());
+        assert!(parse_manifest("4711471147114711,Sensor A\n4711471147114711,Sensor B", "sensors.csv").is_err());
+    }
+
+    #[test]
+    fn test_read_manifest() {
+        let manifest_file = std::env::temp_dir().join("susee-test-provisioning-manifest.csv");
+        write(&manifest_file, "4711471147114711,Sensor,asset\n").unwrap();
+        let manifest = read_manifest(manifest_file.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&manifest_file).unwrap();
+        assert_eq!(manifest, vec![entry("4711471147114711", "Sensor", "asset")]);
+        assert!(read_manifest("not-existing-manifest.csv").is_err());
+    }
+}
@@ -186,7 +186,8 @@ impl<TSR, CmdFetchT, StreamsTransportT> SensorFunctions for CmdProcessor<CmdFetc
                         Subscription {
                             subscription_link: subscription_link.to_string(),
                             pup_key: get_public_key_str(user),
-                            initialization_cnt: subscriber_manager.get_initialization_cnt()
+                            initialization_cnt: subscriber_manager.get_initialization_cnt(),
+                            initialization_epoch: subscriber_manager.get_initialization_epoch(),
                         })?
                 );
             }
@@ -266,7 +267,8 @@ impl<TSR, CmdFetchT, StreamsTransportT> SensorFunctions for CmdProcessor<CmdFetc
         confirm_req_builder.subscription(
             sub_msg_link.to_string(),
             get_public_key_str(user),
-            subscriber_mngr.get_initialization_cnt()
+            subscriber_mngr.get_initialization_cnt(),
+            subscriber_mngr.get_initialization_epoch(),
         )
     }
 
@@ -298,9 +300,10 @@ impl<TSR, CmdFetchT, StreamsTransportT> SensorFunctions for CmdProcessor<CmdFetc
         todo!()
     }
 
-    async fn dev_eui_handshake(&self, confirm_req_builder: &RequestBuilderConfirm) -> hyper::http::Result<Request<Body>> {
+    async fn dev_eui_handshake(&self, pairing_code: String, confirm_req_builder: &RequestBuilderConfirm) -> hyper::http::Result<Request<Body>> {
         confirm_req_builder.dev_eui_handshake(
-            self.dev_eui.clone()
+            self.dev_eui.clone(),
+            pairing_code
         )
     }
 }
@@ -1,4 +1,5 @@
 use std::cell::Cell;
+use std::path::Path;
 use std::str::FromStr;
 
 use rand::Rng;
@@ -41,7 +42,9 @@ use streams_tools::{
 use susee_tools::{
     get_wallet_filename,
     SUSEE_CONST_COMMAND_CONFIRM_FETCH_WAIT_SEC,
-    SUSEE_CONST_SECRET_PASSWORD,
+    get_state_password,
+    get_state_password_from_env_or_keyfile,
+    get_data_folder_file_path,
 };
 
 use super::cli::{
@@ -65,6 +68,39 @@ use crate::std::client_data_persistence_std::ClientDataPersistenceStd;
 
 const MAX_SIZE_RANDOM_MESSAGE: usize = 4096;
 
+const CLIENT_STATE_FILE_NAME: &str = "client-state-sensor.bin";
+const DEFAULT_STATE_PASSWORD_FILE_NAME: &str = "state-password-sensor.key";
+
+// Password used to encrypt the Streams Client State by older versions of the sensor
+const LEGACY_STATE_PASSWORD: &str = "SUSEE";
+
+// The password used to encrypt the Streams Client State is taken from the environment variable
+// SUSEE_STATE_PASSWORD, the --state-password-file or the default keyfile in the data_dir.
+// If none of these exist but a Streams Client State has already been stored, the state has been
+// created by an older version of the sensor using the LEGACY_STATE_PASSWORD. In this case
+// the LEGACY_STATE_PASSWORD is used instead of creating a new random password which would not
+// be able to decrypt the existing state.
+fn get_sensor_state_password(cli: &SensorCli) -> Result<String> {
+    let default_keyfile = get_data_folder_file_path(&cli.data_dir, DEFAULT_STATE_PASSWORD_FILE_NAME);
+    if get_state_password_from_env_or_keyfile(&cli.matches, cli.arg_keys.base.state_password_file)?.is_none()
+        && !Path::new(default_keyfile.as_str()).exists()
+        && Path::new(CLIENT_STATE_FILE_NAME).exists()
+    {
+        log::warn!("[Sensor - fn get_sensor_state_password()] No state password has been configured but the Streams client state '{}' \
+                    already exists. Using the password of older sensor versions to decrypt it. \
+                    To switch to an installation specific password, delete '{}' and initialize the sensor again.",
+                   CLIENT_STATE_FILE_NAME, CLIENT_STATE_FILE_NAME);
+        return Ok(LEGACY_STATE_PASSWORD.to_string());
+    }
+
+    get_state_password(
+        &cli.matches,
+        cli.arg_keys.base.state_password_file,
+        &cli.data_dir,
+        DEFAULT_STATE_PASSWORD_FILE_NAME,
+    )
+}
+
 fn get_wallet(cli: &SensorCli) -> Result<PlainTextWallet> {
     let wallet_filename = get_wallet_filename(
         &cli.matches,
@@ -73,8 +109,10 @@ fn get_wallet(cli: &SensorCli) -> Result<PlainTextWallet> {
         "wallet-sensor.txt",
     )?;
 
+    let state_password = get_sensor_state_password(cli)?;
+
     Ok(PlainTextWallet::new(
-        SUSEE_CONST_SECRET_PASSWORD,
+        state_password.as_str(),
         Some(wallet_filename.as_str()),
         None,
     ))
@@ -121,7 +159,7 @@ pub async fn create_subscriber_manager<'a>(
     Ok(SubscriberManagerPlainTextWalletHttpClient::new(
         transport,
         wallet,
-        ClientDataPersistenceStd::new_prepared("client-state-sensor.bin"),
+        ClientDataPersistenceStd::new_prepared(CLIENT_STATE_FILE_NAME),
     )
     .await)
 }
@@ -191,6 +229,7 @@ pub async fn process_act_as_remote_control<'a>(cli: SensorCli<'a>) -> Result<()>
             http_url: iota_bridge_url.to_string(),
             confirm_fetch_wait_sec: 5,
             dev_eui: STREAMS_TOOLS_CONST_DEV_EUI_NOT_DEFINED.to_string(),
+            confirm_fetch_timeout_sec: None,
         });
     }
 
@@ -309,19 +348,21 @@ impl<'a> SensorFunctions for CmdProcessor<'a> {
         subscriber_mngr: &mut Self::SubscriberManager,
         confirm_req_builder: &RequestBuilderConfirm,
     ) -> hyper::http::Result<Request<Body>> {
-        let (sub_msg_link, public_key_str, initialization_cnt) =
+        let (sub_msg_link, public_key_str, initialization_cnt, initialization_epoch) =
             SensorManager::subscribe_to_channel(announcement_link_str, subscriber_mngr)
                 .await
                 .expect("Error on calling SensorManager::subscribe_to_channel");
-        confirm_req_builder.subscription(sub_msg_link, public_key_str, initialization_cnt)
+        confirm_req_builder.subscription(sub_msg_link, public_key_str, initialization_cnt, initialization_epoch)
     }
 
     async fn dev_eui_handshake(
         &self,
+        pairing_code: String,
         confirm_req_builder: &RequestBuilderConfirm,
     ) -> hyper::http::Result<Request<Body>> {
         confirm_req_builder.dev_eui_handshake(
-            self.dev_eui.clone()
+            self.dev_eui.clone(),
+            pairing_code
         )
     }
 
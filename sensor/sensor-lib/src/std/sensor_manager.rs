@@ -32,6 +32,7 @@ use streams_tools::{
     subscriber_manager::get_public_key_str,
     binary_persist::{
         Subscription,
+        InitializationEpoch,
     }
 };
 
@@ -118,6 +119,7 @@ impl SensorManager {
         comment: &str,
         key_name: &str,
         init_cnt: u8,
+        init_epoch: InitializationEpoch,
     ) -> Result<Subscription> {
         let public_key = get_public_key_str(subscriber);
         log::info!(
@@ -125,19 +127,22 @@ impl SensorManager {
              {} Link:     {}
                   Tangle Index:     {:#?}
              User public key: {}
-             Initialization count:  {}\n",
+             Initialization count:  {}
+             Initialization epoch:  {}\n",
             comment,
             key_name,
             subscription_link.to_string(),
             hex::encode(subscription_link.to_msg_index()),
             public_key,
             init_cnt,
+            init_epoch,
 
         );
         Ok(Subscription {
             subscription_link: subscription_link.to_string(),
             pup_key: public_key,
             initialization_cnt: init_cnt,
+            initialization_epoch: init_epoch,
         })
     }
 
@@ -152,7 +157,8 @@ impl SensorManager {
                         &subscription_link,
                         "A subscription with the following details has already been created",
                         "Subscription",
-                        subscriber_manager.get_initialization_cnt()
+                        subscriber_manager.get_initialization_cnt(),
+                        subscriber_manager.get_initialization_epoch(),
                     ).expect("Error on println_subscription_details")
                 );
             }
@@ -173,19 +179,21 @@ impl SensorManager {
         }
     }
 
-    pub async fn subscribe_to_channel(announcement_link_str: &str, subscriber_mngr: &mut SubscriberManagerPlainTextWalletHttpClient) -> Result<(String, String, u8)> {
+    pub async fn subscribe_to_channel(announcement_link_str: &str, subscriber_mngr: &mut SubscriberManagerPlainTextWalletHttpClient) -> Result<(String, String, u8, InitializationEpoch)> {
         let ann_address = Address::from_str(&announcement_link_str).map_err(|e|anyhow!(e))?;
         let sub_msg_link = subscriber_mngr.subscribe(ann_address).await?;
         let initialization_cnt = subscriber_mngr.get_initialization_cnt();
+        let initialization_epoch = subscriber_mngr.get_initialization_epoch();
         Self::println_subscription_details(
             &subscriber_mngr.user.as_ref().unwrap(),
             &sub_msg_link,
             "A subscription with the following details has been created",
             "Subscription",
             initialization_cnt,
+            initialization_epoch,
         )?;
         let public_key_str = get_public_key_str(&subscriber_mngr.user.as_ref().unwrap());
-        Ok((sub_msg_link.to_string(), public_key_str, initialization_cnt))
+        Ok((sub_msg_link.to_string(), public_key_str, initialization_cnt, initialization_epoch))
     }
 
     pub async fn register_keyload_msg(keyload_msg_link_str: &str, subscriber_mngr: &mut SubscriberManagerPlainTextWalletHttpClient) -> Result<()> {
@@ -198,6 +206,7 @@ impl SensorManager {
             "Messages will be send in the branch defined by the following keyload message",
             "Keyload  msg",
             subscriber_mngr.get_initialization_cnt(),
+            subscriber_mngr.get_initialization_epoch(),
         )?;
 
         Ok(())
@@ -257,6 +257,21 @@ pub fn deserialize_bool(fn_name: &str, prop_name: &str, buffer: &[u8], range: &m
     Ok(ret_val)
 }
 
+// Deserializes a u32 that has been appended to a binary format by a newer software version.
+// Returns None if the buffer ends at range.end, which is the case for data persisted by older
+// versions. Buffers having 1 to 3 trailing bytes are truncated or malformed and are rejected.
+pub fn deserialize_trailing_u32(prop_name: &str, buffer: &[u8], range: &mut Range<usize>) -> Result<Option<u32>> {
+    if buffer.len() <= range.end {
+        return Ok(None);
+    }
+    if buffer.len() < range.end + 4 {
+        bail!("Could not read {}: Expected 4 bytes but the buffer contains only {} trailing bytes",
+            prop_name, buffer.len() - range.end);
+    }
+    range.increment(4);
+    Ok(Some(u32::try_from_bytes(&buffer[range.clone()])?))
+}
+
 pub fn test_binary_persistance<T: BinaryPersist + PartialEq + Debug>( test_item: T) {
     let buf = test_item.as_vecu8().expect("Error on persisting test_item");
     let other_item = T::try_from_bytes(&buf)
@@ -185,29 +185,45 @@ impl BinaryPersist for StartSendingMessages {
     }
 }
 
+// pairing_code: Optional one-time code that has to be echoed back by the sensor in the
+//               DevEuiHandshake confirmation. An empty string means no pairing code is used.
 #[derive(Default)]
-pub struct DevEuiHandshakeCmd {}
+pub struct DevEuiHandshakeCmd {
+    pub pairing_code: String,
+}
 
 impl EnumeratedPersistableArgs<Command> for DevEuiHandshakeCmd {
     const INSTANCE: &'static Command = &Command::DEV_EUI_HANDSHAKE;
     const NEEDS_TO_WAIT_FOR_TANGLE_MILESTONE: bool = false;
 
-    fn set_str_arg(&mut self, _str_arg: String) {}
+    fn set_str_arg(&mut self, str_arg: String) {
+        self.pairing_code = str_arg;
+    }
 }
 
 impl BinaryPersist for DevEuiHandshakeCmd {
-    fn needed_size(&self) -> usize { Self::INSTANCE.needed_size() }
+    fn needed_size(&self) -> usize {
+        Command::LENGTH_BYTES + calc_string_binary_length(&self.pairing_code)
+    }
 
     fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize> {
-        Self::INSTANCE.to_bytes(buffer)
+        let mut range: Range<usize> = RangeIterator::new(0);
+        serialize_binary_persistable_and_one_string(Self::INSTANCE.clone(), &self.pairing_code, buffer, &mut range)?;
+        Ok(range.end)
     }
 
     fn try_from_bytes(buffer: &[u8]) -> Result<Self> where Self: Sized {
-        let enumerated_persistable = EnumeratedPersistableInner::try_from_bytes::<Command>(buffer)?;
-        if enumerated_persistable != *Self::INSTANCE {
-            bail!("Expected command of type DevEuiHandshakeCmd but deserialized command of type {}.", enumerated_persistable)
+        // Commands created by older iota-bridge versions consist of the command type only
+        if buffer.len() <= Command::LENGTH_BYTES {
+            let enumerated_persistable = EnumeratedPersistableInner::try_from_bytes::<Command>(buffer)?;
+            if enumerated_persistable != *Self::INSTANCE {
+                bail!("Expected command of type DevEuiHandshakeCmd but deserialized command of type {}.", enumerated_persistable)
+            }
+            return Ok(DevEuiHandshakeCmd::default());
         }
-        Ok(DevEuiHandshakeCmd {})
+        let mut range: Range<usize> = RangeIterator::new(0);
+        let ret_val = deserialize_enumerated_persistable_arg_with_one_string::<DevEuiHandshakeCmd, Command>(buffer, &mut range)?;
+        Ok(ret_val)
     }
 }
 
@@ -8,6 +8,7 @@ use std::{
 
 use anyhow::{
     Result,
+    bail,
 };
 
 use crate::binary_persist::{
@@ -20,7 +21,9 @@ use crate::binary_persist::{
     calc_string_binary_length,
     deserialize_enumerated_persistable_arg_with_one_string,
     serialize_string,
-    deserialize_string
+    deserialize_string,
+    deserialize_trailing_u32,
+    InitializationEpoch,
 };
 use crate::streams_transport::streams_transport::STREAMS_TOOLS_CONST_DEV_EUI_NOT_DEFINED;
 
@@ -96,6 +99,7 @@ pub struct Subscription {
     pub subscription_link: String,
     pub pup_key: String,
     pub initialization_cnt: u8,
+    pub initialization_epoch: InitializationEpoch,
 }
 
 impl Default for Subscription {
@@ -104,6 +108,7 @@ impl Default for Subscription {
             subscription_link: String::from("None"),
             pup_key: String::from("None"),
             initialization_cnt: 0,
+            initialization_epoch: 0,
         }
     }
 }
@@ -123,6 +128,7 @@ impl BinaryPersist for Subscription {
         ret_val += calc_string_binary_length(&self.subscription_link); // SUBSCRIPTION_LINK
         ret_val += calc_string_binary_length(&self.pup_key);           // PUP_KEY
         ret_val += 1;                                                        // INITIALIZATION_CNT
+        ret_val += 4;                                                        // INITIALIZATION_EPOCH
         ret_val
     }
 
@@ -135,6 +141,9 @@ impl BinaryPersist for Subscription {
         // INITIALIZATION_CNT
         range.increment(1);
         self.initialization_cnt.to_bytes(&mut buffer[range.clone()]).expect("Error on persisting initialization_cnt");
+        // INITIALIZATION_EPOCH
+        range.increment(4);
+        self.initialization_epoch.to_bytes(&mut buffer[range.clone()]).expect("Error on persisting initialization_epoch");
         Ok(range.end)
     }
 
@@ -146,7 +155,14 @@ impl BinaryPersist for Subscription {
         ret_val.pup_key = deserialize_string(buffer, & mut range)?;
         // INITIALIZATION_CNT
         range.increment(1);
-        ret_val.initialization_cnt = u8::try_from_bytes(&buffer[range]).expect("Error on reading initialization_cnt");
+        if buffer.len() < range.end {
+            bail!("Could not read initialization_cnt: The Subscription confirmation is truncated");
+        }
+        ret_val.initialization_cnt = u8::try_from_bytes(&buffer[range.clone()])?;
+        // INITIALIZATION_EPOCH - Is missing in confirmations of older sensor versions
+        if let Some(initialization_epoch) = deserialize_trailing_u32("initialization_epoch", buffer, &mut range)? {
+            ret_val.initialization_epoch = initialization_epoch;
+        }
 
         Ok(ret_val)
     }
@@ -154,10 +170,11 @@ impl BinaryPersist for Subscription {
 
 impl fmt::Display for Subscription {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Subscription:\n subscription_link: {}\n pup_key: {}\n initialization_cnt: {}",
+        write!(f, "Subscription:\n subscription_link: {}\n pup_key: {}\n initialization_cnt: {}\n initialization_epoch: {}",
                self.subscription_link,
                self.pup_key,
                self.initialization_cnt,
+               self.initialization_epoch,
         )
     }
 }
@@ -286,14 +303,18 @@ confirmation_without_args!(Confirmation::KEYLOAD_REGISTRATION, KeyloadRegistrati
 pub struct ClearClientState {}
 confirmation_without_args!(Confirmation::CLEAR_CLIENT_STATE, ClearClientState);
 
+// pairing_code: The pairing code of the DevEuiHandshakeCmd echoed back by the sensor.
+//               Empty if the DevEuiHandshakeCmd did not contain a pairing code.
 pub struct DevEuiHandshake {
     pub dev_eui: String,
+    pub pairing_code: String,
 }
 
 impl Default for DevEuiHandshake {
     fn default() -> Self {
         DevEuiHandshake {
             dev_eui: STREAMS_TOOLS_CONST_DEV_EUI_NOT_DEFINED.to_string(),
+            pairing_code: String::new(),
         }
     }
 }
@@ -309,26 +330,81 @@ impl EnumeratedPersistableArgs<Confirmation> for DevEuiHandshake {
 
 impl BinaryPersist for DevEuiHandshake {
     fn needed_size(&self) -> usize {
-        Confirmation::LENGTH_BYTES + calc_string_binary_length(&self.dev_eui)
+        let mut ret_val = Confirmation::LENGTH_BYTES;                   // CONFIRMATION_TYPE
+        ret_val += calc_string_binary_length(&self.dev_eui);           // DEV_EUI
+        ret_val += calc_string_binary_length(&self.pairing_code);      // PAIRING_CODE
+        ret_val
     }
 
     fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize> {
         let mut range: Range<usize> = RangeIterator::new(0);
+        // CONFIRMATION_TYPE + DEV_EUI
         serialize_binary_persistable_and_one_string(Self::INSTANCE.clone(), &self.dev_eui, buffer, &mut range)?;
+        // PAIRING_CODE
+        serialize_string(&self.pairing_code, buffer, &mut range)?;
         Ok(range.end)
     }
 
     fn try_from_bytes(buffer: &[u8]) -> Result<Self> where Self: Sized {
         let mut range: Range<usize> = RangeIterator::new(0);
-        let ret_val = deserialize_enumerated_persistable_arg_with_one_string::<DevEuiHandshake, Confirmation>(buffer, &mut range)?;
+        // CONFIRMATION_TYPE + DEV_EUI
+        let mut ret_val = deserialize_enumerated_persistable_arg_with_one_string::<DevEuiHandshake, Confirmation>(buffer, &mut range)?;
+        // PAIRING_CODE - Is missing in confirmations of older sensor versions
+        if range.end < buffer.len() {
+            ret_val.pairing_code = deserialize_string(buffer, &mut range)?;
+        }
         Ok(ret_val)
     }
 }
 
+// The pairing_code is a secret and therefore only reported as present or missing
 impl fmt::Display for DevEuiHandshake {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "DevEuiHandshake: dev_eui: {}",
+        write!(f, "DevEuiHandshake: dev_eui: {}, pairing_code: {}",
                self.dev_eui,
+               if self.pairing_code.is_empty() { "missing" } else { "present" },
         )
     }
 }
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib binary_persist::binary_persist_confirmation::tests
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_with_truncated_initialization_epoch() {
+        let subscription = Subscription {
+            subscription_link: "subscription-link".to_string(),
+            pup_key: "pup-key".to_string(),
+            initialization_cnt: 3,
+            initialization_epoch: 4711,
+        };
+        let buffer = subscription.as_vecu8().unwrap();
+        let read = Subscription::try_from_bytes(buffer.as_slice()).unwrap();
+        assert_eq!(read.initialization_cnt, 3);
+        assert_eq!(read.initialization_epoch, 4711);
+
+        // Confirmations of older sensor versions don't contain the initialization_epoch
+        let legacy = Subscription::try_from_bytes(&buffer[..buffer.len() - 4]).unwrap();
+        assert_eq!(legacy.initialization_cnt, 3);
+        assert_eq!(legacy.initialization_epoch, 0);
+
+        for trailing_bytes in 1..4 {
+            assert!(Subscription::try_from_bytes(&buffer[..buffer.len() - 4 + trailing_bytes]).is_err());
+        }
+        // initialization_cnt is missing
+        assert!(Subscription::try_from_bytes(&buffer[..buffer.len() - 5]).is_err());
+    }
+
+    #[test]
+    fn test_dev_eui_handshake_display_hides_pairing_code() {
+        let handshake = DevEuiHandshake {
+            dev_eui: "1234".to_string(),
+            pairing_code: "secret-code".to_string(),
+        };
+        assert_eq!(handshake.to_string(), "DevEuiHandshake: dev_eui: 1234, pairing_code: present");
+    }
+}
@@ -223,6 +223,55 @@ pub struct TangleAddressCompressed {
 const INITIALIZATION_CNT_SIZE: usize = 1;
 pub const INITIALIZATION_CNT_MAX_VALUE: u8 = u8::MAX;
 
+// The initialization_cnt is transferred as a single byte to keep compressed messages small.
+// Once INITIALIZATION_CNT_MAX_VALUE has been reached, the next re-initialization wraps the
+// initialization_cnt around to 0 and increments the initialization_epoch. The initialization_epoch
+// is never transferred in compressed messages. It is stored in the sensor wallet and in the
+// iota-bridge LoraWanNode database so that the full initialization count
+// (see get_full_initialization_cnt()) of a sensor stays unambiguous.
+pub type InitializationEpoch = u32;
+
+// Received initialization_cnt values are compared using serial number arithmetic (RFC 1982):
+// A value is newer than a reference value if it is less than INITIALIZATION_CNT_SERIAL_WINDOW
+// increments ahead of the reference value (modulo 256). This way an older initialization_cnt
+// stays unambiguous as long as a sensor is not re-initialized more than 127 times while
+// the iota-bridge does not receive any of its messages.
+pub const INITIALIZATION_CNT_SERIAL_WINDOW: u8 = 128;
+
+pub fn get_full_initialization_cnt(initialization_cnt: u8, initialization_epoch: InitializationEpoch) -> u64 {
+    (initialization_epoch as u64) * (INITIALIZATION_CNT_MAX_VALUE as u64 + 1) + initialization_cnt as u64
+}
+
+// Returns the initialization_cnt and initialization_epoch following the given ones
+pub fn get_next_initialization_cnt(initialization_cnt: u8, initialization_epoch: InitializationEpoch) -> (u8, InitializationEpoch) {
+    if initialization_cnt == INITIALIZATION_CNT_MAX_VALUE {
+        (0, initialization_epoch.wrapping_add(1))
+    } else {
+        (initialization_cnt + 1, initialization_epoch)
+    }
+}
+
+pub fn is_newer_initialization_cnt(initialization_cnt: u8, reference_cnt: u8) -> bool {
+    let distance = initialization_cnt.wrapping_sub(reference_cnt);
+    distance != 0 && distance < INITIALIZATION_CNT_SERIAL_WINDOW
+}
+
+// Returns the initialization_epoch of a received initialization_cnt using a known
+// initialization_cnt and initialization_epoch of the same sensor as reference.
+pub fn resolve_initialization_epoch(initialization_cnt: u8, reference_cnt: u8, reference_epoch: InitializationEpoch) -> InitializationEpoch {
+    if is_newer_initialization_cnt(initialization_cnt, reference_cnt) {
+        if initialization_cnt < reference_cnt {
+            reference_epoch.wrapping_add(1)
+        } else {
+            reference_epoch
+        }
+    } else if initialization_cnt > reference_cnt {
+        reference_epoch.saturating_sub(1)
+    } else {
+        reference_epoch
+    }
+}
+
 // Length of a string representation of a TangleAddressCompressed
 // MSGID_SIZE * 2                   -> Hex representation of the MsgId
 // + 1                              -> ':'
@@ -544,6 +593,26 @@ mod tests {
         test_binary_persistance(get_compressed_address());
     }
 
+    #[test]
+    fn test_initialization_cnt_wrap_around() {
+        assert_eq!(get_next_initialization_cnt(3, 0), (4, 0));
+        assert_eq!(get_next_initialization_cnt(INITIALIZATION_CNT_MAX_VALUE, 0), (0, 1));
+        assert_eq!(get_full_initialization_cnt(0, 1), 256);
+        assert_eq!(get_full_initialization_cnt(2, 3), 770);
+
+        assert!(is_newer_initialization_cnt(1, 0));
+        assert!(is_newer_initialization_cnt(2, 250));
+        assert!(!is_newer_initialization_cnt(250, 2));
+        assert!(!is_newer_initialization_cnt(7, 7));
+
+        assert_eq!(resolve_initialization_epoch(7, 7, 2), 2);
+        assert_eq!(resolve_initialization_epoch(9, 7, 2), 2);
+        assert_eq!(resolve_initialization_epoch(2, 250, 2), 3);
+        assert_eq!(resolve_initialization_epoch(250, 2, 3), 2);
+        assert_eq!(resolve_initialization_epoch(5, 7, 2), 2);
+        assert_eq!(resolve_initialization_epoch(250, 2, 0), 0);
+    }
+
     #[test]
     fn test_streams_api_function() {
         let api_function = StreamsApiFunction::SendCompressedMessage;
@@ -0,0 +1,512 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        OnceLock,
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        mpsc::{
+            channel,
+            Sender,
+            Receiver,
+        },
+    },
+    thread,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde::{
+    Serialize,
+    de::DeserializeOwned,
+};
+
+use serde_json::{
+    Value as JsonValue,
+    Map as JsonMap,
+};
+
+use postgres::{
+    Client,
+    NoTls,
+    Row,
+    types::{
+        ToSql,
+        Type,
+    },
+};
+
+use r2d2::{
+    ManageConnection,
+    Pool,
+    PooledConnection,
+};
+
+use super::{
+    StorageBackend,
+    ColumnType,
+};
+
+type JsonRow = JsonMap<String, JsonValue>;
+
+// Values of serialized items that can be passed as parameters to the postgres client.
+// NULL values are never passed as parameter. Instead a NULL literal is used
+// in the INSERT statement.
+enum PostgresValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    Bytea(Vec<u8>),
+}
+
+impl PostgresValue {
+    // Vec<u8> fields are serialized by serde_json as array of numbers. These arrays are
+    // converted into BYTEA values. Other arrays or objects are not supported.
+    fn from_json(column_name: &str, value: JsonValue) -> Result<Option<Self>> {
+        let ret_val = match value {
+            JsonValue::Null => None,
+            JsonValue::Bool(val) => Some(PostgresValue::Bool(val)),
+            JsonValue::Number(num) => {
+                if let Some(val) = num.as_i64() {
+                    Some(PostgresValue::Integer(val))
+                } else if let Some(val) = num.as_f64() {
+                    Some(PostgresValue::Float(val))
+                } else {
+                    bail!("Number value of column '{}' can not be stored as BIGINT or DOUBLE PRECISION", column_name)
+                }
+            },
+            JsonValue::String(val) => Some(PostgresValue::Text(val)),
+            JsonValue::Array(values) => {
+                let mut bytes = Vec::with_capacity(values.len());
+                for val in values {
+                    match val.as_u64() {
+                        Some(byte) if byte <= u8::MAX as u64 => bytes.push(byte as u8),
+                        _ => bail!("Array value of column '{}' is not a byte array", column_name),
+                    }
+                }
+                Some(PostgresValue::Bytea(bytes))
+            },
+            JsonValue::Object(_) => bail!("Object value of column '{}' is not supported", column_name),
+        };
+        Ok(ret_val)
+    }
+
+    fn as_to_sql(&self) -> &(dyn ToSql + Sync) {
+        match self {
+            PostgresValue::Bool(val) => val,
+            PostgresValue::Integer(val) => val,
+            PostgresValue::Float(val) => val,
+            PostgresValue::Text(val) => val,
+            PostgresValue::Bytea(val) => val,
+        }
+    }
+}
+
+fn row_to_json(row: &Row) -> Result<JsonRow> {
+    let mut ret_val = JsonRow::new();
+    for (idx, column) in row.columns().iter().enumerate() {
+        let column_type = column.type_();
+        let value: JsonValue = if *column_type == Type::BOOL {
+            row.try_get::<_, Option<bool>>(idx)?.into()
+        } else if *column_type == Type::INT2 {
+            row.try_get::<_, Option<i16>>(idx)?.into()
+        } else if *column_type == Type::INT4 {
+            row.try_get::<_, Option<i32>>(idx)?.into()
+        } else if *column_type == Type::INT8 {
+            row.try_get::<_, Option<i64>>(idx)?.into()
+        } else if *column_type == Type::FLOAT8 {
+            row.try_get::<_, Option<f64>>(idx)?.into()
+        } else if *column_type == Type::TEXT || *column_type == Type::VARCHAR || *column_type == Type::BPCHAR || *column_type == Type::NAME {
+            row.try_get::<_, Option<String>>(idx)?.into()
+        } else if *column_type == Type::BYTEA {
+            row.try_get::<_, Option<Vec<u8>>>(idx)?.into()
+        } else {
+            bail!("Column '{}' has the unsupported postgres type '{}'", column.name(), column_type)
+        };
+        ret_val.insert(column.name().to_string(), value);
+    }
+    Ok(ret_val)
+}
+
+enum PostgresCommand {
+    Execute {
+        statement: String,
+        params: Vec<PostgresValue>,
+        reply: Sender<Result<u64>>,
+    },
+    Query {
+        statement: String,
+        params: Vec<PostgresValue>,
+        reply: Sender<Result<Vec<JsonRow>>>,
+    },
+}
+
+// The synchronous postgres::Client runs its own tokio runtime and therefore can not be used
+// from within the async code of the iota-bridge or management-console. Instead every client is
+// owned by a dedicated thread that processes the PostgresCommands send via its
+// PostgresConnection. The thread ends when the PostgresConnection is dropped.
+fn run_postgres_client_thread(mut client: Client, receiver: Receiver<PostgresCommand>, is_broken: Arc<AtomicBool>) {
+    while let Ok(command) = receiver.recv() {
+        match command {
+            PostgresCommand::Execute { statement, params, reply } => {
+                let params_ref: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_to_sql()).collect();
+                let result = client.execute(statement.as_str(), params_ref.as_slice())
+                    .map_err(|e| anyhow!("Error on executing statement '{}'. Error: {}", statement, e));
+                let _ = reply.send(result);
+            },
+            PostgresCommand::Query { statement, params, reply } => {
+                let params_ref: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_to_sql()).collect();
+                let result = client.query(statement.as_str(), params_ref.as_slice())
+                    .map_err(|e| anyhow!("Error on querying statement '{}'. Error: {}", statement, e))
+                    .and_then(|rows| rows.iter().map(|row| row_to_json(row)).collect());
+                let _ = reply.send(result);
+            },
+        }
+        if client.is_closed() {
+            log::warn!("[fn run_postgres_client_thread()] The postgres connection has been closed by the server");
+            is_broken.store(true, Ordering::SeqCst);
+            return;
+        }
+    }
+    log::debug!("[fn run_postgres_client_thread()] The PostgresConnection has been dropped. Closing postgres connection.");
+}
+
+// A postgres connection managed by the connection pool of the PostgresBackend.
+// The postgres::Client is owned by a client thread (see run_postgres_client_thread()).
+pub struct PostgresConnection {
+    sender: Sender<PostgresCommand>,
+    is_broken: Arc<AtomicBool>,
+}
+
+impl PostgresConnection {
+    fn connect(url: &str) -> Result<Self> {
+        let (sender, receiver) = channel::<PostgresCommand>();
+        let (ready_sender, ready_receiver) = channel::<Result<()>>();
+        let is_broken = Arc::new(AtomicBool::new(false));
+        let thread_is_broken = is_broken.clone();
+        let url = url.to_string();
+        thread::spawn(move || {
+            match Client::connect(url.as_str(), NoTls) {
+                Ok(client) => {
+                    let _ = ready_sender.send(Ok(()));
+                    run_postgres_client_thread(client, receiver, thread_is_broken);
+                },
+                Err(e) => {
+                    let _ = ready_sender.send(Err(anyhow!("Error on connecting to PostgreSQL database. Error: {}", e)));
+                }
+            }
+        });
+        ready_receiver.recv()??;
+        Ok(PostgresConnection { sender, is_broken })
+    }
+
+    fn send_command(&self, command: PostgresCommand) -> Result<()> {
+        self.sender.send(command)
+            .map_err(|_| {
+                self.is_broken.store(true, Ordering::SeqCst);
+                anyhow!("The postgres client thread has been terminated")
+            })
+    }
+
+    fn execute(&self, statement: String, params: Vec<PostgresValue>) -> Result<u64> {
+        let (reply, reply_receiver) = channel();
+        self.send_command(PostgresCommand::Execute { statement, params, reply })?;
+        reply_receiver.recv()?
+    }
+
+    fn query(&self, statement: String, params: Vec<PostgresValue>) -> Result<Vec<JsonRow>> {
+        let (reply, reply_receiver) = channel();
+        self.send_command(PostgresCommand::Query { statement, params, reply })?;
+        reply_receiver.recv()?
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresPoolError(String);
+
+impl fmt::Display for PostgresPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for PostgresPoolError {}
+
+// r2d2::ManageConnection implementation for PostgresConnections
+pub struct PostgresConnectionManager {
+    url: String,
+}
+
+impl ManageConnection for PostgresConnectionManager {
+    type Connection = PostgresConnection;
+    type Error = PostgresPoolError;
+
+    fn connect(&self) -> Result<PostgresConnection, PostgresPoolError> {
+        PostgresConnection::connect(self.url.as_str())
+            .map_err(|e| PostgresPoolError(e.to_string()))
+    }
+
+    fn is_valid(&self, conn: &mut PostgresConnection) -> Result<(), PostgresPoolError> {
+        conn.query("SELECT 1".to_string(), Vec::new())
+            .map(|_| ())
+            .map_err(|e| PostgresPoolError(e.to_string()))
+    }
+
+    fn has_broken(&self, conn: &mut PostgresConnection) -> bool {
+        conn.is_broken.load(Ordering::SeqCst)
+    }
+}
+
+type PostgresPool = Pool<PostgresConnectionManager>;
+type PooledPostgresConnection = PooledConnection<PostgresConnectionManager>;
+
+// Maximum number of connections of the connection pool used for one database url
+const POSTGRES_POOL_MAX_SIZE: u32 = 10;
+
+// PostgreSQL implementation of the StorageBackend.
+// All PostgresBackend instances using the same database url share one connection pool.
+// Every statement is executed using a connection taken from the pool for this statement,
+// except while a transaction is running (see begin_transaction()).
+// Clones share the transaction of the cloned instance.
+#[derive(Clone)]
+pub struct PostgresBackend {
+    pool: PostgresPool,
+    transaction_connection: Arc<Mutex<Option<PooledPostgresConnection>>>,
+}
+
+// A transaction on a PostgresBackend, created by PostgresBackend::begin_transaction().
+// Dropping an uncommitted transaction rolls it back.
+pub struct PostgresTransaction {
+    backend: PostgresBackend,
+    committed: bool,
+}
+
+impl PostgresTransaction {
+    pub fn commit(mut self) -> Result<()> {
+        self.committed = true;
+        self.backend.execute("COMMIT")?;
+        Ok(())
+    }
+}
+
+impl Drop for PostgresTransaction {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Err(e) = self.backend.execute("ROLLBACK") {
+                log::error!("[PostgresTransaction.drop()] Error on rolling back the transaction: {}", e);
+                // The connection may still be in the aborted transaction and must not be reused
+                if let Ok(transaction_connection) = self.backend.transaction_connection.lock() {
+                    if let Some(connection) = transaction_connection.as_ref() {
+                        connection.is_broken.store(true, Ordering::SeqCst);
+                    }
+                }
+            }
+        }
+        // Returns the connection to the pool
+        match self.backend.transaction_connection.lock() {
+            Ok(mut transaction_connection) => *transaction_connection = None,
+            Err(poisoned) => *poisoned.into_inner() = None,
+        }
+    }
+}
+
+impl PostgresBackend {
+    pub fn connect(url: &str) -> Result<Self> {
+        let pool = Pool::builder()
+            .max_size(POSTGRES_POOL_MAX_SIZE)
+            .min_idle(Some(1))
+            // Broken connections are detected by has_broken(), so that statements don't need an
+            // additional round trip to validate the connection
+            .test_on_check_out(false)
+            .build(PostgresConnectionManager { url: url.to_string() })
+            .map_err(|e| anyhow!("Error on connecting to PostgreSQL database. Error: {}", e))?;
+        Ok(Self::new_with_pool(pool))
+    }
+
+    fn new_with_pool(pool: PostgresPool) -> Self {
+        PostgresBackend {
+            pool,
+            transaction_connection: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // DaoManagers open new connections very frequently (e.g. when they are cloned).
+    // To avoid a connection pool per DaoManager instance, one pool per url is shared
+    // by all PostgresBackend instances of the process.
+    pub fn get_shared_backend(url: &str) -> Result<Self> {
+        static SHARED_POOLS: OnceLock<Mutex<HashMap<String, PostgresPool>>> = OnceLock::new();
+        let mut pools = SHARED_POOLS.get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .map_err(|_| anyhow!("Mutex of shared PostgreSQL connection pools is poisoned"))?;
+        if let Some(pool) = pools.get(url) {
+            return Ok(Self::new_with_pool(pool.clone()));
+        }
+        let backend = Self::connect(url)?;
+        pools.insert(url.to_string(), backend.pool.clone());
+        Ok(backend)
+    }
+
+    // Begins a transaction using a connection that is taken from the pool for the whole
+    // transaction. All statements of this PostgresBackend are executed using this
+    // connection until the transaction is committed or dropped. Statements of other
+    // PostgresBackend instances use other connections and are not blocked.
+    // Nested transactions are not supported.
+    pub fn begin_transaction(&self) -> Result<PostgresTransaction> {
+        {
+            let mut transaction_connection = self.lock_transaction_connection()?;
+            if transaction_connection.is_some() {
+                bail!("The PostgresBackend already runs a transaction. Nested transactions are not supported");
+            }
+            *transaction_connection = Some(self.get_pooled_connection()?);
+        }
+        // If BEGIN fails, dropping the transaction returns the connection to the pool
+        let transaction = PostgresTransaction {
+            backend: self.clone(),
+            committed: false,
+        };
+        transaction.backend.execute("BEGIN")?;
+        Ok(transaction)
+    }
+
+    fn lock_transaction_connection(&self) -> Result<MutexGuard<Option<PooledPostgresConnection>>> {
+        self.transaction_connection.lock()
+            .map_err(|_| anyhow!("Mutex of the PostgreSQL transaction connection is poisoned"))
+    }
+
+    fn get_pooled_connection(&self) -> Result<PooledPostgresConnection> {
+        self.pool.get()
+            .map_err(|e| anyhow!("Could not get a connection from the PostgreSQL connection pool. Error: {}", e))
+    }
+
+    fn with_connection<T, F: FnOnce(&PostgresConnection) -> Result<T>>(&self, f: F) -> Result<T> {
+        let transaction_connection = self.lock_transaction_connection()?;
+        if let Some(connection) = transaction_connection.as_ref() {
+            return f(connection);
+        }
+        drop(transaction_connection);
+        let connection = self.get_pooled_connection()?;
+        f(&connection)
+    }
+
+    fn send_execute(&self, statement: String, params: Vec<PostgresValue>) -> Result<u64> {
+        self.with_connection(|connection| connection.execute(statement, params))
+    }
+
+    fn send_query(&self, statement: String, params: Vec<PostgresValue>) -> Result<Vec<JsonRow>> {
+        self.with_connection(|connection| connection.query(statement, params))
+    }
+
+    fn get_upsert_statement_and_params<ItemT: Serialize>(&self, table_name: &str, primary_key_column: &str, item: &ItemT) -> Result<(String, Vec<PostgresValue>)> {
+        let json_item = match serde_json::to_value(item)? {
+            JsonValue::Object(map) => map,
+            _ => bail!("Items of table '{}' need to be serialized as JSON object", table_name),
+        };
+        let mut column_names = Vec::<String>::new();
+        let mut values = Vec::<String>::new();
+        let mut params = Vec::<PostgresValue>::new();
+        for (column_name, value) in json_item {
+            match PostgresValue::from_json(column_name.as_str(), value)? {
+                Some(param) => {
+                    params.push(param);
+                    values.push(format!("${}", params.len()));
+                },
+                // A NULL primary key is generated by the database (see insert_item_returning_key())
+                None if column_name == primary_key_column => continue,
+                // Other NULL values are written so that optional fields can be cleared
+                None => values.push("NULL".to_string()),
+            }
+            column_names.push(column_name);
+        }
+        let updates: Vec<String> = column_names.iter()
+            .filter(|name| name.as_str() != primary_key_column)
+            .map(|name| format!("{0} = EXCLUDED.{0}", name))
+            .collect();
+        let on_conflict = if updates.is_empty() {
+            "DO NOTHING".to_string()
+        } else {
+            format!("DO UPDATE SET {}", updates.join(", "))
+        };
+        let statement = format!("INSERT INTO \"{}\" ({}) VALUES ({}) ON CONFLICT ({}) {}",
+            table_name,
+            column_names.join(", "),
+            values.join(", "),
+            primary_key_column,
+            on_conflict,
+        );
+        Ok((statement, params))
+    }
+}
+
+impl StorageBackend for PostgresBackend {
+    fn table_exists(&self, table_name: &str) -> Result<bool> {
+        let rows = self.send_query(
+            "SELECT table_name FROM information_schema.tables WHERE table_schema = current_schema() AND table_name = $1".to_string(),
+            vec![PostgresValue::Text(table_name.to_string())]
+        )?;
+        Ok(rows.len() > 0)
+    }
+
+    fn column_exists(&self, table_name: &str, column_name: &str) -> Result<bool> {
+        let rows = self.send_query(
+            "SELECT column_name FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2".to_string(),
+            vec![PostgresValue::Text(table_name.to_string()), PostgresValue::Text(column_name.to_string())]
+        )?;
+        Ok(rows.len() > 0)
+    }
+
+    fn get_sql_column_type(&self, column_type: ColumnType) -> &'static str {
+        match column_type {
+            ColumnType::Text => "TEXT",
+            ColumnType::Integer => "BIGINT",
+            ColumnType::Blob => "BYTEA",
+            ColumnType::Bool => "BOOL",
+            ColumnType::GeneratedIntegerKey => "BIGSERIAL PRIMARY KEY",
+        }
+    }
+
+    fn execute(&self, statement: &str) -> Result<usize> {
+        Ok(self.send_execute(statement.to_string(), Vec::new())? as usize)
+    }
+
+    fn query_items<ItemT: DeserializeOwned>(&self, statement: &str) -> Result<Vec<ItemT>> {
+        let rows = self.send_query(statement.to_string(), Vec::new())?;
+        rows.into_iter()
+            .map(|row| serde_json::from_value::<ItemT>(JsonValue::Object(row))
+                .map_err(|e| anyhow!("Error on deserializing rows of statement '{}'. Error: {}", statement, e)))
+            .collect()
+    }
+
+    fn query_count(&self, statement: &str) -> Result<usize> {
+        let rows = self.send_query(statement.to_string(), Vec::new())?;
+        rows.first()
+            .and_then(|row| row.values().next())
+            .and_then(|value| value.as_u64())
+            .map(|count| count as usize)
+            .ok_or_else(|| anyhow!("Statement '{}' did not return a count", statement))
+    }
+
+    fn upsert_item<ItemT: Serialize>(&self, table_name: &str, primary_key_column: &str, item: &ItemT) -> Result<usize> {
+        let (statement, params) = self.get_upsert_statement_and_params(table_name, primary_key_column, item)?;
+        Ok(self.send_execute(statement, params)? as usize)
+    }
+
+    fn insert_item_returning_key<ItemT: Serialize>(&self, table_name: &str, primary_key_column: &str, item: &ItemT) -> Result<i64> {
+        let (mut statement, params) = self.get_upsert_statement_and_params(table_name, primary_key_column, item)?;
+        statement += format!(" RETURNING {}", primary_key_column).as_str();
+        let rows = self.send_query(statement, params)?;
+        rows.first()
+            .and_then(|row| row.get(primary_key_column))
+            .and_then(|value| value.as_i64())
+            .ok_or_else(|| anyhow!("Could not read the generated '{}' key for table '{}'", primary_key_column, table_name))
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{
+    Result,
+    anyhow,
+};
+
+use rusqlite::Connection;
+
+use serde::{
+    Serialize,
+    de::DeserializeOwned,
+};
+
+use serde_rusqlite::{
+    from_rows_ref,
+    to_params_named,
+};
+
+use fallible_streaming_iterator::FallibleStreamingIterator;
+
+use super::{
+    StorageBackend,
+    ColumnType,
+};
+
+pub struct SqliteBackend {
+    connection: Connection,
+}
+
+impl SqliteBackend {
+    pub fn open(file_path_and_name: &str) -> Result<Self> {
+        let connection = Connection::open(file_path_and_name)
+            .map_err(|e| anyhow!("Error on open/create SQlite database file '{}'. Error: {}", file_path_and_name, e))?;
+        Ok(SqliteBackend { connection })
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Ok(SqliteBackend { connection: Connection::open_in_memory()? })
+    }
+
+    pub fn get_connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    fn get_upsert_statement_and_params<ItemT: Serialize>(&self, table_name: &str, item: &ItemT) -> Result<(String, serde_rusqlite::NamedParamSlice)> {
+        let params = to_params_named(item)
+            .map_err(|e| anyhow!("Error on serializing item for table '{}'. Error: {}", table_name, e))?;
+        let param_names: Vec<String> = params.to_slice().iter().map(|(name, _)| name.to_string()).collect();
+        let column_names: Vec<&str> = param_names.iter().map(|name| name.trim_start_matches(':')).collect();
+        let statement = format!("INSERT OR REPLACE INTO \"{}\" ({}) VALUES ({})",
+            table_name,
+            column_names.join(", "),
+            param_names.join(", "),
+        );
+        Ok((statement, params))
+    }
+}
+
+impl StorageBackend for SqliteBackend {
+    fn table_exists(&self, table_name: &str) -> Result<bool> {
+        let mut statement = self.connection.prepare("SELECT name FROM sqlite_schema WHERE type='table' AND name=?1")?;
+        let rows = statement.query([table_name])?;
+        Ok(rows.count()? > 0)
+    }
+
+    fn column_exists(&self, table_name: &str, column_name: &str) -> Result<bool> {
+        let mut statement = self.connection.prepare("SELECT name FROM pragma_table_info(?1) WHERE name=?2")?;
+        let rows = statement.query([table_name, column_name])?;
+        Ok(rows.count()? > 0)
+    }
+
+    fn get_sql_column_type(&self, column_type: ColumnType) -> &'static str {
+        match column_type {
+            ColumnType::Text => "TEXT",
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Blob => "BLOB",
+            ColumnType::Bool => "BOOL",
+            ColumnType::GeneratedIntegerKey => "INTEGER NOT NULL PRIMARY KEY",
+        }
+    }
+
+    fn execute(&self, statement: &str) -> Result<usize> {
+        self.connection.execute(statement, [])
+            .map_err(|e| anyhow!("Error on executing statement '{}'. Error: {}", statement, e))
+    }
+
+    fn query_items<ItemT: DeserializeOwned>(&self, statement: &str) -> Result<Vec<ItemT>> {
+        let mut prepared = self.connection.prepare(statement)
+            .map_err(|e| anyhow!("Error on preparing statement '{}'. Error: {}", statement, e))?;
+        let mut rows = prepared.query([])?;
+        let res = from_rows_ref::<ItemT>(&mut rows);
+        res.collect::<std::result::Result<Vec<ItemT>, _>>()
+            .map_err(|e| anyhow!("Error on deserializing rows of statement '{}'. Error: {}", statement, e))
+    }
+
+    fn query_count(&self, statement: &str) -> Result<usize> {
+        let count = self.connection.query_row(statement, [], |row| row.get::<_, i64>(0))
+            .map_err(|e| anyhow!("Error on querying statement '{}'. Error: {}", statement, e))?;
+        Ok(count as usize)
+    }
+
+    fn upsert_item<ItemT: Serialize>(&self, table_name: &str, _primary_key_column: &str, item: &ItemT) -> Result<usize> {
+        let (statement, params) = self.get_upsert_statement_and_params(table_name, item)?;
+        self.connection.execute(statement.as_str(), params.to_slice().as_slice())
+            .map_err(|e| anyhow!("Error on executing statement '{}'. Error: {}", statement, e))
+    }
+
+    fn insert_item_returning_key<ItemT: Serialize>(&self, table_name: &str, primary_key_column: &str, item: &ItemT) -> Result<i64> {
+        // SQLite generates the key if NULL is inserted into an 'INTEGER PRIMARY KEY' column.
+        // In all cases the key equals the rowid of the inserted row.
+        self.upsert_item(table_name, primary_key_column, item)?;
+        Ok(self.connection.last_insert_rowid())
+    }
+}
@@ -2,22 +2,36 @@ use std::fmt;
 
 use anyhow::{
     Result,
+    anyhow,
     bail
 };
 
-use rusqlite::{
-    Connection,
-    Rows,
-    Statement
+use serde::de::{
+    DeserializeOwned,
 };
 
-use serde_rusqlite::from_rows_ref;
+use crypto::hashes::{
+    blake2b::Blake2b256,
+    Digest,
+};
 
-use serde::de::{
-    DeserializeOwned,
+pub use crate::dao_backend::{
+    DbConnection,
+    StorageBackend,
+    ColumnDefinition,
+    ColumnType,
 };
 
-use fallible_streaming_iterator::FallibleStreamingIterator;
+use crate::{
+    dao_backend::is_postgres_url,
+    dao_migration::{
+        Migration,
+        INITIAL_SCHEMA_VERSION,
+        get_latest_migration_record,
+        delete_migration_history,
+        migrate_db_schema,
+    },
+};
 
 pub type DbSchemaVersionType = i32;
 
@@ -45,12 +59,16 @@ pub trait DaoManager {
     const DAO_MANAGER_NAME: &'static str;
     const PRIMARY_KEY_COLUMN_NAME: &'static str;
     const DB_SCHEMA_VERSION: DbSchemaVersionType;
+    // Ordered list of the schema changes since the initial schema of the table.
+    // The version of the last migration must equal DB_SCHEMA_VERSION.
+    // See dao_migration.rs for more details.
+    const MIGRATIONS: &'static [Migration] = &[];
 
     fn new(options: Self::OptionsType) -> Self;
 
     fn get_table_name(&self) -> String;
 
-    fn get_connection(&self) -> &Connection;
+    fn get_connection(&self) -> &DbConnection;
 
     fn update_db_schema_to_current_version(&self) -> Result<()>;
 
@@ -84,28 +102,41 @@ pub enum MatchType {
     ExactMatch,
     StartsWith,
     ListEverything,
+    // Column value is greater than or equal to the condition value
+    GreaterOrEqual,
+    // Column value is less than or equal to the condition value
+    LessOrEqual,
 }
 
-fn get_rows_from_statement<'a>(statement: &'a mut Statement, statement_str: &String, item_type_name: &str) -> Rows<'a> {
-    statement.query([])
-        .expect(format!("Error on querying prepared 'SELECT ... FROM' statement for {}. Statement: {}",
-                        item_type_name,
-                        statement_str).as_str())
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
 }
 
-fn get_value_from_statement<'a, ValueTypeT>(statement: &'a mut Statement, statement_str: &String, item_type_name: &str) -> Vec<ValueTypeT>
-where
-    ValueTypeT: DeserializeOwned
-{
-    let mut rows = get_rows_from_statement(statement, &statement_str, item_type_name);
-    let mut res = from_rows_ref::<ValueTypeT>(&mut rows);
-    let mut ret_val = Vec::<ValueTypeT>::new();
-    while let Some(item) = res.next() {
-        let to_push = item
-            .expect(format!("Error on unwrapping next {} from_rows_ref", item_type_name).as_str());
-        ret_val.push(to_push);
+#[derive(Clone, Debug)]
+pub struct OrderBy {
+    pub column_name: String,
+    pub sort_order: SortOrder,
+}
+
+impl OrderBy {
+    pub fn new(column_name: &str, sort_order: SortOrder) -> Self {
+        OrderBy {
+            column_name: column_name.to_string(),
+            sort_order,
+        }
+    }
+}
+
+impl fmt::Display for OrderBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.sort_order {
+            SortOrder::Ascending => "ASC",
+            SortOrder::Descending => "DESC",
+        };
+        write!(f, "ORDER BY {} {}", self.column_name, direction)
     }
-    ret_val
 }
 
 pub fn find_all_items_in_db<'a, DaoManagerT: DaoManager>(
@@ -132,6 +163,18 @@ pub fn filter_items<'a, DaoManagerT: DaoManager>(
     conditions: &Vec<Condition>,
     limit: Option<Limit>
 ) -> Result<(Vec<DaoManagerT::ItemType>, usize)>
+{
+    filter_items_ordered(dao_manager, conditions, None, limit)
+}
+
+// Same as filter_items() but the returned items are sorted as specified by order_by.
+// The count of all items matching the conditions is not influenced by order_by.
+pub fn filter_items_ordered<'a, DaoManagerT: DaoManager>(
+    dao_manager: &DaoManagerT,
+    conditions: &Vec<Condition>,
+    order_by: Option<OrderBy>,
+    limit: Option<Limit>
+) -> Result<(Vec<DaoManagerT::ItemType>, usize)>
 {
     let limit_offset =  limit.or(Some(
         Limit{
@@ -139,13 +182,39 @@ pub fn filter_items<'a, DaoManagerT: DaoManager>(
             offset: 0
         }
     ));
-    let (mut statement, statement_str) = build_query_statement(dao_manager, conditions, limit_offset)?;
-    let ret_val = get_value_from_statement::<DaoManagerT::ItemType>(&mut statement, &statement_str, DaoManagerT::ITEM_TYPE_NAME);
+    let statement_str = build_query_statement(dao_manager, conditions, order_by, limit_offset);
+    let ret_val = dao_manager.get_connection().query_items::<DaoManagerT::ItemType>(statement_str.as_str())
+        .map_err(|e| anyhow!("Error on querying {} items. Error: {}", DaoManagerT::ITEM_TYPE_NAME, e))?;
 
-    let (mut cnt_statement, cnt_statement_str) = build_select_count_statement(dao_manager, conditions)?;
-    let counts = get_value_from_statement::<usize>(&mut cnt_statement, &cnt_statement_str, "count");
+    let cnt_statement_str = build_select_count_statement(dao_manager, conditions);
+    let count = dao_manager.get_connection().query_count(cnt_statement_str.as_str())
+        .map_err(|e| anyhow!("Error on counting {} items. Error: {}", DaoManagerT::ITEM_TYPE_NAME, e))?;
+
+    Ok((ret_val, count))
+}
 
-    Ok((ret_val, counts[0]))
+// Same as filter_items() but all items matching the conditions are returned. The items are
+// fetched page by page, ordered by the primary key, so that the number of returned items is
+// not limited to MAX_NUMBER_OF_ROWS_TO_FETCH.
+pub fn filter_all_items<'a, DaoManagerT: DaoManager>(
+    dao_manager: &DaoManagerT,
+    conditions: &Vec<Condition>,
+) -> Result<Vec<DaoManagerT::ItemType>>
+{
+    let mut ret_val = Vec::<DaoManagerT::ItemType>::new();
+    loop {
+        let (items, _) = filter_items_ordered(
+            dao_manager,
+            conditions,
+            Some(OrderBy::new(DaoManagerT::PRIMARY_KEY_COLUMN_NAME, SortOrder::Ascending)),
+            Some(Limit { limit: MAX_NUMBER_OF_ROWS_TO_FETCH, offset: ret_val.len() }),
+        )?;
+        let is_last_page = items.len() < MAX_NUMBER_OF_ROWS_TO_FETCH;
+        ret_val.extend(items);
+        if is_last_page {
+            return Ok(ret_val);
+        }
+    }
 }
 
 pub fn get_item_from_db<'a, DaoManagerT: DaoManager>(
@@ -159,24 +228,22 @@ pub fn get_item_from_db<'a, DaoManagerT: DaoManager>(
         column_value: primary_key.to_string(),
         match_type
     }];
-    let (mut statement, statement_str) = build_query_statement(dao_manager, &conditions, None)?;
-    let rows = get_rows_from_statement(&mut statement, &statement_str, DaoManagerT::ITEM_TYPE_NAME);
-    get_item_from_single_row_rowset(dao_manager, primary_key, rows, statement_str)
+    let statement_str = build_query_statement(dao_manager, &conditions, None, None);
+    let items = dao_manager.get_connection().query_items::<DaoManagerT::ItemType>(statement_str.as_str())
+        .map_err(|e| anyhow!("Error on querying {} item. Error: {}", DaoManagerT::ITEM_TYPE_NAME, e))?;
+    get_item_from_single_row_rowset(dao_manager, primary_key, items, statement_str)
 }
 
 fn get_item_from_single_row_rowset<'a, DaoManagerT: DaoManager>(
     dao_manager: &DaoManagerT,
     key: &DaoManagerT::PrimaryKeyType,
-    mut rows: Rows, statement_str: String
+    items: Vec<DaoManagerT::ItemType>,
+    statement_str: String
 ) -> Result<DaoManagerT::ItemType>
 {
-    let mut res = from_rows_ref::<DaoManagerT::ItemType>(&mut rows);
-    if let Some(item) = res.next() {
-        let ret_val = item
-            .expect(format!("Error on unwrapping next {} from_rows_ref",
-                            DaoManagerT::ITEM_TYPE_NAME).as_str());
-
-        if let Some(_additional_item) = res.next() {
+    let mut items_iter = items.into_iter();
+    if let Some(ret_val) = items_iter.next() {
+        if let Some(_additional_item) = items_iter.next() {
             bail!("Found more than one {} in table '{}' having a matching '{}' column value starting with '{}'\n\
                    Used SQL statement: {}",
                 DaoManagerT::ITEM_TYPE_NAME,
@@ -237,11 +304,15 @@ struct WherePart {
 
 impl From<Condition> for WherePart {
     fn from(value: Condition) -> Self {
-        let mut operator = "=";
         let mut wildcard = "";
-        if value.match_type == MatchType::StartsWith {
-            operator = "LIKE";
-            wildcard = "%";
+        let operator = match value.match_type {
+            MatchType::StartsWith => {
+                wildcard = "%";
+                "LIKE"
+            },
+            MatchType::GreaterOrEqual => ">=",
+            MatchType::LessOrEqual => "<=",
+            _ => "=",
         };
         WherePart{
             column_name: value.column_name.to_string(),
@@ -283,21 +354,18 @@ impl StatementParts {
 fn build_query_statement<'a, DaoManagerT: DaoManager>(
     dao_manager: &'a DaoManagerT,
     conditions: &Vec<Condition>,
+    order_by: Option<OrderBy>,
     limit: Option<Limit>,
-) -> Result<(Statement<'a>, String)>
+) -> String
 {
     let parts = StatementParts::new::<DaoManagerT>(limit, conditions.clone());
     let where_condition = get_where_condition::<DaoManagerT>(parts.clone());
-    let statement_str = format!("SELECT * FROM {t_name} {where} {lim_ofs}",
-                                t_name = dao_manager.get_table_name(),
-                                where = where_condition,
-                                lim_ofs = parts.limit_offset,
-    );
-    let statement = dao_manager.get_connection().prepare(statement_str.as_str())
-        .expect(format!("Error on preparing 'SELECT * FROM' for {}. Statement: {}",
-                        DaoManagerT::ITEM_TYPE_NAME,
-                        statement_str).as_str());
-    Ok((statement, statement_str))
+    format!("SELECT * FROM \"{t_name}\" {where} {order} {lim_ofs}",
+            t_name = dao_manager.get_table_name(),
+            where = where_condition,
+            order = order_by.map_or("".to_string(), |order_by| order_by.to_string()),
+            lim_ofs = parts.limit_offset,
+    )
 }
 
 fn get_where_condition<DaoManagerT: DaoManager>(parts: StatementParts) -> String {
@@ -309,7 +377,7 @@ fn get_where_condition<DaoManagerT: DaoManager>(parts: StatementParts) -> String
             }
             ret_val += format!("{col_name} {op} '{col_val}{wldcrd}'",
                     col_name = where_part.column_name,
-                    col_val = where_part.column_value,
+                    col_val = escape_sql_string(where_part.column_value.as_str()),
                     op = where_part.operator,
                     wldcrd = where_part.wildcard,
             ).as_str();
@@ -323,53 +391,105 @@ fn get_where_condition<DaoManagerT: DaoManager>(parts: StatementParts) -> String
 fn build_select_count_statement<'a, DaoManagerT: DaoManager>(
     dao_manager: &'a DaoManagerT,
     conditions: &Vec<Condition>,
-) -> Result<(Statement<'a>, String)>
+) -> String
 {
     let parts = StatementParts::new::<DaoManagerT>(None, conditions.clone());
     let where_condition = get_where_condition::<DaoManagerT>(parts);
-    let statement_str = format!("SELECT COUNT(*) FROM {t_name} {where}",
-                                t_name = dao_manager.get_table_name(),
-                                where = where_condition,
-    );
-    let statement = dao_manager.get_connection().prepare(statement_str.as_str())
-        .expect(format!("Error on preparing 'SELECT COUNT(*) FROM' for {}. Statement: {}",
-                        DaoManagerT::ITEM_TYPE_NAME,
-                        statement_str).as_str());
-    Ok((statement, statement_str))
+    format!("SELECT COUNT(*) FROM \"{t_name}\" {where}",
+            t_name = dao_manager.get_table_name(),
+            where = where_condition,
+    )
 }
 
 
+// Escapes single quotes of string values that are embedded into SQL statements
+pub fn escape_sql_string(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+// Returns 0 if the table of the dao_manager does not exist. Otherwise the version recorded in
+// the schema_migration table is returned (see dao_migration.rs). Tables created by older
+// versions, that did not record their schema version, are handled as INITIAL_SCHEMA_VERSION.
 pub fn get_schema_version_in_database<DaoManagerT: DaoManager>(dao_manager: &DaoManagerT) -> Result<DbSchemaVersionType> {
-    // Currently we do not manage updates of db schemas. We are using only the initial
-    // db schema. Therefore we only check if the table needed for our data
-    // already exists. If the table exists we'll return DB_SCHEMA_VERSION otherwise 0.
-    // To use this code in production the version of the database schema
-    // must be incremented every time the schema is changed and the schema version
-    // must be stored in an additional 'entity_versions' table so that this function
-    // can read out the version.
-    let statement_str = format!(
-        "SELECT name FROM sqlite_schema WHERE type='table' AND name='{}'",
-        dao_manager.get_table_name()
-    );
-    let mut statement = dao_manager.get_connection().prepare(statement_str.as_str())
-        .expect(format!("Error on preparing statement '{}'", statement_str).as_str());
-
-    let rows = statement.query([])
-        .expect(format!("Error on querying statement '{}'", statement_str).as_str());
-
-    let count_rows= rows.count()?;
-    let ret_val = if count_rows == 0 {0} else {DaoManagerT::DB_SCHEMA_VERSION} as DbSchemaVersionType;
+    let connection = dao_manager.get_connection();
+    let table_name = dao_manager.get_table_name();
+    if !connection.table_exists(table_name.as_str())? {
+        return Ok(0);
+    }
+    let ret_val = get_latest_migration_record(connection, table_name.as_str())?
+        .map_or(INITIAL_SCHEMA_VERSION, |record| record.version);
     Ok(ret_val)
 }
 
+// Drops the table of the dao_manager using the connection. Nothing happens if the table does not exist.
+// The connection is passed separately, so that several tables can be dropped in one transaction.
+pub fn drop_table_if_exists<DaoManagerT: DaoManager>(connection: &DbConnection, dao_manager: &DaoManagerT) -> Result<()> {
+    let table_name = dao_manager.get_table_name();
+    let _rows = connection.execute(
+        format!("DROP TABLE IF EXISTS \"{}\"", table_name).as_str()
+    )?;
+    delete_migration_history(connection, table_name.as_str())
+}
+
+// Deletes the item having the primary key from the table of the dao_manager using the connection.
+// Nothing happens if the table does not exist. See drop_table_if_exists() for the connection.
+pub fn delete_item_if_table_exists<DaoManagerT: DaoManager>(connection: &DbConnection, dao_manager: &DaoManagerT, key: &str) -> Result<()> {
+    let table_name = dao_manager.get_table_name();
+    if connection.table_exists(table_name.as_str())? {
+        let _rows = connection.execute(
+            format!("DELETE FROM \"{}\" WHERE {} = '{}'",
+                    table_name, DaoManagerT::PRIMARY_KEY_COLUMN_NAME, escape_sql_string(key)).as_str()
+        )?;
+    }
+    Ok(())
+}
+
+// PostgreSQL truncates identifiers that are longer than 63 bytes
+const MAX_TABLE_NAME_LEN: usize = 63;
+const CHANNEL_TABLE_NAME_HASH_LEN: usize = 20;
+
+// Returns the name of a table storing items of a single Streams channel, e.g. 'message_{channel_id}'.
+// Streams channel ids consist of 80 hex characters, so these names would exceed the maximum
+// identifier length of PostgreSQL. For PostgreSQL connections the channel id is therefore
+// replaced by the hex encoded first 20 bytes of its Blake2b256 hash, which results in a stable
+// name for the channel. SQLite tables keep the unshortened name.
+pub fn get_channel_table_name(connection: &DbConnection, prefix: &str, channel_id: &str) -> String {
+    let table_name = format!("{}_{}", prefix, channel_id);
+    if !connection.is_postgres() || table_name.len() <= MAX_TABLE_NAME_LEN {
+        return table_name;
+    }
+    get_shortened_channel_table_name(prefix, channel_id)
+}
+
+fn get_shortened_channel_table_name(prefix: &str, channel_id: &str) -> String {
+    let mut hasher = Blake2b256::new();
+    hasher.update(channel_id.as_bytes());
+    format!("{}_{}", prefix, hex::encode(&hasher.finalize()[..CHANNEL_TABLE_NAME_HASH_LEN]))
+}
+
+// Rebuilds the SQLite database file to free the space of deleted rows (see SQLite VACUUM).
+// PostgreSQL databases are cleaned up by the autovacuum daemon of the server, so nothing
+// is done for PostgreSQL connection URLs.
+pub fn vacuum_database(file_path_and_name: &str) -> Result<()> {
+    if is_postgres_url(file_path_and_name) {
+        return Ok(());
+    }
+    let _rows = DbConnection::open(file_path_and_name)?.execute("VACUUM")?;
+    Ok(())
+}
+
+// Creates the table of the dao_manager or applies the MIGRATIONS of the DaoManager that are
+// missing in the database. Tables having a newer schema version, e.g. because the database
+// has been migrated by a newer software version, are not changed.
 pub fn update_db_schema_to_current_version<DaoManagerT: DaoManager>(dao_manager: &DaoManagerT) -> Result<()> {
     let schema_version_in_db = get_schema_version_in_database(dao_manager)?;
-    if schema_version_in_db < DaoManagerT::DB_SCHEMA_VERSION {
-        dao_manager.init_db_schema()
-            .expect(format!("Error on initializing the database for item {} resp. table {}",
-                            DaoManagerT::ITEM_TYPE_NAME,
-                            dao_manager.get_table_name()
-            ).as_str());
+    if schema_version_in_db > DaoManagerT::DB_SCHEMA_VERSION {
+        log::warn!("[fn update_db_schema_to_current_version()] Table '{}' uses schema version {} which is newer than the version {} supported by this software",
+            dao_manager.get_table_name(), schema_version_in_db, DaoManagerT::DB_SCHEMA_VERSION);
+    } else if schema_version_in_db < DaoManagerT::DB_SCHEMA_VERSION {
+        let _plan = migrate_db_schema(dao_manager, DaoManagerT::DB_SCHEMA_VERSION, false)
+            .map_err(|e| anyhow!("Error on updating the database schema for item {} resp. table {}: {}",
+                                 DaoManagerT::ITEM_TYPE_NAME, dao_manager.get_table_name(), e))?;
     }
     Ok(())
 }
@@ -406,6 +526,22 @@ impl<DaoManagerT: DaoManager + Clone> DaoDataStore<DaoManagerT> {
         self.items.filter(conditions, limit)
     }
 
+    // Returns all items. In contrast to find_all("", None) the number of items is not limited
+    // to MAX_NUMBER_OF_ROWS_TO_FETCH (see filter_all_items()).
+    pub fn find_all_unlimited(&self) -> Result<Vec<DaoManagerT::ItemType>> {
+        filter_all_items(&self.items, &Vec::new())
+    }
+
+    // Same as filter() with a limit of None, but all matching items are returned
+    // (see filter_all_items()).
+    pub fn filter_unlimited(&self, conditions: Vec<Condition>) -> Result<Vec<DaoManagerT::ItemType>> {
+        filter_all_items(&self.items, &conditions)
+    }
+
+    pub fn filter_ordered(&self, conditions: Vec<Condition>, order_by: Option<OrderBy>, limit: Option<Limit>) -> Result<(Vec<DaoManagerT::ItemType>, usize)> {
+        filter_items_ordered(&self.items, &conditions, order_by, limit)
+    }
+
     pub fn get_first_filtered_item(&self, conditions: Vec<Condition>) -> Option<DaoManagerT::ItemType> {
         let mut ret_val = None;
         match self.items.filter(
@@ -450,8 +586,16 @@ impl<DaoManagerT: DaoManager + Clone> DaoDataStore<DaoManagerT> {
     pub fn delete_item_in_db(&self, key: &DaoManagerT::PrimaryKeyType) -> Result<()> {
         self.items.delete_item_in_db(key)
     }
+
+    // Runs f in a transaction of the database connection used by this data store.
+    // See DbConnection::run_in_transaction() for more details.
+    pub fn run_in_transaction<F: FnOnce() -> Result<()>>(&self, f: F) -> Result<()> {
+        self.items.get_connection().run_in_transaction(f)
+    }
 }
 
+// file_path_and_name can also be a PostgreSQL connection URL ("postgres://...").
+// See DbConnection for more details.
 #[derive(Clone)]
 pub struct DbFileBasedDaoManagerOptions {
     pub file_path_and_name: String,
@@ -460,9 +604,9 @@ pub struct DbFileBasedDaoManagerOptions {
 pub trait DbFileBasedDaoManagerOpt: Clone {
     fn file_path_and_name(&self) -> String;
 
-    fn get_new_connection(&self) -> Connection {
-        Connection::open(self.file_path_and_name())
-            .expect(format!("Error on open/create SQlite database file '{}'", self.file_path_and_name()).as_str())
+    fn get_new_connection(&self) -> DbConnection {
+        DbConnection::open(self.file_path_and_name().as_str())
+            .expect("Error on opening the database connection")
     }
 }
 
@@ -477,8 +621,8 @@ impl DbFileBasedDaoManagerOpt for DbFileBasedDaoManagerOptions {
 impl DbFileBasedDaoManagerOpt for DbFileBasedDaoManagerOptions {
     fn file_path_and_name(&self) -> String { "NOT USED".to_string() }
 
-    fn get_new_connection(&self) -> Connection {
-        Connection::open_in_memory().unwrap()
+    fn get_new_connection(&self) -> DbConnection {
+        DbConnection::open_in_memory().unwrap()
     }
 }
 
@@ -487,9 +631,7 @@ impl DbFileBasedDaoManagerOpt for DbFileBasedDaoManagerOptions {
 //
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, Mutex};
-    use rusqlite::params;
-    use serde_rusqlite::to_params_named;
+    use std::sync::Arc;
     use super::*;
     use serde::{
         Serialize,
@@ -505,12 +647,12 @@ mod tests {
 
     #[derive(Clone)]
     pub struct TestItemDaoManager {
-        connection: Arc<Mutex<Connection>>,
+        connection: Arc<DbConnection>,
     }
 
     #[derive(Clone)]
     pub struct TestItemDaoManagerOptions{
-        pub connection: Arc<Mutex<Connection>>
+        pub connection: Arc<DbConnection>
     }
 
     impl<'a> DaoManager for TestItemDaoManager {
@@ -530,8 +672,8 @@ mod tests {
             }
         }
 
-        fn get_connection(&self) -> &Connection {
-            unimplemented!()
+        fn get_connection(&self) -> &DbConnection {
+            &self.connection
         }
 
         fn get_table_name(&self) -> String { "test_item".to_string() }
@@ -541,13 +683,11 @@ mod tests {
         }
 
         fn init_db_schema(&self) -> Result<()> {
-            let connection = self.connection.lock().unwrap();
-            connection.execute(format!("CREATE TABLE IF NOT EXISTS {} (\
-                {} TEXT NOT NULL PRIMARY KEY,\
-                some_data BLOB NOT NULL\
-            )
-            ", self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(), [])
-                .expect("Error on executing 'CREATE TABLE' for TestItem");
+            self.connection.create_table(self.get_table_name().as_str(), &[
+                ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+                ColumnDefinition::new("some_data", ColumnType::Blob, "NOT NULL"),
+            ])
+                .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for TestItem: {}", e))?;
             Ok(())
         }
 
@@ -564,28 +704,22 @@ mod tests {
         }
 
         fn write_item_to_db(&self, item: &Self::ItemType) -> Result<Self::PrimaryKeyType> {
-            let connection = self.connection.lock().unwrap();
-            let _rows = connection.execute(
-                format!(
-                    "INSERT OR REPLACE INTO {} ({}, some_data) VALUES (:id, :some_data)",
-                    self.get_table_name(),
-                    Self::PRIMARY_KEY_COLUMN_NAME
-                ).as_str(),
-                to_params_named(item).unwrap().to_slice().as_slice()
+            let _rows = self.connection.upsert_item(
+                self.get_table_name().as_str(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                item
             ).unwrap();
             Ok(item.id.clone())
         }
 
         fn delete_item_in_db(&self, key: &Self::PrimaryKeyType) -> Result<()> {
-            let connection = self.connection.lock().unwrap();
-            let _rows = connection.execute(
+            let _rows = self.connection.execute(
                 format!(
                     "DELETE FROM {} WHERE {} = '{}'",
                     self.get_table_name(),
                     Self::PRIMARY_KEY_COLUMN_NAME,
                     key
-                ).as_str(),
-                params![]
+                ).as_str()
             ).unwrap();
             Ok(())
         }
@@ -610,9 +744,9 @@ mod tests {
     #[test]
     fn test_item_dao_manager() {
         let options = TestItemDaoManagerOptions {
-            connection: Arc::new(Mutex::new(
-                Connection::open_in_memory().unwrap()
-            ))
+            connection: Arc::new(
+                DbConnection::open_in_memory().unwrap()
+            )
         };
         let test_item_dao_manager = TestItemDaoManager::new(options);
         test_item_dao_manager.init_db_schema().unwrap();
@@ -640,9 +774,9 @@ mod tests {
 
     fn create_data_store_with_item_0() -> (TestItemDaoManager, DaoDataStore<TestItemDaoManager>, TestItem, SerializationCallbackRefToClosureString) {
         let options = TestItemDaoManagerOptions {
-            connection: Arc::new(Mutex::new(
-                Connection::open_in_memory().unwrap()
-            ))
+            connection: Arc::new(
+                DbConnection::open_in_memory().unwrap()
+            )
         };
         let test_item_dao_manager = TestItemDaoManager::new(options.clone());
         test_item_dao_manager.init_db_schema().unwrap();
@@ -731,4 +865,33 @@ mod tests {
         let test_item_from_db_0 = test_item_dao_manager.get_item_from_db(&test_item_0.id).unwrap();
         assert_eq!(test_item_from_db_0, test_item_0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_channel_table_name() {
+        let channel_id = "e3e4b1a8e0a3e1c6d3c1c55ac7b5e1d23f1a21b6b0ef1c0a5d9d1a4c2ee7b6f30000000000000000";
+        let connection = DbConnection::open_in_memory().unwrap();
+        assert_eq!(get_channel_table_name(&connection, "message", channel_id), format!("message_{}", channel_id));
+        let shortened = get_shortened_channel_table_name("message", channel_id);
+        assert!(shortened.len() <= MAX_TABLE_NAME_LEN);
+        assert_eq!(shortened, get_shortened_channel_table_name("message", channel_id));
+        assert_ne!(shortened, get_shortened_channel_table_name("poi", channel_id));
+    }
+
+    #[test]
+    fn test_find_all_unlimited() {
+        let options = TestItemDaoManagerOptions {
+            connection: Arc::new(
+                DbConnection::open_in_memory().unwrap()
+            )
+        };
+        TestItemDaoManager::new(options.clone()).init_db_schema().unwrap();
+        let test_item_store = TestItemDataStore::new(options);
+        let items_cnt = MAX_NUMBER_OF_ROWS_TO_FETCH + 1;
+        for idx in 0..items_cnt {
+            test_item_store.write_item_to_db(&TestItem { id: format!("item-{:05}", idx), some_data: vec![1] }).unwrap();
+        }
+        let items = test_item_store.find_all_unlimited().unwrap();
+        assert_eq!(items.len(), items_cnt);
+        assert_eq!(items.last().unwrap().id, format!("item-{:05}", items_cnt - 1));
+    }
+}
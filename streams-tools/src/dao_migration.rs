@@ -0,0 +1,599 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::dao_helpers::{
+    DaoManager,
+    DbConnection,
+    DbSchemaVersionType,
+    StorageBackend,
+    ColumnDefinition,
+    ColumnType,
+    escape_sql_string,
+    get_schema_version_in_database,
+};
+
+// Every applied migration is recorded in this table. The schema version of a DaoManager table
+// is the version of the latest record of the table.
+pub const SCHEMA_MIGRATION_TABLE_NAME: &str = "schema_migration";
+
+// Version of the schema created by the first release of a DaoManager. Tables that have been
+// created before their migrations have been recorded in the schema_migration table
+// are handled as having this version.
+pub const INITIAL_SCHEMA_VERSION: DbSchemaVersionType = 1;
+
+// A single step of a Migration.
+// The table name of the DaoManager is passed to every step, so that one migration list can be
+// used for DaoManagers having several tables (e.g. the message_{channel_id} tables).
+pub enum MigrationStep {
+    // Adds the column if it does not exist. The constraints of the column must contain a
+    // DEFAULT value to be applicable for existing rows.
+    AddColumn(ColumnDefinition),
+    // Drops the column if it exists
+    DropColumn { column_name: &'static str },
+    // SQL statement using the SQLite dialect (see StorageBackend).
+    // The placeholder '{table}' is replaced by the table name.
+    Sql(&'static str),
+    // Steps that can not be expressed as SQL statement, e.g. data conversions.
+    // The function gets the connection and the table name.
+    Rust { description: &'static str, run: fn(&DbConnection, &str) -> Result<()> },
+}
+
+impl MigrationStep {
+    fn apply(&self, connection: &DbConnection, table_name: &str) -> Result<()> {
+        match self {
+            MigrationStep::AddColumn(column) => {
+                if !connection.column_exists(table_name, column.name)? {
+                    connection.add_column(table_name, column)?;
+                }
+            },
+            MigrationStep::DropColumn { column_name } => {
+                if connection.column_exists(table_name, column_name)? {
+                    connection.execute(format!("ALTER TABLE \"{}\" DROP COLUMN {}", table_name, column_name).as_str())?;
+                }
+            },
+            MigrationStep::Sql(statement) => {
+                connection.execute(statement.replace("{table}", table_name).as_str())?;
+            },
+            MigrationStep::Rust { run, .. } => run(connection, table_name)?,
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self, table_name: &str) -> String {
+        match self {
+            MigrationStep::AddColumn(column) =>
+                format!("ALTER TABLE \"{}\" ADD COLUMN {}", table_name, column),
+            MigrationStep::DropColumn { column_name } =>
+                format!("ALTER TABLE \"{}\" DROP COLUMN {}", table_name, column_name),
+            MigrationStep::Sql(statement) => statement.replace("{table}", table_name),
+            MigrationStep::Rust { description, .. } => format!("Rust: {}", description),
+        }
+    }
+}
+
+// Changes the schema of a DaoManager table from version - 1 to version (up) and back (down).
+// The first migration of a DaoManager has version INITIAL_SCHEMA_VERSION + 1.
+//
+// The up steps of migrations that have been released before the schema_migration table
+// existed need to be idempotent, because they are applied to tables of unknown schema
+// version (see INITIAL_SCHEMA_VERSION).
+pub struct Migration {
+    pub version: DbSchemaVersionType,
+    pub description: &'static str,
+    pub up: &'static [MigrationStep],
+    pub down: &'static [MigrationStep],
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SchemaMigrationRecord {
+    pub id: Option<i64>,
+    pub table_name: String,
+    // Schema version of the table after the migration has been applied
+    pub version: DbSchemaVersionType,
+    pub description: String,
+    pub applied_at_secs: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MigrationDirection {
+    Up,
+    Down,
+}
+
+impl fmt::Display for MigrationDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationDirection::Up => write!(f, "up"),
+            MigrationDirection::Down => write!(f, "down"),
+        }
+    }
+}
+
+// The migrations needed to bring a table from version_in_db to target_version.
+// A version_in_db of 0 means that the table does not exist and will be created.
+pub struct MigrationPlan {
+    pub table_name: String,
+    pub version_in_db: DbSchemaVersionType,
+    pub target_version: DbSchemaVersionType,
+    pub migrations: Vec<(MigrationDirection, &'static Migration)>,
+}
+
+impl MigrationPlan {
+    pub fn has_changes(&self) -> bool {
+        self.version_in_db != self.target_version
+    }
+}
+
+impl fmt::Display for MigrationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.version_in_db == 0 {
+            return write!(f, "Table '{}': Create table using schema version {}", self.table_name, self.target_version);
+        }
+        if !self.has_changes() {
+            return write!(f, "Table '{}': Schema version {} is up to date", self.table_name, self.version_in_db);
+        }
+        write!(f, "Table '{}': Schema version {} -> {}", self.table_name, self.version_in_db, self.target_version)?;
+        for (direction, migration) in self.migrations.iter() {
+            let steps = match direction {
+                MigrationDirection::Up => migration.up,
+                MigrationDirection::Down => migration.down,
+            };
+            write!(f, "\n    {} {}: {}", direction, migration.version, migration.description)?;
+            for step in steps.iter() {
+                write!(f, "\n        {}", step.describe(self.table_name.as_str()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Options of the --migrate CLI command of the iota-bridge and the management-console
+#[derive(Clone, Default)]
+pub struct MigrationOptions {
+    // Only the migration plans are returned, the database is not changed
+    pub dry_run: bool,
+    // If None, all existing tables are migrated to the schema version of the DaoManager.
+    // Otherwise only the specified table is migrated.
+    pub target: Option<MigrationTarget>,
+}
+
+impl MigrationOptions {
+    pub fn check_target_table_has_been_found(&self, plans: &[MigrationPlan]) -> Result<()> {
+        if let Some(target) = &self.target {
+            if !plans.iter().any(|plan| plan.table_name == target.table_name) {
+                bail!("The table '{}' does not exist", target.table_name);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Migrates a single table up or down to the specified schema version.
+// Is parsed from "<TABLE_NAME>=<VERSION>".
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrationTarget {
+    pub table_name: String,
+    pub version: DbSchemaVersionType,
+}
+
+impl FromStr for MigrationTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (table_name, version) = s.split_once('=')
+            .ok_or_else(|| anyhow!("Invalid migration target '{}'. Expected format: <TABLE_NAME>=<VERSION>", s))?;
+        let version = DbSchemaVersionType::from_str(version.trim())
+            .map_err(|e| anyhow!("Invalid schema version in migration target '{}'. Error: {}", s, e))?;
+        Ok(MigrationTarget {
+            table_name: table_name.trim().to_string(),
+            version,
+        })
+    }
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn create_schema_migration_table_if_not_exists(connection: &DbConnection) -> Result<()> {
+    connection.create_table(SCHEMA_MIGRATION_TABLE_NAME, &[
+        ColumnDefinition::new("id", ColumnType::GeneratedIntegerKey, ""),
+        ColumnDefinition::new("table_name", ColumnType::Text, "NOT NULL"),
+        ColumnDefinition::new("version", ColumnType::Integer, "NOT NULL"),
+        ColumnDefinition::new("description", ColumnType::Text, "NOT NULL"),
+        ColumnDefinition::new("applied_at_secs", ColumnType::Integer, "NOT NULL"),
+    ])?;
+    connection.execute(format!(
+        "CREATE INDEX IF NOT EXISTS idx_{0}_table_name ON {0} (table_name)",
+        SCHEMA_MIGRATION_TABLE_NAME).as_str())?;
+    Ok(())
+}
+
+fn write_migration_record(connection: &DbConnection, table_name: &str, version: DbSchemaVersionType, description: String) -> Result<()> {
+    create_schema_migration_table_if_not_exists(connection)?;
+    let record = SchemaMigrationRecord {
+        id: None,
+        table_name: table_name.to_string(),
+        version,
+        description,
+        applied_at_secs: now_secs(),
+    };
+    let _id = connection.insert_item_returning_key(SCHEMA_MIGRATION_TABLE_NAME, "id", &record)?;
+    Ok(())
+}
+
+// Returns the migration records of the table in the order they have been applied
+pub fn get_migration_history(connection: &DbConnection, table_name: &str) -> Result<Vec<SchemaMigrationRecord>> {
+    if !connection.table_exists(SCHEMA_MIGRATION_TABLE_NAME)? {
+        return Ok(Vec::new());
+    }
+    connection.query_items(format!("SELECT * FROM {} WHERE table_name = '{}' ORDER BY id",
+        SCHEMA_MIGRATION_TABLE_NAME, escape_sql_string(table_name)).as_str())
+}
+
+pub fn get_latest_migration_record(connection: &DbConnection, table_name: &str) -> Result<Option<SchemaMigrationRecord>> {
+    if !connection.table_exists(SCHEMA_MIGRATION_TABLE_NAME)? {
+        return Ok(None);
+    }
+    let mut records: Vec<SchemaMigrationRecord> = connection.query_items(
+        format!("SELECT * FROM {} WHERE table_name = '{}' ORDER BY id DESC LIMIT 1",
+                SCHEMA_MIGRATION_TABLE_NAME, escape_sql_string(table_name)).as_str())?;
+    Ok(records.pop())
+}
+
+// Needs to be called when a table is dropped, so that a recreated table does not inherit
+// the schema version of the dropped table
+pub fn delete_migration_history(connection: &DbConnection, table_name: &str) -> Result<()> {
+    if connection.table_exists(SCHEMA_MIGRATION_TABLE_NAME)? {
+        let _rows = connection.execute(format!("DELETE FROM {} WHERE table_name = '{}'",
+            SCHEMA_MIGRATION_TABLE_NAME, escape_sql_string(table_name)).as_str())?;
+    }
+    Ok(())
+}
+
+// Checks that the versions of the MIGRATIONS of the DaoManager are consecutive and
+// end with the DB_SCHEMA_VERSION
+fn check_migrations<DaoManagerT: DaoManager>() -> Result<()> {
+    let mut expected_version = INITIAL_SCHEMA_VERSION;
+    for migration in DaoManagerT::MIGRATIONS.iter() {
+        expected_version += 1;
+        if migration.version != expected_version {
+            bail!("Migration '{}' of {} has version {} but version {} was expected",
+                migration.description, DaoManagerT::DAO_MANAGER_NAME, migration.version, expected_version);
+        }
+    }
+    if expected_version != DaoManagerT::DB_SCHEMA_VERSION {
+        bail!("The MIGRATIONS of {} end with version {} but DB_SCHEMA_VERSION is {}",
+            DaoManagerT::DAO_MANAGER_NAME, expected_version, DaoManagerT::DB_SCHEMA_VERSION);
+    }
+    Ok(())
+}
+
+pub fn get_migration_plan<DaoManagerT: DaoManager>(dao_manager: &DaoManagerT, target_version: DbSchemaVersionType) -> Result<MigrationPlan> {
+    check_migrations::<DaoManagerT>()?;
+    let table_name = dao_manager.get_table_name();
+    if target_version < INITIAL_SCHEMA_VERSION || target_version > DaoManagerT::DB_SCHEMA_VERSION {
+        bail!("Invalid target schema version {} for table '{}'. Supported versions are {} to {}",
+            target_version, table_name, INITIAL_SCHEMA_VERSION, DaoManagerT::DB_SCHEMA_VERSION);
+    }
+    let version_in_db = get_schema_version_in_database(dao_manager)?;
+    if version_in_db > DaoManagerT::DB_SCHEMA_VERSION {
+        bail!("Table '{}' uses schema version {} which is newer than the version {} supported by this software",
+            table_name, version_in_db, DaoManagerT::DB_SCHEMA_VERSION);
+    }
+    if version_in_db == 0 && target_version != DaoManagerT::DB_SCHEMA_VERSION {
+        bail!("Table '{}' does not exist and can only be created using schema version {}",
+            table_name, DaoManagerT::DB_SCHEMA_VERSION);
+    }
+
+    let migrations = if version_in_db == 0 {
+        Vec::new()
+    } else if version_in_db < target_version {
+        DaoManagerT::MIGRATIONS.iter()
+            .filter(|migration| migration.version > version_in_db && migration.version <= target_version)
+            .map(|migration| (MigrationDirection::Up, migration))
+            .collect()
+    } else {
+        DaoManagerT::MIGRATIONS.iter()
+            .rev()
+            .filter(|migration| migration.version > target_version && migration.version <= version_in_db)
+            .map(|migration| (MigrationDirection::Down, migration))
+            .collect()
+    };
+    Ok(MigrationPlan {
+        table_name,
+        version_in_db,
+        target_version,
+        migrations,
+    })
+}
+
+// Migrates the table of the dao_manager to the target_version. Tables that do not exist are
+// created using init_db_schema(). Every migration is applied in its own transaction.
+// If dry_run is true, only the MigrationPlan is returned.
+pub fn migrate_db_schema<DaoManagerT: DaoManager>(dao_manager: &DaoManagerT, target_version: DbSchemaVersionType, dry_run: bool) -> Result<MigrationPlan> {
+    let plan = get_migration_plan(dao_manager, target_version)?;
+    if dry_run || !plan.has_changes() {
+        return Ok(plan);
+    }
+    let connection = dao_manager.get_connection();
+    let table_name = plan.table_name.as_str();
+    if plan.version_in_db == 0 {
+        connection.run_in_transaction(|| {
+            dao_manager.init_db_schema()?;
+            write_migration_record(connection, table_name, target_version, "Create table".to_string())
+        })?;
+        return Ok(plan);
+    }
+    for (direction, migration) in plan.migrations.iter() {
+        log::info!("[fn migrate_db_schema()] Migrating table '{}' {} to version {}: {}",
+            table_name, direction, migration.version, migration.description);
+        connection.run_in_transaction(|| {
+            match direction {
+                MigrationDirection::Up => {
+                    for step in migration.up.iter() {
+                        step.apply(connection, table_name)?;
+                    }
+                    write_migration_record(connection, table_name, migration.version, migration.description.to_string())
+                },
+                MigrationDirection::Down => {
+                    for step in migration.down.iter() {
+                        step.apply(connection, table_name)?;
+                    }
+                    write_migration_record(connection, table_name, migration.version - 1, format!("Revert: {}", migration.description))
+                },
+            }
+        }).map_err(|e| anyhow!("Error on migrating table '{}' {} to version {}: {}", table_name, direction, migration.version, e))?;
+    }
+    Ok(plan)
+}
+
+// Used by the --migrate CLI commands to migrate the table of a DaoManager according to the
+// migration_opt. Returns None if the table does not exist or is not the target of
+// the migration_opt. Tables that do not exist are created when they are used the first time.
+pub fn migrate_table<DaoManagerT: DaoManager>(options: DaoManagerT::OptionsType, migration_opt: &MigrationOptions) -> Result<Option<MigrationPlan>> {
+    let dao_manager = DaoManagerT::new(options);
+    let target_version = match &migration_opt.target {
+        Some(target) if target.table_name == dao_manager.get_table_name() => target.version,
+        Some(_) => return Ok(None),
+        None => DaoManagerT::DB_SCHEMA_VERSION,
+    };
+    if get_schema_version_in_database(&dao_manager)? == 0 {
+        return Ok(None);
+    }
+    migrate_db_schema(&dao_manager, target_version, migration_opt.dry_run).map(Some)
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib dao_migration::tests --features iota_bridge
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dao_helpers::{
+            DbFileBasedDaoManagerOptions,
+            update_db_schema_to_current_version,
+        },
+        user_manager::dao::{
+            user::{
+                User,
+                UserDaoManager,
+            },
+            message::{
+                Message,
+                MessageDaoManager,
+                MessageDataStoreOptions,
+            },
+        },
+    };
+
+    // Fixture databases created by older versions of the applications
+    const MANAGEMENT_CONSOLE_BASELINE_FIXTURE: &str = include_str!("../../test/db-migration/management-console-baseline.sql");
+    const MANAGEMENT_CONSOLE_USER_V2_FIXTURE: &str = include_str!("../../test/db-migration/management-console-user-v2.sql");
+    #[cfg(feature = "iota_bridge")]
+    const IOTA_BRIDGE_BASELINE_FIXTURE: &str = include_str!("../../test/db-migration/iota-bridge-baseline.sql");
+
+    const CHANNEL_ID: &str = "a1b2c3";
+
+    fn load_fixture(connection: &DbConnection, fixture: &str) {
+        for statement in fixture.split(';').map(|statement| statement.trim()).filter(|statement| !statement.is_empty()) {
+            connection.execute(statement).unwrap();
+        }
+    }
+
+    fn get_user_dao_manager() -> UserDaoManager {
+        UserDaoManager::new(DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() })
+    }
+
+    fn get_message_dao_manager() -> MessageDaoManager {
+        MessageDaoManager::new(MessageDataStoreOptions {
+            file_path_and_name: ":memory:".to_string(),
+            streams_channel_id: CHANNEL_ID.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_migration_target_from_str() {
+        assert_eq!(MigrationTarget::from_str("user=2").unwrap(), MigrationTarget { table_name: "user".to_string(), version: 2 });
+        assert!(MigrationTarget::from_str("user").is_err());
+        assert!(MigrationTarget::from_str("user=two").is_err());
+    }
+
+    #[test]
+    fn test_create_table() {
+        let users = get_user_dao_manager();
+        update_db_schema_to_current_version(&users).unwrap();
+        assert_eq!(get_schema_version_in_database(&users).unwrap(), UserDaoManager::DB_SCHEMA_VERSION);
+        let history = get_migration_history(users.get_connection(), "user").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].version, UserDaoManager::DB_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_upgrade_baseline_fixture() {
+        let users = get_user_dao_manager();
+        load_fixture(users.get_connection(), MANAGEMENT_CONSOLE_BASELINE_FIXTURE);
+        assert_eq!(get_schema_version_in_database(&users).unwrap(), INITIAL_SCHEMA_VERSION);
+
+        let plan = migrate_db_schema(&users, UserDaoManager::DB_SCHEMA_VERSION, false).unwrap();
+        assert_eq!(plan.version_in_db, INITIAL_SCHEMA_VERSION);
+        assert_eq!(plan.migrations.len(), UserDaoManager::MIGRATIONS.len());
+        assert_eq!(get_schema_version_in_database(&users).unwrap(), UserDaoManager::DB_SCHEMA_VERSION);
+
+        let user: User = users.get_item_from_db(&"c0ffee".to_string()).unwrap();
+        assert_eq!(user.name, "Sensor 1");
+        assert_eq!(user.status, "active");
+        assert_eq!(user.payload_decoder, "");
+        let versions: Vec<DbSchemaVersionType> = get_migration_history(users.get_connection(), "user").unwrap()
+            .into_iter()
+            .map(|record| record.version)
+            .collect();
+        assert_eq!(versions, vec![2, 3, 4, 5]);
+
+        // Already migrated tables are not changed
+        let plan = migrate_db_schema(&users, UserDaoManager::DB_SCHEMA_VERSION, false).unwrap();
+        assert!(!plan.has_changes());
+    }
+
+    #[test]
+    fn test_upgrade_partially_migrated_fixture() {
+        // The user table has been updated by the ad-hoc column checks of an older version
+        // so that the columns of version 2 already exist
+        let users = get_user_dao_manager();
+        load_fixture(users.get_connection(), MANAGEMENT_CONSOLE_USER_V2_FIXTURE);
+        update_db_schema_to_current_version(&users).unwrap();
+        assert_eq!(get_schema_version_in_database(&users).unwrap(), UserDaoManager::DB_SCHEMA_VERSION);
+        let user: User = users.get_item_from_db(&"c0ffee".to_string()).unwrap();
+        assert_eq!(user.status, "retired");
+        assert_eq!(user.retired_at_secs, 1700000000);
+    }
+
+    #[test]
+    fn test_upgrade_message_table_fixture() {
+        let messages = get_message_dao_manager();
+        load_fixture(messages.get_connection(), MANAGEMENT_CONSOLE_BASELINE_FIXTURE);
+        update_db_schema_to_current_version(&messages).unwrap();
+        assert_eq!(get_schema_version_in_database(&messages).unwrap(), MessageDaoManager::DB_SCHEMA_VERSION);
+        let message: Message = messages.get_item_from_db(&"msg-1".to_string()).unwrap();
+        assert_eq!(message.wrapped_binary, vec![1, 2, 3]);
+        assert_eq!(message.timestamp_secs, 0);
+        assert!(!message.has_link_info());
+        assert!(!message.integrity_checked);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let users = get_user_dao_manager();
+        load_fixture(users.get_connection(), MANAGEMENT_CONSOLE_BASELINE_FIXTURE);
+        let plan = migrate_db_schema(&users, UserDaoManager::DB_SCHEMA_VERSION, true).unwrap();
+        assert!(plan.has_changes());
+        assert!(plan.to_string().contains("ADD COLUMN payload_decoder"));
+        assert_eq!(get_schema_version_in_database(&users).unwrap(), INITIAL_SCHEMA_VERSION);
+        assert!(!users.get_connection().column_exists("user", "payload_decoder").unwrap());
+        assert!(!users.get_connection().table_exists(SCHEMA_MIGRATION_TABLE_NAME).unwrap());
+    }
+
+    #[test]
+    fn test_down_migration() {
+        let users = get_user_dao_manager();
+        load_fixture(users.get_connection(), MANAGEMENT_CONSOLE_BASELINE_FIXTURE);
+        update_db_schema_to_current_version(&users).unwrap();
+
+        let plan = migrate_db_schema(&users, 2, false).unwrap();
+        assert_eq!(plan.migrations.len(), UserDaoManager::DB_SCHEMA_VERSION as usize - 2);
+        assert!(plan.migrations.iter().all(|(direction, _)| *direction == MigrationDirection::Down));
+        assert_eq!(get_schema_version_in_database(&users).unwrap(), 2);
+        assert!(!users.get_connection().column_exists("user", "payload_decoder").unwrap());
+        assert!(users.get_connection().column_exists("user", "status").unwrap());
+        assert!(users.get_item_from_db(&"c0ffee".to_string()).is_ok());
+
+        // Versions that are not supported by this software are rejected
+        assert!(migrate_db_schema(&users, 0, false).is_err());
+        assert!(migrate_db_schema(&users, UserDaoManager::DB_SCHEMA_VERSION + 1, false).is_err());
+    }
+
+    #[test]
+    fn test_failing_migration_is_rolled_back() {
+        let users = get_user_dao_manager();
+        load_fixture(users.get_connection(), MANAGEMENT_CONSOLE_BASELINE_FIXTURE);
+        let connection = users.get_connection();
+        let failing_steps = [
+            MigrationStep::AddColumn(ColumnDefinition::new("some_column", ColumnType::Text, "NOT NULL DEFAULT ''")),
+            MigrationStep::Sql("UPDATE \"{table}\" SET not_existing_column = 1"),
+        ];
+        let result = connection.run_in_transaction(|| {
+            for step in failing_steps.iter() {
+                step.apply(connection, "user")?;
+            }
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert!(!connection.column_exists("user", "some_column").unwrap());
+    }
+
+    #[test]
+    fn test_migration_lists() {
+        check_migrations::<UserDaoManager>().unwrap();
+        check_migrations::<MessageDaoManager>().unwrap();
+        check_migrations::<crate::user_manager::dao::sensor_initialization::SensorInitializationDaoManager>().unwrap();
+        check_migrations::<crate::user_manager::dao::sync_cursor::SyncCursorDaoManager>().unwrap();
+        check_migrations::<crate::user_manager::dao::poi::PoiDaoManager>().unwrap();
+        check_migrations::<crate::user_manager::dao::channel_integrity::ChannelIntegrityDaoManager>().unwrap();
+    }
+
+    #[cfg(feature = "iota_bridge")]
+    #[test]
+    fn test_upgrade_iota_bridge_baseline_fixture() {
+        use crate::iota_bridge::dao::{
+            lora_wan_node::LoraWanNodeDaoManager,
+            pending_request::PendingRequestDaoManager,
+            buffered_message::BufferedMessageDaoManager,
+            queue_element::QueueElementDaoManager,
+            cluster_lease::ClusterLeaseDaoManager,
+            LoraWanNode,
+            PendingRequest,
+        };
+        check_migrations::<LoraWanNodeDaoManager>().unwrap();
+        check_migrations::<PendingRequestDaoManager>().unwrap();
+        check_migrations::<BufferedMessageDaoManager>().unwrap();
+        check_migrations::<QueueElementDaoManager>().unwrap();
+        check_migrations::<ClusterLeaseDaoManager>().unwrap();
+
+        let db_opt = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let nodes = LoraWanNodeDaoManager::new(db_opt.clone());
+        load_fixture(nodes.get_connection(), IOTA_BRIDGE_BASELINE_FIXTURE);
+        update_db_schema_to_current_version(&nodes).unwrap();
+        let node: LoraWanNode = nodes.get_item_from_db(&"1122334455667788".to_string()).unwrap();
+        assert_eq!(node.initialization_cnt, 3);
+        assert_eq!(node.initialization_epoch, 0);
+
+        let pending_requests = PendingRequestDaoManager::new(db_opt);
+        load_fixture(pending_requests.get_connection(), IOTA_BRIDGE_BASELINE_FIXTURE);
+        let plan = migrate_db_schema(&pending_requests, PendingRequestDaoManager::DB_SCHEMA_VERSION, false).unwrap();
+        assert_eq!(plan.migrations.len(), 2);
+        let pending_request: PendingRequest = pending_requests.get_item_from_db(&1).unwrap();
+        assert_eq!(pending_request.dev_eui, "1122334455667788");
+        assert_eq!(pending_request.created_at_secs, 0);
+    }
+}
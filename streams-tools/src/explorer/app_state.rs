@@ -4,23 +4,53 @@ use std::{
 };
 
 use crate::{
-    UserDataStore
+    UserDataStore,
+    user_manager::{
+        webhook::WebhookOptions,
+        message_feed::MessageFeed,
+    },
+    wallet::MultiChannelWalletType,
+    explorer::{
+        payload::{
+            PayloadDecoder,
+            PayloadDecoderRegistry,
+        },
+        auth::ApiTokenDataStore,
+        sync_channels_loop::SyncChannelsLoopOptions,
+    },
 };
 
 #[derive(Clone)]
 pub struct MessagesState {
     pub iota_node_url: String,
     pub wallet_filename: String,
+    pub wallet_type: MultiChannelWalletType,
     pub db_file_name: String,
-    //TODO: Needs to be managed by stronghold
     pub streams_user_serialization_password: String,
+    pub webhook_options: Option<WebhookOptions>,
+    pub payload_decoders: PayloadDecoderRegistry,
+    pub default_payload_decoder: Option<String>,
+    pub message_feed: MessageFeed,
 }
+
+impl MessagesState {
+    // Returns the decoder assigned to a node or the default_payload_decoder if the node has
+    // no decoder assigned. node_payload_decoder is the payload_decoder of the nodes User.
+    pub fn get_payload_decoder(&self, node_payload_decoder: &str) -> Option<Arc<dyn PayloadDecoder>> {
+        self.payload_decoders.get_node_decoder(node_payload_decoder, self.default_payload_decoder.as_deref())
+    }
+}
+
 unsafe impl Send for MessagesState {}
 unsafe impl Sync for MessagesState {}
 
 pub struct AppStateInner {
     pub messages: MessagesState,
     pub user_store: UserDataStore,
+    // None if the authentication is disabled
+    pub api_token_store: Option<ApiTokenDataStore>,
+    // Used to synchronize channels on request
+    pub sync_channels: SyncChannelsLoopOptions,
 }
 
 unsafe impl Send for AppStateInner {}
@@ -43,11 +73,13 @@ impl Clone for AppState {
 }
 
 impl AppState {
-    pub fn new(messages_state: MessagesState, user_store: UserDataStore) -> AppState {
+    pub fn new(messages_state: MessagesState, user_store: UserDataStore, api_token_store: Option<ApiTokenDataStore>, sync_channels: SyncChannelsLoopOptions) -> AppState {
         AppState(
             Arc::new(AppStateInner {
                 messages: messages_state,
                 user_store,
+                api_token_store,
+                sync_channels,
             })
         )
     }
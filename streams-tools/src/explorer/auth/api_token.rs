@@ -0,0 +1,331 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use rand::RngCore;
+
+use crypto::hashes::{
+    blake2b::Blake2b256,
+    Digest,
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureString,
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DaoDataStore,
+        DbFileBasedDaoManagerOptions,
+        DbSchemaVersionType,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        filter_items,
+        get_item_from_db,
+        find_all_items_in_db,
+        escape_sql_string,
+        update_db_schema_to_current_version,
+    }
+};
+
+const TOKEN_ID_LEN: usize = 8;
+const TOKEN_SECRET_LEN: usize = 32;
+const TOKEN_SEPARATOR: char = '.';
+
+// Roles of Message Explorer API tokens in ascending order of their permissions:
+//
+//      ReadOnly -> Read access to nodes and messages. Can be restricted to the nodes
+//                  having an external_id that starts with a specific prefix.
+//      Operator -> Read access to all nodes and messages. Can assign payload decoders
+//                  and trigger the synchronization of channels.
+//      Admin    -> Full access including updates of nodes (PUT /nodes).
+//                  Needed to access the admin api of the management-console.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum ApiRole {
+    ReadOnly,
+    Operator,
+    Admin,
+}
+
+impl ApiRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiRole::ReadOnly => "read_only",
+            ApiRole::Operator => "operator",
+            ApiRole::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for ApiRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ApiRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "read_only" => Ok(ApiRole::ReadOnly),
+            "operator" => Ok(ApiRole::Operator),
+            "admin" => Ok(ApiRole::Admin),
+            _ => bail!("Unknown ApiRole '{}'. Possible values are: read_only, operator, admin", s),
+        }
+    }
+}
+
+// An API token of the Message Explorer.
+// Clients send the token as bearer token: "Authorization: Bearer <token_id>.<secret>".
+// Only a hash of the secret is stored, so the secret can not be recovered after the token
+// has been created. The role is stored as string (see ApiRole::as_str()).
+// external_id_prefix is only used for read_only tokens. Empty means all nodes.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct ApiToken {
+    pub token_id: String,
+    pub secret_hash: String,
+    pub role: String,
+    pub external_id_prefix: String,
+    pub description: String,
+    pub created_at_secs: i64,
+}
+
+impl ApiToken {
+    // Returns the new ApiToken and the bearer token that needs to be handed to the client
+    pub fn new_random(role: ApiRole, external_id_prefix: &str, description: &str) -> Result<(Self, String)> {
+        if role != ApiRole::ReadOnly && !external_id_prefix.is_empty() {
+            bail!("An external_id prefix can only be used for {} tokens", ApiRole::ReadOnly);
+        }
+        let mut token_id = [0u8; TOKEN_ID_LEN];
+        let mut secret = [0u8; TOKEN_SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut token_id);
+        rand::thread_rng().fill_bytes(&mut secret);
+        let token_id = hex::encode(token_id);
+        let secret = hex::encode(secret);
+        let api_token = ApiToken {
+            token_id: token_id.clone(),
+            secret_hash: get_secret_hash(secret.as_str()),
+            role: role.to_string(),
+            external_id_prefix: external_id_prefix.to_string(),
+            description: description.to_string(),
+            created_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default(),
+        };
+        Ok((api_token, format!("{}{}{}", token_id, TOKEN_SEPARATOR, secret)))
+    }
+
+    pub fn get_role(&self) -> Result<ApiRole> {
+        ApiRole::from_str(self.role.as_str())
+    }
+
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        let secret_hash = get_secret_hash(secret);
+        // Compare in constant time to not reveal the number of matching characters
+        secret_hash.len() == self.secret_hash.len() && secret_hash.bytes()
+            .zip(self.secret_hash.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+// Token ids are created by ApiToken::new_random() and consist of TOKEN_ID_LEN * 2 lowercase hex characters
+fn is_valid_token_id(token_id: &str) -> bool {
+    token_id.len() == TOKEN_ID_LEN * 2 && token_id.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn get_secret_hash(secret: &str) -> String {
+    let mut hasher = Blake2b256::new();
+    hasher.update(secret.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// Returns the stored ApiToken if the bearer_token is valid
+pub fn authenticate_bearer_token(api_token_store: &ApiTokenDataStore, bearer_token: &str) -> Result<ApiToken> {
+    let (token_id, secret) = bearer_token.trim().split_once(TOKEN_SEPARATOR)
+        .ok_or_else(|| anyhow!("Malformed API token"))?;
+    if !is_valid_token_id(token_id) {
+        bail!("Malformed API token");
+    }
+    let api_token = api_token_store.get_item_read_only(&token_id.to_string())
+        .map_err(|_| anyhow!("Unknown API token"))?;
+    if !api_token.verify_secret(secret) {
+        bail!("Invalid API token");
+    }
+    Ok(api_token)
+}
+
+pub struct ApiTokenDaoManager {
+    connection: DbConnection,
+    options: DbFileBasedDaoManagerOptions,
+}
+
+impl Clone for ApiTokenDaoManager {
+    fn clone(&self) -> Self {
+        ApiTokenDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl DaoManager for ApiTokenDaoManager {
+    type ItemType = ApiToken;
+    type PrimaryKeyType = String;
+    type SerializationCallbackType = SerializationCallbackRefToClosureString;
+    type OptionsType = DbFileBasedDaoManagerOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "ApiToken";
+    const DAO_MANAGER_NAME: &'static str = "ApiTokenDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "token_id";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+
+    fn new(options: DbFileBasedDaoManagerOptions) -> Self {
+        ApiTokenDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn get_table_name(&self) -> String { "api_token".to_string() }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("secret_hash", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("role", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("external_id_prefix", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("description", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("created_at_secs", ColumnType::Integer, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for ApiToken: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, token_id: &Self::PrimaryKeyType) -> Result<ApiToken> {
+        get_item_from_db(self, token_id, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, token_id_starts_with: &str) -> Result<ApiToken>{
+        get_item_from_db(self, &token_id_starts_with.to_string(), MatchType::StartsWith)
+    }
+
+    fn find_all(&self, token_id_starts_with: &str, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        find_all_items_in_db(self, &token_id_starts_with.to_string(), limit)
+    }
+
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
+    }
+
+    fn write_item_to_db(&self, item: &ApiToken) -> Result<Self::PrimaryKeyType> {
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for ApiToken: {}", e))?;
+        Ok(item.token_id.clone())
+    }
+
+    fn get_serialization_callback(&self, _item: &Self::ItemType) -> Self::SerializationCallbackType {
+        unimplemented!()
+    }
+
+    fn delete_item_in_db(&self, key: &Self::PrimaryKeyType) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "DELETE FROM \"{}\" WHERE {} = '{}'",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                escape_sql_string(key)
+            ).as_str()
+        )?;
+        Ok(())
+    }
+}
+
+unsafe impl Send for ApiTokenDaoManager {}
+unsafe impl Sync for ApiTokenDaoManager {}
+
+pub type ApiTokenDataStore = DaoDataStore<ApiTokenDaoManager>;
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib explorer::auth::api_token::tests --features explorer
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_api_token_store() -> ApiTokenDataStore {
+        ApiTokenDataStore::new(DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() })
+    }
+
+    #[test]
+    fn test_authenticate_bearer_token() {
+        let store = get_api_token_store();
+        let (api_token, bearer_token) = ApiToken::new_random(ApiRole::ReadOnly, "4711", "Utility dashboard").unwrap();
+        store.write_item_to_db(&api_token).unwrap();
+
+        let authenticated = authenticate_bearer_token(&store, bearer_token.as_str()).unwrap();
+        assert_eq!(authenticated, api_token);
+        assert_eq!(authenticated.get_role().unwrap(), ApiRole::ReadOnly);
+        assert!(!authenticated.secret_hash.contains(bearer_token.split('.').last().unwrap()));
+
+        let wrong_secret = format!("{}.{}", api_token.token_id, "00".repeat(TOKEN_SECRET_LEN));
+        assert!(authenticate_bearer_token(&store, wrong_secret.as_str()).is_err());
+        assert!(authenticate_bearer_token(&store, "malformed").is_err());
+        let upper_case_id = format!("{}.{}", api_token.token_id.to_uppercase(), bearer_token.split('.').last().unwrap());
+        assert!(authenticate_bearer_token(&store, upper_case_id.as_str()).is_err());
+
+        store.delete_item_in_db(&api_token.token_id).unwrap();
+        assert!(authenticate_bearer_token(&store, bearer_token.as_str()).is_err());
+    }
+
+    #[test]
+    fn test_reject_sql_injection_in_token_id() {
+        let store = get_api_token_store();
+        let secret = "chosen-secret";
+        let injected_token_id = format!("x' UNION SELECT 'x','{}','admin','','',0 --", get_secret_hash(secret));
+        let bearer_token = format!("{}.{}", injected_token_id, secret);
+        assert!(authenticate_bearer_token(&store, bearer_token.as_str()).is_err());
+        // Also the DAO layer must not be injectable if the token_id check would be skipped
+        assert!(store.get_item_read_only(&injected_token_id).is_err());
+    }
+
+    #[test]
+    fn test_api_roles() {
+        for role in [ApiRole::ReadOnly, ApiRole::Operator, ApiRole::Admin] {
+            assert_eq!(ApiRole::from_str(role.as_str()).unwrap(), role);
+        }
+        assert!(ApiRole::ReadOnly < ApiRole::Operator);
+        assert!(ApiRole::Operator < ApiRole::Admin);
+        assert!(ApiRole::from_str("root").is_err());
+        assert!(ApiToken::new_random(ApiRole::Admin, "4711", "").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::str::FromStr;
+
+use hyper::http::StatusCode;
+
+use streams::Address;
+
+use crate::{
+    UserDataStore,
+    explorer::error::{
+        Result,
+        AppError,
+    },
+};
+
+use super::api_token::{
+    ApiRole,
+    ApiToken,
+};
+
+// Permissions of the client that sent a request. Controllers use the AuthContext
+// to restrict read_only clients to the nodes matching the external_id_prefix of their token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthContext {
+    pub token_id: String,
+    pub role: ApiRole,
+    pub external_id_prefix: String,
+}
+
+impl AuthContext {
+    // Used if the authentication of the Message Explorer is disabled
+    pub fn unrestricted() -> Self {
+        AuthContext {
+            token_id: String::new(),
+            role: ApiRole::Admin,
+            external_id_prefix: String::new(),
+        }
+    }
+
+    pub fn new_from_api_token(api_token: &ApiToken) -> anyhow::Result<Self> {
+        Ok(AuthContext {
+            token_id: api_token.token_id.clone(),
+            role: api_token.get_role()?,
+            external_id_prefix: api_token.external_id_prefix.clone(),
+        })
+    }
+
+    pub fn has_role(&self, required_role: ApiRole) -> bool {
+        self.role >= required_role
+    }
+
+    // Returns the external_id prefix the client is restricted to.
+    // Only read_only tokens having an external_id_prefix are restricted.
+    pub fn get_external_id_restriction(&self) -> Option<&str> {
+        if self.role == ApiRole::ReadOnly && !self.external_id_prefix.is_empty() {
+            Some(self.external_id_prefix.as_str())
+        } else {
+            None
+        }
+    }
+
+    pub fn can_access_external_id(&self, external_id: &str) -> bool {
+        match self.get_external_id_restriction() {
+            Some(prefix) => external_id.starts_with(prefix),
+            None => true,
+        }
+    }
+
+    pub(crate) fn check_external_id(&self, external_id: &str) -> Result<()> {
+        if self.can_access_external_id(external_id) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("The API token has no access to the node with external_id {}", external_id)))
+        }
+    }
+
+    pub(crate) fn check_channel_id(&self, user_store: &UserDataStore, channel_id: &str) -> Result<()> {
+        if self.get_external_id_restriction().is_none() {
+            return Ok(());
+        }
+        let user = user_store.get_item_read_only(&channel_id.to_string())
+            .map_err(|_| AppError::ChannelDoesNotExist(channel_id.to_string()))?;
+        if self.can_access_external_id(user.external_id.as_str()) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("The API token has no access to the channel {}", channel_id)))
+        }
+    }
+
+    // message_id is a Streams message id including the channel id
+    pub(crate) fn check_message_id(&self, user_store: &UserDataStore, message_id: &str) -> Result<()> {
+        if self.get_external_id_restriction().is_none() {
+            return Ok(());
+        }
+        let address = Address::from_str(message_id).map_err(|_| AppError::GenericWithMessage(
+            StatusCode::BAD_REQUEST,
+            format!("Could not parse the channel-id from message-id {}. The message-id seems to be malformed.", message_id)
+        ))?;
+        self.check_channel_id(user_store, address.base().to_string().as_str())
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib explorer::auth::auth_context::tests --features explorer
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_auth_context(role: ApiRole, external_id_prefix: &str) -> AuthContext {
+        AuthContext {
+            token_id: "0123456789abcdef".to_string(),
+            role,
+            external_id_prefix: external_id_prefix.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_external_id_restriction() {
+        let restricted = get_auth_context(ApiRole::ReadOnly, "4711");
+        assert_eq!(restricted.get_external_id_restriction(), Some("4711"));
+        assert!(restricted.can_access_external_id("47110001"));
+        assert!(!restricted.can_access_external_id("08150001"));
+        assert!(restricted.check_external_id("08150001").is_err());
+
+        assert!(get_auth_context(ApiRole::ReadOnly, "").can_access_external_id("08150001"));
+        assert!(get_auth_context(ApiRole::Operator, "4711").can_access_external_id("08150001"));
+        assert!(AuthContext::unrestricted().get_external_id_restriction().is_none());
+    }
+
+    #[test]
+    fn test_roles() {
+        let operator = get_auth_context(ApiRole::Operator, "");
+        assert!(operator.has_role(ApiRole::ReadOnly));
+        assert!(operator.has_role(ApiRole::Operator));
+        assert!(!operator.has_role(ApiRole::Admin));
+        assert!(AuthContext::unrestricted().has_role(ApiRole::Admin));
+    }
+}
@@ -0,0 +1,146 @@
+use hyper::http::{
+    Method,
+    Request,
+    header::{
+        AUTHORIZATION,
+        WWW_AUTHENTICATE,
+    },
+    HeaderValue,
+};
+
+use axum::{
+    middleware::Next,
+    response::{
+        IntoResponse,
+        Response,
+    },
+};
+
+use crate::explorer::{
+    app_state::AppState,
+    error::AppError,
+};
+
+use super::{
+    api_token::{
+        ApiRole,
+        ApiTokenDataStore,
+        authenticate_bearer_token,
+    },
+    auth_context::AuthContext,
+};
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+// Paths that can be accessed without an API token
+pub fn is_public_path(path: &str) -> bool {
+    path == "/" || path.starts_with("/swagger-ui") || path.starts_with("/api-docs")
+}
+
+// Returns the role that is at least needed to process the request
+pub fn get_required_role(method: &Method, path: &str) -> ApiRole {
+    if method == Method::PUT {
+        if path.starts_with("/nodes/") {
+            return ApiRole::Admin;
+        }
+        if path.starts_with("/payload/decoders/assignment") {
+            return ApiRole::Operator;
+        }
+    }
+    if method == Method::POST && path.starts_with("/nodes/") && path.ends_with("/sync") {
+        return ApiRole::Operator;
+    }
+    ApiRole::ReadOnly
+}
+
+fn unauthorized(msg: &str) -> Response {
+    let mut response = AppError::Unauthorized(msg.to_string()).into_response();
+    response.headers_mut().insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+// Authenticates the bearer token contained in the Authorization header of the request.
+// If the authentication fails, the Unauthorized response to be sent to the client is returned.
+// Also used by the admin api of the management-console.
+pub fn authenticate_request<B>(api_token_store: &ApiTokenDataStore, request: &Request<B>) -> Result<AuthContext, Response> {
+    let bearer_token = match request.headers().get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix(BEARER_PREFIX)) {
+        Some(token) => token,
+        None => return Err(unauthorized("A bearer token is needed in the Authorization header")),
+    };
+    authenticate_bearer_token(api_token_store, bearer_token)
+        .and_then(|api_token| AuthContext::new_from_api_token(&api_token))
+        .map_err(|e| unauthorized(e.to_string().as_str()))
+}
+
+// Authenticates the bearer token of the request and inserts an AuthContext into the
+// request extensions which is used by the controllers to restrict the accessible nodes.
+// If the authentication is disabled (no ApiTokenDataStore in the AppState), every request
+// gets an unrestricted AuthContext.
+pub(crate) async fn authenticate<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    if is_public_path(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let app_state = match request.extensions().get::<AppState>() {
+        Some(app_state) => app_state.clone(),
+        None => return AppError::InternalServerError("AppState is not available".to_string()).into_response(),
+    };
+
+    let auth_context = match app_state.api_token_store.as_ref() {
+        None => AuthContext::unrestricted(),
+        Some(api_token_store) => match authenticate_request(api_token_store, &request) {
+            Ok(auth_context) => auth_context,
+            Err(response) => return response,
+        }
+    };
+
+    let required_role = get_required_role(request.method(), request.uri().path());
+    if !auth_context.has_role(required_role) {
+        return AppError::Forbidden(format!(
+            "The request needs an API token with role {}. The role of the used token is {}",
+            required_role,
+            auth_context.role
+        )).into_response();
+    }
+
+    request.extensions_mut().insert(auth_context);
+    next.run(request).await
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib explorer::auth::auth_middleware::tests --features explorer
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::http::StatusCode;
+    use crate::dao_helpers::DbFileBasedDaoManagerOptions;
+
+    #[test]
+    fn test_get_required_role() {
+        assert_eq!(get_required_role(&Method::GET, "/nodes/abcd"), ApiRole::ReadOnly);
+        assert_eq!(get_required_role(&Method::PUT, "/nodes/abcd"), ApiRole::Admin);
+        assert_eq!(get_required_role(&Method::PUT, "/payload/decoders/assignment"), ApiRole::Operator);
+        assert_eq!(get_required_role(&Method::POST, "/payload/decode"), ApiRole::ReadOnly);
+        assert_eq!(get_required_role(&Method::POST, "/nodes/abcd/sync"), ApiRole::Operator);
+        assert!(is_public_path("/swagger-ui/index.html"));
+        assert!(!is_public_path("/messages"));
+    }
+
+    #[test]
+    fn test_authenticate_request_rejects_injected_token() {
+        let api_token_store = ApiTokenDataStore::new(DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() });
+        // The injected row contains the Blake2b256 hash of the secret "secret" and role admin
+        let request = Request::builder()
+            .uri("/messages")
+            .header(AUTHORIZATION, "Bearer x' UNION SELECT 'x','ae4aafb3c7d8616b739f05a30d06b4e2236fdea712168159f346e8a3efd57d9e','admin','','',0 --.secret")
+            .body(())
+            .unwrap();
+        match authenticate_request(&api_token_store, &request) {
+            Ok(_) => panic!("The injected bearer token must not be accepted"),
+            Err(response) => assert_eq!(response.status(), StatusCode::UNAUTHORIZED),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+mod api_token;
+mod auth_context;
+mod auth_middleware;
+
+pub use {
+    api_token::*,
+    auth_context::*,
+    auth_middleware::*,
+};
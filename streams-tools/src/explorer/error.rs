@@ -27,6 +27,10 @@ pub enum AppError {
     AtLeastOneConditionNeeded(String),
     /// A channel with the specified channel-id does not exist
     ChannelDoesNotExist(String),
+    /// A valid API token is needed
+    Unauthorized(String),
+    /// The API token does not permit the request
+    Forbidden(String),
     /// A generic error occurred, see http status code and message for more details
     GenericWithMessage(StatusCode, String),
 //     /// A generic error occurred, see http status code for more details
@@ -48,6 +52,14 @@ impl IntoResponse for AppError {
                 StatusCode::BAD_REQUEST,
                 format!("The channel with channel-id {} does not exist", channel_id),
             ),
+            Self::Unauthorized(msg) => (
+                StatusCode::UNAUTHORIZED,
+                format!("authentication failed: {}", msg),
+            ),
+            Self::Forbidden(msg) => (
+                StatusCode::FORBIDDEN,
+                format!("access denied: {}", msg),
+            ),
             Self::GenericWithMessage(status, msg) => (status, msg),
 //            Self::Generic(status) => (status, "".to_string()),
         };
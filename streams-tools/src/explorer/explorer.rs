@@ -7,7 +7,10 @@ use hyper::{
     http::{HeaderValue, Request}
 };
 
-use anyhow::Result;
+use anyhow::{
+    Result,
+    bail,
+};
 
 use tower_http::{
     trace::{
@@ -17,6 +20,7 @@ use tower_http::{
     },
     cors::{
         Any,
+        AllowOrigin,
         CorsLayer,
     },
     LatencyUnit
@@ -28,35 +32,78 @@ use tracing::{
 };
 
 use axum::{
-    http::header::CONTENT_TYPE,
+    http::header::{
+        CONTENT_TYPE,
+        AUTHORIZATION,
+    },
+    middleware,
     Extension
 };
 
 use crate::{
     UserDataStore,
-    user_manager::multi_channel_management::MultiChannelManagerOptions,
-    threading_helpers::run_background_worker_in_own_thread
+    user_manager::{
+        multi_channel_management::MultiChannelManagerOptions,
+        proof_of_inclusion::PoiClientOptions,
+        webhook::WebhookOptions,
+        message_feed::MessageFeed,
+        message_retention::MessageRetentionPolicy,
+    },
+    threading_helpers::run_background_worker_in_own_thread,
+    wallet::MultiChannelWalletType,
+    dao_helpers::DbFileBasedDaoManagerOptions,
 };
 
 use super::{
+    auth::{
+        ApiTokenDataStore,
+        authenticate,
+    },
     app_state::{
         AppState,
         MessagesState,
     },
     router::router,
+    payload::PayloadDecoderRegistry,
     sync_channels_loop::{
         SyncChannelsLoopOptions,
-        SyncChannelsWorker
-    }
+        SyncChannelsWorker,
+        LiveSyncChannelsWorker,
+    },
+    message_retention_loop::{
+        MessageRetentionLoopOptions,
+        MessageRetentionWorker,
+    },
+    webhook_retry_loop::{
+        WebhookRetryLoopOptions,
+        WebhookRetryWorker,
+    },
 };
 
-pub fn cors() -> CorsLayer {
-    let swagger_url = "http://localhost:8001";
+pub const DEFAULT_CORS_ALLOWED_ORIGIN: &str = "http://localhost:8001";
+
+// allowed_origins: Origins that are allowed to access the API from a browser.
+//                  Use "*" to allow any origin. If empty, DEFAULT_CORS_ALLOWED_ORIGIN is used.
+pub fn cors(allowed_origins: &[String]) -> Result<CorsLayer> {
+    let allow_origin = if allowed_origins.is_empty() {
+        AllowOrigin::exact(HeaderValue::from_static(DEFAULT_CORS_ALLOWED_ORIGIN))
+    } else if allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        let mut origins = Vec::<HeaderValue>::new();
+        for origin in allowed_origins {
+            match origin.parse::<HeaderValue>() {
+                Ok(header_value) => origins.push(header_value),
+                Err(_) => bail!("Invalid CORS origin '{}'", origin),
+            }
+        }
+        AllowOrigin::list(origins)
+    };
 
-    CorsLayer::new()
-        .allow_origin(swagger_url.parse::<HeaderValue>().unwrap())
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
         .allow_methods(Any)
-        .allow_headers(vec![CONTENT_TYPE])
+        .allow_headers(vec![CONTENT_TYPE, AUTHORIZATION]))
 }
 
 fn init_tracing() {
@@ -71,51 +118,148 @@ fn init_tracing() {
 pub struct ExplorerOptions {
     pub iota_node: String,
     pub wallet_filename: String,
+    pub wallet_type: MultiChannelWalletType,
     pub db_file_name: String,
     pub listener_ip_address_port: String,
     pub streams_user_serialization_password: String,
+    pub poi_client_options: Option<PoiClientOptions>,
+    pub webhook_options: Option<WebhookOptions>,
+    // Decoders that can be assigned to nodes. Register additional decoders here to
+    // support custom payload formats.
+    pub payload_decoders: PayloadDecoderRegistry,
+    // Decoder used for nodes that have no decoder assigned
+    pub default_payload_decoder: Option<String>,
+    // Interval used to synchronize recently active channels while clients
+    // are connected to the /messages/stream endpoint
+    pub live_sync_interval_secs: u64,
+    // Maximum number of channels synchronized in parallel by the background sync loop
+    pub sync_channels_max_workers: usize,
+    // If true, every request (except the OpenAPI documentation) needs a valid API token.
+    // API tokens are stored in the local SQLite database file (db_file_name).
+    pub auth_enabled: bool,
+    // Origins allowed by the CORS policy. See function cors() for more details.
+    pub cors_allowed_origins: Vec<String>,
+    // Retention policy used to prune the cached messages in the background.
+    // Nothing is pruned if the policy is not enabled.
+    pub message_retention: MessageRetentionPolicy,
 }
 
+pub const DEFAULT_LIVE_SYNC_INTERVAL_SECS: u64 = 10;
+
 impl From<ExplorerOptions> for MessagesState {
     fn from(value: ExplorerOptions) -> Self {
         MessagesState{
             iota_node_url: value.iota_node,
             wallet_filename: value.wallet_filename,
+            wallet_type: value.wallet_type,
             db_file_name: value.db_file_name,
             streams_user_serialization_password: value.streams_user_serialization_password,
+            webhook_options: value.webhook_options,
+            payload_decoders: value.payload_decoders,
+            default_payload_decoder: value.default_payload_decoder,
+            message_feed: MessageFeed::new(),
         }
     }
 }
 
-async fn run_sync_channels_loop_in_background(user_store: UserDataStore, options: ExplorerOptions) {
-    let sync_channels_loop_options = SyncChannelsLoopOptions::new(
+fn get_sync_channels_loop_options(user_store: UserDataStore, options: ExplorerOptions, message_feed: MessageFeed) -> SyncChannelsLoopOptions {
+    let milestone_timestamp_client_options = PoiClientOptions::new(options.iota_node.as_str());
+    let mut sync_channels_loop_options = SyncChannelsLoopOptions::new(
         user_store,
         MultiChannelManagerOptions {
             iota_node: options.iota_node,
             wallet_filename: options.wallet_filename,
+            wallet_type: options.wallet_type,
             streams_user_serialization_password: options.streams_user_serialization_password,
             message_data_store_for_msg_caching: None,
             inx_collector_access_throttle_sleep_time_millisecs: Some(100),
         },
         options.db_file_name
     );
+    sync_channels_loop_options.poi_client_options = options.poi_client_options;
+    sync_channels_loop_options.milestone_timestamp_client_options = Some(milestone_timestamp_client_options);
+    sync_channels_loop_options.webhook_options = options.webhook_options;
+    sync_channels_loop_options.message_feed = Some(message_feed);
+    sync_channels_loop_options.live_sync_interval_in_secs = options.live_sync_interval_secs;
+    sync_channels_loop_options.sync_channels_max_workers = options.sync_channels_max_workers;
+    sync_channels_loop_options
+}
 
-    let _join = run_background_worker_in_own_thread::<SyncChannelsWorker>(sync_channels_loop_options);
+async fn run_sync_channels_loop_in_background(sync_channels_loop_options: SyncChannelsLoopOptions) {
+    let _join = run_background_worker_in_own_thread::<SyncChannelsWorker>(sync_channels_loop_options.clone());
+    let _join_live_sync = run_background_worker_in_own_thread::<LiveSyncChannelsWorker>(sync_channels_loop_options);
+}
+
+fn run_message_retention_loop_in_background(user_store: UserDataStore, options: &ExplorerOptions, sync_channels_loop_options: &SyncChannelsLoopOptions) {
+    if options.message_retention.is_enabled() {
+        let _join = run_background_worker_in_own_thread::<MessageRetentionWorker>(MessageRetentionLoopOptions::new(
+            user_store,
+            options.db_file_name.clone(),
+            options.message_retention.clone(),
+            sync_channels_loop_options.channel_sync_locks.clone(),
+        ));
+    }
+}
+
+fn run_webhook_retry_loop_in_background(options: &ExplorerOptions) {
+    if let Some(webhook_options) = options.webhook_options.as_ref() {
+        let _join = run_background_worker_in_own_thread::<WebhookRetryWorker>(WebhookRetryLoopOptions::new(
+            webhook_options.clone(),
+            options.db_file_name.clone(),
+        ));
+    }
 }
 
 pub async fn run_explorer_api_server(user_store: UserDataStore, options: ExplorerOptions) -> Result<()> {
     init_tracing();
 
     let addr: SocketAddr = options.listener_ip_address_port.parse()?;
+    if let Some(default_decoder) = options.default_payload_decoder.as_ref() {
+        if let Err(e) = options.payload_decoders.validate_decoder_name(default_decoder.as_str()) {
+            bail!("Invalid default payload decoder. {}", e);
+        }
+    }
+    if options.live_sync_interval_secs == 0 {
+        bail!("The live sync interval must be greater than 0");
+    }
+    if options.sync_channels_max_workers == 0 {
+        bail!("The maximum number of sync workers must be greater than 0");
+    }
+
+    let cors_layer = cors(&options.cors_allowed_origins)?;
+
+    let api_token_store = if options.auth_enabled {
+        Some(ApiTokenDataStore::new(DbFileBasedDaoManagerOptions {
+            file_path_and_name: options.db_file_name.clone()
+        }))
+    } else {
+        tracing::warn!("Authentication is disabled. Every client has full access to the Message Explorer API");
+        None
+    };
+
+    let messages_state: MessagesState = options.clone().into();
+    // The ChannelSyncLocks of the SyncChannelsLoopOptions are shared by the sync loops, the
+    // retention loop and all endpoints that load the Streams client state of a channel
+    // (e.g. /nodes/{channel_id}/sync, GET /messages and the message export)
+    let sync_channels_loop_options = get_sync_channels_loop_options(
+        user_store.clone(),
+        options.clone(),
+        messages_state.message_feed.clone()
+    );
 
     let app_state = AppState::new(
-        options.clone().into(),
+        messages_state,
         user_store.clone(),
+        api_token_store,
+        sync_channels_loop_options.clone(),
     );
 
-    run_sync_channels_loop_in_background(user_store.clone(), options.clone()).await;
+    run_message_retention_loop_in_background(user_store.clone(), &options, &sync_channels_loop_options);
+    run_webhook_retry_loop_in_background(&options);
+    run_sync_channels_loop_in_background(sync_channels_loop_options).await;
 
     let app = router()
+        .layer(middleware::from_fn(authenticate))
         .layer(TraceLayer::new_for_http()
             .make_span_with(|_request: &Request<Body>| {
                 tracing::debug_span!("http-request")
@@ -134,7 +278,7 @@ pub async fn run_explorer_api_server(user_store: UserDataStore, options: Explore
                 .level(Level::INFO)
                 .latency_unit(LatencyUnit::Micros))
         )
-        .layer(cors())
+        .layer(cors_layer)
         .layer(Extension(app_state));
 
     tracing::info!("listening on {}", addr);
@@ -0,0 +1,450 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::{
+    Result,
+    Error as AnyError,
+    anyhow,
+    bail,
+};
+
+use async_trait::async_trait;
+
+use serde::Serialize;
+
+use tokio::sync::mpsc::{
+    channel,
+    Sender,
+    Receiver,
+};
+
+use lets::transport::tangle::Client;
+
+use crate::{
+    UserDataStore,
+    MessageManager,
+    explorer::{
+        messages::Message,
+        payload::{
+            DecodedPayload,
+            PayloadDecoder,
+            PayloadDecoderRegistry,
+        },
+        sync_channels_loop::ChannelSyncLocks,
+    },
+    user_manager::{
+        dao::message::{
+            MessageDataStore,
+            MessageDataStoreOptions,
+            MessageTimeFilter,
+        },
+        message_indexer::MessageIndexer,
+        webhook::WebhookOptions,
+        message_feed::MessageFeed,
+        multi_channel_management::{
+            MultiChannelManagerOptions,
+            get_channel_manager_for_channel_id,
+            get_read_only_channel_manager_for_channel_id,
+        },
+    },
+    threading_helpers::{
+        Worker,
+        run_background_worker_in_own_thread,
+    },
+    dao_helpers::Limit,
+    helpers::get_tangle_address_from_strings,
+};
+
+// Number of cached messages that are fetched from the message cache at once
+const EXPORT_PAGE_SIZE: usize = 100;
+
+// Number of exported lines that can be buffered before the export waits
+// for the consumer of the export Receiver
+const EXPORT_CHANNEL_CAPACITY: usize = 64;
+
+const CSV_HEADER: [&str; 11] = [
+    "channel_id",
+    "external_id",
+    "message_id",
+    "msg_index",
+    "timestamp_secs",
+    "received_at_secs",
+    "milestone_timestamp",
+    "payload",
+    "decoder",
+    "decoded",
+    "decode_error",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::JsonLines => "application/jsonl; charset=utf-8",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" => Ok(ExportFormat::JsonLines),
+            _ => bail!("Unknown export format '{}'. Use 'csv' or 'jsonl'.", s),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file_extension())
+    }
+}
+
+// A single exported message. Used as JSON Lines document and as CSV row.
+#[derive(Serialize, Debug)]
+pub struct ExportedMessage {
+    pub channel_id: String,
+    pub external_id: String,
+    pub message_id: String,
+    pub msg_index: String,
+    pub timestamp_secs: i64,
+    pub received_at_secs: i64,
+    pub milestone_timestamp: i64,
+    // Hex encoded decrypted payload
+    pub payload: String,
+    pub decoded: Option<DecodedPayload>,
+}
+
+impl ExportedMessage {
+    pub fn new(channel_id: &str, external_id: &str, message: Message) -> Self {
+        ExportedMessage {
+            channel_id: channel_id.to_string(),
+            external_id: external_id.to_string(),
+            payload: hex::encode(message.get_payload()),
+            message_id: message.id,
+            msg_index: message.msg_index,
+            timestamp_secs: message.timestamp_secs,
+            received_at_secs: message.received_at_secs,
+            milestone_timestamp: message.milestone_timestamp,
+            decoded: message.decoded,
+        }
+    }
+
+    // Returns the line (including the line break) representing this message in the specified format
+    pub fn to_line(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Csv => Ok(self.to_csv_line()),
+            ExportFormat::JsonLines => Ok(format!("{}\n", serde_json::to_string(self)?)),
+        }
+    }
+
+    fn to_csv_line(&self) -> String {
+        let (decoder, decoded, decode_error) = match self.decoded.as_ref() {
+            Some(decoded) => (
+                decoded.decoder.clone(),
+                if decoded.is_decoded() { decoded.value.to_string() } else { String::new() },
+                decoded.error.clone(),
+            ),
+            None => (String::new(), String::new(), String::new()),
+        };
+        let fields = [
+            self.channel_id.clone(),
+            self.external_id.clone(),
+            self.message_id.clone(),
+            self.msg_index.clone(),
+            self.timestamp_secs.to_string(),
+            self.received_at_secs.to_string(),
+            self.milestone_timestamp.to_string(),
+            self.payload.clone(),
+            decoder,
+            decoded,
+            decode_error,
+        ];
+        to_csv_row(fields.iter().map(|field| field.as_str()))
+    }
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_csv_row<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    let mut ret_val = fields.map(escape_csv_field).collect::<Vec<String>>().join(",");
+    ret_val.push_str("\r\n");
+    ret_val
+}
+
+#[derive(Clone)]
+pub struct MessageExportOptions {
+    pub channel_ids: Vec<String>,
+    pub format: ExportFormat,
+    pub time_filter: MessageTimeFilter,
+    pub multi_channel_mngr_opt: MultiChannelManagerOptions,
+    pub db_file_name: String,
+    pub payload_decoders: PayloadDecoderRegistry,
+    pub default_payload_decoder: Option<String>,
+    // New messages received while the channels are synchronized are notified using these options
+    pub webhook_options: Option<WebhookOptions>,
+    // New messages received while the channels are synchronized are published to this feed
+    pub message_feed: Option<MessageFeed>,
+    // Each channel is locked while it is exported. If the export runs in the Message Explorer
+    // process, these must be the ChannelSyncLocks of the SyncChannelsLoopOptions.
+    pub channel_sync_locks: ChannelSyncLocks,
+}
+
+impl MessageExportOptions {
+    pub fn new(channel_ids: Vec<String>, format: ExportFormat, multi_channel_mngr_opt: MultiChannelManagerOptions, db_file_name: String) -> Self {
+        MessageExportOptions {
+            channel_ids,
+            format,
+            time_filter: MessageTimeFilter::default(),
+            multi_channel_mngr_opt,
+            db_file_name,
+            payload_decoders: PayloadDecoderRegistry::default(),
+            default_payload_decoder: None,
+            webhook_options: None,
+            message_feed: None,
+            channel_sync_locks: ChannelSyncLocks::default(),
+        }
+    }
+}
+
+// Exports the decrypted messages of all channels specified in the options.
+// The export runs in its own thread and provides the exported data line by line
+// via the returned Receiver. The export is paged and waits for the Receiver to
+// consume the provided lines so that the messages are never loaded into memory at once.
+// If the export fails, the error is provided as last item. Dropping the Receiver stops the export.
+pub fn start_message_export(user_store: &UserDataStore, options: MessageExportOptions) -> Result<Receiver<Result<String>>> {
+    if options.channel_ids.is_empty() {
+        bail!("At least one channel-id is needed to export messages");
+    }
+    let mut channels = Vec::<ExportChannel>::new();
+    for channel_id in options.channel_ids.iter() {
+        let user = user_store.get_item_read_only(channel_id)
+            .map_err(|_| anyhow!("The channel with channel-id {} does not exist", channel_id))?;
+        channels.push(ExportChannel {
+            channel_id: channel_id.clone(),
+            external_id: user.external_id,
+            payload_decoder: options.payload_decoders.get_node_decoder(
+                user.payload_decoder.as_str(),
+                options.default_payload_decoder.as_deref()
+            ),
+        });
+    }
+    let (sender, receiver) = channel(EXPORT_CHANNEL_CAPACITY);
+    let _join_handle = run_background_worker_in_own_thread::<ExportWorker>(ExportWorkerOptions {
+        channels,
+        u_store: user_store.clone(),
+        options,
+        sender,
+    });
+    Ok(receiver)
+}
+
+#[derive(Clone)]
+struct ExportChannel {
+    channel_id: String,
+    external_id: String,
+    payload_decoder: Option<Arc<dyn PayloadDecoder>>,
+}
+
+struct ExportWorkerOptions {
+    channels: Vec<ExportChannel>,
+    u_store: UserDataStore,
+    options: MessageExportOptions,
+    sender: Sender<Result<String>>,
+}
+
+struct ExportWorker;
+
+#[async_trait(?Send)]
+impl Worker for ExportWorker {
+    type OptionsType = ExportWorkerOptions;
+    type ResultType = ();
+    type ErrorType = AnyError;
+
+    async fn run(opt: ExportWorkerOptions) -> Result<()> {
+        if opt.options.format == ExportFormat::Csv {
+            if opt.sender.send(Ok(to_csv_row(CSV_HEADER.iter().copied()))).await.is_err() {
+                return Ok(());
+            }
+        }
+        for channel in opt.channels.iter() {
+            match Self::export_channel(&opt, channel).await {
+                Ok(true) => {},
+                // The Receiver has been dropped
+                Ok(false) => return Ok(()),
+                Err(e) => {
+                    log::error!("[fn run()] Error while exporting messages of channel {}: {}", channel.channel_id, e);
+                    let _ = opt.sender.send(Err(e)).await;
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ExportWorker {
+    // Returns false if the Receiver has been dropped
+    async fn export_channel(opt: &ExportWorkerOptions, channel: &ExportChannel) -> Result<bool> {
+        let msg_data_store_opt = MessageDataStoreOptions {
+            file_path_and_name: opt.options.db_file_name.clone(),
+            streams_channel_id: channel.channel_id.clone(),
+        };
+        let mut multi_channel_mngr_opt = opt.options.multi_channel_mngr_opt.clone();
+        multi_channel_mngr_opt.message_data_store_for_msg_caching = Some(msg_data_store_opt.clone());
+        Self::sync_channel(opt, channel, &multi_channel_mngr_opt).await?;
+
+        // The messages are decrypted using a copy of the synced Streams client state, so that the
+        // ChannelSyncLocks do not need to be held while the lines are consumed by the Receiver
+        let mut channel_manager = get_read_only_channel_manager_for_channel_id(
+            channel.channel_id.as_str(),
+            &opt.u_store,
+            &multi_channel_mngr_opt,
+        ).await?;
+        let user = channel_manager.user.as_mut()
+            .ok_or_else(|| anyhow!("The channel with channel-id {} does not exist", channel.channel_id))?;
+
+        let msg_data_store = MessageDataStore::new(msg_data_store_opt);
+        let mut offset: usize = 0;
+        loop {
+            let (msg_meta_data_list, _) = msg_data_store.filter_ordered(
+                opt.options.time_filter.get_conditions(),
+                opt.options.time_filter.get_order_by(),
+                Some(Limit { limit: EXPORT_PAGE_SIZE, offset }),
+            )?;
+            if msg_meta_data_list.is_empty() {
+                break;
+            }
+            offset += msg_meta_data_list.len();
+            for msg_meta_data in msg_meta_data_list {
+                let address = get_tangle_address_from_strings(channel.channel_id.as_str(), msg_meta_data.message_id.as_str())
+                    .map_err(|e| anyhow!(e))?;
+                let lets_msg = user.receive_message(address).await
+                    .map_err(|e| anyhow!("Could not receive message {}: {}", address, e))?;
+                let mut message: Message = lets_msg.into();
+                message.set_timestamps(&msg_meta_data);
+                if let Some(decoder) = channel.payload_decoder.as_ref() {
+                    message.decode_payload(decoder.as_ref());
+                }
+                let line = ExportedMessage::new(channel.channel_id.as_str(), channel.external_id.as_str(), message)
+                    .to_line(opt.options.format)?;
+                if opt.sender.send(Ok(line)).await.is_err() {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    // Fetches new messages of the channel into the message cache
+    async fn sync_channel(opt: &ExportWorkerOptions, channel: &ExportChannel, multi_channel_mngr_opt: &MultiChannelManagerOptions) -> Result<()> {
+        // The Streams client state is stored when the channel_manager is dropped,
+        // so the guard must outlive the channel_manager
+        let _guard = opt.options.channel_sync_locks.lock(channel.channel_id.as_str()).await;
+        let mut channel_manager = get_channel_manager_for_channel_id(
+            channel.channel_id.as_str(),
+            &opt.u_store,
+            multi_channel_mngr_opt,
+        ).await?;
+        let user = channel_manager.user.as_mut()
+            .ok_or_else(|| anyhow!("The channel with channel-id {} does not exist", channel.channel_id))?;
+
+        let mut msg_mngr = MessageManager::<Client<MessageIndexer>>::new(
+            user,
+            channel.channel_id.clone(),
+            opt.options.db_file_name.clone()
+        );
+        // New messages received here will not be received again by the sync_channels_loop
+        // so that webhook notifications need to be send here too
+        if let Some(webhook_options) = opt.options.webhook_options.as_ref() {
+            msg_mngr.set_webhook_options(webhook_options.clone(), channel.external_id.as_str());
+        }
+        if let Some(message_feed) = opt.options.message_feed.as_ref() {
+            msg_mngr.set_message_feed(message_feed.clone(), channel.external_id.as_str());
+        }
+        let _new_messages_cnt = msg_mngr.sync().await?;
+        Ok(())
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib explorer::message_export::tests --features explorer
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_exported_message(decoded: Option<DecodedPayload>) -> ExportedMessage {
+        ExportedMessage {
+            channel_id: "cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000".to_string(),
+            external_id: "504F53E833055C50".to_string(),
+            message_id: "cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000:84d48c0cc279564b467f7e74".to_string(),
+            msg_index: "84d48c0cc279564b467f7e74".to_string(),
+            timestamp_secs: 1677628800,
+            received_at_secs: 1677628810,
+            milestone_timestamp: 1677628800,
+            payload: "7b7d".to_string(),
+            decoded,
+        }
+    }
+
+    #[test]
+    fn test_export_format() {
+        assert_eq!(ExportFormat::from_str("csv").unwrap(), ExportFormat::Csv);
+        assert_eq!(ExportFormat::from_str("jsonl").unwrap(), ExportFormat::JsonLines);
+        assert!(ExportFormat::from_str("xml").is_err());
+    }
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("abc"), "abc");
+        assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv_field("{\"a\":1}"), "\"{\"\"a\"\":1}\"");
+    }
+
+    #[test]
+    fn test_csv_line() {
+        let decoded = DecodedPayload::new(&crate::explorer::payload::JsonDecoder, b"{\"meter_id\":1}");
+        let line = get_exported_message(Some(decoded)).to_line(ExportFormat::Csv).unwrap();
+        assert!(line.ends_with(",7b7d,json,\"{\"\"meter_id\"\":1}\",\r\n"));
+        assert_eq!(line.split(',').count(), CSV_HEADER.len());
+
+        let line = get_exported_message(None).to_line(ExportFormat::Csv).unwrap();
+        assert!(line.ends_with(",7b7d,,,\r\n"));
+    }
+
+    #[test]
+    fn test_json_line() {
+        let line = get_exported_message(None).to_line(ExportFormat::JsonLines).unwrap();
+        assert!(line.ends_with("}\n"));
+        let value: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+        assert_eq!(value["external_id"], "504F53E833055C50");
+        assert_eq!(value["timestamp_secs"], 1677628800);
+        assert!(value["decoded"].is_null());
+    }
+}
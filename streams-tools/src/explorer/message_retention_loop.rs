@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use anyhow::{
+    Result,
+    Error as AnyError
+};
+
+use async_trait::async_trait;
+
+use tokio::time::interval;
+
+use crate::{
+    UserDataStore,
+    user_manager::message_retention::{
+        MessageRetentionPolicy,
+        PruneResult,
+        prune_channel_messages,
+    },
+    threading_helpers::Worker,
+    dao_helpers::vacuum_database,
+};
+
+use super::sync_channels_loop::{
+    ChannelSyncLocks,
+    now_secs,
+};
+
+pub const DEFAULT_MESSAGE_RETENTION_INTERVAL_SECS: u64 = 24 * 3600;
+
+#[derive(Clone)]
+pub struct MessageRetentionLoopOptions {
+    pub user_store: UserDataStore,
+    pub message_data_store_file_path_and_name: String,
+    pub policy: MessageRetentionPolicy,
+    pub prune_interval_in_secs: u64,
+    // Channels that are currently synchronized are skipped and pruned by the next run.
+    // Must be the ChannelSyncLocks of the SyncChannelsLoopOptions.
+    pub channel_sync_locks: ChannelSyncLocks,
+}
+
+unsafe impl Send for MessageRetentionLoopOptions {}
+unsafe impl Sync for MessageRetentionLoopOptions {}
+
+impl MessageRetentionLoopOptions {
+    pub fn new(user_store: UserDataStore, msg_data_store_file_path_name: String, policy: MessageRetentionPolicy, channel_sync_locks: ChannelSyncLocks) -> Self {
+        MessageRetentionLoopOptions {
+            user_store,
+            message_data_store_file_path_and_name: msg_data_store_file_path_name,
+            policy,
+            prune_interval_in_secs: DEFAULT_MESSAGE_RETENTION_INTERVAL_SECS,
+            channel_sync_locks,
+        }
+    }
+}
+
+// Prunes the message cache of all channels according to the MessageRetentionPolicy
+// and compacts the message cache database afterwards
+pub struct MessageRetentionWorker;
+
+#[async_trait(?Send)]
+impl Worker for MessageRetentionWorker {
+    type OptionsType = MessageRetentionLoopOptions;
+    type ResultType = ();
+    type ErrorType = AnyError;
+
+    async fn run(opt: MessageRetentionLoopOptions) -> Result<()> {
+        run_message_retention_loop(opt).await;
+        Ok(())
+    }
+}
+
+pub async fn run_message_retention_loop(opt: MessageRetentionLoopOptions) {
+    log::info!("[fn run_message_retention_loop] Using {}", opt.policy);
+    let mut interval = interval(Duration::from_secs(opt.prune_interval_in_secs));
+    loop {
+        interval.tick().await;
+        match prune_message_cache(&opt) {
+            Ok(prune_result) => {
+                log::info!("[fn run_message_retention_loop] Finished pruning the message cache: {}", prune_result);
+            }
+            Err(err) => {
+                log::error!("[fn run_message_retention_loop] Got error from prune_message_cache(): {}", err);
+            }
+        }
+    }
+}
+
+fn prune_message_cache(opt: &MessageRetentionLoopOptions) -> Result<PruneResult> {
+    let mut ret_val = PruneResult::default();
+    let users = opt.user_store.find_all_unlimited()?;
+    let now = now_secs();
+    for user in users {
+        let channel_id = user.streams_channel_id.as_str();
+        let _guard = match opt.channel_sync_locks.try_lock(channel_id) {
+            Some(guard) => guard,
+            None => {
+                log::debug!("[fn prune_message_cache] Skipping channel {} which is currently synced", channel_id);
+                continue;
+            }
+        };
+        match prune_channel_messages(opt.message_data_store_file_path_and_name.as_str(), channel_id, &opt.policy, now) {
+            Ok(prune_result) => {
+                if prune_result.has_changes() {
+                    log::info!("[fn prune_message_cache] Pruned channel {}: {}", channel_id, prune_result);
+                }
+                ret_val.add(&prune_result);
+            },
+            Err(e) => {
+                log::error!("[fn prune_message_cache] Could not prune channel {}. Continuing loop over users. Error: {}", channel_id, e);
+            }
+        }
+    }
+    if ret_val.has_changes() {
+        log::info!("[fn prune_message_cache] Compacting database '{}'", opt.message_data_store_file_path_and_name);
+        vacuum_database(opt.message_data_store_file_path_and_name.as_str())?;
+    }
+    Ok(ret_val)
+}
@@ -1,5 +1,19 @@
 use axum::{
-    response::IntoResponse,
+    http::{
+        StatusCode,
+        header::{
+        CONTENT_TYPE,
+        CONTENT_DISPOSITION,
+        },
+    },
+    response::{
+        IntoResponse,
+        sse::{
+            Sse,
+            KeepAlive,
+        },
+    },
+    body::StreamBody,
     extract::{
         Path,
         Query
@@ -12,6 +26,7 @@ use crate::{
     explorer::{
         error::AppError,
         app_state::AppState,
+        auth::AuthContext,
         shared::{
             Page,
             PagingOptions,
@@ -25,7 +40,10 @@ use super::{
     messages_dto::{
         Message,
         MessageId,
+        MessagePoi,
         MessageConditions,
+        MessageExportConditions,
+        MessageStreamConditions,
     },
     messages_service as service,
 };
@@ -33,13 +51,16 @@ use super::{
 /// List messages of a node
 ///
 /// List messages of a Streams channel of a specific node. Binary data are provided as hex encoded strings.
+/// Messages can be filtered by a time range and sorted by their timestamp.
+/// If a payload decoder is assigned to the node, the decoded payload is provided and messages
+/// can be filtered by fields of the decoded payload.
 #[utoipa::path(
     get,
     operation_id = "messages_index",
     path = "/messages",
     responses(
         (status = 200, description = "Successfully responded with list of Messages. Binary data are provided as hex encoded strings."),
-        (status = 400, description = "Channel with specified channel-id does not exist"),
+        (status = 400, description = "Channel with specified channel-id does not exist or invalid time range, sort order or decoded field filter"),
     ),
     params(
         MessageConditions,
@@ -50,10 +71,16 @@ pub (crate) async fn index(
     Query(conditions): Query<MessageConditions>,
     optional_paging: Option<Query<PagingOptions>>,
     Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthContext>,
 ) -> Result<Json<Page<Message>>, AppError> {
+    let time_filter = conditions.get_time_filter()
+        .map_err(|e| AppError::GenericWithMessage(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let decoded_field_filter = conditions.get_decoded_field_filter()
+        .map_err(|e| AppError::GenericWithMessage(StatusCode::BAD_REQUEST, e.to_string()))?;
     if let Some(channel_id) = conditions.channel_id {
+        auth.check_channel_id(&state.user_store, channel_id.as_str())?;
         let paging = get_paging(optional_paging);
-        let (ret_val, items_cnt_total) = service::index(&state, channel_id.as_str(), paging.clone()).await?;
+        let (ret_val, items_cnt_total) = service::index(&state, channel_id.as_str(), time_filter, decoded_field_filter, paging.clone()).await?;
         wrap_with_page_meta_and_json_serialize(ret_val, paging.unwrap(), items_cnt_total)
     } else {
         Err(AppError::AtLeastOneConditionNeeded("'channel_id' is missing".to_string()))
@@ -75,7 +102,144 @@ pub (crate) async fn index(
         MessageId,
     )
 )]
-pub (crate) async fn get(Path(id): Path<MessageId>, Extension(state): Extension<AppState>) -> impl IntoResponse {
-    let ret_val = service::get(&state.messages, &state.user_store, id.message_id.as_str()).await.map(|resp| Json(resp));
+pub (crate) async fn get(Path(id): Path<MessageId>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> impl IntoResponse {
+    if let Err(e) = auth.check_message_id(&state.user_store, id.message_id.as_str()) {
+        return e.into_response();
+    }
+    let ret_val = service::get(&state, id.message_id.as_str()).await.map(|resp| Json(resp));
     ret_val.into_response()
-}
\ No newline at end of file
+}
+
+/// Get the Proof of Inclusion of a message
+///
+/// Get the Proof of Inclusion of a specific message. Proofs of Inclusion are fetched from an inx-poi
+/// endpoint when the channel of the message is synchronized.
+#[utoipa::path(
+    get,
+    operation_id = "messages_get_poi",
+    path = "/messages/{message_id}/poi",
+    responses(
+        (status = 200, description = "Successfully responded the Proof of Inclusion of the requested message", body = MessagePoi),
+        (status = 404, description = "No Proof of Inclusion available for the specified msg_id")
+    ),
+    params(
+        MessageId,
+    )
+)]
+pub (crate) async fn get_poi(Path(id): Path<MessageId>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> impl IntoResponse {
+    if let Err(e) = auth.check_message_id(&state.user_store, id.message_id.as_str()) {
+        return e.into_response();
+    }
+    let ret_val = service::get_poi(&state.messages, id.message_id.as_str()).map(|resp| Json(resp));
+    ret_val.into_response()
+}
+
+/// Export the Proof of Inclusion of a message
+///
+/// Download the Proof of Inclusion of a specific message as inx-poi JSON document.
+/// The document can be validated using the 'validate' endpoint of inx-poi.
+#[utoipa::path(
+    get,
+    operation_id = "messages_export_poi",
+    path = "/messages/{message_id}/poi/export",
+    responses(
+        (status = 200, description = "Successfully responded the inx-poi JSON document", content_type = "application/json"),
+        (status = 404, description = "No Proof of Inclusion available for the specified msg_id")
+    ),
+    params(
+        MessageId,
+    )
+)]
+pub (crate) async fn export_poi(Path(id): Path<MessageId>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> impl IntoResponse {
+    if let Err(e) = auth.check_message_id(&state.user_store, id.message_id.as_str()) {
+        return e.into_response();
+    }
+    match service::get_poi(&state.messages, id.message_id.as_str()) {
+        Ok(poi) => {
+            let content_disposition = format!("attachment; filename=\"poi-{}.json\"", poi.block_id);
+            (
+                [
+                    (CONTENT_TYPE, "application/json".to_string()),
+                    (CONTENT_DISPOSITION, content_disposition),
+                ],
+                poi.proof_of_inclusion.to_string()
+            ).into_response()
+        },
+        Err(e) => e.into_response()
+    }
+}
+
+/// Export the messages of one or more nodes
+///
+/// Export all decrypted messages of the specified Streams channels as CSV or JSON Lines file.
+/// Each exported message contains the channel-id, external id, message id, msg_index, timestamps,
+/// the hex encoded payload and the decoded payload if a payload decoder is assigned to the node.
+/// The export is streamed so that large message histories can be downloaded.
+#[utoipa::path(
+    get,
+    operation_id = "messages_export",
+    path = "/messages/export",
+    responses(
+        (status = 200, description = "Successfully streamed the exported messages", content_type = "text/csv"),
+        (status = 400, description = "Channel with specified channel-id does not exist or invalid export format or time range"),
+    ),
+    params(
+        MessageExportConditions,
+    )
+)]
+pub (crate) async fn export(Query(conditions): Query<MessageExportConditions>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> impl IntoResponse {
+    match service::export(&state, &conditions, &auth) {
+        Ok((format, receiver)) => {
+            let content_disposition = format!("attachment; filename=\"messages.{}\"", format.file_extension());
+            let lines = futures::stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|line| (
+                    line.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+                    receiver
+                ))
+            });
+            (
+                [
+                    (CONTENT_TYPE, format.content_type().to_string()),
+                    (CONTENT_DISPOSITION, content_disposition),
+                ],
+                StreamBody::new(lines)
+            ).into_response()
+        },
+        Err(e) => e.into_response()
+    }
+}
+
+/// Stream new messages
+///
+/// Stream new messages as Server-Sent Events as soon as they are received by the Message Explorer.
+/// Each event contains a Message (see '/messages/{message_id}') as JSON data. The event id is the message id.
+/// Messages can be filtered by 'channel_id' or 'external_id'. If no filter is specified, the messages of all
+/// nodes are streamed. The channels of the filtered node and recently active channels are synchronized
+/// frequently while clients are connected.
+#[utoipa::path(
+    get,
+    operation_id = "messages_stream",
+    path = "/messages/stream",
+    responses(
+        (status = 200, description = "Successfully subscribed. New messages are streamed as Server-Sent Events", content_type = "text/event-stream"),
+        (status = 400, description = "Channel with specified channel-id does not exist or 'channel_id' and 'external_id' have been combined"),
+        (status = 404, description = "A Node with the specified external_id does not exist"),
+    ),
+    params(
+        MessageStreamConditions,
+    )
+)]
+pub (crate) async fn stream(Query(conditions): Query<MessageStreamConditions>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> impl IntoResponse {
+    match service::subscribe_message_stream(&state, &conditions, &auth) {
+        Ok(subscription) => {
+            let events = futures::stream::unfold((state, subscription), |(state, mut subscription)| async move {
+                service::next_message_stream_event(&state, &mut subscription).await
+                    .map(|event| (event, (state, subscription)))
+            });
+            Sse::new(events)
+                .keep_alive(KeepAlive::default())
+                .into_response()
+        },
+        Err(e) => e.into_response()
+    }
+}
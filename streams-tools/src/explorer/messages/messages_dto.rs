@@ -18,11 +18,37 @@ use utoipa::{
     ToSchema
 };
 
+use serde_json::Value as JsonValue;
+
 use streams::{
     Message as StreamsMessage,
     Address,
 };
 
+use crate::{
+    dao_helpers::SortOrder,
+    explorer::{
+        payload::{
+            DecodedPayload,
+            PayloadDecoder,
+        },
+        message_export::ExportFormat,
+    },
+    user_manager::{
+        dao::{
+            poi::Poi,
+            message::{
+                Message as DaoMessage,
+                MessageTimeFilter,
+            },
+        },
+        message_feed::{
+            MessageFeedEvent,
+            MessageFeedFilter,
+        },
+    },
+};
+
 #[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct Message {
     pub id: String,
@@ -34,6 +60,16 @@ pub struct Message {
     pub private_data_decrypted: Vec<u8>,
     pub msg_index: String,
     pub streams_content: String,
+    /// Unix timestamp (seconds) of the message. Equals 'milestone_timestamp' if known, otherwise 'received_at_secs'.
+    /// Messages cached by older Message Explorer versions have a 0 timestamp.
+    pub timestamp_secs: i64,
+    /// Unix timestamp (seconds) when the message has been received by the Message Explorer
+    pub received_at_secs: i64,
+    /// Unix timestamp of the milestone referencing the block of the message.
+    /// 0 if the block has not been referenced by a milestone or the milestone could not be determined.
+    pub milestone_timestamp: i64,
+    /// Payload decoded by the payload decoder of the Node. Null if no decoder is assigned to the Node.
+    pub decoded: Option<DecodedPayload>,
 }
 
 impl From<StreamsMessage> for Message {
@@ -60,6 +96,33 @@ impl From<StreamsMessage> for Message {
             private_data_decrypted: private_data_decrypted.to_vec(),
             msg_index: hex::encode(streams_msg.address.to_msg_index()),
             streams_content,
+            timestamp_secs: 0,
+            received_at_secs: 0,
+            milestone_timestamp: 0,
+            decoded: None,
+        }
+    }
+}
+
+impl From<MessageFeedEvent> for Message {
+    fn from(event: MessageFeedEvent) -> Self {
+        Message {
+            id: event.message_id,
+            public_text: from_utf8(event.public_payload.as_slice())
+                .unwrap_or("")
+                .to_string(),
+            private_text_decrypted: from_utf8(event.masked_payload.as_slice())
+                .unwrap_or("")
+                .to_string(),
+            public_data: event.public_payload,
+            private_data_decrypted: event.masked_payload,
+            msg_index: event.msg_index,
+            streams_content: "".to_string(),
+            // The milestone referencing the message is not known yet
+            timestamp_secs: event.received_at_secs,
+            received_at_secs: event.received_at_secs,
+            milestone_timestamp: 0,
+            decoded: None,
         }
     }
 }
@@ -75,18 +138,244 @@ impl Message {
             public_data: [].to_vec(),
             private_data_decrypted: [].to_vec(),
             streams_content: "".to_string(),
+            timestamp_secs: 0,
+            received_at_secs: 0,
+            milestone_timestamp: 0,
+            decoded: None,
         })
     }
+
+    pub fn set_timestamps(&mut self, dao_message: &DaoMessage) {
+        self.timestamp_secs = dao_message.timestamp_secs;
+        self.received_at_secs = dao_message.received_at_secs;
+        self.milestone_timestamp = dao_message.milestone_timestamp;
+    }
+
+    // Sensors send their payload masked so that public_data is only used if there is no masked payload
+    pub fn get_payload(&self) -> &[u8] {
+        if self.private_data_decrypted.is_empty() {
+            self.public_data.as_slice()
+        } else {
+            self.private_data_decrypted.as_slice()
+        }
+    }
+
+    pub fn decode_payload(&mut self, decoder: &dyn PayloadDecoder) {
+        self.decoded = Some(DecodedPayload::new(decoder, self.get_payload()));
+    }
 }
 
 pub type MessageList = Vec<Message>;
 
+/// Proof of Inclusion of a message, fetched from an inx-poi endpoint
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct MessagePoi {
+    pub message_id: String,
+    /// Id of the block containing the message
+    pub block_id: String,
+    /// Index of the milestone referencing the block
+    pub milestone_index: u32,
+    /// Unix timestamp of the milestone referencing the block
+    pub milestone_timestamp: u32,
+    /// True if the Merkle proof has been verified against the inclusionMerkleRoot of the milestone
+    pub is_verified: bool,
+    /// The Proof of Inclusion as been created by inx-poi
+    #[schema(value_type = Object)]
+    pub proof_of_inclusion: JsonValue,
+}
+
+impl MessagePoi {
+    pub fn new_from_poi(message_id: String, poi: Poi) -> Result<Self> {
+        Ok(MessagePoi {
+            message_id,
+            block_id: poi.block_id,
+            milestone_index: poi.milestone_index,
+            milestone_timestamp: poi.milestone_timestamp,
+            is_verified: poi.is_verified,
+            proof_of_inclusion: serde_json::from_str(poi.proof_of_inclusion.as_str())?,
+        })
+    }
+}
+
 /// Filter existing messages
 #[derive(Serialize, Deserialize, Debug, IntoParams)]
 pub struct MessageConditions {
     /// Filter by Streams channel-id. Find existing channel-id of existing nodes using the '/node' endpoint
     #[param(required=true, max_length=80, min_length=80, example ="cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000")]
     pub channel_id: Option<String>,
+    /// Only messages having a 'timestamp_secs' greater than or equal to this unix timestamp (seconds)
+    #[param(example = 1677628800)]
+    pub from: Option<i64>,
+    /// Only messages having a 'timestamp_secs' less than or equal to this unix timestamp (seconds)
+    #[param(example = 1680307199)]
+    pub to: Option<i64>,
+    /// Sort messages by 'timestamp_secs': 'asc' or 'desc'. If not specified, messages are listed in the order of the message cache.
+    #[param(example = "asc")]
+    pub sort: Option<String>,
+    /// Filter by a field of the decoded payload. Use the name of a top level field (e.g. 'meter_id')
+    /// or a JSON pointer (e.g. '/meter/id'). Needs to be combined with 'decoded_equals', 'decoded_min' or 'decoded_max'.
+    /// Messages that could not be decoded are filtered out.
+    #[param(min_length=1, example = "meter_id")]
+    pub decoded_field: Option<String>,
+    /// The decoded field equals this value
+    #[param(example = "60251504")]
+    pub decoded_equals: Option<String>,
+    /// The decoded field is a number greater than or equal to this value
+    pub decoded_min: Option<f64>,
+    /// The decoded field is a number less than or equal to this value
+    pub decoded_max: Option<f64>,
+}
+
+// Filters messages by a field of their decoded payload
+#[derive(Clone, Debug)]
+pub struct DecodedFieldFilter {
+    pub field: String,
+    pub equals: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl DecodedFieldFilter {
+    pub fn matches(&self, decoded: Option<&DecodedPayload>) -> bool {
+        let field_value = match decoded.filter(|decoded| decoded.is_decoded()).and_then(|decoded| decoded.get_field(self.field.as_str())) {
+            Some(value) => value,
+            None => return false,
+        };
+        if let Some(equals) = self.equals.as_ref() {
+            let matches_equals = match field_value {
+                JsonValue::String(text) => text == equals,
+                JsonValue::Number(number) => match (number.as_f64(), equals.parse::<f64>()) {
+                    (Some(number), Ok(expected)) => number == expected,
+                    _ => number.to_string() == *equals,
+                },
+                other => other.to_string() == *equals,
+            };
+            if !matches_equals {
+                return false;
+            }
+        }
+        if self.min.is_some() || self.max.is_some() {
+            let number = match field_value.as_f64() {
+                Some(number) => number,
+                None => return false,
+            };
+            if self.min.map_or(false, |min| number < min) || self.max.map_or(false, |max| number > max) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl MessageConditions {
+    pub fn get_time_filter(&self) -> Result<MessageTimeFilter> {
+        let sort_order = match self.sort.as_deref() {
+            None => None,
+            Some("asc") => Some(SortOrder::Ascending),
+            Some("desc") => Some(SortOrder::Descending),
+            Some(other) => return Err(anyhow!("Unknown sort order '{}'. Use 'asc' or 'desc'.", other)),
+        };
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(anyhow!("'from' ({}) must not be greater than 'to' ({})", from, to));
+            }
+        }
+        Ok(MessageTimeFilter {
+            from_secs: self.from,
+            to_secs: self.to,
+            sort_order,
+        })
+    }
+
+    pub fn get_decoded_field_filter(&self) -> Result<Option<DecodedFieldFilter>> {
+        let has_criteria = self.decoded_equals.is_some() || self.decoded_min.is_some() || self.decoded_max.is_some();
+        match self.decoded_field.as_ref() {
+            Some(field) => {
+                if !has_criteria {
+                    return Err(anyhow!("'decoded_field' needs to be combined with 'decoded_equals', 'decoded_min' or 'decoded_max'"));
+                }
+                Ok(Some(DecodedFieldFilter {
+                    field: field.clone(),
+                    equals: self.decoded_equals.clone(),
+                    min: self.decoded_min,
+                    max: self.decoded_max,
+                }))
+            },
+            None => {
+                if has_criteria {
+                    return Err(anyhow!("'decoded_equals', 'decoded_min' and 'decoded_max' need to be combined with 'decoded_field'"));
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Select the messages to be exported
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+pub struct MessageExportConditions {
+    /// One or more comma separated Streams channel-ids. Find existing channel-id of existing nodes using the '/node' endpoint
+    #[param(example ="cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000")]
+    pub channel_id: String,
+    /// Export format: 'csv' or 'jsonl' (JSON Lines). Default is 'csv'.
+    #[param(example = "csv")]
+    pub format: Option<String>,
+    /// Only messages having a 'timestamp_secs' greater than or equal to this unix timestamp (seconds)
+    #[param(example = 1677628800)]
+    pub from: Option<i64>,
+    /// Only messages having a 'timestamp_secs' less than or equal to this unix timestamp (seconds)
+    #[param(example = 1680307199)]
+    pub to: Option<i64>,
+}
+
+impl MessageExportConditions {
+    pub fn get_channel_ids(&self) -> Vec<String> {
+        self.channel_id.split(',')
+            .map(|channel_id| channel_id.trim())
+            .filter(|channel_id| !channel_id.is_empty())
+            .map(|channel_id| channel_id.to_string())
+            .collect()
+    }
+
+    pub fn get_format(&self) -> Result<ExportFormat> {
+        self.format.as_deref().map_or(Ok(ExportFormat::Csv), ExportFormat::from_str)
+    }
+
+    pub fn get_time_filter(&self) -> Result<MessageTimeFilter> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(anyhow!("'from' ({}) must not be greater than 'to' ({})", from, to));
+            }
+        }
+        Ok(MessageTimeFilter {
+            from_secs: self.from,
+            to_secs: self.to,
+            sort_order: None,
+        })
+    }
+}
+
+/// Select the messages of the live message stream
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+pub struct MessageStreamConditions {
+    /// Only stream messages of the node having this Streams channel-id
+    #[param(max_length=80, min_length=80, example ="cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000")]
+    pub channel_id: Option<String>,
+    /// Only stream messages of the node having this external id (in example a LoRaWAN DevEUI).
+    /// Messages of a re-initialized sensor are streamed using the external id of the sensor too.
+    #[param(min_length=1, example ="504F53E833055C50")]
+    pub external_id: Option<String>,
+}
+
+impl MessageStreamConditions {
+    pub fn get_filter(&self) -> Result<MessageFeedFilter> {
+        match (self.channel_id.as_ref(), self.external_id.as_ref()) {
+            (Some(_), Some(_)) => Err(anyhow!("'channel_id' and 'external_id' must not be combined")),
+            (Some(channel_id), None) => Ok(MessageFeedFilter::ChannelId(channel_id.clone())),
+            (None, Some(external_id)) => Ok(MessageFeedFilter::ExternalId(external_id.clone())),
+            (None, None) => Ok(MessageFeedFilter::All),
+        }
+    }
 }
 
 /// Specify the message id
@@ -8,7 +8,11 @@ use super::messages_controller as controller;
 pub fn routes() -> Router {
     Router::new()
         .route("/", routing::get(controller::index))
+        .route("/export", routing::get(controller::export))
+        .route("/stream", routing::get(controller::stream))
         .route("/:message_id", routing::get(controller::get))
+        .route("/:message_id/poi", routing::get(controller::get_poi))
+        .route("/:message_id/poi/export", routing::get(controller::export_poi))
 }
 
-pub const INFO: &str = "Search for messages of a specific node in the tangle and view message details";
\ No newline at end of file
+pub const INFO: &str = "Search for messages of a specific node in the tangle, view message details, export messages and stream new messages";
\ No newline at end of file
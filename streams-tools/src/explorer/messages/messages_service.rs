@@ -1,4 +1,7 @@
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 
@@ -8,6 +11,10 @@ use streams::{
     Address,
 };
 
+use tokio::sync::mpsc::Receiver;
+
+use axum::response::sse::Event;
+
 use lets::transport::tangle::Client;
 
 use crate::{
@@ -25,13 +32,36 @@ use crate::{
             AppError,
         },
         shared::PagingOptions,
+        payload::PayloadDecoder,
+        auth::AuthContext,
+        message_export::{
+            ExportFormat,
+            MessageExportOptions,
+            start_message_export,
+        },
+        sync_channels_loop::ChannelSyncLocks,
     },
     user_manager::{
-        dao::message::MessageDataStoreOptions,
+        dao::{
+            message::{
+                MessageDataStore,
+                MessageDataStoreOptions,
+                MessageTimeFilter,
+                Message as DaoMessage,
+            },
+            poi::PoiDataStore,
+        },
         message_indexer::MessageIndexer,
+        webhook::WebhookOptions,
+        message_feed::{
+            MessageFeed,
+            MessageFeedFilter,
+            MessageFeedSubscription,
+        },
         multi_channel_management::{
             MultiChannelManagerOptions,
-            get_channel_manager_for_channel_id
+            get_channel_manager_for_channel_id,
+            get_user_for_external_id,
         },
         MessageManager,
         UserDataStore,
@@ -44,6 +74,10 @@ use super::{
     messages_dto::{
         Message,
         MessageList,
+        MessagePoi,
+        MessageExportConditions,
+        MessageStreamConditions,
+        DecodedFieldFilter,
     },
 };
 
@@ -52,6 +86,7 @@ impl MessagesState {
         MultiChannelManagerOptions{
             iota_node: self.iota_node_url.clone(),
             wallet_filename: self.wallet_filename.clone(),
+            wallet_type: self.wallet_type.clone(),
             streams_user_serialization_password: self.streams_user_serialization_password.clone(),
             message_data_store_for_msg_caching: None,
             inx_collector_access_throttle_sleep_time_millisecs: None
@@ -59,13 +94,40 @@ impl MessagesState {
     }
 }
 
-pub(crate) async fn index(state: &AppState, channel_id: &str, paging_opt: Option<PagingOptions>) -> Result<(MessageList, usize)> {
-    run_worker_in_own_thread::<IndexWorker>(IndexWorkerOptions::new(
+// Page size used to fetch all cached messages of a channel that need to be
+// filtered by their decoded payload
+const DECODED_FILTER_PAGE_SIZE: usize = 1000;
+
+pub(crate) async fn index(
+    state: &AppState,
+    channel_id: &str,
+    time_filter: MessageTimeFilter,
+    decoded_field_filter: Option<DecodedFieldFilter>,
+    paging_opt: Option<PagingOptions>
+) -> Result<(MessageList, usize)> {
+    let mut worker_options = IndexWorkerOptions::new(
         &state.messages,
         &state.user_store,
+        &state.sync_channels.channel_sync_locks,
         channel_id,
+        time_filter,
         paging_opt,
-    )).await
+    );
+    if decoded_field_filter.is_some() && worker_options.payload_decoder.is_none() {
+        return Err(AppError::GenericWithMessage(
+            StatusCode::BAD_REQUEST,
+            format!("Messages can not be filtered by decoded fields because no payload decoder is assigned to channel {}", channel_id)
+        ));
+    }
+    worker_options.decoded_field_filter = decoded_field_filter;
+    run_worker_in_own_thread::<IndexWorker>(worker_options).await
+}
+
+fn get_payload_decoder_for_channel(messages: &MessagesState, user_store: &UserDataStore, channel_id: &str) -> Option<Arc<dyn PayloadDecoder>> {
+    let node_payload_decoder = user_store.get_item_read_only(&channel_id.to_string())
+        .map(|user| user.payload_decoder)
+        .unwrap_or_default();
+    messages.get_payload_decoder(node_payload_decoder.as_str())
 }
 
 #[derive(Clone)]
@@ -74,11 +136,17 @@ struct IndexWorkerOptions {
     u_store: UserDataStore,
     multi_channel_mngr_opt: MultiChannelManagerOptions,
     db_file_name: String,
+    time_filter: MessageTimeFilter,
+    decoded_field_filter: Option<DecodedFieldFilter>,
+    payload_decoder: Option<Arc<dyn PayloadDecoder>>,
     paging_opt: Option<PagingOptions>,
+    webhook_options: Option<WebhookOptions>,
+    message_feed: MessageFeed,
+    channel_sync_locks: ChannelSyncLocks,
 }
 
 impl IndexWorkerOptions {
-    pub fn new(messages: &MessagesState, user_store: &UserDataStore, channel_id: &str, paging_opt: Option<PagingOptions>) -> IndexWorkerOptions {
+    pub fn new(messages: &MessagesState, user_store: &UserDataStore, channel_sync_locks: &ChannelSyncLocks, channel_id: &str, time_filter: MessageTimeFilter, paging_opt: Option<PagingOptions>) -> IndexWorkerOptions {
         let mut multi_channel_mngr_opt = messages.as_multi_channel_manager_options();
         multi_channel_mngr_opt.message_data_store_for_msg_caching = Some(MessageDataStoreOptions {
             file_path_and_name: messages.db_file_name.clone(),
@@ -89,7 +157,13 @@ impl IndexWorkerOptions {
             u_store: user_store.clone(),
             multi_channel_mngr_opt,
             db_file_name: messages.db_file_name.clone(),
+            time_filter,
+            decoded_field_filter: None,
+            payload_decoder: get_payload_decoder_for_channel(messages, user_store, channel_id),
             paging_opt,
+            webhook_options: messages.webhook_options.clone(),
+            message_feed: messages.message_feed.clone(),
+            channel_sync_locks: channel_sync_locks.clone(),
         }
     }
 }
@@ -103,6 +177,9 @@ impl Worker for IndexWorker {
     type ErrorType = AppError;
 
     async fn run(opt: IndexWorkerOptions) -> Result<(MessageList, usize)> {
+        // The Streams client state is stored when the channel_manager is dropped,
+        // so the guard must outlive the channel_manager
+        let _guard = opt.channel_sync_locks.lock(opt.channel_id.as_str()).await;
         let mut channel_manager = match get_channel_manager_for_channel_id(
                 &opt.channel_id,
                 &opt.u_store,
@@ -119,21 +196,54 @@ impl Worker for IndexWorker {
                 opt.channel_id.clone(),
                 opt.db_file_name
             );
+            // New messages received here will not be received again by the sync_channels_loop
+            // so that webhook notifications need to be send and the message_feed needs to be
+            // published here too
+            let dev_eui = opt.u_store.get_item_read_only(&opt.channel_id)
+                .map(|user| user.external_id)
+                .unwrap_or_default();
+            if let Some(webhook_options) = opt.webhook_options {
+                msg_mngr.set_webhook_options(webhook_options, dev_eui.as_str());
+            }
+            msg_mngr.set_message_feed(opt.message_feed, dev_eui.as_str());
             msg_mngr.sync().await?;
             let db_limit_offset = opt.paging_opt.map(|paging_opt| Limit::from(paging_opt));
-            if let Ok((msg_meta_data_list, items_count_total)) = msg_mngr.index(db_limit_offset) {
+            // If messages are filtered by their decoded payload, all messages in the time range need
+            // to be decoded and paging is done after the filter has been applied
+            let (msg_meta_data_result, filtered_limit_offset) = if opt.decoded_field_filter.is_some() {
+                (Self::get_all_msg_meta_data(&msg_mngr, &opt.time_filter), db_limit_offset)
+            } else {
+                (msg_mngr.index_by_time(&opt.time_filter, db_limit_offset), None)
+            };
+            if let Ok((msg_meta_data_list, mut items_count_total)) = msg_meta_data_result {
                 let mut ret_val = MessageList::new();
                 for msg_meta_data in msg_meta_data_list {
                     let address = get_tangle_address_from_strings(opt.channel_id.as_str(), msg_meta_data.message_id.as_str())
                         .expect("get_tangle_address_from_strings error");
-                    if let Ok(lets_msg) = user.receive_message(address).await{
-                        ret_val.push(lets_msg.into());
+                    let mut message: Message = if let Ok(lets_msg) = user.receive_message(address).await{
+                        lets_msg.into()
                     } else {
-                        ret_val.push(Message::new_from_id(
+                        Message::new_from_id(
                             address.to_string(),
                             "Error could not receive message from tangle".to_string(),
                             "".to_string()
-                        ).expect(format!("Error on creating Message::new_from_id with id {}", opt.channel_id).as_str()));
+                        ).expect(format!("Error on creating Message::new_from_id with id {}", opt.channel_id).as_str())
+                    };
+                    message.set_timestamps(&msg_meta_data);
+                    if let Some(decoder) = opt.payload_decoder.as_ref() {
+                        message.decode_payload(decoder.as_ref());
+                    }
+                    if let Some(decoded_field_filter) = opt.decoded_field_filter.as_ref() {
+                        if !decoded_field_filter.matches(message.decoded.as_ref()) {
+                            continue;
+                        }
+                    }
+                    ret_val.push(message);
+                }
+                if opt.decoded_field_filter.is_some() {
+                    items_count_total = ret_val.len();
+                    if let Some(limit) = filtered_limit_offset {
+                        ret_val = ret_val.into_iter().skip(limit.offset).take(limit.limit).collect();
                     }
                 }
                 Ok((ret_val, items_count_total))
@@ -148,12 +258,29 @@ impl Worker for IndexWorker {
     }
 }
 
-pub(crate) async fn get(messages: &MessagesState, user_store: &UserDataStore, message_id: &str) -> Result<Message> {
+impl IndexWorker {
+    fn get_all_msg_meta_data<TransT>(msg_mngr: &MessageManager<TransT>, time_filter: &MessageTimeFilter) -> anyhow::Result<(Vec<DaoMessage>, usize)> {
+        let mut ret_val = Vec::<DaoMessage>::new();
+        loop {
+            let limit = Limit { limit: DECODED_FILTER_PAGE_SIZE, offset: ret_val.len() };
+            let (mut msg_meta_data_list, _) = msg_mngr.index_by_time(time_filter, Some(limit))?;
+            if msg_meta_data_list.is_empty() {
+                break;
+            }
+            ret_val.append(&mut msg_meta_data_list);
+        }
+        let items_count_total = ret_val.len();
+        Ok((ret_val, items_count_total))
+    }
+}
+
+pub(crate) async fn get(state: &AppState, message_id: &str) -> Result<Message> {
     if let Ok(tangle_address) = Address::from_str(message_id) {
         run_worker_in_own_thread::<GetWorker>(GetWorkerOptions::new(
             tangle_address,
-            messages,
-            user_store,
+            &state.messages,
+            &state.user_store,
+            &state.sync_channels.channel_sync_locks,
         )).await
     } else {
         Err(AppError::GenericWithMessage(
@@ -168,10 +295,12 @@ struct GetWorkerOptions {
     tangle_address: Address,
     u_store: UserDataStore,
     multi_channel_mngr_opt: MultiChannelManagerOptions,
+    payload_decoder: Option<Arc<dyn PayloadDecoder>>,
+    channel_sync_locks: ChannelSyncLocks,
 }
 
 impl GetWorkerOptions {
-    pub fn new(tangle_address: Address, messages: &MessagesState, user_store: &UserDataStore) -> GetWorkerOptions {
+    pub fn new(tangle_address: Address, messages: &MessagesState, user_store: &UserDataStore, channel_sync_locks: &ChannelSyncLocks) -> GetWorkerOptions {
         let mut multi_channel_mngr_opt = messages.as_multi_channel_manager_options();
         multi_channel_mngr_opt.message_data_store_for_msg_caching = Some(MessageDataStoreOptions {
             file_path_and_name: messages.db_file_name.clone(),
@@ -181,6 +310,8 @@ impl GetWorkerOptions {
             tangle_address,
             u_store: user_store.clone(),
             multi_channel_mngr_opt,
+            payload_decoder: get_payload_decoder_for_channel(messages, user_store, tangle_address.base().to_string().as_str()),
+            channel_sync_locks: channel_sync_locks.clone(),
         }
     }
 }
@@ -194,6 +325,9 @@ impl Worker for GetWorker {
     type ErrorType = AppError;
 
     async fn run(opt: GetWorkerOptions) -> Result<Message> {
+        // receive_message() changes the Streams client state which is stored when the
+        // channel_manager is dropped
+        let _guard = opt.channel_sync_locks.lock(opt.tangle_address.base().to_string().as_str()).await;
         let mut channel_manager = get_channel_manager_for_channel_id(
             &opt.tangle_address.base().to_string(),
             &opt.u_store,
@@ -201,7 +335,18 @@ impl Worker for GetWorker {
         ).await?;
         if let Some(author) = channel_manager.user.as_mut() {
             if let Ok(unwrapped_msg) = author.receive_message(opt.tangle_address).await {
-                Ok(unwrapped_msg.into())
+                let mut ret_val: Message = unwrapped_msg.into();
+                // receive_message() has cached the message so that the timestamps are available
+                if let Some(msg_data_store_opt) = opt.multi_channel_mngr_opt.message_data_store_for_msg_caching.as_ref() {
+                    let msg_data_store = MessageDataStore::new(msg_data_store_opt.clone());
+                    if let Ok(dao_message) = msg_data_store.get_item_read_only(&hex::encode(opt.tangle_address.relative())) {
+                        ret_val.set_timestamps(&dao_message);
+                    }
+                }
+                if let Some(decoder) = opt.payload_decoder.as_ref() {
+                    ret_val.decode_payload(decoder.as_ref());
+                }
+                Ok(ret_val)
             } else {
                 Err(AppError::GenericWithMessage(
                     StatusCode::NOT_FOUND,
@@ -212,4 +357,93 @@ impl Worker for GetWorker {
             Err(AppError::ChannelDoesNotExist(opt.tangle_address.base().to_string()))
         }
     }
-}
\ No newline at end of file
+}
+
+pub(crate) fn export(state: &AppState, conditions: &MessageExportConditions, auth: &AuthContext) -> Result<(ExportFormat, Receiver<anyhow::Result<String>>)> {
+    let bad_request = |e: anyhow::Error| AppError::GenericWithMessage(StatusCode::BAD_REQUEST, e.to_string());
+    let format = conditions.get_format().map_err(bad_request)?;
+    let channel_ids = conditions.get_channel_ids();
+    for channel_id in channel_ids.iter() {
+        auth.check_channel_id(&state.user_store, channel_id.as_str())?;
+    }
+    let mut export_options = MessageExportOptions::new(
+        channel_ids,
+        format,
+        state.messages.as_multi_channel_manager_options(),
+        state.messages.db_file_name.clone(),
+    );
+    export_options.time_filter = conditions.get_time_filter().map_err(bad_request)?;
+    export_options.payload_decoders = state.messages.payload_decoders.clone();
+    export_options.default_payload_decoder = state.messages.default_payload_decoder.clone();
+    export_options.webhook_options = state.messages.webhook_options.clone();
+    export_options.message_feed = Some(state.messages.message_feed.clone());
+    export_options.channel_sync_locks = state.sync_channels.channel_sync_locks.clone();
+    let receiver = start_message_export(&state.user_store, export_options).map_err(bad_request)?;
+    Ok((format, receiver))
+}
+
+pub(crate) fn subscribe_message_stream(state: &AppState, conditions: &MessageStreamConditions, auth: &AuthContext) -> Result<MessageFeedSubscription> {
+    let mut filter = conditions.get_filter()
+        .map_err(|e| AppError::GenericWithMessage(StatusCode::BAD_REQUEST, e.to_string()))?;
+    match &filter {
+        MessageFeedFilter::All => if let Some(prefix) = auth.get_external_id_restriction() {
+            filter = MessageFeedFilter::ExternalIdPrefix(prefix.to_string());
+        },
+        MessageFeedFilter::ChannelId(channel_id) => auth.check_channel_id(&state.user_store, channel_id.as_str())?,
+        MessageFeedFilter::ExternalId(external_id) => auth.check_external_id(external_id.as_str())?,
+        MessageFeedFilter::ExternalIdPrefix(_) => {},
+    }
+    // The channels of the filtered node are synchronized frequently as long as the subscription exists
+    let watched_channel_ids = match &filter {
+        MessageFeedFilter::All | MessageFeedFilter::ExternalIdPrefix(_) => Vec::new(),
+        MessageFeedFilter::ChannelId(channel_id) => {
+            state.user_store.get_item_read_only(channel_id)
+                .map_err(|_| AppError::ChannelDoesNotExist(channel_id.clone()))?;
+            vec![channel_id.clone()]
+        },
+        MessageFeedFilter::ExternalId(external_id) => {
+            match get_user_for_external_id(&state.user_store, external_id.as_str())? {
+                Some(user) => vec![user.streams_channel_id],
+                None => return Err(AppError::GenericWithMessage(
+                    StatusCode::NOT_FOUND,
+                    format!("A node with the external_id {} does not exist", external_id)
+                )),
+            }
+        },
+    };
+    Ok(state.messages.message_feed.subscribe(filter, watched_channel_ids))
+}
+
+// Waits for the next message of the subscription and converts it into a server sent event.
+// Returns None if the MessageFeed has been closed.
+pub(crate) async fn next_message_stream_event(state: &AppState, subscription: &mut MessageFeedSubscription) -> Option<std::result::Result<Event, axum::Error>> {
+    let feed_event = subscription.recv().await?;
+    let payload_decoder = get_payload_decoder_for_channel(&state.messages, &state.user_store, feed_event.channel_id.as_str());
+    let mut message: Message = feed_event.into();
+    if let Some(decoder) = payload_decoder {
+        message.decode_payload(decoder.as_ref());
+    }
+    Some(Event::default()
+        .event("message")
+        .id(message.id.clone())
+        .json_data(&message)
+    )
+}
+
+pub(crate) fn get_poi(messages: &MessagesState, message_id: &str) -> Result<MessagePoi> {
+    let tangle_address = Address::from_str(message_id).map_err(|_| AppError::GenericWithMessage(
+        StatusCode::BAD_REQUEST,
+        format!("Could not parse the channel-id from message-id {}. The message-id seems to be malformed.", message_id)
+    ))?;
+    let poi_data_store = PoiDataStore::new(MessageDataStoreOptions {
+        file_path_and_name: messages.db_file_name.clone(),
+        streams_channel_id: tangle_address.base().to_string(),
+    });
+    match poi_data_store.get_item_read_only(&hex::encode(tangle_address.relative())) {
+        Ok(poi) => Ok(MessagePoi::new_from_poi(message_id.to_string(), poi)?),
+        Err(_) => Err(AppError::GenericWithMessage(
+            StatusCode::NOT_FOUND,
+            format!("No Proof of Inclusion available for message {}", message_id)
+        )),
+    }
+}
@@ -9,5 +9,9 @@ pub use {
     messages_controller::{
         __path_index,
         __path_get,
+        __path_get_poi,
+        __path_export_poi,
+        __path_export,
+        __path_stream,
     }
 };
\ No newline at end of file
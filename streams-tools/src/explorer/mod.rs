@@ -4,11 +4,16 @@ mod nodes;
 mod app_state;
 mod router;
 
+pub mod auth;
 pub mod error;
 pub mod explorer;
+pub mod message_export;
+pub mod message_retention_loop;
 pub mod payload;
 pub mod sync_channels_loop;
+pub mod webhook_retry_loop;
 
 pub use {
     explorer::*,
+    router::SecurityAddon,
 };
\ No newline at end of file
@@ -10,5 +10,7 @@ pub use {
         __path_index,
         __path_get,
         __path_put,
+        __path_get_integrity,
+        __path_sync,
     }
 };
\ No newline at end of file
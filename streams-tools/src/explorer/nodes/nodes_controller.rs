@@ -11,6 +11,7 @@ use crate::{
     explorer::{
         error::AppError,
         app_state::AppState,
+        auth::AuthContext,
         shared::{
             Page,
             PagingOptions,
@@ -24,6 +25,8 @@ use super::{
     nodes_dto::{
         Node,
         NodeConditions,
+        NodeIntegrity,
+        NodeSyncStatus,
         ChannelId,
     },
     nodes_service as service,
@@ -31,13 +34,19 @@ use super::{
 
 /// Search for Nodes
 ///
-/// Search for Nodes by Streams channel id, Name or external id
+/// Search for Nodes by Streams channel id, Name, external id or lifecycle status.
+/// Use the 'stale_since' parameter to find nodes that have gone silent.
+///
+/// The response contains statistics computed from the message cache for each node:
+/// message count, time of the first and last message, average message interval and
+/// the initialization count of the latest sensor initialization.
 #[utoipa::path(
     get,
     operation_id = "nodes_index",
     path = "/nodes",
     responses(
-        (status = 200, description = "Successfully responded with list of Nodes")
+        (status = 200, description = "Successfully responded with list of Nodes"),
+        (status = 400, description = "Unknown lifecycle status")
     ),
     params(
         NodeConditions,
@@ -48,11 +57,14 @@ pub (crate) async fn index(
     Query(conditions): Query<NodeConditions>,
     optional_paging: Option<Query<PagingOptions>>,
     Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthContext>,
 ) -> Result<Json<Page<Node>>, AppError> {
     let paging = get_paging(optional_paging);
     let (ret_val, items_cnt_total) = service::index(
         conditions,
         &state.user_store,
+        state.messages.db_file_name.as_str(),
+        auth.get_external_id_restriction(),
         paging.clone()
     )?;
     wrap_with_page_meta_and_json_serialize(ret_val, paging.unwrap(), items_cnt_total)
@@ -71,15 +83,18 @@ pub (crate) async fn index(
         ChannelId,
     )
 )]
-pub (crate) async fn get(Path(id): Path<ChannelId>, Extension(state): Extension<AppState>) -> Result<Json<Node>, AppError> {
-    service::get(&id.channel_id, &state.user_store).map(|resp| Json(resp))
+pub (crate) async fn get(Path(id): Path<ChannelId>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> Result<Json<Node>, AppError> {
+    auth.check_channel_id(&state.user_store, id.channel_id.as_str())?;
+    service::get(&id.channel_id, &state.user_store, state.messages.db_file_name.as_str()).map(|resp| Json(resp))
 }
 
 /// Update a specific Node
 ///
 /// Update the Node specified by the 'channel_id' path parameter.
 /// The 'channel_id' field of the Node provided in the request body will be ignored.
-/// Only Node fields 'external_id' and 'name' will be updated.
+/// Only Node fields 'external_id' and 'name' will be updated. The lifecycle status fields
+/// are managed by the Management Console. Payload decoders are assigned using the
+/// '/payload/decoders/assignment' endpoint.
 #[utoipa::path(
     put,
     operation_id = "nodes_put",
@@ -94,5 +109,52 @@ pub (crate) async fn get(Path(id): Path<ChannelId>, Extension(state): Extension<
     )
 )]
 pub (crate) async fn put(Path(id): Path<ChannelId>, Extension(state): Extension<AppState>, Json(node): Json<Node>) -> Result<Json<Node>, AppError> {
-    service::put(&id.channel_id, &state.user_store, node).map(|resp| Json(resp))
+    service::put(&id.channel_id, &state.user_store, state.messages.db_file_name.as_str(), node).map(|resp| Json(resp))
+}
+
+/// Get the integrity of the message chain of a specific Node
+///
+/// Each time a channel is synchronized, the linked chain of messages starting with the keyload
+/// and ending with the latest sensor message is checked for missing links, gaps in the
+/// sequence numbers, duplicates and messages of unexpected publishers.
+/// Responds the result of the latest check.
+#[utoipa::path(
+    get,
+    operation_id = "nodes_get_integrity",
+    path = "/nodes/{channel_id}/integrity",
+    responses(
+        (status = 200, description = "Successfully responded integrity of requested node", body = NodeIntegrity),
+        (status = 400, description = "Node with specified channel_id does not exist"),
+        (status = 404, description = "The message chain of the node has not been checked yet")
+    ),
+    params(
+        ChannelId,
+    )
+)]
+pub (crate) async fn get_integrity(Path(id): Path<ChannelId>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> Result<Json<NodeIntegrity>, AppError> {
+    auth.check_channel_id(&state.user_store, id.channel_id.as_str())?;
+    service::get_integrity(&id.channel_id, &state.user_store, state.messages.db_file_name.as_str()).map(|resp| Json(resp))
+}
+
+/// Synchronize the channel of a specific Node immediately
+///
+/// Channels are synchronized by a background loop once an hour. Use this endpoint to fetch
+/// new messages of a Node immediately. Only messages published since the last synchronization
+/// are fetched. Responds the synchronization status of the Node.
+#[utoipa::path(
+    post,
+    operation_id = "nodes_sync",
+    path = "/nodes/{channel_id}/sync",
+    responses(
+        (status = 200, description = "Successfully synchronized the channel of the requested node", body = NodeSyncStatus),
+        (status = 400, description = "Node with specified channel_id does not exist or is archived"),
+        (status = 409, description = "The channel is currently synchronized by another worker")
+    ),
+    params(
+        ChannelId,
+    )
+)]
+pub (crate) async fn sync(Path(id): Path<ChannelId>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>) -> Result<Json<NodeSyncStatus>, AppError> {
+    auth.check_channel_id(&state.user_store, id.channel_id.as_str())?;
+    service::sync(&id.channel_id, &state).await.map(|resp| Json(resp))
 }
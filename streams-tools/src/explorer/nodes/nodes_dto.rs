@@ -8,11 +8,47 @@ use utoipa::{
     ToSchema
 };
 
-#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[derive(Serialize, Deserialize, Debug, Default, ToSchema)]
 pub struct Node {
     pub channel_id: String,
     pub name: String,
     pub external_id: String,
+    /// Lifecycle status of the channel: active, retired or archived
+    #[serde(default)]
+    #[schema(example = "active")]
+    pub status: String,
+    /// Unix timestamp (seconds) of the channel retirement. 0 for active channels.
+    #[serde(default)]
+    pub retired_at_secs: i64,
+    /// Streams channel-id of the channel replacing this channel. Empty if there is no successor.
+    #[serde(default)]
+    pub successor_channel_id: String,
+    /// Name of the payload decoder assigned to the Node. Empty if no decoder is assigned.
+    #[serde(default)]
+    #[schema(example = "compact_json")]
+    pub payload_decoder: String,
+    /// Identifier of the Node used by external systems, e.g. taken from a provisioning manifest.
+    /// Empty if no asset_id has been assigned.
+    #[serde(default)]
+    pub asset_id: String,
+    /// Number of messages in the message cache of the Explorer. Statistics fields are
+    /// computed from the message cache and are ignored when a Node is updated.
+    #[serde(default)]
+    pub messages_cnt: usize,
+    /// Unix timestamp (seconds) of the first cached message. 0 if there is no message.
+    #[serde(default)]
+    pub first_message_at_secs: i64,
+    /// Unix timestamp (seconds) of the last cached message. 0 if there is no message.
+    #[serde(default)]
+    pub last_message_at_secs: i64,
+    /// Average time between two messages in seconds. 0 if there are less than two messages.
+    #[serde(default)]
+    pub avg_message_interval_secs: i64,
+    /// Initialization count of the latest sensor initialization including the
+    /// initialization count wrap-arounds (initialization epochs). Empty if the sensor
+    /// has not been initialized using the automatic sensor initialization.
+    #[serde(default)]
+    pub initialization_cnt: Option<u64>,
 }
 
 /// Filter existing nodes
@@ -27,6 +63,13 @@ pub struct NodeConditions {
     /// Name starts with the specified value
     #[param(min_length=1)]
     pub name_start: Option<String>,
+    /// Lifecycle status equals the specified value: active, retired or archived
+    #[param(min_length=1, example ="active")]
+    pub status: Option<String>,
+    /// Only nodes that have not sent any message since this unix timestamp (seconds).
+    /// Nodes without any cached message are included.
+    #[param(example = 1677628800)]
+    pub stale_since: Option<i64>,
 }
 
 /// Specify the IOTA Streams channel id
@@ -35,4 +78,74 @@ pub struct ChannelId {
     /// Streams channel-id. Channel ids of existing nodes can be listed using the '/node' endpoint
     #[param(max_length=80, min_length=80, example ="cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000")]
     pub channel_id: String,
-}
\ No newline at end of file
+}
+
+/// Synchronization status of a Node after its channel has been synchronized
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct NodeSyncStatus {
+    pub channel_id: String,
+    /// Number of new messages received by the requested synchronization
+    pub new_messages_cnt: u32,
+    /// Message id of the latest message received by a synchronization. Empty if no message has been received yet.
+    pub last_message_id: String,
+    /// Unix timestamp (seconds) of the last synchronization that received a new message
+    pub last_message_received_at_secs: i64,
+    /// Unix timestamp (seconds) of the last synchronization
+    pub last_synced_at_secs: i64,
+    /// Number of new messages received by all synchronizations
+    pub synced_messages_cnt: i64,
+}
+
+/// Result of the latest integrity check of the message chain of a Node.
+/// The message chain is checked each time the channel is synchronized.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct NodeIntegrity {
+    pub channel_id: String,
+    /// Unix timestamp (seconds) of the integrity check
+    pub checked_at_secs: i64,
+    /// true if no issues have been found
+    pub is_intact: bool,
+    pub issues_cnt: u32,
+    pub checked_messages_cnt: usize,
+    /// Number of messages that could not be checked because their header could not be read
+    pub unchecked_messages_cnt: usize,
+    /// Hex encoded public key of the channel author
+    pub author_publisher: String,
+    /// Hex encoded public key of the sensor
+    pub sensor_publisher: String,
+    /// Messages linking to a message that has not been found
+    pub missing_links: Vec<MissingLink>,
+    /// Missing sequence numbers of a publisher
+    pub sequence_gaps: Vec<SequenceGap>,
+    pub duplicates: Vec<DuplicateMessage>,
+    /// Publishers that are neither the channel author nor the sensor
+    pub unexpected_publishers: Vec<UnexpectedPublisher>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct MissingLink {
+    pub message_id: String,
+    /// Id of the message that has not been found
+    pub linked_msg_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SequenceGap {
+    pub publisher: String,
+    pub last_sequence_before_gap: i64,
+    pub first_sequence_after_gap: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct DuplicateMessage {
+    pub message_id: String,
+    /// Id of the message that has been found first
+    pub duplicate_of: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct UnexpectedPublisher {
+    pub publisher: String,
+    pub message_ids: Vec<String>,
+}
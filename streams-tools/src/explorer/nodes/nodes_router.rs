@@ -10,6 +10,8 @@ pub fn routes() -> Router {
         .route("/", routing::get(controller::index))
         .route("/:channel_id", routing::get(controller::get))
         .route("/:channel_id", routing::put(controller::put))
+        .route("/:channel_id/integrity", routing::get(controller::get_integrity))
+        .route("/:channel_id/sync", routing::post(controller::sync))
 }
 
-pub const INFO: &str = "Search for nodes and view + update node details";
\ No newline at end of file
+pub const INFO: &str = "Search for nodes, view + update node details, check the integrity of their message chains and synchronize their channels";
\ No newline at end of file
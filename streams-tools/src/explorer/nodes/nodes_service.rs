@@ -1,37 +1,73 @@
+use std::{
+    str::FromStr,
+    convert::TryFrom,
+};
+
+use hyper::http::StatusCode;
+
 use crate::{
     dao_helpers::{
         DaoManager,
+        DbFileBasedDaoManagerOptions,
         Limit,
         Condition,
         Conditions,
         MatchType,
     },
-    user_manager::dao::user::{
-        User,
-        UserDataStore,
-        UserDaoManager,
+    user_manager::dao::{
+        user::{
+            User,
+            ChannelStatus,
+            UserDataStore,
+            UserDaoManager,
+        },
+        channel_integrity::{
+            ChannelIntegrity,
+            ChannelIntegrityDataStore,
+        },
+        message::{
+            MessageDataStore,
+            MessageDataStoreOptions,
+            MessageStatistics,
+        },
+        sensor_initialization::SensorInitializationDataStore,
+        sync_cursor::SyncCursorDataStore,
     },
     explorer::{
         error::{
             Result,
             AppError,
         },
+        app_state::AppState,
         shared::PagingOptions,
+        sync_channels_loop::SyncChannelWorker,
     },
+    threading_helpers::run_worker_in_own_thread,
 };
 
 use super::{
     NodeConditions,
     Node,
+    NodeIntegrity,
+    NodeSyncStatus,
+    MissingLink,
+    SequenceGap,
+    DuplicateMessage,
+    UnexpectedPublisher,
 };
 
-fn get_dao_conditions(dto_cond: NodeConditions) -> Vec<Condition> {
+fn get_dao_conditions(dto_cond: NodeConditions) -> Result<Vec<Condition>> {
+    if let Some(status) = dto_cond.status.as_ref() {
+        ChannelStatus::from_str(status.as_str())
+            .map_err(|e| AppError::GenericWithMessage(StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
     let mut ret_val = Vec::<Condition>::new();
     let mut conditions = Conditions(&mut ret_val);
     conditions.add(dto_cond.channel_id_start, "streams_channel_id", MatchType::StartsWith);
     conditions.add(dto_cond.external_id, "external_id", MatchType::ExactMatch);
     conditions.add(dto_cond.name_start, "name", MatchType::StartsWith);
-    ret_val
+    conditions.add(dto_cond.status, "status", MatchType::ExactMatch);
+    Ok(ret_val)
 }
 
 impl From<&User> for Node {
@@ -39,7 +75,13 @@ impl From<&User> for Node {
         Node {
             channel_id: value.streams_channel_id.clone(),
             name: value.name.clone(),
-            external_id: value.external_id.clone()
+            external_id: value.external_id.clone(),
+            status: value.status.clone(),
+            retired_at_secs: value.retired_at_secs,
+            successor_channel_id: value.successor_channel_id.clone(),
+            payload_decoder: value.payload_decoder.clone(),
+            asset_id: value.asset_id.clone(),
+            ..Default::default()
         }
     }
 }
@@ -47,31 +89,118 @@ impl From<User> for Node {
     fn from(value: User) -> Self { (&value).into() }
 }
 
-pub(crate) fn index(conditions: NodeConditions, user_store: &UserDataStore, paging_opt: Option<PagingOptions>) -> Result<(Vec<Node>, usize)> {
+impl TryFrom<ChannelIntegrity> for NodeIntegrity {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ChannelIntegrity) -> anyhow::Result<Self> {
+        let report = value.get_report()?;
+        Ok(NodeIntegrity {
+            channel_id: value.streams_channel_id,
+            checked_at_secs: value.checked_at_secs,
+            is_intact: value.is_intact,
+            issues_cnt: value.issues_cnt,
+            checked_messages_cnt: report.checked_messages_cnt,
+            unchecked_messages_cnt: report.unchecked_messages_cnt,
+            author_publisher: report.author_publisher,
+            sensor_publisher: report.sensor_publisher,
+            missing_links: report.missing_links.into_iter().map(|link| MissingLink {
+                message_id: link.message_id,
+                linked_msg_id: link.linked_msg_id,
+            }).collect(),
+            sequence_gaps: report.sequence_gaps.into_iter().map(|gap| SequenceGap {
+                publisher: gap.publisher,
+                last_sequence_before_gap: gap.last_sequence_before_gap,
+                first_sequence_after_gap: gap.first_sequence_after_gap,
+            }).collect(),
+            duplicates: report.duplicates.into_iter().map(|dup| DuplicateMessage {
+                message_id: dup.message_id,
+                duplicate_of: dup.duplicate_of,
+                reason: dup.reason,
+            }).collect(),
+            unexpected_publishers: report.unexpected_publishers.into_iter().map(|publisher| UnexpectedPublisher {
+                publisher: publisher.publisher,
+                message_ids: publisher.message_ids,
+            }).collect(),
+        })
+    }
+}
+
+// Creates a Node including the statistics computed from the message cache
+fn get_node_with_statistics(user: &User, db_file_name: &str, init_store: &SensorInitializationDataStore) -> Result<Node> {
+    let mut ret_val: Node = user.into();
+    let message_data_store = MessageDataStore::new(MessageDataStoreOptions {
+        file_path_and_name: db_file_name.to_string(),
+        streams_channel_id: user.streams_channel_id.clone(),
+    });
+    let statistics = MessageStatistics::new_from_data_store(&message_data_store)?;
+    ret_val.messages_cnt = statistics.messages_cnt;
+    ret_val.first_message_at_secs = statistics.first_message_at_secs;
+    ret_val.last_message_at_secs = statistics.last_message_at_secs;
+    ret_val.avg_message_interval_secs = statistics.avg_message_interval_secs();
+    ret_val.initialization_cnt = init_store.get_item_read_only(&user.external_id)
+        .map(|initialization| initialization.get_full_initialization_cnt())
+        .ok();
+    Ok(ret_val)
+}
+
+fn get_init_store(db_file_name: &str) -> SensorInitializationDataStore {
+    SensorInitializationDataStore::new(DbFileBasedDaoManagerOptions {
+        file_path_and_name: db_file_name.to_string(),
+    })
+}
+
+// external_id_prefix: If specified, only nodes having an external_id starting with the prefix are listed
+pub(crate) fn index(conditions: NodeConditions, user_store: &UserDataStore, db_file_name: &str, external_id_prefix: Option<&str>, paging_opt: Option<PagingOptions>) -> Result<(Vec<Node>, usize)> {
     let db_limit_offset = paging_opt.map(|paging_opt| Limit::from(paging_opt));
-    let dao_conditions = get_dao_conditions(conditions);
-    let (users, items_cnt_total) = user_store.filter(dao_conditions, db_limit_offset)?;
-    let nodes = users.iter().map(|user| user.into() ).collect();
+    let stale_since = conditions.stale_since;
+    let mut dao_conditions = get_dao_conditions(conditions)?;
+    Conditions(&mut dao_conditions).add(external_id_prefix.map(|prefix| prefix.to_string()), "external_id", MatchType::StartsWith);
+    let init_store = get_init_store(db_file_name);
+    // The statistics are computed from the message cache, so if nodes are filtered by their
+    // last message all nodes need to be fetched and paging is done after the filter has been applied
+    let (db_limit_offset, filtered_limit_offset) = if stale_since.is_some() {
+        (None, db_limit_offset)
+    } else {
+        (db_limit_offset, None)
+    };
+    let (users, mut items_cnt_total) = user_store.filter(dao_conditions, db_limit_offset)?;
+    let mut nodes = Vec::<Node>::new();
+    for user in users.iter() {
+        let node = get_node_with_statistics(user, db_file_name, &init_store)?;
+        if let Some(stale_since) = stale_since {
+            if node.last_message_at_secs >= stale_since {
+                continue;
+            }
+        }
+        nodes.push(node);
+    }
+    if stale_since.is_some() {
+        items_cnt_total = nodes.len();
+        if let Some(limit) = filtered_limit_offset {
+            nodes = nodes.into_iter().skip(limit.offset).take(limit.limit).collect();
+        }
+    }
 
     Ok((nodes, items_cnt_total))
 }
 
-pub(crate) fn get(channel_id: &<UserDaoManager as DaoManager>::PrimaryKeyType, user_store: &UserDataStore) -> Result<Node> {
+pub(crate) fn get(channel_id: &<UserDaoManager as DaoManager>::PrimaryKeyType, user_store: &UserDataStore, db_file_name: &str) -> Result<Node> {
     let user = match user_store.get_item_read_only(channel_id) {
         Ok(user) => user,
         Err(_) => {
             return Err(AppError::ChannelDoesNotExist(channel_id.to_string()))
         }
     };
-    Ok(user.into())
+    get_node_with_statistics(&user, db_file_name, &get_init_store(db_file_name))
 }
 
 
-pub(crate) fn put(channel_id: &<UserDaoManager as DaoManager>::PrimaryKeyType, user_store: &UserDataStore, node: Node) -> Result<Node> {
+pub(crate) fn put(channel_id: &<UserDaoManager as DaoManager>::PrimaryKeyType, user_store: &UserDataStore, db_file_name: &str, node: Node) -> Result<Node> {
     let user = match user_store.get_item(channel_id) {
         Ok((mut user, _)) => {
             user.name = node.name;
             user.external_id = node.external_id;
+            user.asset_id = node.asset_id;
             user_store.write_item_to_db(&user)?;
             user
         },
@@ -79,5 +208,49 @@ pub(crate) fn put(channel_id: &<UserDaoManager as DaoManager>::PrimaryKeyType, u
             return Err(AppError::ChannelDoesNotExist(channel_id.to_string()))
         }
     };
-    Ok(user.into())
-}
\ No newline at end of file
+    get_node_with_statistics(&user, db_file_name, &get_init_store(db_file_name))
+}
+
+pub(crate) fn get_integrity(channel_id: &<UserDaoManager as DaoManager>::PrimaryKeyType, user_store: &UserDataStore, db_file_name: &str) -> Result<NodeIntegrity> {
+    if user_store.get_item_read_only(channel_id).is_err() {
+        return Err(AppError::ChannelDoesNotExist(channel_id.to_string()));
+    }
+    let integrity_store = ChannelIntegrityDataStore::new(DbFileBasedDaoManagerOptions {
+        file_path_and_name: db_file_name.to_string(),
+    });
+    match integrity_store.get_item_read_only(channel_id) {
+        Ok(integrity) => Ok(NodeIntegrity::try_from(integrity)?),
+        Err(_) => Err(AppError::GenericWithMessage(
+            StatusCode::NOT_FOUND,
+            format!("The message chain of channel {} has not been checked yet", channel_id)
+        )),
+    }
+}
+
+// Synchronizes the channel immediately instead of waiting for the sync loop
+pub(crate) async fn sync(channel_id: &<UserDaoManager as DaoManager>::PrimaryKeyType, state: &AppState) -> Result<NodeSyncStatus> {
+    let user = state.user_store.get_item_read_only(channel_id)
+        .map_err(|_| AppError::ChannelDoesNotExist(channel_id.to_string()))?;
+    if user.is_archived() {
+        return Err(AppError::GenericWithMessage(
+            StatusCode::BAD_REQUEST,
+            format!("The channel {} is archived. Archived channels are not synchronized", channel_id)
+        ));
+    }
+    let new_messages_cnt = run_worker_in_own_thread::<SyncChannelWorker>((state.sync_channels.clone(), channel_id.clone())).await?
+        .ok_or_else(|| AppError::GenericWithMessage(
+            StatusCode::CONFLICT,
+            format!("The channel {} is currently being synchronized. Please retry later", channel_id)
+        ))?;
+    let cursor = SyncCursorDataStore::new(DbFileBasedDaoManagerOptions {
+        file_path_and_name: state.messages.db_file_name.clone(),
+    }).get_item_read_only(channel_id)?;
+    Ok(NodeSyncStatus {
+        channel_id: channel_id.to_string(),
+        new_messages_cnt,
+        last_message_id: cursor.last_msg_id,
+        last_message_received_at_secs: cursor.last_msg_received_at_secs,
+        last_synced_at_secs: cursor.last_synced_at_secs,
+        synced_messages_cnt: cursor.synced_msgs_cnt,
+    })
+}
@@ -2,11 +2,15 @@ mod payload_controller;
 mod payload_service;
 mod payload_dto;
 mod payload_router;
+mod payload_decoders;
 
 pub use {
     payload_router::*,
     payload_dto::*,
+    payload_decoders::*,
     payload_controller::{
         __path_decode,
+        __path_decoders_index,
+        __path_assign_decoder,
     }
-};
\ No newline at end of file
+};
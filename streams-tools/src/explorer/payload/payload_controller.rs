@@ -11,18 +11,25 @@ use hyper::body::Bytes;
 use crate::explorer::{
     error::AppError,
     app_state::AppState,
+    auth::AuthContext,
     messages::Message,
+    nodes::Node,
 };
 
 use super::{
     payload_service as service,
-    payload_dto::DecodeQueryParams,
+    payload_dto::{
+        DecodeQueryParams,
+        PayloadDecoderInfo,
+        DecoderAssignment,
+    },
 };
 
 /// Decode a payload
 ///
 /// Decode a payload that has been send by a Node that is identified by its 'external_id'.
-/// The payload needs to be provided in the request body.
+/// The payload needs to be provided in the request body. If a payload decoder is assigned to
+/// the Node, the decoded message payload is provided in the 'decoded' field.
 #[utoipa::path(
     post,
     operation_id = "decode_post",
@@ -35,7 +42,8 @@ use super::{
     ),
     params(DecodeQueryParams)
 )]
-pub (crate) async fn decode(Query(params): Query<DecodeQueryParams>, Extension(state): Extension<AppState>, body_bytes: Bytes) -> Result<Json<Message>, AppError> {
+pub (crate) async fn decode(Query(params): Query<DecodeQueryParams>, Extension(state): Extension<AppState>, Extension(auth): Extension<AuthContext>, body_bytes: Bytes) -> Result<Json<Message>, AppError> {
+    auth.check_external_id(params.external_id.as_str())?;
     service::decode(
         &params.external_id,
         &state.user_store,
@@ -44,3 +52,37 @@ pub (crate) async fn decode(Query(params): Query<DecodeQueryParams>, Extension(s
     )
     .await.map(|resp| Json(resp))
 }
+
+/// List available payload decoders
+///
+/// List the payload decoders that can be assigned to Nodes using the '/payload/decoders/assignment' endpoint
+#[utoipa::path(
+    get,
+    operation_id = "decoders_index",
+    path = "/payload/decoders",
+    responses(
+        (status = 200, description = "Successfully responded with list of payload decoders", body = [PayloadDecoderInfo]),
+    ),
+)]
+pub (crate) async fn decoders_index(Extension(state): Extension<AppState>) -> Json<Vec<PayloadDecoderInfo>> {
+    Json(service::get_decoders(&state.messages.payload_decoders))
+}
+
+/// Assign a payload decoder to Nodes
+///
+/// Assign a payload decoder to the Node specified by 'channel_id' or to all Nodes having the specified 'external_id'.
+/// The decoder is used to provide the decoded payload of the messages of the Nodes.
+#[utoipa::path(
+    put,
+    operation_id = "assign_decoder",
+    path = "/payload/decoders/assignment",
+    responses(
+        (status = 200, description = "Successfully assigned the decoder. Responds the updated Nodes", body = [Node]),
+        (status = 400, description = "Unknown decoder or channel, or neither 'channel_id' nor 'external_id' has been specified"),
+        (status = 404, description = "A Node with the specified external_id does not exist"),
+    ),
+    params(DecoderAssignment)
+)]
+pub (crate) async fn assign_decoder(Query(assignment): Query<DecoderAssignment>, Extension(state): Extension<AppState>) -> Result<Json<Vec<Node>>, AppError> {
+    service::assign_decoder(&state.user_store, &state.messages, assignment).map(|resp| Json(resp))
+}
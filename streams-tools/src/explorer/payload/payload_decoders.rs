@@ -0,0 +1,299 @@
+use std::{
+    fmt,
+    sync::Arc,
+    str::from_utf8,
+    convert::TryFrom,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde_json::{
+    Value as JsonValue,
+    Map as JsonMap,
+};
+
+// Decodes the payload of a Streams message into a JSON value.
+// Implement this trait and register the decoder in the PayloadDecoderRegistry
+// to support additional payload formats.
+pub trait PayloadDecoder: Send + Sync {
+    // Unique name of the decoder that is used to assign the decoder to nodes
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    fn decode(&self, payload: &[u8]) -> Result<JsonValue>;
+}
+
+// Decodes utf8 encoded JSON payloads
+pub struct JsonDecoder;
+
+impl JsonDecoder {
+    pub const NAME: &'static str = "json";
+}
+
+impl PayloadDecoder for JsonDecoder {
+    fn name(&self) -> &'static str { Self::NAME }
+
+    fn description(&self) -> &'static str {
+        "UTF-8 encoded JSON document"
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<JsonValue> {
+        let text = from_utf8(payload)
+            .map_err(|e| anyhow!("The payload is not valid UTF-8. Error: {}", e))?;
+        // Sensors may send zero padded buffers
+        Ok(serde_json::from_str(text.trim_end_matches(char::from(0)))?)
+    }
+}
+
+// Decodes meter readings that are sent without the redundant OBIS fields
+// (see test/payloads/meter_reading_1_compact.json). The OBIS fields are added
+// to the decoded value so that compact and full meter readings can be processed
+// the same way. Other JSON payloads are decoded like the JsonDecoder does.
+pub struct CompactJsonDecoder;
+
+impl CompactJsonDecoder {
+    pub const NAME: &'static str = "compact_json";
+
+    fn get_obis_code(qualifier: &str) -> Option<&'static str> {
+        match qualifier {
+            "a-plus" => Some("1-0:1.8.0"),
+            "a-minus" => Some("1-0:2.8.0"),
+            _ => None,
+        }
+    }
+
+    fn add_obis_fields(reading: &mut JsonMap<String, JsonValue>) {
+        if reading.get("type").and_then(|t| t.as_str()) != Some("meter_reading") || reading.contains_key("obis_value") {
+            return;
+        }
+        let register_value = match reading.get("register_value") {
+            Some(value) => value.clone(),
+            None => return,
+        };
+        let obis_code = reading.get("qualifier")
+            .and_then(|qualifier| qualifier.as_str())
+            .and_then(Self::get_obis_code);
+        if let Some(obis_code) = obis_code {
+            reading.insert(obis_code.to_string(), register_value.clone());
+        }
+        reading.insert("obis_value".to_string(), register_value);
+    }
+}
+
+impl PayloadDecoder for CompactJsonDecoder {
+    fn name(&self) -> &'static str { Self::NAME }
+
+    fn description(&self) -> &'static str {
+        "UTF-8 encoded compact JSON meter reading. Missing OBIS fields are added to the decoded value."
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<JsonValue> {
+        let mut ret_val = JsonDecoder.decode(payload)?;
+        if let Some(reading) = ret_val.as_object_mut() {
+            Self::add_obis_fields(reading);
+        }
+        Ok(ret_val)
+    }
+}
+
+// Decodes CBOR (RFC 8949) encoded payloads.
+// Byte strings are provided as hex encoded strings, map keys are converted into strings.
+pub struct CborDecoder;
+
+impl CborDecoder {
+    pub const NAME: &'static str = "cbor";
+
+    fn to_json_value(value: ciborium::value::Value) -> Result<JsonValue> {
+        use ciborium::value::Value as CborValue;
+        Ok(match value {
+            CborValue::Integer(int) => {
+                let int = i128::from(int);
+                if let Ok(signed) = i64::try_from(int) {
+                    JsonValue::from(signed)
+                } else if let Ok(unsigned) = u64::try_from(int) {
+                    JsonValue::from(unsigned)
+                } else {
+                    JsonValue::String(int.to_string())
+                }
+            },
+            CborValue::Bytes(bytes) => JsonValue::String(hex::encode(bytes)),
+            CborValue::Float(float) => JsonValue::from(float),
+            CborValue::Text(text) => JsonValue::String(text),
+            CborValue::Bool(boolean) => JsonValue::Bool(boolean),
+            CborValue::Null => JsonValue::Null,
+            CborValue::Tag(_tag, value) => Self::to_json_value(*value)?,
+            CborValue::Array(values) => JsonValue::Array(
+                values.into_iter().map(Self::to_json_value).collect::<Result<Vec<JsonValue>>>()?
+            ),
+            CborValue::Map(entries) => {
+                let mut map = JsonMap::new();
+                for (key, value) in entries {
+                    let key = match Self::to_json_value(key)? {
+                        JsonValue::String(text) => text,
+                        other => other.to_string(),
+                    };
+                    map.insert(key, Self::to_json_value(value)?);
+                }
+                JsonValue::Object(map)
+            },
+            _ => bail!("Unsupported CBOR value"),
+        })
+    }
+}
+
+impl PayloadDecoder for CborDecoder {
+    fn name(&self) -> &'static str { Self::NAME }
+
+    fn description(&self) -> &'static str {
+        "CBOR encoded data. Byte strings are provided hex encoded."
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<JsonValue> {
+        let value: ciborium::value::Value = ciborium::de::from_reader(payload)
+            .map_err(|e| anyhow!("The payload is not valid CBOR. Error: {}", e))?;
+        Self::to_json_value(value)
+    }
+}
+
+// Provides the payload as hex encoded string
+pub struct RawDecoder;
+
+impl RawDecoder {
+    pub const NAME: &'static str = "raw";
+}
+
+impl PayloadDecoder for RawDecoder {
+    fn name(&self) -> &'static str { Self::NAME }
+
+    fn description(&self) -> &'static str {
+        "Hex encoded binary data"
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<JsonValue> {
+        Ok(JsonValue::String(hex::encode(payload)))
+    }
+}
+
+// Holds the payload decoders that can be assigned to nodes.
+// Default::default() provides a registry containing the json, compact_json,
+// cbor and raw decoders.
+#[derive(Clone)]
+pub struct PayloadDecoderRegistry {
+    decoders: Vec<Arc<dyn PayloadDecoder>>,
+}
+
+impl PayloadDecoderRegistry {
+    pub fn new() -> Self {
+        PayloadDecoderRegistry {
+            decoders: Vec::new(),
+        }
+    }
+
+    // A decoder having the same name as an already registered decoder replaces the old one
+    pub fn register(&mut self, decoder: Arc<dyn PayloadDecoder>) {
+        self.decoders.retain(|registered| registered.name() != decoder.name());
+        self.decoders.push(decoder);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn PayloadDecoder>> {
+        self.decoders.iter().find(|decoder| decoder.name() == name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn PayloadDecoder>> {
+        self.decoders.iter()
+    }
+
+    // Returns the decoder assigned to a node or the default_decoder if the node has
+    // no decoder assigned. node_payload_decoder is the payload_decoder of the nodes User.
+    pub fn get_node_decoder(&self, node_payload_decoder: &str, default_decoder: Option<&str>) -> Option<Arc<dyn PayloadDecoder>> {
+        if node_payload_decoder.is_empty() {
+            default_decoder.and_then(|default_decoder| self.get(default_decoder))
+        } else {
+            self.get(node_payload_decoder)
+        }
+    }
+
+    pub fn validate_decoder_name(&self, name: &str) -> Result<()> {
+        if !self.contains(name) {
+            let names: Vec<&str> = self.iter().map(|decoder| decoder.name()).collect();
+            bail!("Unknown payload decoder '{}'. Possible values are: {}", name, names.join(", "));
+        }
+        Ok(())
+    }
+}
+
+impl Default for PayloadDecoderRegistry {
+    fn default() -> Self {
+        let mut ret_val = Self::new();
+        ret_val.register(Arc::new(JsonDecoder));
+        ret_val.register(Arc::new(CompactJsonDecoder));
+        ret_val.register(Arc::new(CborDecoder));
+        ret_val.register(Arc::new(RawDecoder));
+        ret_val
+    }
+}
+
+impl fmt::Debug for PayloadDecoderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter().map(|decoder| decoder.name())).finish()
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib explorer::payload::payload_decoders::tests --features explorer
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METER_READING_1: &[u8] = include_bytes!("../../../../test/payloads/meter_reading_1.json");
+    const METER_READING_1_COMPACT: &[u8] = include_bytes!("../../../../test/payloads/meter_reading_1_compact.json");
+
+    #[test]
+    fn test_json_decoder() {
+        let value = JsonDecoder.decode(METER_READING_1).unwrap();
+        assert_eq!(value["meter_id"], 60251504);
+        assert_eq!(value["1-0:1.8.0"], 2201.02);
+        assert!(JsonDecoder.decode(&[0xff, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_compact_json_decoder() {
+        let value = CompactJsonDecoder.decode(METER_READING_1_COMPACT).unwrap();
+        let full_value = JsonDecoder.decode(METER_READING_1).unwrap();
+        assert_eq!(value, full_value);
+    }
+
+    #[test]
+    fn test_cbor_decoder() {
+        let mut payload = Vec::<u8>::new();
+        let cbor_value = ciborium::value::Value::Map(vec![
+            (ciborium::value::Value::Text("meter_id".to_string()), ciborium::value::Value::Integer(60251504_u32.into())),
+            (ciborium::value::Value::Integer(1_u8.into()), ciborium::value::Value::Bytes(vec![0xab, 0xcd])),
+        ]);
+        ciborium::ser::into_writer(&cbor_value, &mut payload).unwrap();
+        let value = CborDecoder.decode(payload.as_slice()).unwrap();
+        assert_eq!(value["meter_id"], 60251504);
+        assert_eq!(value["1"], "abcd");
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = PayloadDecoderRegistry::default();
+        assert!(registry.contains(JsonDecoder::NAME));
+        assert!(registry.contains(CompactJsonDecoder::NAME));
+        assert!(registry.contains(CborDecoder::NAME));
+        assert!(registry.contains(RawDecoder::NAME));
+        assert!(registry.validate_decoder_name("xml").is_err());
+        assert_eq!(registry.get(RawDecoder::NAME).unwrap().decode(&[0x01, 0xff]).unwrap(), "01ff");
+    }
+}
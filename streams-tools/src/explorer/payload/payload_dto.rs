@@ -3,14 +3,125 @@ use serde::{
     Serialize
 };
 
+use serde_json::Value as JsonValue;
+
 use utoipa::{
     IntoParams,
+    ToSchema,
 };
 
+use super::payload_decoders::PayloadDecoder;
+
 /// Identify the Node that has send the payload
 #[derive(Serialize, Deserialize, Debug, IntoParams)]
 pub struct DecodeQueryParams {
     /// External ID (in example a LoRaWAN DevEUI). Find  external ids of existing Nodes using the '/node' endpoint
     #[param(max_length=1023, min_length=1, example ="504F53E833055C50")]
     pub external_id: String,
-}
\ No newline at end of file
+}
+
+/// Payload decoder that can be assigned to Nodes
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct PayloadDecoderInfo {
+    #[schema(example = "json")]
+    pub name: String,
+    pub description: String,
+}
+
+impl From<&dyn PayloadDecoder> for PayloadDecoderInfo {
+    fn from(value: &dyn PayloadDecoder) -> Self {
+        PayloadDecoderInfo {
+            name: value.name().to_string(),
+            description: value.description().to_string(),
+        }
+    }
+}
+
+/// Assign a payload decoder to Nodes
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+pub struct DecoderAssignment {
+    /// Name of the payload decoder. Use an empty string to remove the assignment.
+    /// Available decoders can be listed using the '/payload/decoders' endpoint.
+    #[param(example = "compact_json")]
+    pub decoder: String,
+    /// Assign the decoder to the Node having this Streams channel-id
+    #[param(max_length=80, min_length=80, example ="cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000")]
+    pub channel_id: Option<String>,
+    /// Assign the decoder to all Nodes having this external id, including retired Nodes
+    /// whose external id has been disambiguated (e.g. '504F53E833055C50-(0)')
+    #[param(min_length=1, example ="504F53E833055C50")]
+    pub external_id: Option<String>,
+}
+
+/// Meter reading as being send by SUSEE sensors
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct MeterReading {
+    pub meter_id: u64,
+    pub register_value: f64,
+    #[serde(default)]
+    #[schema(example = "a-plus")]
+    pub qualifier: String,
+    #[serde(default)]
+    #[schema(example = "electricity_kwh")]
+    pub medium: String,
+    #[serde(default)]
+    pub obis_value: Option<f64>,
+    #[serde(default)]
+    pub header_version: u32,
+}
+
+impl MeterReading {
+    pub fn try_from_decoded_value(value: &JsonValue) -> Option<Self> {
+        if value.get("type").and_then(|t| t.as_str()) == Some("meter_reading") {
+            serde_json::from_value(value.clone()).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Payload of a message decoded by the payload decoder assigned to the Node
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DecodedPayload {
+    /// Name of the used payload decoder
+    pub decoder: String,
+    /// The decoded payload. Null if the payload could not be decoded.
+    #[schema(value_type = Object)]
+    pub value: JsonValue,
+    /// Typed meter reading if the decoded payload is a meter reading
+    pub meter_reading: Option<MeterReading>,
+    /// Error message if the payload could not be decoded
+    pub error: String,
+}
+
+impl DecodedPayload {
+    pub fn new(decoder: &dyn PayloadDecoder, payload: &[u8]) -> Self {
+        match decoder.decode(payload) {
+            Ok(value) => DecodedPayload {
+                decoder: decoder.name().to_string(),
+                meter_reading: MeterReading::try_from_decoded_value(&value),
+                value,
+                error: String::new(),
+            },
+            Err(e) => DecodedPayload {
+                decoder: decoder.name().to_string(),
+                value: JsonValue::Null,
+                meter_reading: None,
+                error: e.to_string(),
+            }
+        }
+    }
+
+    pub fn is_decoded(&self) -> bool {
+        self.error.is_empty()
+    }
+
+    // field_name is either the name of a top level field or a JSON pointer (e.g. "/meter/id")
+    pub fn get_field(&self, field_name: &str) -> Option<&JsonValue> {
+        if field_name.starts_with('/') {
+            self.value.pointer(field_name)
+        } else {
+            self.value.get(field_name)
+        }
+    }
+}
@@ -8,6 +8,8 @@ use super::payload_controller as controller;
 pub fn routes() -> Router {
     Router::new()
         .route("/decode", routing::post(controller::decode))
+        .route("/decoders", routing::get(controller::decoders_index))
+        .route("/decoders/assignment", routing::put(controller::assign_decoder))
 }
 
-pub const INFO: &str = "Decode payloads of a Node identified by its external_id";
\ No newline at end of file
+pub const INFO: &str = "Decode payloads of a Node identified by its external_id and manage the payload decoders of Nodes";
\ No newline at end of file
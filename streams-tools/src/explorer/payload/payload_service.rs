@@ -21,11 +21,18 @@ use crate::{
             AppError,
         },
         messages::Message,
+        nodes::Node,
         app_state::MessagesState,
     },
     user_manager::dao::user::{
+        User,
         UserDataStore,
     },
+    dao_helpers::{
+        Condition,
+        Conditions,
+        MatchType,
+    },
     multi_channel_management::{
         get_channel_manager_for_external_id,
         MultiChannelManagerOptions
@@ -48,6 +55,14 @@ use crate::{
     }
 };
 
+use super::{
+    payload_decoders::PayloadDecoderRegistry,
+    payload_dto::{
+        PayloadDecoderInfo,
+        DecoderAssignment,
+    },
+};
+
 pub(crate) async fn decode(external_id: &String, user_store: &UserDataStore, messages: &MessagesState, payload: Vec<u8>) -> Result<Message> {
     run_worker_in_own_thread::<DecodeWorker>(DecodeWorkerOptions::new(
         external_id,
@@ -63,6 +78,7 @@ struct DecodeWorkerOptions {
     user_store: UserDataStore,
     payload: Vec<u8>,
     multi_channel_mngr_opt: MultiChannelManagerOptions,
+    messages: MessagesState,
 }
 
 impl DecodeWorkerOptions {
@@ -72,6 +88,7 @@ impl DecodeWorkerOptions {
             user_store: user_store.clone(),
             multi_channel_mngr_opt: messages.as_multi_channel_manager_options(),
             payload,
+            messages: messages.clone(),
         }
     }
 }
@@ -158,7 +175,14 @@ impl DecodeWorker {
             let address = Address::new(announcement_link.base(), msg_id);
             if let Some(author) = channel_manager.user.as_mut() {
                 if let Ok(unwrapped_msg) = author.receive_message(address).await {
-                    Ok(unwrapped_msg.into())
+                    let mut ret_val: Message = unwrapped_msg.into();
+                    let node_payload_decoder = opt.user_store.get_item_read_only(&address.base().to_string())
+                        .map(|user| user.payload_decoder)
+                        .unwrap_or_default();
+                    if let Some(decoder) = opt.messages.get_payload_decoder(node_payload_decoder.as_str()) {
+                        ret_val.decode_payload(decoder.as_ref());
+                    }
+                    Ok(ret_val)
                 } else {
                     Err(AppError::GenericWithMessage(
                         StatusCode::NOT_FOUND,
@@ -176,4 +200,54 @@ impl DecodeWorker {
             ))
         }
     }
-}
\ No newline at end of file
+}
+pub(crate) fn get_decoders(decoders: &PayloadDecoderRegistry) -> Vec<PayloadDecoderInfo> {
+    decoders.iter().map(|decoder| decoder.as_ref().into()).collect()
+}
+
+// The disambiguated external_ids of retired channels look like '<external_id>-(<n>)'
+fn is_external_id_of_node(external_id: &str, node_external_id: &str) -> bool {
+    node_external_id == external_id || node_external_id.starts_with(format!("{}-(", external_id).as_str())
+}
+
+fn get_users_for_external_id(user_store: &UserDataStore, external_id: &str) -> Result<Vec<User>> {
+    let mut conditions = Vec::<Condition>::new();
+    Conditions(&mut conditions).add(Some(external_id.to_string()), "external_id", MatchType::StartsWith);
+    let (users, _) = user_store.filter(conditions, None)?;
+    Ok(users.into_iter()
+        .filter(|user| is_external_id_of_node(external_id, user.external_id.as_str()))
+        .collect())
+}
+
+pub(crate) fn assign_decoder(user_store: &UserDataStore, messages: &MessagesState, assignment: DecoderAssignment) -> Result<Vec<Node>> {
+    if !assignment.decoder.is_empty() {
+        messages.payload_decoders.validate_decoder_name(assignment.decoder.as_str())
+            .map_err(|e| AppError::GenericWithMessage(StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
+    let mut users = match (assignment.channel_id, assignment.external_id) {
+        (Some(channel_id), None) => {
+            let user = user_store.get_item_read_only(&channel_id)
+                .map_err(|_| AppError::ChannelDoesNotExist(channel_id))?;
+            vec![user]
+        },
+        (None, Some(external_id)) => {
+            let users = get_users_for_external_id(user_store, external_id.as_str())?;
+            if users.is_empty() {
+                return Err(AppError::GenericWithMessage(
+                    StatusCode::NOT_FOUND,
+                    format!("A node with the external_id {} does not exist", external_id)
+                ));
+            }
+            users
+        },
+        _ => return Err(AppError::GenericWithMessage(
+            StatusCode::BAD_REQUEST,
+            "Either 'channel_id' or 'external_id' needs to be specified".to_string()
+        )),
+    };
+    for user in users.iter_mut() {
+        user.payload_decoder = assignment.decoder.clone();
+        user_store.write_item_to_db(user)?;
+    }
+    Ok(users.iter().map(|user| user.into()).collect())
+}
@@ -6,7 +6,15 @@ use axum::{
 use utoipa::{
     OpenApi,
     Modify,
-    openapi::Required,
+    openapi::{
+        Required,
+        ResponseBuilder,
+        security::{
+            HttpAuthScheme,
+            HttpBuilder,
+            SecurityScheme,
+        },
+    },
     ToSchema,
 };
 use utoipa_swagger_ui::SwaggerUi;
@@ -42,21 +50,39 @@ pub fn router() -> Router {
 }
 
 #[derive(OpenApi)]
-#[openapi(modifiers(&SetPagingParamsToNotRequired))]
+#[openapi(modifiers(&SetPagingParamsToNotRequired, &SecurityAddon))]
 #[openapi(
     info(title="SUSEE Message Explorer", description = "Explore messages of SUSEE nodes"),
     paths(
         nodes::index,
         nodes::get,
         nodes::put,
+        nodes::get_integrity,
+        nodes::sync,
         messages::index,
         messages::get,
+        messages::get_poi,
+        messages::export_poi,
+        messages::export,
+        messages::stream,
         payload::decode,
+        payload::decoders_index,
+        payload::assign_decoder,
     ),
     components(
         schemas(
             nodes::Node,
+            nodes::NodeIntegrity,
+            nodes::NodeSyncStatus,
+            nodes::MissingLink,
+            nodes::SequenceGap,
+            nodes::DuplicateMessage,
+            nodes::UnexpectedPublisher,
             messages::Message,
+            messages::MessagePoi,
+            payload::DecodedPayload,
+            payload::MeterReading,
+            payload::PayloadDecoderInfo,
             page_dto::Page<nodes::Node>,
             page_dto::Page<messages::Message>,
             page_dto::PageMeta,
@@ -66,6 +92,9 @@ pub fn router() -> Router {
     ),
     tags(
         (name = "susee message explorer")
+    ),
+    security(
+        ("bearer_auth" = [])
     )
 )]
 pub struct ApiDoc;
@@ -88,6 +117,38 @@ impl Modify for SetPagingParamsToNotRequired {
     }
 }
 
+// Adds the bearer token security scheme and the responses of the authentication
+// middleware (see auth::authenticate()) to all operations
+pub struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer_auth",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("<token_id>.<secret>")
+                        .description(Some("API token created with the Management Console (--create-api-token)"))
+                        .build()
+                ),
+            );
+        }
+        for path_tuple in &mut openapi.paths.paths {
+            for op_tuple in &mut path_tuple.1.operations {
+                let responses = &mut op_tuple.1.responses.responses;
+                responses.entry("401".to_string()).or_insert_with(||
+                    ResponseBuilder::new().description("Missing, unknown or invalid API token").build().into()
+                );
+                responses.entry("403".to_string()).or_insert_with(||
+                    ResponseBuilder::new().description("The role or the external_id prefix of the API token does not permit the request").build().into()
+                );
+            }
+        }
+    }
+}
+
 #[derive(ToSchema)]
 #[schema(title="JSON array")]
 struct DataT {}
\ No newline at end of file
@@ -1,5 +1,18 @@
 use std::{
-    time::Duration
+    cell::RefCell,
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
 };
 
 use anyhow::{
@@ -9,10 +22,13 @@ use anyhow::{
 
 use async_trait::async_trait;
 
+use futures::StreamExt;
+
 use tokio::{
     time::{
         Instant,
         interval,
+        sleep,
     }
 };
 
@@ -24,17 +40,77 @@ use crate::{
     user_manager::{
         dao::{
             User,
-            message::MessageDataStoreOptions
+            message::MessageDataStoreOptions,
+            sync_cursor::{
+                SyncCursor,
+                SyncCursorDataStore,
+            },
         },
         message_indexer::MessageIndexer,
+        proof_of_inclusion::PoiClientOptions,
+        webhook::WebhookOptions,
+        message_feed::MessageFeed,
         multi_channel_management::{
             MultiChannelManagerOptions,
             get_channel_manager_for_channel_id,
         },
     },
     threading_helpers::Worker,
+    dao_helpers::DbFileBasedDaoManagerOptions,
 };
 
+pub const DEFAULT_SYNC_CHANNELS_MAX_WORKERS: usize = 4;
+
+const CHANNEL_SYNC_LOCK_POLL_INTERVAL_MILLISECS: u64 = 100;
+
+// Prevents that a channel is synchronized by several workers at the same time
+// (e.g. by the sync loop, the live sync loop and an explicitly requested sync).
+// Otherwise the Streams client state stored by one worker could be overwritten by
+// the outdated state of another worker.
+// All code that loads and stores the Streams client state of a channel (sync loops,
+// GET /messages, message export) must hold the lock from loading the Streams client
+// state until the state has been stored again.
+#[derive(Clone, Default)]
+pub struct ChannelSyncLocks {
+    locked_channel_ids: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ChannelSyncLocks {
+    // Returns None if the channel is already locked. The lock is released when the
+    // returned ChannelSyncGuard is dropped.
+    pub fn try_lock(&self, channel_id: &str) -> Option<ChannelSyncGuard> {
+        if self.locked_channel_ids.lock().unwrap().insert(channel_id.to_string()) {
+            Some(ChannelSyncGuard {
+                locks: self.clone(),
+                channel_id: channel_id.to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    // Waits until the channel is not locked by another worker anymore
+    pub async fn lock(&self, channel_id: &str) -> ChannelSyncGuard {
+        loop {
+            if let Some(guard) = self.try_lock(channel_id) {
+                return guard;
+            }
+            sleep(Duration::from_millis(CHANNEL_SYNC_LOCK_POLL_INTERVAL_MILLISECS)).await;
+        }
+    }
+}
+
+pub struct ChannelSyncGuard {
+    locks: ChannelSyncLocks,
+    channel_id: String,
+}
+
+impl Drop for ChannelSyncGuard {
+    fn drop(&mut self) {
+        self.locks.locked_channel_ids.lock().unwrap().remove(&self.channel_id);
+    }
+}
+
 #[derive(Clone)]
 pub struct SyncChannelsLoopOptions {
     pub user_store: UserDataStore,
@@ -42,6 +118,20 @@ pub struct SyncChannelsLoopOptions {
     pub message_data_store_file_path_and_name: String,
     pub sync_channels_interval_in_secs: u64,
     pub sync_channels_stop_before_next_run_secs: u64,
+    // Maximum number of channels that are synchronized in parallel by the sync loop
+    pub sync_channels_max_workers: usize,
+    pub channel_sync_locks: ChannelSyncLocks,
+    pub poi_client_options: Option<PoiClientOptions>,
+    // Used to record the milestone timestamps of the synced messages if no
+    // poi_client_options are set
+    pub milestone_timestamp_client_options: Option<PoiClientOptions>,
+    pub webhook_options: Option<WebhookOptions>,
+    // New messages are published to the message_feed. Channels watched by feed subscribers and
+    // channels that received messages during the last live_sync_recent_activity_secs are
+    // synchronized every live_sync_interval_in_secs while the feed has subscribers.
+    pub message_feed: Option<MessageFeed>,
+    pub live_sync_interval_in_secs: u64,
+    pub live_sync_recent_activity_secs: u64,
 }
 
 unsafe impl Send for SyncChannelsLoopOptions {}
@@ -55,6 +145,14 @@ impl SyncChannelsLoopOptions {
             message_data_store_file_path_and_name: msg_data_store_file_path_name,
             sync_channels_interval_in_secs: 3600,
             sync_channels_stop_before_next_run_secs: 600,
+            sync_channels_max_workers: DEFAULT_SYNC_CHANNELS_MAX_WORKERS,
+            channel_sync_locks: ChannelSyncLocks::default(),
+            poi_client_options: None,
+            milestone_timestamp_client_options: None,
+            webhook_options: None,
+            message_feed: None,
+            live_sync_interval_in_secs: 10,
+            live_sync_recent_activity_secs: 3600,
         }
     }
 }
@@ -73,21 +171,60 @@ impl Worker for SyncChannelsWorker {
     }
 }
 
+// Synchronizes recently active channels frequently so that new messages are published
+// to the message_feed of the SyncChannelsLoopOptions shortly after they have been sent
+pub struct LiveSyncChannelsWorker;
+
+#[async_trait(?Send)]
+impl Worker for LiveSyncChannelsWorker {
+    type OptionsType = SyncChannelsLoopOptions;
+    type ResultType = ();
+    type ErrorType = AnyError;
+
+    async fn run(opt: SyncChannelsLoopOptions) -> Result<()> {
+        run_live_sync_loop(opt).await;
+        Ok(())
+    }
+}
+
+// Synchronizes a single channel immediately, e.g. if requested by a client of the Message Explorer.
+// Responds None if the channel is currently synchronized by another worker.
+pub struct SyncChannelWorker;
+
+#[async_trait(?Send)]
+impl Worker for SyncChannelWorker {
+    type OptionsType = (SyncChannelsLoopOptions, String);
+    type ResultType = Option<u32>;
+    type ErrorType = AnyError;
+
+    async fn run(opt: (SyncChannelsLoopOptions, String)) -> Result<Option<u32>> {
+        let (opt, channel_id) = opt;
+        let _guard = match opt.channel_sync_locks.try_lock(channel_id.as_str()) {
+            Some(guard) => guard,
+            None => return Ok(None),
+        };
+        let mut user = opt.user_store.get_item_read_only(&channel_id)?;
+        sync_channel(&mut user, &opt).await.map(|new_msgs_cnt| Some(new_msgs_cnt.unwrap_or_default()))
+    }
+}
+
 #[derive(Debug)]
 struct UserLoopStatus {
     pub remaining: usize,
     pub processed: usize,
+    pub new_messages: u32,
 }
 
 impl UserLoopStatus {
     fn new(remaining: usize, processed: usize) -> Self {
-        UserLoopStatus { remaining, processed }
+        UserLoopStatus { remaining, processed, new_messages: 0 }
     }
 
     pub fn log_status(&self) {
-        log::info!("[fn run_sync_channels_loop] Finished syncing channels(). {} channels processed. {} channels remaining.",
+        log::info!("[fn run_sync_channels_loop] Finished syncing channels(). {} channels processed. {} channels remaining. {} new messages.",
                    self.processed,
-                   self.remaining
+                   self.remaining,
+                   self.new_messages,
         );
     }
 }
@@ -108,37 +245,140 @@ pub async fn run_sync_channels_loop(opt: SyncChannelsLoopOptions) {
     }
 }
 
+pub async fn run_live_sync_loop(opt: SyncChannelsLoopOptions) {
+    let message_feed = match opt.message_feed.as_ref() {
+        Some(feed) => feed.clone(),
+        None => {
+            log::warn!("[fn run_live_sync_loop] No message_feed specified. Live sync loop will not be started.");
+            return;
+        }
+    };
+    let recent_activity_period = Duration::from_secs(opt.live_sync_recent_activity_secs);
+    let mut interval = interval(Duration::from_secs(opt.live_sync_interval_in_secs));
+    loop {
+        interval.tick().await;
+        if !message_feed.has_subscribers() {
+            continue;
+        }
+        for channel_id in message_feed.get_active_channel_ids(recent_activity_period) {
+            let _guard = match opt.channel_sync_locks.try_lock(channel_id.as_str()) {
+                Some(guard) => guard,
+                None => {
+                    log::debug!("[fn run_live_sync_loop] Channel {} is already being synced", channel_id);
+                    continue;
+                }
+            };
+            let mut user = match opt.user_store.get_item_read_only(&channel_id) {
+                Ok(user) => user,
+                Err(e) => {
+                    log::warn!("[fn run_live_sync_loop] Could not find channel {}. Error: {}", channel_id, e);
+                    continue;
+                }
+            };
+            if user.is_archived() {
+                continue;
+            }
+            log::debug!("[fn run_live_sync_loop] Start syncing channel {}", channel_id);
+            if let Err(e) = sync_channel(&mut user, &opt).await {
+                log::error!("[fn run_live_sync_loop] fn sync_channel returned error for channel {}. Error: {}", channel_id, e);
+            }
+        }
+    }
+}
+
+pub(super) fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+// Returns the users in the order they are synchronized by the sync loop:
+// * Channels that received messages during the last recent_activity_secs come first,
+//   the most recently active channel first.
+// * All other channels follow, the channel that has been synced least recently first.
+//   Channels that have never been synced are synced before all other inactive channels.
+//   Channels skipped because a sync loop run has exceeded its max duration are therefore
+//   synced first in the next run.
+fn get_channels_in_sync_order(users: Vec<User>, cursors: &HashMap<String, SyncCursor>, now_secs: i64, recent_activity_secs: i64) -> Vec<User> {
+    let mut ret_val = users;
+    ret_val.sort_by_key(|user| {
+        let cursor = cursors.get(&user.streams_channel_id);
+        let last_msg_received_at = cursor.map(|cursor| cursor.last_msg_received_at_secs).unwrap_or_default();
+        let last_synced_at = cursor.map(|cursor| cursor.last_synced_at_secs).unwrap_or_default();
+        if last_msg_received_at > 0 && now_secs - last_msg_received_at <= recent_activity_secs {
+            (0, -last_msg_received_at)
+        } else {
+            (1, last_synced_at)
+        }
+    });
+    ret_val
+}
+
 async fn sync_each_channel_in_user_store(opt: SyncChannelsLoopOptions) -> Result<UserLoopStatus> {
     let loop_start = Instant::now();
     let max_duration_to_run_loop = Duration::from_secs(
         opt.sync_channels_interval_in_secs - opt.sync_channels_stop_before_next_run_secs
     );
-    log::info!("[fn sync_each_channel_in_user_store] Start syncing channels of all users");
-    let (mut users, items_cnt_total) = opt.user_store.find_all("", None)?;
-    let mut ret_val = UserLoopStatus::new(items_cnt_total, 0);
-    for user in users.iter_mut() {
-        log::info!("[fn sync_each_channel_in_user_store] Start syncing channel {}", user.streams_channel_id);
-        match sync_channel(user, &opt).await {
-            Ok(num_channels_processed) => {
-                ret_val.processed += num_channels_processed;
-                ret_val.remaining -= num_channels_processed;
-            },
-            Err(e) => {
-                log::error!("[fn sync_each_channel_in_user_store] fn sync_channel returned error. Continuing loop over users. Error: {}", e);
+    log::info!("[fn sync_each_channel_in_user_store] Start syncing channels of all users using {} workers", opt.sync_channels_max_workers);
+    let users = opt.user_store.find_all_unlimited()?;
+    let items_cnt_total = users.len();
+    let cursor_store = SyncCursorDataStore::new(DbFileBasedDaoManagerOptions {
+        file_path_and_name: opt.message_data_store_file_path_and_name.clone(),
+    });
+    let cursors: HashMap<String, SyncCursor> = cursor_store.find_all_unlimited()?.into_iter()
+        .map(|cursor| (cursor.streams_channel_id.clone(), cursor))
+        .collect();
+    let users = get_channels_in_sync_order(users, &cursors, now_secs(), opt.live_sync_recent_activity_secs as i64);
+
+    // All workers run in the current thread, so a RefCell is sufficient
+    let status = RefCell::new(UserLoopStatus::new(items_cnt_total, 0));
+    futures::stream::iter(users)
+        .for_each_concurrent(opt.sync_channels_max_workers.max(1), |mut user| {
+            let opt = &opt;
+            let status = &status;
+            async move {
+                if user.is_archived() {
+                    log::debug!("[fn sync_each_channel_in_user_store] Skipping archived channel {}", user.streams_channel_id);
+                    status.borrow_mut().remaining -= 1;
+                    return;
+                }
+                if Instant::now().duration_since(loop_start) >= max_duration_to_run_loop {
+                    log::debug!("[fn sync_each_channel_in_user_store] Skipping channel {} due to max duration exceeded {:?}",
+                        user.streams_channel_id, max_duration_to_run_loop);
+                    return;
+                }
+                let _guard = match opt.channel_sync_locks.try_lock(user.streams_channel_id.as_str()) {
+                    Some(guard) => guard,
+                    None => {
+                        log::debug!("[fn sync_each_channel_in_user_store] Channel {} is already being synced", user.streams_channel_id);
+                        return;
+                    }
+                };
+                log::info!("[fn sync_each_channel_in_user_store] Start syncing channel {}", user.streams_channel_id);
+                match sync_channel(&mut user, opt).await {
+                    Ok(Some(new_messages)) => {
+                        let mut status = status.borrow_mut();
+                        status.processed += 1;
+                        status.remaining -= 1;
+                        status.new_messages += new_messages;
+                    },
+                    Ok(None) => {},
+                    Err(e) => {
+                        log::error!("[fn sync_each_channel_in_user_store] fn sync_channel returned error for channel {}. Continuing loop over users. Error: {}",
+                            user.streams_channel_id, e);
+                    }
+                };
             }
-        };
-        let duration_since_loop_start = Instant::now().duration_since(loop_start);
-        log::debug!("[fn sync_each_channel_in_user_store] duration_since_loop_start: {} secs", duration_since_loop_start.as_secs());
-        if duration_since_loop_start >= max_duration_to_run_loop {
-            log::info!("[fn sync_each_channel_in_user_store] Breaking loop over users due to max duration exceeded {:?}", max_duration_to_run_loop);
-            break;
-        }
-    }
-    log::info!("[fn sync_each_channel_in_user_store] Stopped syncing channels of all users");
-    Ok(ret_val)
+        })
+        .await;
+    log::info!("[fn sync_each_channel_in_user_store] Stopped syncing channels of all users after {} secs",
+        Instant::now().duration_since(loop_start).as_secs());
+    Ok(status.into_inner())
 }
 
-async fn sync_channel(user: &mut User, opt: &SyncChannelsLoopOptions) -> Result<usize>{
+// Returns the number of new messages or None if the channel has no Streams client state
+async fn sync_channel(user: &mut User, opt: &SyncChannelsLoopOptions) -> Result<Option<u32>>{
     let mut multi_channel_mngr_opt = opt.multi_channel_mngr_opt.clone();
     multi_channel_mngr_opt.message_data_store_for_msg_caching = Some(
         MessageDataStoreOptions{
@@ -151,18 +391,106 @@ async fn sync_channel(user: &mut User, opt: &SyncChannelsLoopOptions) -> Result<
         &opt.user_store,
         &multi_channel_mngr_opt,
     ).await?;
-    let mut num_channels_processed = 0;
+    let mut ret_val = None;
     if let Some(author) = channel_manager.user.as_mut() {
         let mut msg_mngr = MessageManager::<Client<MessageIndexer>>::new(
             author,
             user.streams_channel_id.clone(),
             opt.message_data_store_file_path_and_name.clone()
         );
-        msg_mngr.sync().await?;
-        num_channels_processed = 1;
+        if let Some(poi_client_options) = opt.poi_client_options.as_ref() {
+            msg_mngr.set_poi_client_options(poi_client_options.clone());
+        }
+        if let Some(milestone_timestamp_client_options) = opt.milestone_timestamp_client_options.as_ref() {
+            msg_mngr.set_milestone_timestamp_client_options(milestone_timestamp_client_options.clone());
+        }
+        if let Some(webhook_options) = opt.webhook_options.as_ref() {
+            // The Management Console uses the DevEUI of the sensor as external_id
+            msg_mngr.set_webhook_options(webhook_options.clone(), user.external_id.as_str());
+        }
+        if let Some(message_feed) = opt.message_feed.as_ref() {
+            msg_mngr.set_message_feed(message_feed.clone(), user.external_id.as_str());
+        }
+        ret_val = Some(msg_mngr.sync().await?);
     }
-    Ok(num_channels_processed)
+    Ok(ret_val)
 }
 
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib explorer::sync_channels_loop::tests --features explorer
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn get_user(channel_id: &str) -> User {
+        User {
+            streams_channel_id: channel_id.to_string(),
+            ..Default::default()
+        }
+    }
 
+    fn get_cursor(channel_id: &str, last_msg_received_at_secs: i64, last_synced_at_secs: i64) -> (String, SyncCursor) {
+        (channel_id.to_string(), SyncCursor {
+            streams_channel_id: channel_id.to_string(),
+            last_msg_received_at_secs,
+            last_synced_at_secs,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_get_channels_in_sync_order() {
+        let now = 100_000;
+        let users = vec![
+            get_user("synced_long_ago"),
+            get_user("recently_active"),
+            get_user("never_synced"),
+            get_user("most_recently_active"),
+            get_user("synced_recently"),
+        ];
+        let cursors: HashMap<String, SyncCursor> = vec![
+            get_cursor("synced_long_ago", 1_000, 50_000),
+            get_cursor("recently_active", now - 600, now - 60),
+            get_cursor("most_recently_active", now - 60, now - 60),
+            get_cursor("synced_recently", 2_000, now - 60),
+        ].into_iter().collect();
+
+        let ordered: Vec<String> = get_channels_in_sync_order(users, &cursors, now, 3600).into_iter()
+            .map(|user| user.streams_channel_id)
+            .collect();
+        assert_eq!(ordered, vec![
+            "most_recently_active",
+            "recently_active",
+            "never_synced",
+            "synced_long_ago",
+            "synced_recently",
+        ]);
+    }
+
+    #[test]
+    fn test_channel_sync_locks() {
+        let locks = ChannelSyncLocks::default();
+        let guard = locks.try_lock("chan_a");
+        assert!(guard.is_some());
+        assert!(locks.try_lock("chan_a").is_none());
+        assert!(locks.clone().try_lock("chan_b").is_some());
+        drop(guard);
+        assert!(locks.try_lock("chan_a").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_channel_sync_locks_wait_for_release() {
+        let locks = ChannelSyncLocks::default();
+        let guard = locks.lock("chan_a").await;
+        let waiting_locks = locks.clone();
+        let waiting = tokio::spawn(async move {
+            let _guard = waiting_locks.lock("chan_a").await;
+        });
+        sleep(Duration::from_millis(3 * CHANNEL_SYNC_LOCK_POLL_INTERVAL_MILLISECS)).await;
+        assert!(!waiting.is_finished());
+        drop(guard);
+        waiting.await.unwrap();
+        assert!(locks.try_lock("chan_a").is_some());
+    }
+}
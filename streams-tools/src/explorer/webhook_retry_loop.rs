@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use anyhow::{
+    Result,
+    Error as AnyError
+};
+
+use async_trait::async_trait;
+
+use tokio::time::interval;
+
+use crate::{
+    user_manager::{
+        webhook::{
+            WebhookClient,
+            WebhookOptions,
+        },
+        dao::webhook_delivery::WebhookDeliveryDataStore,
+    },
+    threading_helpers::Worker,
+    dao_helpers::DbFileBasedDaoManagerOptions,
+};
+
+pub const DEFAULT_WEBHOOK_RETRY_INTERVAL_SECS: u64 = 10;
+
+#[derive(Clone)]
+pub struct WebhookRetryLoopOptions {
+    pub webhook_options: WebhookOptions,
+    pub db_file_name: String,
+    pub retry_interval_in_secs: u64,
+}
+
+impl WebhookRetryLoopOptions {
+    pub fn new(webhook_options: WebhookOptions, db_file_name: String) -> Self {
+        WebhookRetryLoopOptions {
+            webhook_options,
+            db_file_name,
+            retry_interval_in_secs: DEFAULT_WEBHOOK_RETRY_INTERVAL_SECS,
+        }
+    }
+}
+
+// Retries the webhook notifications that have been queued because they could not be
+// delivered by the channel sync (see WebhookClient::notify())
+pub struct WebhookRetryWorker;
+
+#[async_trait(?Send)]
+impl Worker for WebhookRetryWorker {
+    type OptionsType = WebhookRetryLoopOptions;
+    type ResultType = ();
+    type ErrorType = AnyError;
+
+    async fn run(opt: WebhookRetryLoopOptions) -> Result<()> {
+        run_webhook_retry_loop(opt).await;
+        Ok(())
+    }
+}
+
+pub async fn run_webhook_retry_loop(opt: WebhookRetryLoopOptions) {
+    log::info!("[fn run_webhook_retry_loop] Using {}", opt.webhook_options);
+    let webhook_client = WebhookClient::new(opt.webhook_options);
+    let delivery_queue = WebhookDeliveryDataStore::new(DbFileBasedDaoManagerOptions {
+        file_path_and_name: opt.db_file_name,
+    });
+    let mut interval = interval(Duration::from_secs(opt.retry_interval_in_secs));
+    loop {
+        interval.tick().await;
+        match webhook_client.retry_queued_deliveries(&delivery_queue).await {
+            Ok(num_delivered) => if num_delivered > 0 {
+                log::info!("[fn run_webhook_retry_loop] Delivered {} queued webhook notifications", num_delivered);
+            },
+            Err(err) => {
+                log::error!("[fn run_webhook_retry_loop] Got error from retry_queued_deliveries(): {}", err);
+            }
+        }
+    }
+}
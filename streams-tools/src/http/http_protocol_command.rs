@@ -228,14 +228,15 @@ impl RequestBuilderCommand {
         )
     }
 
-    pub fn dev_eui_handshake(self: &Self) -> Result<Request<Body>> {
-        RequestBuilderTools::get_request_builder()
-            .method("GET")
-            .uri(self.tools.get_uri(
-                &EndpointUris::get_uri___dev_eui_handshake(
-                    self.dev_eui.borrow().as_str())
-            ).as_str())
-            .body(Body::empty())
+    // The pairing_code is send to the sensor that needs to echo it back in the
+    // DevEuiHandshake confirmation. Use an empty pairing_code to not use a pairing code.
+    pub fn dev_eui_handshake(self: &Self, pairing_code: &str) -> Result<Request<Body>> {
+        self.tools.send_enumerated_persistable_args(
+            DevEuiHandshakeCmd{ pairing_code: pairing_code.to_string() },
+            EndpointUris::get_uri___dev_eui_handshake(
+                self.dev_eui.borrow().as_str()
+            ).as_str()
+        )
     }
 }
 
@@ -279,11 +280,16 @@ pub async fn dispatch_request_command(req_parts: &DispatchedRequestParts, callba
             callbacks.register_remote_command(segments.last.as_str(), &req_parts.binary_body, "register_keyload_msg").await
         },
 
+        // Used by older management-console versions. The DevEuiHandshakeCmd has no pairing code.
         (&Method::GET, EndpointUris::DEV_EUI_HANDSHAKE) => {
             let buffer = get_body_bytes_from_enumerated_persistable(&Command::DEV_EUI_HANDSHAKE)?;
             callbacks.register_remote_command(segments.last.as_str(), &buffer, "dev_eui_handshake").await
         },
 
+        (&Method::POST, EndpointUris::DEV_EUI_HANDSHAKE) => {
+            callbacks.register_remote_command(segments.last.as_str(), &req_parts.binary_body, "dev_eui_handshake").await
+        },
+
         // Return the 404 Not Found for other routes.
         _ => req_parts.log_and_return_404("dispatch_request_command", "")
     }
@@ -29,6 +29,7 @@ use crate::{
         SubscriberStatus,
         SendMessages,
         DevEuiHandshake,
+        InitializationEpoch,
         binary_persist_confirmation::{
             Subscription,
             Confirmation
@@ -122,12 +123,13 @@ impl RequestBuilderConfirm {
             .body(Body::empty())
     }
 
-    pub fn subscription(self: &Self, subscription_link: String, pup_key: String, initialization_cnt: u8) -> Result<Request<Body>> {
+    pub fn subscription(self: &Self, subscription_link: String, pup_key: String, initialization_cnt: u8, initialization_epoch: InitializationEpoch) -> Result<Request<Body>> {
         self.tools.send_enumerated_persistable_args(
             Subscription{
                 subscription_link,
                 pup_key,
                 initialization_cnt,
+                initialization_epoch,
             },
             &EndpointUris::get_uri___subscription(self.dev_eui.borrow().as_str())
         )
@@ -178,9 +180,11 @@ impl RequestBuilderConfirm {
     ///                 (2) the dev_eui field of this RequestBuilderConfirm instance,
     ///                     which is used as url-parameter for the iota-bridge request
     ///                     so it usually should be set to 'ANY'.
-    pub fn dev_eui_handshake(self: &Self, dev_eui: String) -> Result<Request<Body>> {
+    /// @param pairing_code: The pairing code received with the DevEuiHandshakeCmd
+    ///                      that is echoed back to the management-console.
+    pub fn dev_eui_handshake(self: &Self, dev_eui: String, pairing_code: String) -> Result<Request<Body>> {
         self.tools.send_enumerated_persistable_args(
-            DevEuiHandshake{dev_eui},
+            DevEuiHandshake{dev_eui, pairing_code},
             &EndpointUris::get_uri___dev_eui_handshake(self.dev_eui.borrow().as_str())
         )
     }
@@ -12,6 +12,7 @@ use hyper::{
 };
 
 use crate::{
+    binary_persist::InitializationEpoch,
     http::{
         ScopeConsume,
         http_tools::{
@@ -41,6 +42,9 @@ impl EndpointUris {
     pub const CREATE_NODE: &'static str = URI_PREFIX_LORAWAN_NODE;
     pub const GET_NODE: &'static str = URI_PREFIX_LORAWAN_NODE;
     pub const IS_NODE_KNOWN: &'static str = URI_PREFIX_LORAWAN_NODE;
+    // GET: Export all lorawan_nodes (pull), POST: Import lorawan_nodes (push)
+    pub const SYNC_NODES: &'static str = "/lorawan-node/registry";
+    pub const SYNC_NODES_LAST_SEGMENT: &'static str = "sync";
 
     pub fn get_uri___create_node(dev_eui: &str) -> String {
         format!("{}/{}", Self::CREATE_NODE, dev_eui)
@@ -48,6 +52,9 @@ impl EndpointUris {
     pub fn get_uri___get_node(dev_eui: &str) -> String {
         format!("{}/{}", Self::GET_NODE, dev_eui)
     }
+    pub fn get_uri___sync_nodes() -> String {
+        format!("{}/{}", Self::SYNC_NODES, Self::SYNC_NODES_LAST_SEGMENT)
+    }
 }
 
 pub struct QueryParameters {}
@@ -90,6 +97,38 @@ impl RequestBuilderLoraWanNode {
             .uri(self.tools.get_uri(EndpointUris::get_uri___get_node(dev_eui).as_str()))
             .body(Body::empty())
     }
+
+    pub fn export_nodes(self: &Self) -> Result<Request<Body>> {
+        RequestBuilderTools::get_request_builder()
+            .method("GET")
+            .uri(self.tools.get_uri(EndpointUris::get_uri___sync_nodes().as_str()))
+            .body(Body::empty())
+    }
+
+    // Imports a single LoraWanNode using the IMPORT_NODES endpoint. Other than create_node(),
+    // the initialization count is transferred, so that the stored node is only replaced
+    // if the imported node has a higher full initialization count.
+    // The node is serialized using the JSON representation of iota_bridge::dao::LoraWanNode,
+    // as the LoraWanNode type is only available with feature 'iota_bridge'.
+    #[cfg(feature = "dao")]
+    pub fn import_node(self: &Self, dev_eui: &str, channel_id: &str, initialization_cnt: u8, initialization_epoch: InitializationEpoch) -> Result<Request<Body>> {
+        let nodes_json = serde_json::json!([{
+            "dev_eui": dev_eui,
+            "initialization_cnt": initialization_cnt,
+            "initialization_epoch": initialization_epoch,
+            "streams_channel_id": channel_id,
+        }]);
+        self.import_nodes(nodes_json.to_string())
+    }
+
+    // nodes_json: JSON array of LoraWanNode instances
+    pub fn import_nodes(self: &Self, nodes_json: String) -> Result<Request<Body>> {
+        RequestBuilderTools::get_request_builder()
+            .method("POST")
+            .uri(self.tools.get_uri(EndpointUris::get_uri___sync_nodes().as_str()))
+            .header("Content-Type", "application/json")
+            .body(Body::from(nodes_json))
+    }
 }
 
 #[async_trait(?Send)]
@@ -97,6 +136,8 @@ pub trait ServerDispatchLoraWanNode: ScopeConsume {
     fn get_uri_prefix(&self) -> &'static str;
     async fn create_node(self: &mut Self, dev_eui: &str, channel_id: &str) -> Result<Response<Body>>;
     async fn get_node(self: &mut Self, dev_eui: &str, only_check_existence: bool) -> Result<Response<Body>>;
+    async fn export_nodes(self: &mut Self) -> Result<Response<Body>>;
+    async fn import_nodes(self: &mut Self, nodes_json: &[u8]) -> Result<Response<Body>>;
 }
 
 pub async fn dispatch_request_lorawan_node(req_parts: &DispatchedRequestParts, callbacks: &mut impl ServerDispatchLoraWanNode) -> Result<Response<Body>> {
@@ -107,6 +148,14 @@ pub async fn dispatch_request_lorawan_node(req_parts: &DispatchedRequestParts, c
     };
 
     match (&req_parts.method, segments.main.as_str()) {
+        (&Method::GET, EndpointUris::SYNC_NODES) if segments.last == EndpointUris::SYNC_NODES_LAST_SEGMENT => {
+            callbacks.export_nodes().await
+        },
+
+        (&Method::POST, EndpointUris::SYNC_NODES) if segments.last == EndpointUris::SYNC_NODES_LAST_SEGMENT => {
+            callbacks.import_nodes(req_parts.binary_body.as_slice()).await
+        },
+
         (&Method::POST, EndpointUris::CREATE_NODE) => {
             let channel_id_key_val: Vec<_> = req_parts.req_url.query_pairs().collect();
             if channel_id_key_val.len() != 1 {
@@ -43,6 +43,11 @@ use crate::{
 use super::{
     BufferedMessageDataStore,
     dao::BufferedMessage,
+    cluster::{
+        ClusterOptions,
+        LeaderElection,
+        BUFFERED_MESSAGE_LOOP_LEASE_NAME,
+    },
     streams_transport_no_tangle::{
         StreamsTransportNoTangle,
         StreamsTransportNoTangleOptions
@@ -59,6 +64,8 @@ pub struct BufferedMessageLoopOptions {
     pub max_send_messages_working_time_in_secs: u64,
     pub idle_status_log_messages_interval_secs: u64,
     pub use_tangle_transport: bool,
+    // If Some, buffered messages are only send if this instance is the elected leader of the cluster
+    pub cluster: Option<ClusterOptions>,
 }
 
 impl BufferedMessageLoopOptions {
@@ -70,6 +77,7 @@ impl BufferedMessageLoopOptions {
             max_send_messages_working_time_in_secs: 1,
             idle_status_log_messages_interval_secs: 60,
             use_tangle_transport: true,
+            cluster: None,
         }
     }
 }
@@ -118,9 +126,18 @@ pub async fn run_buffered_message_loop(opt: BufferedMessageLoopOptions) {
     let mut do_log_even_if_loop_is_in_idle_status;
     let mut last_log_output_instant = Instant::now();
     let idle_status_log_messages_duration = Duration::from_secs(opt.idle_status_log_messages_interval_secs);
+    let mut leader_election = opt.cluster.as_ref().map(|cluster|
+        LeaderElection::new(cluster.clone(), BUFFERED_MESSAGE_LOOP_LEASE_NAME)
+    );
 
     loop {
         interval.tick().await;
+        if let Some(election) = leader_election.as_mut() {
+            if !election.is_leader() {
+                log::debug!("[fn run_buffered_message_loop] This instance is not the cluster leader - Skipping send_all_buffered_messages()");
+                continue;
+            }
+        }
         log::debug!("[fn run_buffered_message_loop] {} Seconds passed - Starting send_all_buffered_messages()", opt.send_messages_interval_in_secs);
         let now = Instant::now();
         do_log_even_if_loop_is_in_idle_status = now.duration_since(last_log_output_instant) > idle_status_log_messages_duration;
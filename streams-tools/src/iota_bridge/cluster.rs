@@ -0,0 +1,130 @@
+use std::{
+    fmt,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::Result;
+
+use crate::dao_helpers::{
+    DaoManager,
+    DbFileBasedDaoManagerOptions,
+};
+
+use super::dao::cluster_lease::ClusterLeaseDaoManager;
+
+// Name of the lease that needs to be held by the iota-bridge instance running the buffered message loop
+pub static BUFFERED_MESSAGE_LOOP_LEASE_NAME: &str = "buffered_message_loop";
+
+// If the iota-bridge instance holding a lease does not renew the lease during this time,
+// another instance will take over. Needs to be significantly longer than the
+// BufferedMessageLoopOptions::send_messages_interval_in_secs.
+pub static DEFAULT_LEADER_LEASE_DURATION_SECS: u64 = 30;
+
+// Several iota-bridge instances using the same database (see DbFileBasedDaoManagerOptions)
+// can be run as cluster. The instances of a cluster share
+// * the LoRaWAN nodes, pending requests and buffered messages stored in the database
+// * the command and confirmation FIFO queues
+// Only one elected instance (the leader) runs the buffered message loop.
+#[derive(Clone)]
+pub struct ClusterOptions {
+    pub instance_id: String,
+    pub db_connection_opt: DbFileBasedDaoManagerOptions,
+    pub leader_lease_duration_secs: u64,
+}
+
+impl ClusterOptions {
+    pub fn new(instance_id: Option<&str>, db_connection_opt: DbFileBasedDaoManagerOptions) -> Self {
+        Self {
+            instance_id: instance_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| Self::get_random_instance_id()),
+            db_connection_opt,
+            leader_lease_duration_secs: DEFAULT_LEADER_LEASE_DURATION_SECS,
+        }
+    }
+
+    fn get_random_instance_id() -> String {
+        format!("iota-bridge-{}-{:08x}", std::process::id(), rand::random::<u32>())
+    }
+}
+
+impl fmt::Display for ClusterOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instance_id: {}, leader_lease_duration_secs: {}",
+               self.instance_id,
+               self.leader_lease_duration_secs,
+        )
+    }
+}
+
+fn get_unix_time_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before UNIX epoch")
+        .as_secs() as i64
+}
+
+// Lease based leader election: The leader is the instance that holds the lease.
+// is_leader() needs to be called periodically to renew the lease.
+pub struct LeaderElection {
+    options: ClusterOptions,
+    lease_name: String,
+    leases: ClusterLeaseDaoManager,
+    is_leader: bool,
+}
+
+impl LeaderElection {
+    pub fn new(options: ClusterOptions, lease_name: &str) -> Self {
+        let leases = ClusterLeaseDaoManager::new(options.db_connection_opt.clone());
+        leases.update_db_schema_to_current_version()
+            .expect("Error on updating database schema for ClusterLeaseDaoManager");
+        Self {
+            options,
+            lease_name: lease_name.to_string(),
+            leases,
+            is_leader: false,
+        }
+    }
+
+    // Tries to acquire resp. renew the lease. Returns true if this instance is the leader.
+    pub fn is_leader(&mut self) -> bool {
+        let is_leader = match self.try_acquire_lease() {
+            Ok(is_leader) => is_leader,
+            Err(e) => {
+                log::error!("[LeaderElection.is_leader()] Error on acquiring lease '{}': {}", self.lease_name, e);
+                false
+            }
+        };
+        if is_leader != self.is_leader {
+            if is_leader {
+                log::info!("[LeaderElection.is_leader()] Instance '{}' is now leader for '{}'", self.options.instance_id, self.lease_name);
+            } else {
+                log::info!("[LeaderElection.is_leader()] Instance '{}' is no more leader for '{}'", self.options.instance_id, self.lease_name);
+            }
+        }
+        self.is_leader = is_leader;
+        is_leader
+    }
+
+    fn try_acquire_lease(&self) -> Result<bool> {
+        self.leases.try_acquire(
+            self.lease_name.as_str(),
+            self.options.instance_id.as_str(),
+            get_unix_time_secs(),
+            self.options.leader_lease_duration_secs as i64,
+        )
+    }
+}
+
+impl Drop for LeaderElection {
+    fn drop(&mut self) {
+        if self.is_leader {
+            if let Err(e) = self.leases.release(self.lease_name.as_str(), self.options.instance_id.as_str()) {
+                log::warn!("[LeaderElection.drop()] Could not release lease '{}': {}", self.lease_name, e);
+            }
+        }
+    }
+}
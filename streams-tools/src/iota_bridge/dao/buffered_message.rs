@@ -1,20 +1,22 @@
-use anyhow::Result;
+use anyhow::{
+    Result,
+    anyhow,
+};
 
 use serde::{
     Deserialize,
     Serialize,
 };
 
-use rusqlite::{
-    Connection,
-    params,
-};
-
 use crate::{
     helpers::SerializationCallbackRefToClosureI64,
     binary_persist::LinkedMessage,
     dao_helpers::{
         DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
         DbSchemaVersionType,
         DaoDataStore,
         DbFileBasedDaoManagerOptions,
@@ -46,7 +48,7 @@ impl BufferedMessage {
 }
 
 pub struct BufferedMessageDaoManager {
-    connection: Connection,
+    connection: DbConnection,
     options: DbFileBasedDaoManagerOptions,
 }
 
@@ -77,7 +79,7 @@ impl DaoManager for BufferedMessageDaoManager {
         }
     }
 
-    fn get_connection(&self) -> &Connection {
+    fn get_connection(&self) -> &DbConnection {
         &self.connection
     }
 
@@ -88,13 +90,12 @@ impl DaoManager for BufferedMessageDaoManager {
     }
 
     fn init_db_schema(&self) -> Result<()> {
-        self.connection.execute(format!("CREATE TABLE {} (\
-                {} INTEGER NOT NULL PRIMARY KEY,\
-                link TEXT NOT NULL,\
-                body BLOB NOT NULL\
-            )
-            ", self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(), [])
-            .expect("Error on executing 'CREATE TABLE' for BufferedMessage");
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::GeneratedIntegerKey, ""),
+            ColumnDefinition::new("link", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("body", ColumnType::Blob, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for BufferedMessage: {}", e))?;
         Ok(())
     }
 
@@ -115,26 +116,9 @@ impl DaoManager for BufferedMessageDaoManager {
     }
 
     fn write_item_to_db(&self, item: &BufferedMessage) -> Result<Self::PrimaryKeyType> {
-        let _rows = if let Some(id) = item.id {
-            self.connection.execute(format!(
-                "INSERT OR REPLACE INTO {} ({}, link, body) VALUES (?, ?, ?)",
-                self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(),
-                                    params![
-                    &id,
-                    &item.link,
-                    &item.body
-            ]).unwrap()
-        } else {
-            self.connection.execute(format!(
-                "INSERT OR REPLACE INTO {} (link, body) VALUES (?, ?)",
-                self.get_table_name()).as_str(),
-                                    params![
-                    &item.link,
-                    &item.body
-            ]).unwrap()
-        };
-
-        Ok(item.id.unwrap_or(self.connection.last_insert_rowid()))
+        let key = self.connection.insert_item_returning_key(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for BufferedMessage: {}", e))?;
+        Ok(key)
     }
 
     fn get_serialization_callback(&self, item: &Self::ItemType) -> Self::SerializationCallbackType {
@@ -160,9 +144,8 @@ impl DaoManager for BufferedMessageDaoManager {
                 self.get_table_name(),
                 Self::PRIMARY_KEY_COLUMN_NAME,
                 id
-            ).as_str(),
-            params![]
-        ).unwrap();
+            ).as_str()
+        )?;
         Ok(())
     }
 }
@@ -0,0 +1,213 @@
+use anyhow::{
+    Result,
+    anyhow,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureString,
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DbSchemaVersionType,
+        DaoDataStore,
+        DbFileBasedDaoManagerOptions,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        get_item_from_db,
+        update_db_schema_to_current_version,
+        escape_sql_string,
+    }
+};
+
+// A lease is held by at most one iota-bridge instance (holder_instance_id) of a cluster
+// until expires_at_secs (seconds since UNIX epoch) has passed.
+// The clocks of all cluster instances need to be synchronized (e.g. using NTP).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct ClusterLease {
+    pub lease_name: String,
+    pub holder_instance_id: String,
+    pub expires_at_secs: i64,
+}
+
+pub struct ClusterLeaseDaoManager {
+    connection: DbConnection,
+    options: DbFileBasedDaoManagerOptions,
+}
+
+impl Clone for ClusterLeaseDaoManager {
+    fn clone(&self) -> Self {
+        ClusterLeaseDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl ClusterLeaseDaoManager {
+    // Acquires or renews the lease for the instance if the lease is not held by another
+    // instance or if the lease of the other instance has expired.
+    // Returns true if the instance holds the lease until now_secs + lease_duration_secs.
+    //
+    // Only single statements are used that are atomic in SQLite and PostgreSQL, so that
+    // concurrent calls of several instances will never result in two lease holders.
+    pub fn try_acquire(&self, lease_name: &str, instance_id: &str, now_secs: i64, lease_duration_secs: i64) -> Result<bool> {
+        let lease_name = escape_sql_string(lease_name);
+        let instance_id = escape_sql_string(instance_id);
+        let expires_at_secs = now_secs + lease_duration_secs;
+        let updated_rows = self.connection.execute(
+            format!(
+                "UPDATE {t_name} SET holder_instance_id = '{instance_id}', expires_at_secs = {expires_at_secs} \
+                 WHERE {pk_col} = '{lease_name}' AND (holder_instance_id = '{instance_id}' OR expires_at_secs < {now_secs})",
+                t_name = self.get_table_name(),
+                pk_col = Self::PRIMARY_KEY_COLUMN_NAME,
+            ).as_str()
+        )?;
+        if updated_rows > 0 {
+            return Ok(true);
+        }
+        // The lease does not exist or is held by another instance. In the latter case the
+        // INSERT fails because of the primary key constraint.
+        let insert_result = self.connection.execute(
+            format!(
+                "INSERT INTO {t_name} ({pk_col}, holder_instance_id, expires_at_secs) \
+                 VALUES ('{lease_name}', '{instance_id}', {expires_at_secs})",
+                t_name = self.get_table_name(),
+                pk_col = Self::PRIMARY_KEY_COLUMN_NAME,
+            ).as_str()
+        );
+        Ok(insert_result.is_ok())
+    }
+
+    // Releases the lease if it is held by the instance so that other instances
+    // can take over immediately
+    pub fn release(&self, lease_name: &str, instance_id: &str) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "UPDATE {} SET expires_at_secs = 0 WHERE {} = '{}' AND holder_instance_id = '{}'",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                escape_sql_string(lease_name),
+                escape_sql_string(instance_id),
+            ).as_str()
+        )?;
+        Ok(())
+    }
+}
+
+impl DaoManager for ClusterLeaseDaoManager {
+    type ItemType = ClusterLease;
+    type PrimaryKeyType = String;
+    type SerializationCallbackType = SerializationCallbackRefToClosureString;
+    type OptionsType = DbFileBasedDaoManagerOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "ClusterLease";
+    const DAO_MANAGER_NAME: &'static str = "ClusterLeaseDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "lease_name";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+
+    fn new(options: DbFileBasedDaoManagerOptions) -> Self {
+        ClusterLeaseDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn get_table_name(&self) -> String { "cluster_lease".to_string() }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("holder_instance_id", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("expires_at_secs", ColumnType::Integer, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for ClusterLease: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, lease_name: &Self::PrimaryKeyType) -> Result<ClusterLease> {
+        get_item_from_db(self, lease_name, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, _lease_name_starts_with: &str) -> Result<ClusterLease>{
+        unimplemented!()
+    }
+
+    fn find_all(&self, _lease_name_starts_with: &str, _limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        unimplemented!()
+    }
+
+    fn filter(&self, _conditions: Vec<Condition>, _limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        unimplemented!()
+    }
+
+    fn write_item_to_db(&self, item: &ClusterLease) -> Result<Self::PrimaryKeyType> {
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for ClusterLease: {}", e))?;
+        Ok(item.lease_name.clone())
+    }
+
+    fn get_serialization_callback(&self, _item: &Self::ItemType) -> Self::SerializationCallbackType {
+        unimplemented!()
+    }
+
+    fn delete_item_in_db(&self, _key: &Self::PrimaryKeyType) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+pub type ClusterLeaseDataStore = DaoDataStore<ClusterLeaseDaoManager>;
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib iota_bridge::dao::cluster_lease::tests  --features iota_bridge
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEASE_NAME: &str = "buffered_message_loop";
+    const NOW_SECS: i64 = 1700000000;
+    const LEASE_DURATION_SECS: i64 = 30;
+
+    #[test]
+    fn test_cluster_lease_election() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let dao_manager = ClusterLeaseDaoManager::new(options);
+        dao_manager.init_db_schema().unwrap();
+
+        assert!(dao_manager.try_acquire(LEASE_NAME, "bridge-a", NOW_SECS, LEASE_DURATION_SECS).unwrap());
+        assert!(!dao_manager.try_acquire(LEASE_NAME, "bridge-b", NOW_SECS + 1, LEASE_DURATION_SECS).unwrap());
+        // Renewal by the lease holder
+        assert!(dao_manager.try_acquire(LEASE_NAME, "bridge-a", NOW_SECS + 20, LEASE_DURATION_SECS).unwrap());
+        assert!(!dao_manager.try_acquire(LEASE_NAME, "bridge-b", NOW_SECS + 40, LEASE_DURATION_SECS).unwrap());
+        // Take over after the lease of bridge-a has expired
+        assert!(dao_manager.try_acquire(LEASE_NAME, "bridge-b", NOW_SECS + 51, LEASE_DURATION_SECS).unwrap());
+        assert!(!dao_manager.try_acquire(LEASE_NAME, "bridge-a", NOW_SECS + 52, LEASE_DURATION_SECS).unwrap());
+
+        let lease = dao_manager.get_item_from_db(&LEASE_NAME.to_string()).unwrap();
+        assert_eq!(lease.holder_instance_id, "bridge-b");
+        assert_eq!(lease.expires_at_secs, NOW_SECS + 51 + LEASE_DURATION_SECS);
+
+        dao_manager.release(LEASE_NAME, "bridge-a").unwrap();
+        assert!(!dao_manager.try_acquire(LEASE_NAME, "bridge-a", NOW_SECS + 53, LEASE_DURATION_SECS).unwrap());
+        dao_manager.release(LEASE_NAME, "bridge-b").unwrap();
+        assert!(dao_manager.try_acquire(LEASE_NAME, "bridge-a", NOW_SECS + 54, LEASE_DURATION_SECS).unwrap());
+    }
+}
@@ -1,17 +1,25 @@
-use anyhow::Result;
+use anyhow::{
+    Result,
+    anyhow,
+};
 
 use serde::{
     Deserialize,
     Serialize
 };
-use rusqlite::Connection;
-
-use serde_rusqlite::to_params_named;
 
 use crate::{
     helpers::SerializationCallbackRefToClosureString,
+    binary_persist::{
+        InitializationEpoch,
+        get_full_initialization_cnt,
+    },
     dao_helpers::{
         DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
         DbSchemaVersionType,
         DaoDataStore,
         DbFileBasedDaoManagerOpt,
@@ -23,18 +31,32 @@ use crate::{
         find_all_items_in_db,
         filter_items,
         update_db_schema_to_current_version,
-    }
+    },
+    dao_migration::{
+        Migration,
+        MigrationStep,
+    },
 };
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
 pub struct LoraWanNode {
     pub dev_eui: String,
     pub initialization_cnt: u8,
+    // Counts the wrap-arounds of the initialization_cnt. Is missing in exports of older versions.
+    // See binary_persist::InitializationEpoch for more details.
+    #[serde(default)]
+    pub initialization_epoch: InitializationEpoch,
     pub streams_channel_id: String,
 }
 
+impl LoraWanNode {
+    pub fn get_full_initialization_cnt(&self) -> u64 {
+        get_full_initialization_cnt(self.initialization_cnt, self.initialization_epoch)
+    }
+}
+
 pub struct LoraWanNodeDaoManager {
-    connection: Connection,
+    connection: DbConnection,
     options: DbFileBasedDaoManagerOptions,
 }
 
@@ -56,7 +78,19 @@ impl DaoManager for LoraWanNodeDaoManager {
     const ITEM_TYPE_NAME: &'static str = "LoraWanNode";
     const DAO_MANAGER_NAME: &'static str = "LoraWanNodeDaoManager";
     const PRIMARY_KEY_COLUMN_NAME: &'static str = "dev_eui";
-    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 2;
+    const MIGRATIONS: &'static [Migration] = &[
+        Migration {
+            version: 2,
+            description: "Add initialization_epoch column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("initialization_epoch", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "initialization_epoch" },
+            ],
+        },
+    ];
 
     fn new(options: DbFileBasedDaoManagerOptions) -> Self {
         LoraWanNodeDaoManager{
@@ -67,7 +101,7 @@ impl DaoManager for LoraWanNodeDaoManager {
 
     fn get_table_name(&self) -> String { "lora_wan_node".to_string() }
 
-    fn get_connection(&self) -> &Connection {
+    fn get_connection(&self) -> &DbConnection {
         &self.connection
     }
 
@@ -76,20 +110,20 @@ impl DaoManager for LoraWanNodeDaoManager {
     }
 
     fn init_db_schema(&self) -> Result<()> {
-        self.connection.execute(format!("CREATE TABLE {} (\
-                {} TEXT NOT NULL PRIMARY KEY,\
-                initialization_cnt INTEGER NOT NULL,\
-                streams_channel_id  TEXT NOT NULL\
-            )
-            ", self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(), [])
-            .expect("Error on executing 'CREATE TABLE' for LoraWanNode");
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("initialization_cnt", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("initialization_epoch", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("streams_channel_id", ColumnType::Text, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for LoraWanNode: {}", e))?;
 
         self.connection.execute(format!(
             "CREATE INDEX idx_{0}_streams_channel_id ON {0} (\
                 streams_channel_id\
              )",
-            self.get_table_name()).as_str(), [])
-            .expect("Error on executing 'CREATE INDEX' for LoraWanNode");
+            self.get_table_name()).as_str())
+            .map_err(|e| anyhow!("Error on executing 'CREATE INDEX' for LoraWanNode: {}", e))?;
         Ok(())
     }
 
@@ -110,21 +144,21 @@ impl DaoManager for LoraWanNodeDaoManager {
     }
 
     fn write_item_to_db(&self, item: &LoraWanNode) -> Result<Self::PrimaryKeyType> {
-        let _rows = self.connection.execute(format!(
-            "INSERT OR REPLACE INTO {} (dev_eui, initialization_cnt, streams_channel_id) \
-            VALUES (:dev_eui, :initialization_cnt, :streams_channel_id)", self.get_table_name()).as_str(),
-                           to_params_named(item).unwrap().to_slice().as_slice()).unwrap();
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for LoraWanNode: {}", e))?;
         Ok(item.dev_eui.clone())
     }
 
     fn get_serialization_callback(&self, item: &Self::ItemType) -> Self::SerializationCallbackType {
         let options = self.options.clone();
         let initialization_cnt = item.initialization_cnt;
+        let initialization_epoch = item.initialization_epoch;
         Box::new( move |dev_eui: String, streams_channel_id_utf8_bytes: Vec<u8>| -> Result<usize> {
             let ret_val = streams_channel_id_utf8_bytes.len();
             let new_node = LoraWanNode {
                 dev_eui,
                 initialization_cnt,
+                initialization_epoch,
                 streams_channel_id: String::from_utf8(streams_channel_id_utf8_bytes)
                     .expect("Error while reading streams_channel_id_utf8_bytes into String instance.")
             };
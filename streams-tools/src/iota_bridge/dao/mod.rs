@@ -1,9 +1,36 @@
 pub mod lora_wan_node;
 pub mod pending_request;
 pub mod buffered_message;
+pub mod queue_element;
+pub mod cluster_lease;
 
 pub use {
     lora_wan_node::LoraWanNode,
     pending_request::PendingRequest,
     buffered_message::BufferedMessage,
-};
\ No newline at end of file
+    queue_element::QueueElement,
+    cluster_lease::ClusterLease,
+};
+
+use anyhow::Result;
+
+use crate::{
+    dao_helpers::DbFileBasedDaoManagerOptions,
+    dao_migration::{
+        MigrationOptions,
+        MigrationPlan,
+        migrate_table,
+    },
+};
+
+// Migrates the tables of the iota-bridge database. The cluster tables only exist if the
+// database is or has been used in cluster mode. See dao_migration::MigrationOptions for more details.
+pub fn migrate_database(db_connection_opt: &DbFileBasedDaoManagerOptions, migration_opt: &MigrationOptions) -> Result<Vec<MigrationPlan>> {
+    let mut ret_val = Vec::<MigrationPlan>::new();
+    ret_val.extend(migrate_table::<lora_wan_node::LoraWanNodeDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<pending_request::PendingRequestDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<buffered_message::BufferedMessageDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<queue_element::QueueElementDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<cluster_lease::ClusterLeaseDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    Ok(ret_val)
+}
@@ -1,19 +1,27 @@
-use anyhow::Result;
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+};
 
 use serde::{
     Deserialize,
     Serialize
 };
 
-use rusqlite::{
-    Connection,
-    params,
-};
-
 use crate::{
     helpers::SerializationCallbackRefToClosureI64,
+    binary_persist::InitializationEpoch,
     dao_helpers::{
         DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
         DbSchemaVersionType,
         DaoDataStore,
         DbFileBasedDaoManagerOptions,
@@ -22,8 +30,13 @@ use crate::{
         MatchType,
         Condition,
         get_item_from_db,
+        filter_items,
         update_db_schema_to_current_version,
-    }
+    },
+    dao_migration::{
+        Migration,
+        MigrationStep,
+    },
 };
 
 pub type MsgIdTransferType = Vec<u8>;
@@ -33,24 +46,36 @@ pub struct PendingRequest {
     pub dev_eui: String,
     pub msg_id: MsgIdTransferType,
     pub initialization_cnt: u8,
+    // Is resolved by the iota-bridge using the stored LoraWanNode of the sensor as reference.
+    // See binary_persist::resolve_initialization_epoch() for more details.
+    pub initialization_epoch: InitializationEpoch,
     pub streams_api_request: Vec<u8>,
-    pub request_key: Option<i64>
+    pub request_key: Option<i64>,
+    // Unix timestamp in seconds. Used to expire pending requests (see retention_loop.rs).
+    // Is 0 for pending requests stored by older versions.
+    #[serde(default)]
+    pub created_at_secs: i64,
 }
 
 impl PendingRequest {
-    pub fn new(dev_eui: String, msg_id: MsgIdTransferType, initialization_cnt: u8, streams_api_request: Vec<u8>) -> Self {
+    pub fn new(dev_eui: String, msg_id: MsgIdTransferType, initialization_cnt: u8, initialization_epoch: InitializationEpoch, streams_api_request: Vec<u8>) -> Self {
         PendingRequest {
             request_key: None,
             dev_eui,
             msg_id,
             initialization_cnt,
+            initialization_epoch,
             streams_api_request,
+            created_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default(),
         }
     }
 }
 
 pub struct PendingRequestDaoManager {
-    connection: Connection,
+    connection: DbConnection,
     options: DbFileBasedDaoManagerOptions,
 }
 
@@ -72,7 +97,29 @@ impl DaoManager for PendingRequestDaoManager {
     const ITEM_TYPE_NAME: &'static str = "PendingRequest";
     const DAO_MANAGER_NAME: &'static str = "PendingRequestDaoManager";
     const PRIMARY_KEY_COLUMN_NAME: &'static str = "request_key";
-    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 3;
+    const MIGRATIONS: &'static [Migration] = &[
+        Migration {
+            version: 2,
+            description: "Add initialization_epoch column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("initialization_epoch", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "initialization_epoch" },
+            ],
+        },
+        Migration {
+            version: 3,
+            description: "Add created_at_secs column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("created_at_secs", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "created_at_secs" },
+            ],
+        },
+    ];
 
     fn new(options: DbFileBasedDaoManagerOptions) -> Self {
         PendingRequestDaoManager{
@@ -81,7 +128,7 @@ impl DaoManager for PendingRequestDaoManager {
         }
     }
 
-    fn get_connection(&self) -> &Connection {
+    fn get_connection(&self) -> &DbConnection {
         &self.connection
     }
 
@@ -92,15 +139,16 @@ impl DaoManager for PendingRequestDaoManager {
     }
 
     fn init_db_schema(&self) -> Result<()> {
-        self.connection.execute(format!("CREATE TABLE {} (\
-                {} INTEGER NOT NULL PRIMARY KEY,\
-                dev_eui TEXT NOT NULL,\
-                msg_id BLOB NOT NULL,\
-                initialization_cnt INTEGER NOT NULL,\
-                streams_api_request BLOB NOT NULL\
-            )
-            ", self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(), [])
-            .expect("Error on executing 'CREATE TABLE' for PendingRequest");
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::GeneratedIntegerKey, ""),
+            ColumnDefinition::new("dev_eui", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("msg_id", ColumnType::Blob, "NOT NULL"),
+            ColumnDefinition::new("initialization_cnt", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("initialization_epoch", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("streams_api_request", ColumnType::Blob, "NOT NULL"),
+            ColumnDefinition::new("created_at_secs", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for PendingRequest: {}", e))?;
         Ok(())
     }
 
@@ -116,34 +164,14 @@ impl DaoManager for PendingRequestDaoManager {
         unimplemented!()
     }
 
-    fn filter(&self, _conditions: Vec<Condition>, _limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
-        unimplemented!()
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
     }
 
     fn write_item_to_db(&self, item: &PendingRequest) -> Result<Self::PrimaryKeyType> {
-        let _rows = if let Some(request_key) = item.request_key {
-            self.connection.execute(format!(
-                "INSERT OR REPLACE INTO {} ({}, dev_eui, msg_id, initialization_cnt, streams_api_request) VALUES (?, ?, ?, ?, ?)",
-                    self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(),
-                params![
-                    &request_key,
-                    &item.dev_eui,
-                    &item.msg_id,
-                    &item.initialization_cnt,
-                    &item.streams_api_request,
-            ]).unwrap()
-        } else {
-            self.connection.execute(format!(
-                "INSERT OR REPLACE INTO {} (dev_eui, msg_id, initialization_cnt, streams_api_request) VALUES (?, ?, ?, ?)", self.get_table_name()).as_str(),
-                params![
-                    &item.dev_eui,
-                    &item.msg_id,
-                    &item.initialization_cnt,
-                    &item.streams_api_request,
-            ]).unwrap()
-        };
-
-        Ok(item.request_key.unwrap_or(self.connection.last_insert_rowid()))
+        let key = self.connection.insert_item_returning_key(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for PendingRequest: {}", e))?;
+        Ok(key)
     }
 
     fn get_serialization_callback(&self, item: &Self::ItemType) -> Self::SerializationCallbackType {
@@ -151,6 +179,8 @@ impl DaoManager for PendingRequestDaoManager {
         let dev_eui = item.dev_eui.clone();
         let msg_id = item.msg_id.clone();
         let initialization_cnt = item.initialization_cnt;
+        let initialization_epoch = item.initialization_epoch;
+        let created_at_secs = item.created_at_secs;
 
         Box::new( move |request_id: Self::PrimaryKeyType, streams_api_request: Vec<u8>| -> Result<usize> {
             let ret_val = streams_api_request.len();
@@ -159,7 +189,9 @@ impl DaoManager for PendingRequestDaoManager {
                 dev_eui,
                 msg_id,
                 initialization_cnt,
+                initialization_epoch,
                 streams_api_request,
+                created_at_secs,
             };
             let this = PendingRequestDaoManager::new(options);
             this.write_item_to_db(&new_pending_req)?;
@@ -175,9 +207,8 @@ impl DaoManager for PendingRequestDaoManager {
                 self.get_table_name(),
                 Self::PRIMARY_KEY_COLUMN_NAME,
                 key
-            ).as_str(),
-            params![]
-        ).unwrap();
+            ).as_str()
+        )?;
         Ok(())
     }
 }
@@ -198,6 +229,7 @@ mod tests {
     const DEV_EUI: &str = "12345678";
     const MSG_ID: [u8; MSGID_SIZE] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
     const INITIALIZATION_CNT: u8 = 0;
+    const INITIALIZATION_EPOCH: InitializationEpoch = 0;
 
     #[test]
     fn test_pending_request_dao_manager() {
@@ -209,6 +241,7 @@ mod tests {
             DEV_EUI.to_string(),
             MSG_ID.to_vec(),
             INITIALIZATION_CNT,
+            INITIALIZATION_EPOCH,
             vec![1, 2, 3]
         );
         let request_key = dao_manager.write_item_to_db(&pending_request).unwrap();
@@ -233,6 +266,7 @@ mod tests {
             DEV_EUI.to_string(),
             MSG_ID.to_vec(),
             INITIALIZATION_CNT,
+            INITIALIZATION_EPOCH,
             vec![1, 2, 3]
         );
 
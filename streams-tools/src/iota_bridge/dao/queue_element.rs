@@ -0,0 +1,242 @@
+use anyhow::{
+    Result,
+    anyhow,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureI64,
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DbSchemaVersionType,
+        DaoDataStore,
+        DbFileBasedDaoManagerOptions,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        get_item_from_db,
+        update_db_schema_to_current_version,
+        escape_sql_string,
+        filter_items,
+    }
+};
+
+// Element of a command or confirmation FIFO queue that is shared by all iota-bridge
+// instances of a cluster. The queue is identified by queue_name and dev_eui.
+// The order of the queue is given by the id (ascending).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct QueueElement {
+    pub id: Option<i64>,
+    pub queue_name: String,
+    pub dev_eui: String,
+    pub payload: Vec<u8>,
+    pub received_millisecs: i64,
+    pub needs_to_wait_for_tangle_milestone: bool,
+}
+
+pub struct QueueElementDaoManager {
+    connection: DbConnection,
+    options: DbFileBasedDaoManagerOptions,
+}
+
+impl Clone for QueueElementDaoManager {
+    fn clone(&self) -> Self {
+        QueueElementDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl QueueElementDaoManager {
+    fn get_queue_where_condition(queue_name: &str, dev_eui: &str) -> String {
+        format!("WHERE queue_name = '{}' AND dev_eui = '{}'",
+            escape_sql_string(queue_name),
+            escape_sql_string(dev_eui),
+        )
+    }
+
+    // Returns the oldest element of the specified queue without removing it
+    pub fn get_front_element(&self, queue_name: &str, dev_eui: &str) -> Result<Option<QueueElement>> {
+        let mut elements: Vec<QueueElement> = self.connection.query_items(
+            format!("SELECT * FROM {} {} ORDER BY {} LIMIT 1",
+                self.get_table_name(),
+                Self::get_queue_where_condition(queue_name, dev_eui),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+            ).as_str()
+        )?;
+        Ok(elements.pop())
+    }
+
+    pub fn get_queue_length(&self, queue_name: &str, dev_eui: &str) -> Result<usize> {
+        self.connection.query_count(
+            format!("SELECT COUNT(*) FROM {} {}",
+                self.get_table_name(),
+                Self::get_queue_where_condition(queue_name, dev_eui),
+            ).as_str()
+        )
+    }
+
+    // Removes the element from its queue.
+    // Returns false if the element has already been removed, e.g. by another iota-bridge
+    // instance that has popped the element concurrently.
+    pub fn remove_element(&self, id: i64) -> Result<bool> {
+        let rows = self.connection.execute(
+            format!(
+                "DELETE FROM {} WHERE {} = {}",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                id
+            ).as_str()
+        )?;
+        Ok(rows > 0)
+    }
+}
+
+impl DaoManager for QueueElementDaoManager {
+    type ItemType = QueueElement;
+    type PrimaryKeyType = i64;
+    type SerializationCallbackType = SerializationCallbackRefToClosureI64;
+    type OptionsType = DbFileBasedDaoManagerOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "QueueElement";
+    const DAO_MANAGER_NAME: &'static str = "QueueElementDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "id";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+
+    fn new(options: DbFileBasedDaoManagerOptions) -> Self {
+        QueueElementDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn get_table_name(&self) -> String { "queue_element".to_string() }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::GeneratedIntegerKey, ""),
+            ColumnDefinition::new("queue_name", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("dev_eui", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("payload", ColumnType::Blob, "NOT NULL"),
+            ColumnDefinition::new("received_millisecs", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("needs_to_wait_for_tangle_milestone", ColumnType::Bool, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for QueueElement: {}", e))?;
+
+        self.connection.execute(format!(
+            "CREATE INDEX idx_{0}_queue_name_dev_eui ON {0} (\
+                queue_name,\
+                dev_eui\
+             )",
+            self.get_table_name()).as_str())
+            .map_err(|e| anyhow!("Error on executing 'CREATE INDEX' for QueueElement: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, id: &Self::PrimaryKeyType) -> Result<QueueElement> {
+        get_item_from_db(self, id, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, _id_starts_with: &str) -> Result<QueueElement>{
+        unimplemented!()
+    }
+
+    fn find_all(&self, _id_starts_with: &str, _limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        unimplemented!()
+    }
+
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
+    }
+
+    fn write_item_to_db(&self, item: &QueueElement) -> Result<Self::PrimaryKeyType> {
+        let key = self.connection.insert_item_returning_key(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for QueueElement: {}", e))?;
+        Ok(key)
+    }
+
+    fn get_serialization_callback(&self, item: &Self::ItemType) -> Self::SerializationCallbackType {
+        let options = self.options.clone();
+        let mut new_element = item.clone();
+        Box::new( move |id: Self::PrimaryKeyType, payload: Vec<u8>| -> Result<usize> {
+            let ret_val = payload.len();
+            new_element.id = Some(id);
+            new_element.payload = payload;
+            let this = QueueElementDaoManager::new(options.clone());
+            this.write_item_to_db(&new_element)?;
+            Ok(ret_val)
+        })
+    }
+
+    fn delete_item_in_db(&self, id: &Self::PrimaryKeyType) -> Result<()> {
+        let _was_removed = self.remove_element(*id)?;
+        Ok(())
+    }
+}
+
+pub type QueueElementDataStore = DaoDataStore<QueueElementDaoManager>;
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib iota_bridge::dao::queue_element::tests  --features iota_bridge
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEV_EUI: &str = "12345678";
+    const QUEUE_NAME: &str = "command";
+
+    fn new_queue_element(queue_name: &str, payload: Vec<u8>) -> QueueElement {
+        QueueElement {
+            id: None,
+            queue_name: queue_name.to_string(),
+            dev_eui: DEV_EUI.to_string(),
+            payload,
+            received_millisecs: 1700000000000,
+            needs_to_wait_for_tangle_milestone: true,
+        }
+    }
+
+    #[test]
+    fn test_queue_element_dao_manager() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let dao_manager = QueueElementDaoManager::new(options);
+        dao_manager.init_db_schema().unwrap();
+
+        let mut first = new_queue_element(QUEUE_NAME, vec![1, 2, 3]);
+        first.id = Some(dao_manager.write_item_to_db(&first).unwrap());
+        let mut second = new_queue_element(QUEUE_NAME, vec![4, 5]);
+        second.id = Some(dao_manager.write_item_to_db(&second).unwrap());
+        dao_manager.write_item_to_db(&new_queue_element("confirm", vec![6])).unwrap();
+
+        assert_eq!(dao_manager.get_queue_length(QUEUE_NAME, DEV_EUI).unwrap(), 2);
+        assert_eq!(dao_manager.get_front_element(QUEUE_NAME, DEV_EUI).unwrap(), Some(first.clone()));
+
+        assert!(dao_manager.remove_element(first.id.unwrap()).unwrap());
+        assert!(!dao_manager.remove_element(first.id.unwrap()).unwrap());
+        assert_eq!(dao_manager.get_front_element(QUEUE_NAME, DEV_EUI).unwrap(), Some(second.clone()));
+
+        dao_manager.delete_item_in_db(&second.id.unwrap()).unwrap();
+        assert_eq!(dao_manager.get_front_element(QUEUE_NAME, DEV_EUI).unwrap(), None);
+        assert_eq!(dao_manager.get_queue_length(QUEUE_NAME, DEV_EUI).unwrap(), 0);
+        assert_eq!(dao_manager.get_queue_length("confirm", DEV_EUI).unwrap(), 1);
+    }
+}
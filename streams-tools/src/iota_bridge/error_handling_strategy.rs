@@ -24,7 +24,8 @@ impl ErrorHandlingStrategy {
         iota-bridge will buffer the message and will later retry
         to send the message via the tangle.
         This option is only suitable if only one iota-bridge
-        instance is run.
+        instance is run or if the iota-bridge instances are
+        run as cluster (see --cluster-mode).
     ";
 
     pub const ALWAYS_RETURN_ERRORS: &'static str = "always-return-errors";
@@ -1,12 +1,28 @@
 use std::{
     collections::VecDeque,
-    time::SystemTime
+    time::{
+        SystemTime,
+        Duration,
+        UNIX_EPOCH,
+    }
 };
 
+use anyhow::Result;
+
 use dashmap::{
     DashMap,
 };
 
+use crate::dao_helpers::{
+    DaoManager,
+    DbFileBasedDaoManagerOptions,
+};
+
+use super::dao::queue_element::{
+    QueueElement,
+    QueueElementDaoManager,
+};
+
 // Might be used in future versions of the iota-bridge. See FIFO_MIN_WAIT_TIME_SECS comment below.
 // use crate::streams_transport::streams_transport::STREAMS_TOOLS_CONST_TRANSPORT_PROCESSING_TIME_SECS;
 
@@ -63,32 +79,154 @@ pub fn create_new_fifo_queue_if_not_exist(fifos: &FifoQueueMap,dev_eui: &str) {
     }
 }
 
+enum FrontElementStatus {
+    Deliverable,
+    NeedsToWait,
+    Expired,
+}
+
+fn get_front_element_status(element: &FifoQueueElement) -> Option<FrontElementStatus> {
+    match element.received.elapsed() {
+        Ok(duration) => {
+            if duration.as_secs_f32() < FIFO_ELEMENT_LIFETIME_SECS {
+                if !element.needs_to_wait_for_tangle_milestone {
+                    Some(FrontElementStatus::Deliverable)
+                } else {
+                    if duration.as_secs_f32() > FIFO_MIN_WAIT_TIME_SECS {
+                        Some(FrontElementStatus::Deliverable)
+                    } else {
+                        let time_to_wait = duration.as_secs_f32() - FIFO_MIN_WAIT_TIME_SECS;
+                        log::info!("[fn fifo_queue_pop_front()] - Minimum wait time has not been reached. time_to_wait: {}", time_to_wait);
+                        Some(FrontElementStatus::NeedsToWait)
+                    }
+                }
+            } else {
+                let lifetime_secs = duration.as_secs_f32();
+                log::info!("[fn fifo_queue_pop_front()] - The maximum lifetime of {} secs has been exceeded by a FifoQueueElement. The element has been dropped. Lifetime has been: {} secs", FIFO_ELEMENT_LIFETIME_SECS, lifetime_secs);
+                Some(FrontElementStatus::Expired)
+            }
+        }
+        _ => None
+    }
+}
+
 pub fn fifo_queue_pop_front(queue: &mut FifoQueue) -> Option<FifoQueueElement> {
     let mut ret_val: Option<FifoQueueElement> = None;
     if !queue.is_empty() {
         if let Some(element) = queue.get(0) {
-            match element.received.elapsed() {
-                Ok(duration) => {
-                    if duration.as_secs_f32() < FIFO_ELEMENT_LIFETIME_SECS {
-                        if !element.needs_to_wait_for_tangle_milestone {
-                            ret_val = queue.pop_front();
-                        } else {
-                            if duration.as_secs_f32() > FIFO_MIN_WAIT_TIME_SECS {
-                                ret_val = queue.pop_front();
-                            } else {
-                                let time_to_wait = duration.as_secs_f32() - FIFO_MIN_WAIT_TIME_SECS;
-                                log::info!("[fn fifo_queue_pop_front()] - Minimum wait time has not been reached. time_to_wait: {}", time_to_wait)
-                            }
-                        }
-                    } else {
-                        let lifetime_secs = duration.as_secs_f32();
-                        log::info!("[fn fifo_queue_pop_front()] - The maximum lifetime of {} secs has been exceeded by a FifoQueueElement. The element has been dropped. Lifetime has been: {} secs", FIFO_ELEMENT_LIFETIME_SECS, lifetime_secs);
-                        let _ = queue.pop_front();
-                    }
-                }
+            match get_front_element_status(element) {
+                Some(FrontElementStatus::Deliverable) => {
+                    ret_val = queue.pop_front();
+                },
+                Some(FrontElementStatus::Expired) => {
+                    let _ = queue.pop_front();
+                },
                 _ => {}
             }
         }
     }
     ret_val
-}
\ No newline at end of file
+}
+
+// FIFO queues stored in the database so that they are shared by all iota-bridge instances
+// of a cluster. A Sensor can fetch commands resp. confirmations from any
+// instance regardless which instance has registered the command or confirmation.
+pub struct SharedFifoQueues {
+    queue_name: &'static str,
+    queue_elements: QueueElementDaoManager,
+}
+
+impl Clone for SharedFifoQueues {
+    fn clone(&self) -> Self {
+        Self {
+            queue_name: self.queue_name,
+            queue_elements: self.queue_elements.clone(),
+        }
+    }
+}
+
+impl SharedFifoQueues {
+    pub fn new(queue_name: &'static str, db_connection_opt: DbFileBasedDaoManagerOptions) -> Self {
+        let queue_elements = QueueElementDaoManager::new(db_connection_opt);
+        queue_elements.update_db_schema_to_current_version()
+            .expect("Error on updating database schema for QueueElementDaoManager");
+        Self {
+            queue_name,
+            queue_elements,
+        }
+    }
+
+    // Returns the length of the queue after the element has been added
+    pub fn push_back(&self, dev_eui: &str, element: FifoQueueElement) -> Result<usize> {
+        let received_millisecs = element.received.duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        self.queue_elements.write_item_to_db(&QueueElement {
+            id: None,
+            queue_name: self.queue_name.to_string(),
+            dev_eui: dev_eui.to_string(),
+            payload: element.payload,
+            received_millisecs,
+            needs_to_wait_for_tangle_milestone: element.needs_to_wait_for_tangle_milestone,
+        })?;
+        self.len(dev_eui)
+    }
+
+    pub fn pop_front(&self, dev_eui: &str) -> Result<Option<FifoQueueElement>> {
+        // Other instances may pop the front element concurrently. In this case
+        // remove_element() returns false and the next element is tried.
+        while let Some(queue_element) = self.queue_elements.get_front_element(self.queue_name, dev_eui)? {
+            let id = queue_element.id.unwrap_or_default();
+            let element = FifoQueueElement {
+                payload: queue_element.payload,
+                received: UNIX_EPOCH + Duration::from_millis(queue_element.received_millisecs as u64),
+                needs_to_wait_for_tangle_milestone: queue_element.needs_to_wait_for_tangle_milestone,
+            };
+            match get_front_element_status(&element) {
+                Some(FrontElementStatus::Deliverable) => {
+                    if self.queue_elements.remove_element(id)? {
+                        return Ok(Some(element));
+                    }
+                },
+                Some(FrontElementStatus::Expired) => {
+                    let _ = self.queue_elements.remove_element(id)?;
+                },
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn len(&self, dev_eui: &str) -> Result<usize> {
+        self.queue_elements.get_queue_length(self.queue_name, dev_eui)
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib iota_bridge::fifo_queue::tests  --features iota_bridge
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEV_EUI: &str = "12345678";
+
+    #[test]
+    fn test_shared_fifo_queues() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let fifos = SharedFifoQueues::new("command", options);
+        assert!(fifos.pop_front(DEV_EUI).unwrap().is_none());
+
+        assert_eq!(fifos.push_back(DEV_EUI, FifoQueueElement::from_binary(&[1, 2, 3], false)).unwrap(), 1);
+        assert_eq!(fifos.push_back(DEV_EUI, FifoQueueElement::from_binary(&[4, 5], false)).unwrap(), 2);
+
+        let mut expired = FifoQueueElement::from_binary(&[6], false);
+        expired.received = SystemTime::now() - Duration::from_secs_f32(FIFO_ELEMENT_LIFETIME_SECS + 1.0);
+        assert_eq!(fifos.push_back("other-dev-eui", expired).unwrap(), 1);
+
+        assert_eq!(fifos.pop_front(DEV_EUI).unwrap().unwrap().payload, vec![1, 2, 3]);
+        assert_eq!(fifos.pop_front(DEV_EUI).unwrap().unwrap().payload, vec![4, 5]);
+        assert!(fifos.pop_front(DEV_EUI).unwrap().is_none());
+
+        assert!(fifos.pop_front("other-dev-eui").unwrap().is_none());
+        assert_eq!(fifos.len("other-dev-eui").unwrap(), 0);
+    }
+}
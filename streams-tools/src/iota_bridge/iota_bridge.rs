@@ -49,6 +49,10 @@ use super::{
     PendingRequestDataStore,
     server_dispatch_streams::TransportFactory,
     error_handling_strategy::ErrorHandlingStrategy,
+    cluster::ClusterOptions,
+    fifo_queue::SharedFifoQueues,
+    server_dispatch_command::COMMAND_QUEUE_NAME,
+    server_dispatch_confirm::CONFIRMATION_QUEUE_NAME,
     streams_node_health::{
         HealthChecker,
         HealthCheckerOptions
@@ -102,6 +106,8 @@ pub struct IotaBridgeOptions {
     pub iota_node: String,
     pub error_handling: ErrorHandlingStrategy,
     pub use_tangle_transport: bool,
+    // If Some, the iota-bridge runs as member of a cluster. See ClusterOptions for more details.
+    pub cluster: Option<ClusterOptions>,
 }
 
 impl IotaBridgeOptions {
@@ -110,16 +116,21 @@ impl IotaBridgeOptions {
             iota_node: iota_node.to_string(),
             error_handling,
             use_tangle_transport: true,
+            cluster: None,
         }
     }
 }
 
 impl fmt::Display for IotaBridgeOptions {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "IotaBridgeOptions:\n   iota_node: {}\n   error_handling: {}\n   use_tangle_transport: {}",
+        let cluster = self.cluster.as_ref()
+            .map(|cluster| cluster.to_string())
+            .unwrap_or("Not used".to_string());
+        write!(f, "IotaBridgeOptions:\n   iota_node: {}\n   error_handling: {}\n   use_tangle_transport: {}\n   cluster: {}",
                self.iota_node,
                self.error_handling,
-               self.use_tangle_transport
+               self.use_tangle_transport,
+               cluster,
         )
     }
 }
@@ -154,11 +165,18 @@ impl<'a> IotaBridge<'a>
             )
         };
 
+        let shared_command_fifos = options.cluster.as_ref().map(|cluster|
+            SharedFifoQueues::new(COMMAND_QUEUE_NAME, cluster.db_connection_opt.clone())
+        );
+        let shared_confirm_fifos = options.cluster.as_ref().map(|cluster|
+            SharedFifoQueues::new(CONFIRMATION_QUEUE_NAME, cluster.db_connection_opt.clone())
+        );
+
         IotaBridge {
             scope_provide: ServerScopeProvide::new(),
             dispatch_streams,
-            dispatch_command: DispatchCommand::new(),
-            dispatch_confirm: DispatchConfirm::new(),
+            dispatch_command: DispatchCommand::new(shared_command_fifos),
+            dispatch_confirm: DispatchConfirm::new(shared_confirm_fifos),
             dispatch_lorawan_node: DispatchLoraWanNode::new(lora_wan_node_store.clone()),
             dispatch_lorawan_rest: DispatchLorawanRest::new(),
             process_finally: ProcessFinally::new(lora_wan_node_store, buffered_message_store),
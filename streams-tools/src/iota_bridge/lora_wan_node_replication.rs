@@ -0,0 +1,278 @@
+use std::{
+    fmt,
+    fs,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use hyper::{
+    Client as HyperClient,
+    Body,
+    body,
+    client::HttpConnector,
+    http::StatusCode,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::http::http_protocol_lorawan_node::RequestBuilderLoraWanNode;
+
+use super::{
+    LoraWanNodeDataStore,
+    dao::LoraWanNode,
+};
+
+// Result of merging a LoraWanNode into a LoraWanNodeDataStore
+#[derive(Debug, PartialEq, Clone)]
+pub enum MergeOutcome {
+    // The DevEUI has not been known before
+    Inserted,
+    // The imported node has a higher full initialization count. The sensor has been reinitialized.
+    Updated,
+    // The imported node equals the stored node
+    Unchanged,
+    // The imported node has a lower full initialization count. The stored node is kept.
+    Outdated,
+    // The full initialization count is equal but the streams_channel_id differs.
+    // The stored node is kept.
+    Conflict,
+}
+
+// Conflicts are resolved using the full initialization count (initialization_epoch and
+// initialization_cnt): The node with the higher full initialization count wins, because
+// the initialization_cnt of a sensor is incremented every time the sensor is initialized
+// (which results in a new streams channel).
+pub fn merge_node(store: &LoraWanNodeDataStore, imported: &LoraWanNode) -> Result<MergeOutcome> {
+    let ret_val = match store.get_item_read_only(&imported.dev_eui) {
+        Ok(existing) => {
+            if imported.get_full_initialization_cnt() > existing.get_full_initialization_cnt() {
+                MergeOutcome::Updated
+            } else if imported.get_full_initialization_cnt() < existing.get_full_initialization_cnt() {
+                MergeOutcome::Outdated
+            } else if imported.streams_channel_id == existing.streams_channel_id {
+                MergeOutcome::Unchanged
+            } else {
+                MergeOutcome::Conflict
+            }
+        },
+        Err(_) => MergeOutcome::Inserted,
+    };
+    if ret_val == MergeOutcome::Inserted || ret_val == MergeOutcome::Updated {
+        store.write_item_to_db(imported)?;
+    }
+    Ok(ret_val)
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct ImportResult {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub outdated: usize,
+    // DevEUIs of the nodes having a conflicting streams_channel_id
+    pub conflicts: Vec<String>,
+}
+
+impl ImportResult {
+    fn add(&mut self, dev_eui: &str, outcome: MergeOutcome) {
+        match outcome {
+            MergeOutcome::Inserted => self.inserted += 1,
+            MergeOutcome::Updated => self.updated += 1,
+            MergeOutcome::Unchanged => self.unchanged += 1,
+            MergeOutcome::Outdated => self.outdated += 1,
+            MergeOutcome::Conflict => self.conflicts.push(dev_eui.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ImportResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "inserted: {}, updated: {}, unchanged: {}, outdated: {}, conflicts: {}",
+               self.inserted,
+               self.updated,
+               self.unchanged,
+               self.outdated,
+               self.conflicts.len(),
+        )?;
+        if self.conflicts.len() > 0 {
+            write!(f, " - Conflicting DevEUIs: {}", self.conflicts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn export_nodes(store: &LoraWanNodeDataStore) -> Result<Vec<LoraWanNode>> {
+    store.find_all_unlimited()
+}
+
+pub fn export_nodes_to_json(store: &LoraWanNodeDataStore) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&export_nodes(store)?)?)
+}
+
+pub fn import_nodes(store: &LoraWanNodeDataStore, nodes: &Vec<LoraWanNode>) -> Result<ImportResult> {
+    let mut ret_val = ImportResult::default();
+    for node in nodes {
+        let outcome = merge_node(store, node)?;
+        if outcome == MergeOutcome::Conflict {
+            log::warn!("[fn import_nodes()] DevEUI: {} - Conflicting streams_channel_id '{}' for initialization_cnt {} (epoch {}). Keeping stored lorawan_node.",
+                node.dev_eui, node.streams_channel_id, node.initialization_cnt, node.initialization_epoch);
+        }
+        ret_val.add(node.dev_eui.as_str(), outcome);
+    }
+    Ok(ret_val)
+}
+
+pub fn import_nodes_from_json(store: &LoraWanNodeDataStore, nodes_json: &[u8]) -> Result<ImportResult> {
+    let nodes: Vec<LoraWanNode> = serde_json::from_slice(nodes_json)
+        .map_err(|e| anyhow!("Could not deserialize lorawan_nodes. Error: {}", e))?;
+    import_nodes(store, &nodes)
+}
+
+pub fn export_nodes_to_file(store: &LoraWanNodeDataStore, file_path_and_name: &str) -> Result<usize> {
+    let nodes = export_nodes(store)?;
+    fs::write(file_path_and_name, serde_json::to_string_pretty(&nodes)?)?;
+    Ok(nodes.len())
+}
+
+pub fn import_nodes_from_file(store: &LoraWanNodeDataStore, file_path_and_name: &str) -> Result<ImportResult> {
+    let nodes_json = fs::read(file_path_and_name)
+        .map_err(|e| anyhow!("Could not read file '{}'. Error: {}", file_path_and_name, e))?;
+    import_nodes_from_json(store, nodes_json.as_slice())
+}
+
+// Synchronizes the lorawan_nodes of the local LoraWanNodeDataStore with a remote iota-bridge
+// using the '/lorawan-node/registry/sync' endpoint of the remote iota-bridge.
+pub struct LoraWanNodeSyncClient {
+    hyper_client: HyperClient<HttpConnector, Body>,
+    request_builder: RequestBuilderLoraWanNode,
+    remote_iota_bridge_url: String,
+}
+
+impl LoraWanNodeSyncClient {
+    pub fn new(remote_iota_bridge_url: &str) -> Self {
+        Self {
+            hyper_client: HyperClient::new(),
+            request_builder: RequestBuilderLoraWanNode::new(remote_iota_bridge_url),
+            remote_iota_bridge_url: remote_iota_bridge_url.to_string(),
+        }
+    }
+
+    // Fetches all lorawan_nodes of the remote iota-bridge and merges them into the local store
+    pub async fn pull(&self, store: &LoraWanNodeDataStore) -> Result<ImportResult> {
+        let response = self.hyper_client.request(self.request_builder.export_nodes()?).await?;
+        if response.status() != StatusCode::OK {
+            bail!("Remote iota-bridge '{}' responded with status {} on exporting lorawan_nodes",
+                self.remote_iota_bridge_url, response.status());
+        }
+        let body_bytes = body::to_bytes(response.into_body()).await?;
+        import_nodes_from_json(store, body_bytes.as_ref())
+    }
+
+    // Sends all lorawan_nodes of the local store to the remote iota-bridge which merges
+    // them into its store. Returns the ImportResult of the remote iota-bridge.
+    pub async fn push(&self, store: &LoraWanNodeDataStore) -> Result<ImportResult> {
+        let request = self.request_builder.import_nodes(export_nodes_to_json(store)?)?;
+        let response = self.hyper_client.request(request).await?;
+        if response.status() != StatusCode::OK {
+            bail!("Remote iota-bridge '{}' responded with status {} on importing lorawan_nodes",
+                self.remote_iota_bridge_url, response.status());
+        }
+        let body_bytes = body::to_bytes(response.into_body()).await?;
+        Ok(serde_json::from_slice(body_bytes.as_ref())?)
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib iota_bridge::lora_wan_node_replication::tests  --features iota_bridge
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao_helpers::DbFileBasedDaoManagerOptions;
+
+    fn node(dev_eui: &str, initialization_cnt: u8, streams_channel_id: &str) -> LoraWanNode {
+        node_with_epoch(dev_eui, initialization_cnt, 0, streams_channel_id)
+    }
+
+    fn node_with_epoch(dev_eui: &str, initialization_cnt: u8, initialization_epoch: u32, streams_channel_id: &str) -> LoraWanNode {
+        LoraWanNode {
+            dev_eui: dev_eui.to_string(),
+            initialization_cnt,
+            initialization_epoch,
+            streams_channel_id: streams_channel_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_import_nodes_conflict_resolution() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let store = LoraWanNodeDataStore::new(options);
+        store.write_item_to_db(&node("0001", 1, "channel-a")).unwrap();
+        store.write_item_to_db(&node("0002", 2, "channel-b")).unwrap();
+        store.write_item_to_db(&node("0003", 1, "channel-c")).unwrap();
+        store.write_item_to_db(&node("0004", 1, "channel-d")).unwrap();
+
+        let imported = vec![
+            node("0001", 2, "channel-a2"),  // updated
+            node("0002", 1, "channel-b0"),  // outdated
+            node("0003", 1, "channel-c"),   // unchanged
+            node("0004", 1, "channel-d2"),  // conflict
+            node("0005", 0, "channel-e"),   // inserted
+        ];
+        let result = import_nodes(&store, &imported).unwrap();
+        assert_eq!(result, ImportResult {
+            inserted: 1,
+            updated: 1,
+            unchanged: 1,
+            outdated: 1,
+            conflicts: vec!["0004".to_string()],
+        });
+
+        assert_eq!(store.get_item_read_only(&"0001".to_string()).unwrap(), node("0001", 2, "channel-a2"));
+        assert_eq!(store.get_item_read_only(&"0002".to_string()).unwrap(), node("0002", 2, "channel-b"));
+        assert_eq!(store.get_item_read_only(&"0004".to_string()).unwrap(), node("0004", 1, "channel-d"));
+        assert_eq!(store.get_item_read_only(&"0005".to_string()).unwrap(), node("0005", 0, "channel-e"));
+
+        let exported = export_nodes(&store).unwrap();
+        assert_eq!(exported.len(), 5);
+        let result = import_nodes_from_json(&store, export_nodes_to_json(&store).unwrap().as_bytes()).unwrap();
+        assert_eq!(result.unchanged, 5);
+    }
+
+    #[test]
+    fn test_merge_node_after_initialization_cnt_wrap_around() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let store = LoraWanNodeDataStore::new(options);
+        store.write_item_to_db(&node_with_epoch("0001", 250, 0, "channel-a")).unwrap();
+        store.write_item_to_db(&node_with_epoch("0002", 2, 1, "channel-b")).unwrap();
+
+        assert_eq!(merge_node(&store, &node_with_epoch("0001", 2, 1, "channel-a2")).unwrap(), MergeOutcome::Updated);
+        assert_eq!(merge_node(&store, &node_with_epoch("0002", 250, 0, "channel-b0")).unwrap(), MergeOutcome::Outdated);
+        assert_eq!(store.get_item_read_only(&"0001".to_string()).unwrap(), node_with_epoch("0001", 2, 1, "channel-a2"));
+        assert_eq!(store.get_item_read_only(&"0002".to_string()).unwrap(), node_with_epoch("0002", 2, 1, "channel-b"));
+
+        // Exports of older versions don't contain the initialization_epoch
+        let imported: Vec<LoraWanNode> = serde_json::from_str(
+            r#"[{"dev_eui": "0003", "initialization_cnt": 7, "streams_channel_id": "channel-c"}]"#
+        ).unwrap();
+        assert_eq!(imported[0], node("0003", 7, "channel-c"));
+    }
+
+    #[test]
+    fn test_export_more_nodes_than_fetched_per_query() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let store = LoraWanNodeDataStore::new(options);
+        let nodes_cnt = 1001;
+        for idx in 0..nodes_cnt {
+            store.write_item_to_db(&node(format!("{:016X}", idx).as_str(), 1, format!("channel-{}", idx).as_str())).unwrap();
+        }
+        assert_eq!(export_nodes(&store).unwrap().len(), nodes_cnt);
+    }
+}
@@ -1,7 +1,10 @@
 pub mod iota_bridge;
 pub mod dao;
 pub mod buffered_message_loop;
+pub mod retention_loop;
 pub mod error_handling_strategy;
+pub mod cluster;
+pub mod lora_wan_node_replication;
 
 mod helpers;
 mod server_dispatch_command;
@@ -43,4 +46,5 @@ pub use {
     server_process_finally::ProcessFinally,
     dispatch_scope::ServerScopeProvide,
     error_handling_strategy::ErrorHandlingStrategy,
+    cluster::ClusterOptions,
 };
\ No newline at end of file
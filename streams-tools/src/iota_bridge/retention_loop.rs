@@ -0,0 +1,176 @@
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use tokio::time::interval;
+
+use anyhow::{
+    Result,
+    Error as AnyError,
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    dao_helpers::{
+        Condition,
+        Conditions,
+        DbFileBasedDaoManagerOptions,
+        Limit,
+        MatchType,
+        vacuum_database,
+    },
+    threading_helpers::Worker,
+};
+
+use super::{
+    PendingRequestDataStore,
+    dao::PendingRequest,
+};
+
+const PRUNE_PAGE_SIZE: usize = 1000;
+
+// Pending requests are stored if a compressed message is received for a sensor that is
+// not known by the iota-bridge. They are deleted when the sensor sends the request again
+// uncompressed. If a sensor never does so, its pending requests would be stored forever.
+//
+// The retention loop deletes all pending requests that are older than pending_request_ttl_secs.
+// If pending requests have been deleted, the SQLite database is compacted afterwards, which also
+// frees the space of buffered messages that have been sent by the buffered message loop.
+#[derive(Clone)]
+pub struct RetentionLoopOptions {
+    pub db_connection_opt: DbFileBasedDaoManagerOptions,
+    pub pending_request_ttl_secs: u64,
+    pub prune_interval_in_secs: u64,
+}
+
+impl RetentionLoopOptions {
+    pub fn new(db_connection_opt: DbFileBasedDaoManagerOptions, pending_request_ttl_secs: u64) -> RetentionLoopOptions {
+        RetentionLoopOptions {
+            db_connection_opt,
+            pending_request_ttl_secs,
+            prune_interval_in_secs: 3600,
+        }
+    }
+}
+
+// Runs the retention loop. Use threading_helpers::run_background_worker_in_own_thread()
+// so that deleting pending requests and compacting the database does not block the
+// thread serving the requests of the sensors.
+pub struct RetentionWorker;
+
+#[async_trait(?Send)]
+impl Worker for RetentionWorker {
+    type OptionsType = RetentionLoopOptions;
+    type ResultType = ();
+    type ErrorType = AnyError;
+
+    async fn run(opt: RetentionLoopOptions) -> Result<()> {
+        run_retention_loop(opt).await;
+        Ok(())
+    }
+}
+
+pub async fn run_retention_loop(opt: RetentionLoopOptions) {
+    log::info!("[fn run_retention_loop] Pending requests will be deleted after {} secs", opt.pending_request_ttl_secs);
+    let mut interval = interval(Duration::from_secs(opt.prune_interval_in_secs));
+    loop {
+        interval.tick().await;
+        match prune_database(&opt) {
+            Ok(expired_cnt) => {
+                log::info!("[fn run_retention_loop] Deleted {} expired pending requests", expired_cnt);
+            }
+            Err(err) => {
+                log::error!("[fn run_retention_loop] Got error from prune_database(): {}", err);
+            }
+        }
+    }
+}
+
+// Returns the number of deleted pending requests
+fn prune_database(opt: &RetentionLoopOptions) -> Result<usize> {
+    let pending_request_store = PendingRequestDataStore::new(opt.db_connection_opt.clone());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default();
+    set_creation_time_of_legacy_pending_requests(&pending_request_store, now)?;
+    let ret_val = delete_expired_pending_requests(&pending_request_store, now - opt.pending_request_ttl_secs as i64)?;
+    if ret_val > 0 {
+        log::debug!("[fn prune_database] Compacting database '{}'", opt.db_connection_opt.file_path_and_name);
+        vacuum_database(opt.db_connection_opt.file_path_and_name.as_str())?;
+    }
+    Ok(ret_val)
+}
+
+fn get_pending_requests_page(pending_request_store: &PendingRequestDataStore, created_at_secs: i64, match_type: MatchType) -> Result<Vec<PendingRequest>> {
+    let mut conditions = Vec::<Condition>::new();
+    Conditions(&mut conditions).add(Some(created_at_secs.to_string()), "created_at_secs", match_type);
+    let (pending_requests, _) = pending_request_store.filter(conditions, Some(Limit { limit: PRUNE_PAGE_SIZE, offset: 0 }))?;
+    Ok(pending_requests)
+}
+
+// Pending requests stored by older versions have no creation time. They are handled as if
+// they have been created now, so that they expire after the TTL.
+fn set_creation_time_of_legacy_pending_requests(pending_request_store: &PendingRequestDataStore, now_secs: i64) -> Result<()> {
+    loop {
+        let legacy_requests = get_pending_requests_page(pending_request_store, 0, MatchType::ExactMatch)?;
+        if legacy_requests.is_empty() {
+            return Ok(());
+        }
+        for mut pending_request in legacy_requests {
+            pending_request.created_at_secs = now_secs;
+            pending_request_store.write_item_to_db(&pending_request)?;
+        }
+    }
+}
+
+fn delete_expired_pending_requests(pending_request_store: &PendingRequestDataStore, created_before_secs: i64) -> Result<usize> {
+    let mut ret_val = 0;
+    loop {
+        let expired_requests = get_pending_requests_page(pending_request_store, created_before_secs - 1, MatchType::LessOrEqual)?;
+        if expired_requests.is_empty() {
+            return Ok(ret_val);
+        }
+        for pending_request in expired_requests {
+            if let Some(request_key) = pending_request.request_key {
+                pending_request_store.delete_item_in_db(&request_key)?;
+                ret_val += 1;
+            }
+        }
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib iota_bridge::retention_loop::tests --features iota_bridge
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_pending_request(pending_request_store: &PendingRequestDataStore, dev_eui: &str, created_at_secs: i64) -> i64 {
+        let mut pending_request = PendingRequest::new(dev_eui.to_string(), vec![1, 2, 3], 0, 0, vec![4, 5, 6]);
+        pending_request.created_at_secs = created_at_secs;
+        pending_request_store.write_item_to_db(&pending_request).unwrap()
+    }
+
+    #[test]
+    fn test_expire_pending_requests() {
+        let pending_request_store = PendingRequestDataStore::new(DbFileBasedDaoManagerOptions {
+            file_path_and_name: "not used".to_string()
+        });
+        let expired_key = write_pending_request(&pending_request_store, "expired", 100);
+        let valid_key = write_pending_request(&pending_request_store, "valid", 300);
+        let legacy_key = write_pending_request(&pending_request_store, "legacy", 0);
+
+        set_creation_time_of_legacy_pending_requests(&pending_request_store, 400).unwrap();
+        assert_eq!(pending_request_store.get_item_read_only(&legacy_key).unwrap().created_at_secs, 400);
+
+        assert_eq!(delete_expired_pending_requests(&pending_request_store, 200).unwrap(), 1);
+        assert!(pending_request_store.get_item_read_only(&expired_key).is_err());
+        assert!(pending_request_store.get_item_read_only(&valid_key).is_ok());
+        assert!(pending_request_store.get_item_read_only(&legacy_key).is_ok());
+    }
+}
@@ -35,13 +35,17 @@ use super::{
         fifo_queue_pop_front,
         get_new_fifo_queue_map,
         create_new_fifo_queue_if_not_exist,
+        SharedFifoQueues,
     },
 };
 
 static mut FIFO_QUEUES: Option<FifoQueueMap> = None;
 
+pub static COMMAND_QUEUE_NAME: &str = "command";
+
 pub struct DispatchCommand<'a> {
     fifos: &'a mut FifoQueueMap,
+    shared_fifos: Option<SharedFifoQueues>,
     scope: Option<Rc<dyn DispatchScope>>,
 }
 
@@ -49,6 +53,7 @@ impl<'a> Clone for DispatchCommand<'a> {
     fn clone(&self) -> DispatchCommand<'a> {
         Self {
             fifos: Self::get_fifo_queues_ref_mut(),
+            shared_fifos: self.shared_fifos.clone(),
             scope: self.scope.clone()
         }
     }
@@ -68,9 +73,12 @@ impl<'a> DispatchCommand<'a>
         }
     }
 
-    pub fn new() -> Self {
+    // If shared_fifos are provided (iota-bridge cluster mode) the commands are
+    // stored in the shared_fifos instead of the process local FIFO_QUEUES.
+    pub fn new(shared_fifos: Option<SharedFifoQueues>) -> Self {
         Self {
             fifos: Self::get_fifo_queues_ref_mut(),
+            shared_fifos,
             scope: None,
         }
     }
@@ -80,6 +88,55 @@ impl<'a> DispatchCommand<'a>
         Command::NO_COMMAND.to_bytes(&mut buffer).unwrap();
         Ok(Response::new(Body::from(buffer.to_vec())))
     }
+
+    fn get_internal_server_error_response() -> Result<Response<Body>> {
+        Response::builder()
+            .status(500)
+            .body(Default::default())
+    }
+
+    fn fetch_next_command_from_shared_fifos(shared_fifos: &SharedFifoQueues, dev_eui: &str) -> Result<Response<Body>> {
+        match shared_fifos.pop_front(dev_eui) {
+            Ok(Some(req_body_binary)) => {
+                let cmd = Command::try_from_bytes(req_body_binary.payload.as_slice()).expect("Could not deserialize command from outgoing binary http body.");
+                log::info!("[fn fetch_next_command()] Returning command {} from shared queue.\nBlob length: {}\nQueue length: {}",
+                    cmd,
+                    req_body_binary.payload.len(),
+                    shared_fifos.len(dev_eui).unwrap_or_default(),
+                );
+                Ok(Response::new(req_body_binary.payload.into()))
+            },
+            Ok(None) => {
+                log::info!("[fn fetch_next_command()] No command available in shared queue - Returning Command::NO_COMMAND");
+                Self::get_no_command_response()
+            },
+            Err(e) => {
+                log::error!("[fn fetch_next_command()] DevEUI: {} - Error on reading shared queue: {} - Returning error 500", dev_eui, e);
+                Self::get_internal_server_error_response()
+            }
+        }
+    }
+
+    fn register_remote_command_in_shared_fifos(shared_fifos: &SharedFifoQueues, dev_eui: &str, req_body_binary: &[u8], api_fn_name: &str) -> Result<Response<Body>> {
+        let cmd = Command::try_from_bytes(req_body_binary).expect("Could not deserialize command from incoming binary http body.");
+        match shared_fifos.push_back(dev_eui, FifoQueueElement::from_binary(
+            req_body_binary, cmd.needs_to_wait_for_tangle_milestone()
+        )) {
+            Ok(queue_length) => {
+                log::info!("[fn {}()] Received command {} for shared queue.\nBinary length: {}\nQueue length: {}",
+                     api_fn_name,
+                     cmd,
+                     req_body_binary.len(),
+                     queue_length,
+                );
+                Ok(Response::new(Default::default()))
+            },
+            Err(e) => {
+                log::error!("[fn register_remote_command()] DevEUI: {} - Could not write to shared queue: {} - Returning error 500", dev_eui, e);
+                Self::get_internal_server_error_response()
+            }
+        }
+    }
 }
 
 #[async_trait(?Send)]
@@ -88,6 +145,9 @@ impl<'a> ServerDispatchCommand for DispatchCommand<'a> {
     fn get_uri_prefix(&self) -> &'static str { URI_PREFIX_COMMAND }
 
     async fn fetch_next_command(self: &mut Self, dev_eui: &str) -> Result<Response<Body>> {
+        if let Some(shared_fifos) = self.shared_fifos.as_ref() {
+            return Self::fetch_next_command_from_shared_fifos(shared_fifos, dev_eui);
+        }
         if let Some(fifo) = self.fifos.get_mut(dev_eui).as_deref_mut() {
             log::debug!("[fn fetch_next_command()] DevEUI: {} - Found FifoQueue with length {}",
                 dev_eui, fifo.len()
@@ -112,6 +172,9 @@ impl<'a> ServerDispatchCommand for DispatchCommand<'a> {
     }
 
     async fn register_remote_command(self: &mut Self, dev_eui: &str, req_body_binary: &[u8], api_fn_name: &str) -> Result<Response<Body>> {
+        if let Some(shared_fifos) = self.shared_fifos.as_ref() {
+            return Self::register_remote_command_in_shared_fifos(shared_fifos, dev_eui, req_body_binary, api_fn_name);
+        }
         create_new_fifo_queue_if_not_exist(&self.fifos, dev_eui);
         if let Some(fifo) = self.fifos.get_mut(dev_eui).as_deref_mut() {
             let cmd = Command::try_from_bytes(req_body_binary).expect("Could not deserialize command from incoming binary http body.");
@@ -22,6 +22,7 @@ use crate::{
             URI_PREFIX_LORAWAN_NODE,
         },
         http_tools::{
+            get_response_400,
             get_response_500,
             get_response_404,
         },
@@ -29,6 +30,10 @@ use crate::{
     iota_bridge::{
         LoraWanNodeDataStore,
         dao::LoraWanNode,
+        lora_wan_node_replication::{
+            export_nodes_to_json,
+            import_nodes_from_json,
+        },
     },
 };
 
@@ -58,6 +63,7 @@ impl ServerDispatchLoraWanNode for DispatchLoraWanNode {
             dev_eui: dev_eui.to_string(),
             streams_channel_id: channel_id.to_string(),
             initialization_cnt: 0,
+            initialization_epoch: 0,
         };
         match self.lorawan_nodes.write_item_to_db(&new_node) {
             Ok(primary_key) => {
@@ -88,6 +94,26 @@ impl ServerDispatchLoraWanNode for DispatchLoraWanNode {
             Err(_) => return get_response_404("lorawan_node not found")
         }
     }
+
+    async fn export_nodes(self: &mut Self) -> Result<Response<Body>> {
+        match export_nodes_to_json(&self.lorawan_nodes) {
+            Ok(nodes_json) => Ok(Response::new(nodes_json.into())),
+            Err(err) => get_response_500(format!("Could not export lorawan_nodes. Error: {}", err).as_str())
+        }
+    }
+
+    async fn import_nodes(self: &mut Self, nodes_json: &[u8]) -> Result<Response<Body>> {
+        match import_nodes_from_json(&self.lorawan_nodes, nodes_json) {
+            Ok(import_result) => {
+                log::info!("[fn import_nodes()] Imported lorawan_nodes - {}", import_result);
+                match serde_json::to_string(&import_result) {
+                    Ok(result_json) => Ok(Response::new(result_json.into())),
+                    Err(err) => get_response_500(format!("Could not serialize import result. Error: {}", err).as_str())
+                }
+            },
+            Err(err) => get_response_400(format!("Could not import lorawan_nodes. Error: {}", err).as_str())
+        }
+    }
 }
 
 #[async_trait(?Send)]
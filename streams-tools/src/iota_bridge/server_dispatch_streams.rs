@@ -50,6 +50,9 @@ use crate::{
         as_msg_id,
         trans_msg_encode,
         trans_msg_len,
+        InitializationEpoch,
+        is_newer_initialization_cnt,
+        resolve_initialization_epoch,
     },
     http::{
         ScopeConsume,
@@ -210,11 +213,13 @@ impl DispatchStreams {
         );
         let msgid = address.msgid.as_bytes().try_into().unwrap();
         let streams_api_request_bytes = Self::get_streams_api_request_bytes(streams_api_request);
+        let initialization_epoch = self.get_initialization_epoch_for_received_cnt(&dev_eui, address.initialization_cnt);
 
         let new_pending_request = PendingRequest::new(
             dev_eui,
             msgid,
             address.initialization_cnt,
+            initialization_epoch,
             streams_api_request_bytes,
         );
 
@@ -229,6 +234,15 @@ impl DispatchStreams {
             .body(resp_body)
     }
 
+    // The initialization_cnt of received compressed messages may have wrapped around.
+    // The stored lorawan_node of the sensor is used as reference to resolve the initialization_epoch.
+    fn get_initialization_epoch_for_received_cnt(&self, dev_eui: &String, initialization_cnt: u8) -> InitializationEpoch {
+        match self.lorawan_nodes.get_item_read_only(dev_eui) {
+            Ok(node) => resolve_initialization_epoch(initialization_cnt, node.initialization_cnt, node.initialization_epoch),
+            Err(_) => 0,
+        }
+    }
+
     fn get_streams_api_request_bytes(streams_api_request: StreamsApiRequest) -> Vec<u8> {
         let request_bytes = streams_api_request.needed_size();
         let mut streams_api_request_bytes = Vec::<u8>::with_capacity(request_bytes);
@@ -258,6 +272,11 @@ impl DispatchStreams {
         let lorawan_node = LoraWanNode {
             dev_eui: pending_request.dev_eui.clone(),
             initialization_cnt,
+            initialization_epoch: resolve_initialization_epoch(
+                initialization_cnt,
+                pending_request.initialization_cnt,
+                pending_request.initialization_epoch
+            ),
             streams_channel_id: channel_id.to_string()
         };
         self.lorawan_nodes.write_item_to_db(&lorawan_node)?;
@@ -290,8 +309,9 @@ impl DispatchStreams {
                 if node.initialization_cnt == link.initialization_cnt {
                     ret_val = Some(node)
                 } else {
-                    log::warn!("[fn get_lorawan_node()] DevEUI: {} - LoraWanNode has initialization_cnt {} but message needs initialization_cnt {}",
-                               dev_eui_str, node.initialization_cnt, link.initialization_cnt);
+                    let age = if is_newer_initialization_cnt(link.initialization_cnt, node.initialization_cnt) {"newer"} else {"older"};
+                    log::warn!("[fn get_lorawan_node()] DevEUI: {} - LoraWanNode has initialization_cnt {} (epoch {}) but message needs {} initialization_cnt {}",
+                               dev_eui_str, node.initialization_cnt, node.initialization_epoch, age, link.initialization_cnt);
                 }
             },
             Err(err) => {
@@ -74,6 +74,7 @@ impl ProcessFinally {
                         // To communicate the initialization_cnt properly the SEND_MESSAGE & RECEIVE_MESSAGE_FROM_ADDRESS need to
                         // transport the initialization_cnt too.
                         initialization_cnt: 0,
+                        initialization_epoch: 0,
                     };
                     match self.lorawan_nodes.write_item_to_db(&new_lorawan_node) {
                         Ok(_) => {}
@@ -17,6 +17,10 @@ pub mod iota_bridge;
 
 #[cfg(feature = "dao")]
 pub mod dao_helpers;
+#[cfg(feature = "dao")]
+pub mod dao_backend;
+#[cfg(feature = "dao")]
+pub mod dao_migration;
 
 #[cfg(feature = "explorer")]
 pub mod explorer;
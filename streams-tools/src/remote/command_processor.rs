@@ -23,7 +23,8 @@ use crate::{
         SubscribeToAnnouncement,
         BinaryPersist,
         StartSendingMessages,
-        RegisterKeyloadMessage
+        RegisterKeyloadMessage,
+        DevEuiHandshakeCmd,
     },
     STREAMS_TOOLS_CONST_ANY_DEV_EUI
 };
@@ -104,6 +105,7 @@ pub trait SensorFunctions {
 
     async fn dev_eui_handshake(
         &self,
+        pairing_code: String,
         confirm_req_builder: &RequestBuilderConfirm,
     ) -> hyper::http::Result<Request<Body>>;
 
@@ -190,6 +192,7 @@ pub async fn process_sensor_commands<SensorT: SensorFunctions>(
     }
 
     if command == Command::DEV_EUI_HANDSHAKE {
+        let cmd_args = DevEuiHandshakeCmd::try_from_bytes(buffer.as_slice())?;
         log::info!("[fn process_sensor_commands()] Processing DEV_EUI_HANDSHAKE");
         let req_builder_confirm_handshake = RequestBuilderConfirm::new(
             sensor.get_iota_bridge_url().as_str(),
@@ -197,7 +200,7 @@ pub async fn process_sensor_commands<SensorT: SensorFunctions>(
         );
 
         confirmation_request = Some(
-            sensor.dev_eui_handshake(&req_builder_confirm_handshake).await?
+            sensor.dev_eui_handshake(cmd_args.pairing_code, &req_builder_confirm_handshake).await?
         );
     }
 
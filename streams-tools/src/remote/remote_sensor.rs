@@ -3,6 +3,7 @@ use crate::{
     STREAMS_TOOLS_CONST_ANY_DEV_EUI,
     http::http_protocol_command::RequestBuilderCommand,
     http::http_protocol_confirm::RequestBuilderConfirm,
+    http::http_protocol_lorawan_node::RequestBuilderLoraWanNode,
     binary_persist::{
         Confirmation,
         BinaryPersist,
@@ -11,7 +12,8 @@ use crate::{
         SubscriberStatus,
         KeyloadRegistration,
         ClearClientState,
-        DevEuiHandshake
+        DevEuiHandshake,
+        InitializationEpoch,
     }
 };
 
@@ -45,6 +47,9 @@ pub struct RemoteSensorOptions {
     pub http_url: String,
     pub confirm_fetch_wait_sec: u32,
     pub dev_eui: String,
+    // If specified, waiting for a confirmation fails after confirm_fetch_timeout_sec seconds.
+    // Otherwise confirmations are fetched until they have been received.
+    pub confirm_fetch_timeout_sec: Option<u32>,
 }
 
 impl Default for RemoteSensorOptions {
@@ -53,6 +58,7 @@ impl Default for RemoteSensorOptions {
             http_url: STREAMS_TOOLS_CONST_IOTA_BRIDGE_URL.to_string(),
             confirm_fetch_wait_sec: 5,
             dev_eui: STREAMS_TOOLS_CONST_ANY_DEV_EUI.to_string(),
+            confirm_fetch_timeout_sec: None,
         }
     }
 }
@@ -105,7 +111,14 @@ impl RemoteSensor {
             T: EnumeratedPersistableArgs<Confirmation> + BinaryPersist + Display
     {
         let confirm_fetch_wait_sec = self.options.confirm_fetch_wait_sec;
+        let mut waited_sec: u32 = 0;
         loop {
+            if let Some(timeout_sec) = self.options.confirm_fetch_timeout_sec {
+                if waited_sec >= timeout_sec {
+                    bail!("DevEUI: {} - No confirmation received within {} secs", self.request_builder_confirm.get_dev_eui(), timeout_sec)
+                }
+            }
+            waited_sec += confirm_fetch_wait_sec;
             for s in 0..confirm_fetch_wait_sec {
                 print!("Fetching next confirmation in {} secs\r", confirm_fetch_wait_sec - s);
                 stdout().flush().unwrap();
@@ -189,13 +202,37 @@ impl RemoteSensor {
         self.poll_confirmation::<ClearClientState>().await
     }
 
-    pub async fn dev_eui_handshake(&self) -> Result<DevEuiHandshake> {
+    // Updates the lorawan_node of the sensor stored by the iota-bridge after the sensor has
+    // been re-initialized. The iota-bridge only replaces the stored node if the initialization
+    // count is higher than the stored one.
+    #[cfg(feature = "dao")]
+    pub async fn update_lorawan_node(&self, channel_id: &str, initialization_cnt: u8, initialization_epoch: InitializationEpoch) -> Result<()> {
+        let request_builder = RequestBuilderLoraWanNode::new(self.options.http_url.as_str());
+        let response = self.http_client.request(request_builder.import_node(
+            self.request_builder_confirm.get_dev_eui().as_str(),
+            channel_id,
+            initialization_cnt,
+            initialization_epoch,
+        )?).await?;
+        if !response.status().is_success() {
+            bail!("DevEUI: {} - The iota-bridge responded with status {} on updating the lorawan_node",
+                self.request_builder_confirm.get_dev_eui(), response.status())
+        }
+        let body_bytes = body::to_bytes(response.into_body()).await?;
+        log::info!("DevEUI: {} - Updated lorawan_node of the iota-bridge: {}",
+            self.request_builder_confirm.get_dev_eui(), String::from_utf8_lossy(body_bytes.as_ref()));
+        Ok(())
+    }
+
+    // Sends a DevEuiHandshakeCmd containing the pairing_code to ANY sensor.
+    // Use an empty pairing_code to not use a pairing code.
+    pub async fn dev_eui_handshake(&self, pairing_code: &str) -> Result<DevEuiHandshake> {
         let handshake_request_builder_command = RequestBuilderCommand::new(
             self.options.http_url.as_str(),
             self.options.dev_eui.as_str(),
             true
         );
-        self.http_client.request(handshake_request_builder_command.dev_eui_handshake()?).await?;
+        self.http_client.request(handshake_request_builder_command.dev_eui_handshake(pairing_code)?).await?;
         self.poll_confirmation::<DevEuiHandshake>().await
     }
 }
\ No newline at end of file
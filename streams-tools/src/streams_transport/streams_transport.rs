@@ -14,6 +14,7 @@ pub static STREAMS_TOOLS_CONST_IOTA_BRIDGE_URL: &str = "http://127.0.0.1:50000";
 pub static STREAMS_TOOLS_CONST_DEFAULT_TCP_LISTENER_ADDRESS: &str = "127.0.0.1:50001";
 pub static STREAMS_TOOLS_CONST_DEFAULT_BASE_BRANCH_TOPIC: &str = "MAIN";
 pub static STREAMS_TOOLS_CONST_INX_COLLECTOR_PORT: u16 = 9030;
+pub static STREAMS_TOOLS_CONST_INX_POI_PORT: u16 = 9687;
 pub static STREAMS_TOOLS_CONST_MINIO_DB_PORT: u16 = 9000;
 pub static STREAMS_TOOLS_CONST_ANY_DEV_EUI: &str = "ANY";
 pub static STREAMS_TOOLS_CONST_DEV_EUI_NOT_DEFINED: &str = "NOT_DEFINED";
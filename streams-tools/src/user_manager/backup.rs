@@ -0,0 +1,383 @@
+use std::{
+    fmt,
+    fs,
+    path::Path,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use rand::RngCore;
+
+use crypto::{
+    ciphers::{
+        chacha::XChaCha20Poly1305,
+        traits::Aead,
+    },
+    keys::pbkdf::PBKDF2_HMAC_SHA256,
+};
+
+use crate::dao_helpers::{
+    DaoManager,
+    DbFileBasedDaoManagerOptions,
+    DbSchemaVersionType,
+    get_schema_version_in_database,
+    filter_all_items,
+};
+
+use super::dao::{
+    User,
+    user::UserDaoManager,
+    message::{
+        Message,
+        MessageDaoManager,
+        MessageDataStoreOptions,
+    },
+};
+
+// Binary layout of a backup file:
+//      MAGIC | FORMAT_VERSION | SALT | NONCE | TAG | CIPHERTEXT
+// The ciphertext is the XChaCha20-Poly1305 encrypted json serialized BackupArchive.
+// MAGIC, FORMAT_VERSION, SALT and NONCE are authenticated as associated data.
+// The key is derived from the backup password using PBKDF2-HMAC-SHA256.
+static BACKUP_FILE_MAGIC: &[u8] = b"SUSEEBAK";
+pub const BACKUP_FORMAT_VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+const TAG_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+const PBKDF2_ITERATIONS: usize = 100_000;
+const HEADER_LENGTH: usize = BACKUP_FILE_MAGIC.len() + 1 + SALT_LENGTH + NONCE_LENGTH;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct ChannelBackup {
+    pub user: User,
+    // Content of the message cache of the channel
+    pub messages: Vec<Message>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct BackupArchive {
+    pub format_version: u8,
+    pub created_at_secs: u64,
+    pub user_db_schema_version: DbSchemaVersionType,
+    pub message_db_schema_version: DbSchemaVersionType,
+    // Password used to encrypt the streams_client_state of the archived users
+    pub streams_user_serialization_password: String,
+    // Content of the wallet file containing the seed used to derive the channel seeds
+    pub wallet_file_content: Vec<u8>,
+    pub channels: Vec<ChannelBackup>,
+}
+
+fn get_message_dao_manager(db_connection_opt: &DbFileBasedDaoManagerOptions, streams_channel_id: &str) -> MessageDaoManager {
+    MessageDaoManager::new(MessageDataStoreOptions {
+        file_path_and_name: db_connection_opt.file_path_and_name.clone(),
+        streams_channel_id: streams_channel_id.to_string(),
+    })
+}
+
+// Returns all cached messages of the channel. The message cache table is not created
+// if it does not exist.
+fn get_cached_messages(messages: &MessageDaoManager) -> Result<Vec<Message>> {
+    if get_schema_version_in_database(messages)? == 0 {
+        return Ok(Vec::new());
+    }
+    filter_all_items(messages, &Vec::new())
+}
+
+impl BackupArchive {
+    pub fn new(db_connection_opt: &DbFileBasedDaoManagerOptions, wallet_file_name: &str, streams_user_serialization_password: &str) -> Result<Self> {
+        let user_dao_manager = UserDaoManager::new(db_connection_opt.clone());
+        let user_db_schema_version = get_schema_version_in_database(&user_dao_manager)?;
+        let users = filter_all_items(&user_dao_manager, &Vec::new())?;
+        let mut channels = Vec::<ChannelBackup>::with_capacity(users.len());
+        for user in users {
+            let message_dao_manager = get_message_dao_manager(db_connection_opt, user.streams_channel_id.as_str());
+            let messages = get_cached_messages(&message_dao_manager)?;
+            channels.push(ChannelBackup { user, messages });
+        }
+        let wallet_file_content = fs::read(wallet_file_name)
+            .map_err(|e| anyhow!("Could not read wallet file '{}'. Error: {}", wallet_file_name, e))?;
+
+        Ok(BackupArchive {
+            format_version: BACKUP_FORMAT_VERSION,
+            created_at_secs: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            user_db_schema_version,
+            message_db_schema_version: MessageDaoManager::DB_SCHEMA_VERSION,
+            streams_user_serialization_password: streams_user_serialization_password.to_string(),
+            wallet_file_content,
+            channels,
+        })
+    }
+
+    pub fn get_messages_cnt(&self) -> usize {
+        self.channels.iter().map(|channel| channel.messages.len()).sum()
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; KEY_LENGTH]> {
+    if password.is_empty() {
+        bail!("The backup password must not be empty");
+    }
+    let mut key = [0_u8; KEY_LENGTH];
+    PBKDF2_HMAC_SHA256(password.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key)
+        .map_err(|e| anyhow!("Could not derive backup key. Error: {:?}", e))?;
+    Ok(key)
+}
+
+pub fn encrypt_backup_archive(archive: &BackupArchive, password: &str) -> Result<Vec<u8>> {
+    let plaintext = serde_json::to_vec(archive)?;
+    let mut header = Vec::<u8>::with_capacity(HEADER_LENGTH);
+    header.extend_from_slice(BACKUP_FILE_MAGIC);
+    header.push(BACKUP_FORMAT_VERSION);
+    let mut salt_and_nonce = [0_u8; SALT_LENGTH + NONCE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt_and_nonce);
+    header.extend_from_slice(&salt_and_nonce);
+    let (salt, nonce) = salt_and_nonce.split_at(SALT_LENGTH);
+
+    let key = derive_key(password, salt)?;
+    let mut ciphertext = vec![0_u8; plaintext.len()];
+    let mut tag = [0_u8; TAG_LENGTH];
+    XChaCha20Poly1305::try_encrypt(&key, nonce, header.as_slice(), plaintext.as_slice(), ciphertext.as_mut_slice(), &mut tag)
+        .map_err(|e| anyhow!("Could not encrypt backup archive. Error: {:?}", e))?;
+
+    let mut ret_val = header;
+    ret_val.extend_from_slice(&tag);
+    ret_val.extend_from_slice(ciphertext.as_slice());
+    Ok(ret_val)
+}
+
+pub fn decrypt_backup_archive(buffer: &[u8], password: &str) -> Result<BackupArchive> {
+    if buffer.len() < HEADER_LENGTH + TAG_LENGTH || !buffer.starts_with(BACKUP_FILE_MAGIC) {
+        bail!("The provided data are not a SUSEE backup archive");
+    }
+    let format_version = buffer[BACKUP_FILE_MAGIC.len()];
+    if format_version != BACKUP_FORMAT_VERSION {
+        bail!("Unsupported backup format version {}. Expected version {}", format_version, BACKUP_FORMAT_VERSION);
+    }
+    let (header, tag_and_ciphertext) = buffer.split_at(HEADER_LENGTH);
+    let (salt, nonce) = header[BACKUP_FILE_MAGIC.len() + 1..].split_at(SALT_LENGTH);
+    let (tag, ciphertext) = tag_and_ciphertext.split_at(TAG_LENGTH);
+
+    let key = derive_key(password, salt)?;
+    let mut plaintext = vec![0_u8; ciphertext.len()];
+    XChaCha20Poly1305::try_decrypt(&key, nonce, header, plaintext.as_mut_slice(), ciphertext, tag)
+        .map_err(|_e| anyhow!("Could not decrypt the backup archive. Wrong password or the archive has been modified."))?;
+    Ok(serde_json::from_slice(plaintext.as_slice())?)
+}
+
+pub fn write_backup_file(file_path_and_name: &str, archive: &BackupArchive, password: &str) -> Result<()> {
+    fs::write(file_path_and_name, encrypt_backup_archive(archive, password)?)
+        .map_err(|e| anyhow!("Could not write backup file '{}'. Error: {}", file_path_and_name, e))
+}
+
+pub fn read_backup_file(file_path_and_name: &str, password: &str) -> Result<BackupArchive> {
+    let buffer = fs::read(file_path_and_name)
+        .map_err(|e| anyhow!("Could not read backup file '{}'. Error: {}", file_path_and_name, e))?;
+    decrypt_backup_archive(buffer.as_slice(), password)
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct RestoreResult {
+    pub restored_channels: usize,
+    pub unchanged_channels: usize,
+    pub restored_messages: usize,
+    // IDs of the channels that have not been restored because the database contains a newer
+    // state or a state that can not be ordered (see is_stored_channel_newer())
+    pub skipped_newer_channels: Vec<String>,
+    pub wallet_file_restored: bool,
+}
+
+impl fmt::Display for RestoreResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "restored channels: {}, unchanged channels: {}, restored messages: {}, wallet file restored: {}, skipped newer channels: {}",
+               self.restored_channels,
+               self.unchanged_channels,
+               self.restored_messages,
+               self.wallet_file_restored,
+               self.skipped_newer_channels.len(),
+        )?;
+        if self.skipped_newer_channels.len() > 0 {
+            write!(f, " - Skipped channels: {}", self.skipped_newer_channels.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn check_schema_version<DaoManagerT: DaoManager>(dao_manager: &DaoManagerT, archived_version: DbSchemaVersionType) -> Result<()> {
+    if archived_version > DaoManagerT::DB_SCHEMA_VERSION {
+        bail!("The backup archive contains {} items of schema version {} which is newer than the supported version {}",
+            DaoManagerT::ITEM_TYPE_NAME, archived_version, DaoManagerT::DB_SCHEMA_VERSION);
+    }
+    // Items of older schema versions are restored using the serde defaults of the fields
+    // that have been added since then
+    let version_in_db = get_schema_version_in_database(dao_manager)?;
+    if version_in_db != 0 && version_in_db < archived_version {
+        bail!("The backup archive contains {} items of schema version {} but the database uses schema version {}",
+            DaoManagerT::ITEM_TYPE_NAME, archived_version, version_in_db);
+    }
+    Ok(())
+}
+
+// The client state having the higher client_state_sequence is the newer one.
+// If the sequences are equal but the client states differ (e.g. both users have been
+// written by versions not maintaining the sequence) it can not be decided which state is
+// newer. In this case the stored channel is kept, as overwriting it could lose the newer state.
+fn is_stored_channel_newer(stored: &User, archived: &User) -> bool {
+    if stored.streams_client_state == archived.streams_client_state {
+        return false;
+    }
+    stored.client_state_sequence >= archived.client_state_sequence
+}
+
+// Restores the users, message caches and the wallet file from the archive.
+// The streams_client_state of the archived users needs to be encrypted using the password
+// that is used by the restoring Management Console.
+// Channels stored in the database that are newer than the archived channels are not overwritten.
+// An existing wallet file is not overwritten.
+pub fn restore_backup_archive(db_connection_opt: &DbFileBasedDaoManagerOptions, wallet_file_name: &str, archive: &BackupArchive) -> Result<RestoreResult> {
+    // Tables created by older versions are migrated first, so that their schema version
+    // is not lower than the archived schema version
+    let users = UserDaoManager::new(db_connection_opt.clone());
+    users.update_db_schema_to_current_version()?;
+    check_schema_version(&users, archive.user_db_schema_version)?;
+    for channel in archive.channels.iter() {
+        let messages = get_message_dao_manager(db_connection_opt, channel.user.streams_channel_id.as_str());
+        messages.update_db_schema_to_current_version()?;
+        check_schema_version(&messages, archive.message_db_schema_version)?;
+    }
+
+    let mut ret_val = RestoreResult::default();
+    for channel in archive.channels.iter() {
+        let channel_id = channel.user.streams_channel_id.clone();
+        if let Ok(stored) = users.get_item_from_db(&channel_id) {
+            if stored == channel.user {
+                ret_val.unchanged_channels += 1;
+                continue;
+            }
+            if is_stored_channel_newer(&stored, &channel.user) {
+                log::warn!("[fn restore_backup_archive()] Channel {} in the database is not older than the archived channel. Will not overwrite it.", channel_id);
+                ret_val.skipped_newer_channels.push(channel_id);
+                continue;
+            }
+        }
+        users.write_item_to_db(&channel.user)?;
+        let messages = get_message_dao_manager(db_connection_opt, channel_id.as_str());
+        for message in channel.messages.iter() {
+            messages.write_item_to_db(message)?;
+        }
+        ret_val.restored_messages += channel.messages.len();
+        ret_val.restored_channels += 1;
+    }
+
+    if Path::new(wallet_file_name).exists() {
+        if fs::read(wallet_file_name)? != archive.wallet_file_content {
+            log::warn!("[fn restore_backup_archive()] The existing wallet file '{}' differs from the archived wallet. Will not overwrite it.", wallet_file_name);
+        }
+    } else {
+        fs::write(wallet_file_name, archive.wallet_file_content.as_slice())?;
+        ret_val.wallet_file_restored = true;
+    }
+    Ok(ret_val)
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::backup::tests
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "backup-password";
+
+    fn get_test_archive() -> BackupArchive {
+        BackupArchive {
+            format_version: BACKUP_FORMAT_VERSION,
+            created_at_secs: 1700000000,
+            user_db_schema_version: 1,
+            message_db_schema_version: 1,
+            streams_user_serialization_password: "state-password".to_string(),
+            wallet_file_content: vec![0, 1, 2, 3],
+            channels: vec![ChannelBackup {
+                user: User {
+                    streams_channel_id: "a1b2c3".to_string(),
+                    streams_client_state: vec![4, 5, 6],
+                    seed_derivation_phrase: "phrase".to_string(),
+                    name: "name".to_string(),
+                    external_id: "0001".to_string(),
+                    ..Default::default()
+                },
+                messages: vec![Message {
+                    message_id: "d4e5f6".to_string(),
+                    wrapped_binary: vec![7, 8, 9],
+                    ..Default::default()
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_backup_archive() {
+        let archive = get_test_archive();
+        let buffer = encrypt_backup_archive(&archive, PASSWORD).unwrap();
+        assert!(buffer.starts_with(BACKUP_FILE_MAGIC));
+        assert_eq!(decrypt_backup_archive(buffer.as_slice(), PASSWORD).unwrap(), archive);
+        assert_eq!(archive.get_messages_cnt(), 1);
+
+        assert!(decrypt_backup_archive(buffer.as_slice(), "wrong-password").is_err());
+
+        let mut modified_ciphertext = buffer.clone();
+        let last = modified_ciphertext.len() - 1;
+        modified_ciphertext[last] ^= 0x01;
+        assert!(decrypt_backup_archive(modified_ciphertext.as_slice(), PASSWORD).is_err());
+
+        let mut modified_header = buffer.clone();
+        modified_header[BACKUP_FILE_MAGIC.len() + 1] ^= 0x01;
+        assert!(decrypt_backup_archive(modified_header.as_slice(), PASSWORD).is_err());
+    }
+
+    #[test]
+    fn test_is_stored_channel_newer() {
+        let archived = get_test_archive().channels[0].user.clone();
+        assert!(!is_stored_channel_newer(&archived, &archived));
+
+        let mut stored = archived.clone();
+        stored.streams_client_state = vec![10, 11, 12];
+        stored.client_state_sequence = archived.client_state_sequence + 1;
+        assert!(is_stored_channel_newer(&stored, &archived));
+        assert!(!is_stored_channel_newer(&archived, &stored));
+
+        // Different states having the same sequence can not be ordered
+        stored.client_state_sequence = archived.client_state_sequence;
+        assert!(is_stored_channel_newer(&stored, &archived));
+    }
+
+    #[test]
+    fn test_get_cached_messages() {
+        let messages = get_message_dao_manager(&DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() }, "a1b2c3");
+        assert!(get_cached_messages(&messages).unwrap().is_empty());
+
+        messages.update_db_schema_to_current_version().unwrap();
+        let messages_cnt = 1001;
+        for idx in 0..messages_cnt {
+            messages.write_item_to_db(&Message {
+                message_id: format!("{:08x}", idx),
+                wrapped_binary: vec![7, 8, 9],
+                ..Default::default()
+            }).unwrap();
+        }
+        assert_eq!(get_cached_messages(&messages).unwrap().len(), messages_cnt);
+    }
+}
@@ -36,7 +36,10 @@ use crypto::{
 
 use crate::{
     binary_persist::Bytes,
-    wallet::plain_text_wallet::PlainTextWallet,
+    wallet::{
+        plain_text_wallet::PlainTextWallet,
+        multi_channel_wallet::MultiChannelWallet,
+    },
     SimpleWallet,
     helpers::{
         get_channel_id_from_link,
@@ -228,6 +231,17 @@ impl<WalletT: SimpleWallet> ChannelManager<WalletT> {
         Ok(msg_link.address())
     }
 
+    // Returns the Streams Client State encrypted using the specified serialization_password
+    // instead of the serialization password provided by the wallet.
+    // Can be used to re-encrypt stored Streams Client States with a new password.
+    pub async fn backup_streams_client_state(&mut self, serialization_password: &str) -> Result<Vec<u8>> {
+        if let Some(user) = self.user.as_mut() {
+            user.backup(serialization_password).await.map_err(|e| anyhow!(e))
+        } else {
+            Err(anyhow!("The Streams Client State has not been imported or created"))
+        }
+    }
+
     async fn export_to_serialization_file(&mut self, file_name: &str) -> Result<()> {
         if let Some(user) = self.user.as_mut() {
             let buffer = user.backup( self.wallet.get_serialization_password()).await.map_err(|e| anyhow!(e))?;
@@ -266,4 +280,5 @@ impl<WalletT: SimpleWallet> Drop for ChannelManager<WalletT> {
     }
 }
 
-pub type ChannelManagerPlainTextWallet = ChannelManager<PlainTextWallet>;
\ No newline at end of file
+pub type ChannelManagerPlainTextWallet = ChannelManager<PlainTextWallet>;
+pub type ChannelManagerMultiChannelWallet = ChannelManager<MultiChannelWallet>;
\ No newline at end of file
@@ -0,0 +1,167 @@
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureString,
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DaoDataStore,
+        DbFileBasedDaoManagerOptions,
+        DbSchemaVersionType,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        filter_items,
+        get_item_from_db,
+        find_all_items_in_db,
+        escape_sql_string,
+        update_db_schema_to_current_version,
+    }
+};
+
+use crate::user_manager::message_integrity::ChannelIntegrityReport;
+
+// Result of the latest integrity check of the message chain of a channel.
+// The report contains the JSON serialized ChannelIntegrityReport.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct ChannelIntegrity {
+    pub streams_channel_id: String,
+    pub checked_at_secs: i64,
+    pub is_intact: bool,
+    pub issues_cnt: u32,
+    pub report: String,
+}
+
+impl ChannelIntegrity {
+    pub fn new_checked_now(streams_channel_id: &str, report: &ChannelIntegrityReport) -> Result<Self> {
+        Ok(ChannelIntegrity {
+            streams_channel_id: streams_channel_id.to_string(),
+            checked_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default(),
+            is_intact: report.is_intact(),
+            issues_cnt: report.issues_cnt() as u32,
+            report: serde_json::to_string(report)?,
+        })
+    }
+
+    pub fn get_report(&self) -> Result<ChannelIntegrityReport> {
+        Ok(serde_json::from_str(self.report.as_str())?)
+    }
+}
+
+pub struct ChannelIntegrityDaoManager {
+    connection: DbConnection,
+    options: DbFileBasedDaoManagerOptions,
+}
+
+impl Clone for ChannelIntegrityDaoManager {
+    fn clone(&self) -> Self {
+        ChannelIntegrityDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl DaoManager for ChannelIntegrityDaoManager {
+    type ItemType = ChannelIntegrity;
+    type PrimaryKeyType = String;
+    type SerializationCallbackType = SerializationCallbackRefToClosureString;
+    type OptionsType = DbFileBasedDaoManagerOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "ChannelIntegrity";
+    const DAO_MANAGER_NAME: &'static str = "ChannelIntegrityDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "streams_channel_id";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+
+    fn new(options: DbFileBasedDaoManagerOptions) -> Self {
+        ChannelIntegrityDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn get_table_name(&self) -> String { "channel_integrity".to_string() }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("checked_at_secs", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("is_intact", ColumnType::Bool, "NOT NULL"),
+            ColumnDefinition::new("issues_cnt", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("report", ColumnType::Text, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for ChannelIntegrity: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, channel_id: &Self::PrimaryKeyType) -> Result<ChannelIntegrity> {
+        get_item_from_db(self, channel_id, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, channel_starts_with: &str) -> Result<ChannelIntegrity>{
+        get_item_from_db(self, &channel_starts_with.to_string(), MatchType::StartsWith)
+    }
+
+    fn find_all(&self, channel_starts_with: &str, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        find_all_items_in_db(self, &channel_starts_with.to_string(), limit)
+    }
+
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
+    }
+
+    fn write_item_to_db(&self, item: &ChannelIntegrity) -> Result<Self::PrimaryKeyType> {
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for ChannelIntegrity: {}", e))?;
+        Ok(item.streams_channel_id.clone())
+    }
+
+    fn get_serialization_callback(&self, _item: &Self::ItemType) -> Self::SerializationCallbackType {
+        unimplemented!()
+    }
+
+    fn delete_item_in_db(&self, key: &Self::PrimaryKeyType) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "DELETE FROM \"{}\" WHERE {} = '{}'",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                escape_sql_string(key)
+            ).as_str()
+        )?;
+        Ok(())
+    }
+}
+
+unsafe impl Send for ChannelIntegrityDaoManager {}
+unsafe impl Sync for ChannelIntegrityDaoManager {}
+
+pub type ChannelIntegrityDataStore = DaoDataStore<ChannelIntegrityDaoManager>;
@@ -1,36 +1,190 @@
-use std::fmt;
-use anyhow::Result;
+use std::{
+    fmt,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+use anyhow::{
+    Result,
+    anyhow,
+};
 
 use serde::{
     Deserialize,
     Serialize
 };
 
-use rusqlite::Connection;
-
-use serde_rusqlite::to_params_named;
-
 use crate::{
     helpers::SerializationCallbackRefToClosureString,
     dao_helpers::{
         DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
         DaoDataStore,
         DbSchemaVersionType,
         DbFileBasedDaoManagerOpt,
         Limit,
         MatchType,
         Condition,
+        Conditions,
+        OrderBy,
+        SortOrder,
         filter_items,
         get_item_from_db,
         find_all_items_in_db,
+        escape_sql_string,
+        get_channel_table_name,
         update_db_schema_to_current_version,
-    }
+    },
+    dao_migration::{
+        Migration,
+        MigrationStep,
+    },
 };
 
+// All timestamps are unix timestamps in seconds.
+// received_at_secs is the local time when the message has been cached.
+// milestone_timestamp is the timestamp of the milestone referencing the block of the message.
+// It is recorded by MessageManager::sync() using the Proof of Inclusion or the core api of the
+// IOTA node and is 0 as long as the message has not been referenced by a milestone.
+// timestamp_secs is the milestone_timestamp if known, otherwise received_at_secs.
+// Messages whose milestone could not be determined (e.g. because the block has been pruned
+// by the IOTA node before the message has been cached) keep received_at_secs as timestamp_secs.
+// Messages cached by older versions have 0 values for all timestamps.
+//
+// publisher (hex encoded public key), publisher_sequence and linked_msg_id are taken from
+// the header of the Streams message and are used to check the integrity of the message chain
+// of the channel. They are empty as long as the MessageManager has not processed the message.
+// integrity_checked is the cursor of the incremental integrity check of the channel. It is set
+// after the message has been checked or if its link info could not be backfilled. Messages
+// without link info are not retried by later integrity checks.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
 pub struct Message {
     pub message_id: String,
     pub wrapped_binary: Vec<u8>,
+    #[serde(default)]
+    pub timestamp_secs: i64,
+    #[serde(default)]
+    pub received_at_secs: i64,
+    #[serde(default)]
+    pub milestone_timestamp: i64,
+    #[serde(default)]
+    pub publisher: String,
+    #[serde(default)]
+    pub publisher_sequence: i64,
+    #[serde(default)]
+    pub linked_msg_id: String,
+    #[serde(default)]
+    pub integrity_checked: bool,
+}
+
+impl Message {
+    pub fn new_received_now(message_id: String, wrapped_binary: Vec<u8>) -> Self {
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+        Message {
+            message_id,
+            wrapped_binary,
+            timestamp_secs: now_secs,
+            received_at_secs: now_secs,
+            milestone_timestamp: 0,
+            publisher: String::new(),
+            publisher_sequence: 0,
+            linked_msg_id: String::new(),
+            integrity_checked: false,
+        }
+    }
+
+    pub fn has_link_info(&self) -> bool {
+        !self.publisher.is_empty()
+    }
+
+    // linked_msg_id is empty for messages that are not linked to a previous message (announcements)
+    pub fn set_link_info(&mut self, publisher: String, publisher_sequence: i64, linked_msg_id: String) {
+        self.publisher = publisher;
+        self.publisher_sequence = publisher_sequence;
+        self.linked_msg_id = linked_msg_id;
+    }
+
+    pub fn set_milestone_timestamp(&mut self, milestone_timestamp: i64) {
+        self.milestone_timestamp = milestone_timestamp;
+        if milestone_timestamp > 0 {
+            self.timestamp_secs = milestone_timestamp;
+        }
+    }
+}
+
+// Time range and sort order used to filter the messages of a channel by their timestamp_secs
+#[derive(Clone, Debug, Default)]
+pub struct MessageTimeFilter {
+    pub from_secs: Option<i64>,
+    pub to_secs: Option<i64>,
+    pub sort_order: Option<SortOrder>,
+}
+
+impl MessageTimeFilter {
+    pub fn get_conditions(&self) -> Vec<Condition> {
+        let mut ret_val = Vec::<Condition>::new();
+        let mut conditions = Conditions(&mut ret_val);
+        conditions.add(self.from_secs.map(|secs| secs.to_string()), "timestamp_secs", MatchType::GreaterOrEqual);
+        conditions.add(self.to_secs.map(|secs| secs.to_string()), "timestamp_secs", MatchType::LessOrEqual);
+        ret_val
+    }
+
+    pub fn get_order_by(&self) -> Option<OrderBy> {
+        self.sort_order.map(|sort_order| OrderBy::new("timestamp_secs", sort_order))
+    }
+}
+
+// Statistics of the messages cached for a channel.
+// Messages cached by older versions have no timestamps and are only counted in messages_cnt.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct MessageStatistics {
+    pub messages_cnt: usize,
+    pub timed_messages_cnt: usize,
+    pub first_message_at_secs: i64,
+    pub last_message_at_secs: i64,
+}
+
+impl MessageStatistics {
+    pub fn new_from_data_store(message_data_store: &MessageDataStore) -> Result<Self> {
+        let first_item_only = || Some(Limit { limit: 1, offset: 0 });
+        let (_, messages_cnt) = message_data_store.find_all("", first_item_only())?;
+        let timed_messages = MessageTimeFilter {
+            from_secs: Some(1),
+            to_secs: None,
+            sort_order: Some(SortOrder::Ascending),
+        };
+        let (first, timed_messages_cnt) = message_data_store.filter_ordered(
+            timed_messages.get_conditions(),
+            timed_messages.get_order_by(),
+            first_item_only()
+        )?;
+        let (last, _) = message_data_store.filter_ordered(
+            timed_messages.get_conditions(),
+            Some(OrderBy::new("timestamp_secs", SortOrder::Descending)),
+            first_item_only()
+        )?;
+        Ok(MessageStatistics {
+            messages_cnt,
+            timed_messages_cnt,
+            first_message_at_secs: first.first().map(|msg| msg.timestamp_secs).unwrap_or_default(),
+            last_message_at_secs: last.first().map(|msg| msg.timestamp_secs).unwrap_or_default(),
+        })
+    }
+
+    // Returns 0 if less than two messages have a timestamp
+    pub fn avg_message_interval_secs(&self) -> i64 {
+        if self.timed_messages_cnt < 2 {
+            return 0;
+        }
+        (self.last_message_at_secs - self.first_message_at_secs) / (self.timed_messages_cnt as i64 - 1)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -53,7 +207,7 @@ impl DbFileBasedDaoManagerOpt for MessageDataStoreOptions {
 
 
 pub struct MessageDaoManager{
-    connection: Connection,
+    connection: DbConnection,
     options: MessageDataStoreOptions,
 }
 
@@ -75,7 +229,47 @@ impl DaoManager for MessageDaoManager {
     const ITEM_TYPE_NAME: &'static str = "Message";
     const DAO_MANAGER_NAME: &'static str = "MessageDaoManager";
     const PRIMARY_KEY_COLUMN_NAME: &'static str = "message_id";
-    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 4;
+    const MIGRATIONS: &'static [Migration] = &[
+        Migration {
+            version: 2,
+            description: "Add message timestamp columns",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("timestamp_secs", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+                MigrationStep::AddColumn(ColumnDefinition::new("received_at_secs", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+                MigrationStep::AddColumn(ColumnDefinition::new("milestone_timestamp", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "milestone_timestamp" },
+                MigrationStep::DropColumn { column_name: "received_at_secs" },
+                MigrationStep::DropColumn { column_name: "timestamp_secs" },
+            ],
+        },
+        Migration {
+            version: 3,
+            description: "Add message link info columns",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("publisher", ColumnType::Text, "NOT NULL DEFAULT ''")),
+                MigrationStep::AddColumn(ColumnDefinition::new("publisher_sequence", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+                MigrationStep::AddColumn(ColumnDefinition::new("linked_msg_id", ColumnType::Text, "NOT NULL DEFAULT ''")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "linked_msg_id" },
+                MigrationStep::DropColumn { column_name: "publisher_sequence" },
+                MigrationStep::DropColumn { column_name: "publisher" },
+            ],
+        },
+        Migration {
+            version: 4,
+            description: "Add integrity_checked column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("integrity_checked", ColumnType::Bool, "NOT NULL DEFAULT FALSE")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "integrity_checked" },
+            ],
+        },
+    ];
 
     fn new(options: MessageDataStoreOptions) -> Self {
         MessageDaoManager{
@@ -85,10 +279,10 @@ impl DaoManager for MessageDaoManager {
     }
 
     fn get_table_name(&self) -> String {
-        format!("message_{}", self.options.streams_channel_id)
+        get_channel_table_name(&self.connection, "message", self.options.streams_channel_id.as_str())
     }
 
-    fn get_connection(&self) -> &Connection {
+    fn get_connection(&self) -> &DbConnection {
         &self.connection
     }
 
@@ -97,12 +291,18 @@ impl DaoManager for MessageDaoManager {
     }
 
     fn init_db_schema(&self) -> Result<()> {
-        self.connection.execute(format!("CREATE TABLE {} (\
-            {} TEXT NOT NULL PRIMARY KEY,\
-            wrapped_binary BLOB NOT NULL\
-            )
-            ", self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(), [])
-            .expect("Error on executing 'CREATE TABLE' for Message");
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("wrapped_binary", ColumnType::Blob, "NOT NULL"),
+            ColumnDefinition::new("timestamp_secs", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("received_at_secs", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("milestone_timestamp", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("publisher", ColumnType::Text, "NOT NULL DEFAULT ''"),
+            ColumnDefinition::new("publisher_sequence", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("linked_msg_id", ColumnType::Text, "NOT NULL DEFAULT ''"),
+            ColumnDefinition::new("integrity_checked", ColumnType::Bool, "NOT NULL DEFAULT FALSE"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for Message: {}", e))?;
         Ok(())
     }
 
@@ -123,20 +323,17 @@ impl DaoManager for MessageDaoManager {
     }
 
     fn write_item_to_db(&self, item: &Message) -> Result<Self::PrimaryKeyType> {
-        let _rows = self.connection.execute(format!(
-            "INSERT OR REPLACE INTO {} (message_id, wrapped_binary) VALUES (\
-                                :message_id,\
-                                :wrapped_binary\
-            )", self.get_table_name()).as_str(),
-                                           to_params_named(item).unwrap().to_slice().as_slice())
-            .expect("Error on executing 'INSERT INTO' for Message");
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for Message: {}", e))?;
         Ok(item.message_id.clone())
     }
 
     fn get_serialization_callback(&self, _item: &Self::ItemType) -> Self::SerializationCallbackType {
         let this = self.clone();
         Box::new( move |message_id: String, wrapped_binary: Vec<u8>| -> Result<usize> {
-            let mut new_msg = Message::default();
+            // The timestamps of an already stored message must not be lost
+            let mut new_msg = this.get_item_from_db(&message_id)
+                .unwrap_or_else(|_| Message::new_received_now(message_id.clone(), Vec::new()));
             let ret_val = wrapped_binary.len();
             new_msg.wrapped_binary = wrapped_binary;
             new_msg.message_id = message_id;
@@ -145,8 +342,16 @@ impl DaoManager for MessageDaoManager {
         })
     }
 
-    fn delete_item_in_db(&self, _key: &Self::PrimaryKeyType) -> Result<()> {
-        unimplemented!();
+    fn delete_item_in_db(&self, key: &Self::PrimaryKeyType) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "DELETE FROM \"{}\" WHERE {} = '{}'",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                escape_sql_string(key)
+            ).as_str()
+        )?;
+        Ok(())
     }
 }
 
@@ -154,3 +359,112 @@ unsafe impl Send for MessageDaoManager {}
 unsafe impl Sync for MessageDaoManager {}
 
 pub type MessageDataStore = DaoDataStore<MessageDaoManager>;
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::dao::message::tests  --features dao
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao_helpers::filter_items_ordered;
+
+    fn get_test_message(message_id: &str, timestamp_secs: i64) -> Message {
+        Message {
+            message_id: message_id.to_string(),
+            wrapped_binary: vec![1, 2, 3],
+            timestamp_secs,
+            received_at_secs: timestamp_secs,
+            milestone_timestamp: 0,
+            ..Default::default()
+        }
+    }
+
+    fn get_message_ids(messages: Vec<Message>) -> Vec<String> {
+        messages.into_iter().map(|msg| msg.message_id).collect()
+    }
+
+    #[test]
+    fn test_set_milestone_timestamp() {
+        let mut message = Message::new_received_now("msg".to_string(), vec![1]);
+        assert!(message.received_at_secs > 0);
+        assert_eq!(message.timestamp_secs, message.received_at_secs);
+        message.set_milestone_timestamp(1700000000);
+        assert_eq!(message.timestamp_secs, 1700000000);
+        assert_eq!(message.milestone_timestamp, 1700000000);
+        assert!(message.received_at_secs > 0);
+    }
+
+    #[test]
+    fn test_filter_by_time() {
+        let dao_manager = MessageDaoManager::new(MessageDataStoreOptions {
+            file_path_and_name: ":memory:".to_string(),
+            streams_channel_id: "a1b2c3".to_string(),
+        });
+        dao_manager.init_db_schema().unwrap();
+        dao_manager.write_item_to_db(&get_test_message("msg-b", 200)).unwrap();
+        dao_manager.write_item_to_db(&get_test_message("msg-a", 300)).unwrap();
+        dao_manager.write_item_to_db(&get_test_message("msg-c", 100)).unwrap();
+
+        let time_filter = MessageTimeFilter {
+            from_secs: Some(150),
+            to_secs: None,
+            sort_order: Some(SortOrder::Ascending),
+        };
+        let (messages, cnt_total) = filter_items_ordered(&dao_manager, &time_filter.get_conditions(), time_filter.get_order_by(), None).unwrap();
+        assert_eq!(cnt_total, 2);
+        assert_eq!(get_message_ids(messages), vec!["msg-b", "msg-a"]);
+
+        let time_filter = MessageTimeFilter {
+            from_secs: Some(100),
+            to_secs: Some(200),
+            sort_order: Some(SortOrder::Descending),
+        };
+        let (messages, cnt_total) = filter_items_ordered(&dao_manager, &time_filter.get_conditions(), time_filter.get_order_by(), None).unwrap();
+        assert_eq!(cnt_total, 2);
+        assert_eq!(get_message_ids(messages), vec!["msg-b", "msg-c"]);
+
+        let (messages, cnt_total) = filter_items_ordered(
+            &dao_manager,
+            &MessageTimeFilter::default().get_conditions(),
+            Some(OrderBy::new("timestamp_secs", SortOrder::Descending)),
+            Some(Limit { limit: 1, offset: 1 })
+        ).unwrap();
+        assert_eq!(cnt_total, 3);
+        assert_eq!(get_message_ids(messages), vec!["msg-b"]);
+    }
+    #[test]
+    fn test_message_statistics() {
+        let message_data_store = MessageDataStore::new(MessageDataStoreOptions {
+            file_path_and_name: ":memory:".to_string(),
+            streams_channel_id: "a1b2c3".to_string(),
+        });
+        assert_eq!(MessageStatistics::new_from_data_store(&message_data_store).unwrap(), MessageStatistics::default());
+
+        message_data_store.write_item_to_db(&get_test_message("msg-b", 200)).unwrap();
+        message_data_store.write_item_to_db(&get_test_message("msg-a", 400)).unwrap();
+        message_data_store.write_item_to_db(&get_test_message("msg-c", 100)).unwrap();
+        // Cached by an older version
+        message_data_store.write_item_to_db(&get_test_message("msg-d", 0)).unwrap();
+
+        let statistics = MessageStatistics::new_from_data_store(&message_data_store).unwrap();
+        assert_eq!(statistics, MessageStatistics {
+            messages_cnt: 4,
+            timed_messages_cnt: 3,
+            first_message_at_secs: 100,
+            last_message_at_secs: 400,
+        });
+        assert_eq!(statistics.avg_message_interval_secs(), 150);
+    }
+
+    #[test]
+    fn test_sqlite_channel_table_name_is_not_shortened() {
+        let streams_channel_id = "e3e4b1a8e0a3e1c6d3c1c55ac7b5e1d23f1a21b6b0ef1c0a5d9d1a4c2ee7b6f30000000000000000";
+        let dao_manager = MessageDaoManager::new(MessageDataStoreOptions {
+            file_path_and_name: ":memory:".to_string(),
+            streams_channel_id: streams_channel_id.to_string(),
+        });
+        assert_eq!(dao_manager.get_table_name(), format!("message_{}", streams_channel_id));
+        dao_manager.update_db_schema_to_current_version().unwrap();
+        assert!(dao_manager.get_connection().table_exists(format!("message_{}", streams_channel_id).as_str()).unwrap());
+    }
+}
@@ -1,6 +1,82 @@
 pub mod user;
 pub mod message;
+pub mod poi;
+pub mod sensor_initialization;
+pub mod channel_integrity;
+pub mod sync_cursor;
+pub mod webhook_delivery;
 
 pub use {
-    user::User,
-};
\ No newline at end of file
+    user::{
+        User,
+        ChannelStatus,
+    },
+};
+
+use anyhow::Result;
+
+use serde::Deserialize;
+
+use crate::{
+    dao_helpers::{
+        DaoManager,
+        DbFileBasedDaoManagerOptions,
+        StorageBackend,
+        get_schema_version_in_database,
+    },
+    dao_migration::{
+        MigrationOptions,
+        MigrationPlan,
+        migrate_table,
+    },
+};
+
+#[derive(Deserialize)]
+struct ChannelIdRow {
+    streams_channel_id: String,
+}
+
+// Reads the channel ids without deserializing User items, because the user table
+// may still use an older schema version
+fn get_channel_ids(db_connection_opt: &DbFileBasedDaoManagerOptions) -> Result<Vec<String>> {
+    let users = user::UserDaoManager::new(db_connection_opt.clone());
+    if get_schema_version_in_database(&users)? == 0 {
+        return Ok(Vec::new());
+    }
+    let rows: Vec<ChannelIdRow> = users.get_connection().query_items(
+        format!("SELECT streams_channel_id FROM \"{}\"", users.get_table_name()).as_str()
+    )?;
+    Ok(rows.into_iter().map(|row| row.streams_channel_id).collect())
+}
+
+// Returns the number of users having a non empty Streams client state. Like get_channel_ids()
+// this does not deserialize User items.
+pub fn count_stored_streams_client_states(db_connection_opt: &DbFileBasedDaoManagerOptions) -> Result<usize> {
+    let users = user::UserDaoManager::new(db_connection_opt.clone());
+    if get_schema_version_in_database(&users)? == 0 {
+        return Ok(0);
+    }
+    users.get_connection().query_count(
+        format!("SELECT COUNT(*) FROM \"{}\" WHERE length(streams_client_state) > 0", users.get_table_name()).as_str()
+    )
+}
+
+// Migrates the tables of the management-console database including the message_{channel_id}
+// and poi_{channel_id} tables of all channels. See dao_migration::MigrationOptions for more details.
+pub fn migrate_database(db_connection_opt: &DbFileBasedDaoManagerOptions, migration_opt: &MigrationOptions) -> Result<Vec<MigrationPlan>> {
+    let mut ret_val = Vec::<MigrationPlan>::new();
+    ret_val.extend(migrate_table::<user::UserDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<sensor_initialization::SensorInitializationDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<channel_integrity::ChannelIntegrityDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<sync_cursor::SyncCursorDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    ret_val.extend(migrate_table::<webhook_delivery::WebhookDeliveryDaoManager>(db_connection_opt.clone(), migration_opt)?);
+    for streams_channel_id in get_channel_ids(db_connection_opt)? {
+        let msg_data_store_opt = message::MessageDataStoreOptions {
+            file_path_and_name: db_connection_opt.file_path_and_name.clone(),
+            streams_channel_id,
+        };
+        ret_val.extend(migrate_table::<message::MessageDaoManager>(msg_data_store_opt.clone(), migration_opt)?);
+        ret_val.extend(migrate_table::<poi::PoiDaoManager>(msg_data_store_opt, migration_opt)?);
+    }
+    Ok(ret_val)
+}
@@ -0,0 +1,157 @@
+use anyhow::{
+    Result,
+    anyhow,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureString,
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DaoDataStore,
+        DbSchemaVersionType,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        filter_items,
+        get_item_from_db,
+        find_all_items_in_db,
+        escape_sql_string,
+        get_channel_table_name,
+        update_db_schema_to_current_version,
+    }
+};
+
+use super::message::MessageDataStoreOptions;
+
+// Proof of Inclusion of a cached Streams message.
+// The message_id equals the message_id of the Message stored in the message_{channel_id}
+// table of the same channel. proof_of_inclusion contains the inx-poi JSON document.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct Poi {
+    pub message_id: String,
+    pub block_id: String,
+    pub milestone_index: u32,
+    pub milestone_timestamp: u32,
+    pub is_verified: bool,
+    pub proof_of_inclusion: String,
+}
+
+pub struct PoiDaoManager {
+    connection: DbConnection,
+    options: MessageDataStoreOptions,
+}
+
+impl Clone for PoiDaoManager {
+    fn clone(&self) -> Self {
+        PoiDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl DaoManager for PoiDaoManager {
+    type ItemType = Poi;
+    type PrimaryKeyType = String;
+    type SerializationCallbackType = SerializationCallbackRefToClosureString;
+    type OptionsType = MessageDataStoreOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "Poi";
+    const DAO_MANAGER_NAME: &'static str = "PoiDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "message_id";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+
+    fn new(options: MessageDataStoreOptions) -> Self {
+        PoiDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_table_name(&self) -> String {
+        get_channel_table_name(&self.connection, "poi", self.options.streams_channel_id.as_str())
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("block_id", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("milestone_index", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("milestone_timestamp", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("is_verified", ColumnType::Bool, "NOT NULL"),
+            ColumnDefinition::new("proof_of_inclusion", ColumnType::Text, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for Poi: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, key: &Self::PrimaryKeyType) -> Result<Poi> {
+        get_item_from_db(self, key, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, message_id_starts_with: &str) -> Result<Poi>{
+        get_item_from_db(self, &message_id_starts_with.to_string(),  MatchType::StartsWith)
+    }
+
+    fn find_all(&self, message_id_starts_with: &str, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        find_all_items_in_db(self, &message_id_starts_with.to_string(), limit)
+    }
+
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
+    }
+
+    fn write_item_to_db(&self, item: &Poi) -> Result<Self::PrimaryKeyType> {
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for Poi: {}", e))?;
+        Ok(item.message_id.clone())
+    }
+
+    fn get_serialization_callback(&self, item: &Self::ItemType) -> Self::SerializationCallbackType {
+        let this = self.clone();
+        let mut new_poi = item.clone();
+        Box::new( move |message_id: String, proof_of_inclusion_utf8_bytes: Vec<u8>| -> Result<usize> {
+            let ret_val = proof_of_inclusion_utf8_bytes.len();
+            new_poi.message_id = message_id;
+            new_poi.proof_of_inclusion = String::from_utf8(proof_of_inclusion_utf8_bytes)
+                .expect("Error while reading proof_of_inclusion_utf8_bytes into String instance.");
+            this.write_item_to_db(&new_poi)?;
+            Ok(ret_val)
+        })
+    }
+
+    fn delete_item_in_db(&self, key: &Self::PrimaryKeyType) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "DELETE FROM \"{}\" WHERE {} = '{}'",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                escape_sql_string(key)
+            ).as_str()
+        )?;
+        Ok(())
+    }
+}
+
+unsafe impl Send for PoiDaoManager {}
+unsafe impl Sync for PoiDaoManager {}
+
+pub type PoiDataStore = DaoDataStore<PoiDaoManager>;
@@ -0,0 +1,326 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureString,
+    binary_persist::{
+        InitializationEpoch,
+        get_full_initialization_cnt,
+    },
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DaoDataStore,
+        DbFileBasedDaoManagerOptions,
+        DbSchemaVersionType,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        filter_items,
+        get_item_from_db,
+        find_all_items_in_db,
+        update_db_schema_to_current_version,
+    },
+    dao_migration::{
+        Migration,
+        MigrationStep,
+    },
+};
+
+// States of the sensor initialization process in the order they are passed.
+// Each state is persisted after the step has been confirmed, so that an interrupted
+// initialization can be resumed with the step following the persisted state:
+//
+//      Handshake   -> The DevEUI-Handshake has been performed
+//      Announced   -> The channel has been created. The User of the channel is stored
+//                     after this state has been persisted
+//      Subscribed  -> The sensor has confirmed the subscription
+//      KeyloadSent -> The keyload message has been sent
+//      Done        -> The sensor has confirmed the keyload registration
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum SensorInitializationState {
+    Handshake,
+    Announced,
+    Subscribed,
+    KeyloadSent,
+    Done,
+}
+
+impl SensorInitializationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SensorInitializationState::Handshake => "Handshake",
+            SensorInitializationState::Announced => "Announced",
+            SensorInitializationState::Subscribed => "Subscribed",
+            SensorInitializationState::KeyloadSent => "KeyloadSent",
+            SensorInitializationState::Done => "Done",
+        }
+    }
+}
+
+impl fmt::Display for SensorInitializationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SensorInitializationState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "Handshake" => Ok(SensorInitializationState::Handshake),
+            "Announced" => Ok(SensorInitializationState::Announced),
+            "Subscribed" => Ok(SensorInitializationState::Subscribed),
+            "KeyloadSent" => Ok(SensorInitializationState::KeyloadSent),
+            "Done" => Ok(SensorInitializationState::Done),
+            _ => bail!("Unknown SensorInitializationState '{}'", s),
+        }
+    }
+}
+
+// Persisted initialization progress of the sensor having the DevEUI dev_eui.
+// The state is stored as string (see SensorInitializationState::as_str()).
+// Fields that belong to states that have not been reached yet are empty.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct SensorInitialization {
+    pub dev_eui: String,
+    pub state: String,
+    pub streams_channel_id: String,
+    pub announcement_link: String,
+    pub subscription_link: String,
+    pub pub_key: String,
+    pub keyload_link: String,
+    pub updated_at_secs: i64,
+    // Initialization count confirmed by the sensor with the subscription
+    pub initialization_cnt: u8,
+    pub initialization_epoch: InitializationEpoch,
+    // Only used for re-initializations: true until the LoraWanNode of the sensor managed by
+    // the iota-bridge has been updated to use the new channel
+    pub lorawan_node_update_pending: bool,
+}
+
+impl SensorInitialization {
+    pub fn new(dev_eui: &str) -> Self {
+        let mut ret_val = SensorInitialization {
+            dev_eui: dev_eui.to_string(),
+            ..Default::default()
+        };
+        ret_val.set_state(SensorInitializationState::Handshake);
+        ret_val
+    }
+
+    pub fn get_state(&self) -> Result<SensorInitializationState> {
+        SensorInitializationState::from_str(self.state.as_str())
+    }
+
+    pub fn set_state(&mut self, state: SensorInitializationState) {
+        self.state = state.to_string();
+        self.updated_at_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state == SensorInitializationState::Done.as_str()
+    }
+
+    pub fn get_full_initialization_cnt(&self) -> u64 {
+        get_full_initialization_cnt(self.initialization_cnt, self.initialization_epoch)
+    }
+}
+
+pub struct SensorInitializationDaoManager {
+    connection: DbConnection,
+    options: DbFileBasedDaoManagerOptions,
+}
+
+impl Clone for SensorInitializationDaoManager {
+    fn clone(&self) -> Self {
+        SensorInitializationDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl DaoManager for SensorInitializationDaoManager {
+    type ItemType = SensorInitialization;
+    type PrimaryKeyType = String;
+    type SerializationCallbackType = SerializationCallbackRefToClosureString;
+    type OptionsType = DbFileBasedDaoManagerOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "SensorInitialization";
+    const DAO_MANAGER_NAME: &'static str = "SensorInitializationDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "dev_eui";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 3;
+    const MIGRATIONS: &'static [Migration] = &[
+        Migration {
+            version: 2,
+            description: "Add initialization count columns",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("initialization_cnt", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+                MigrationStep::AddColumn(ColumnDefinition::new("initialization_epoch", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "initialization_epoch" },
+                MigrationStep::DropColumn { column_name: "initialization_cnt" },
+            ],
+        },
+        Migration {
+            version: 3,
+            description: "Add lorawan_node_update_pending column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("lorawan_node_update_pending", ColumnType::Bool, "NOT NULL DEFAULT FALSE")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "lorawan_node_update_pending" },
+            ],
+        },
+    ];
+
+    fn new(options: DbFileBasedDaoManagerOptions) -> Self {
+        SensorInitializationDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn get_table_name(&self) -> String { "sensor_initialization".to_string() }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("state", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("streams_channel_id", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("announcement_link", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("subscription_link", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("pub_key", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("keyload_link", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("updated_at_secs", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("initialization_cnt", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("initialization_epoch", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("lorawan_node_update_pending", ColumnType::Bool, "NOT NULL DEFAULT FALSE"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for SensorInitialization: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, dev_eui: &Self::PrimaryKeyType) -> Result<SensorInitialization> {
+        get_item_from_db(self, dev_eui, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, dev_eui_starts_with: &str) -> Result<SensorInitialization>{
+        get_item_from_db(self, &dev_eui_starts_with.to_string(), MatchType::StartsWith)
+    }
+
+    fn find_all(&self, dev_eui_starts_with: &str, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        find_all_items_in_db(self, &dev_eui_starts_with.to_string(), limit)
+    }
+
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
+    }
+
+    fn write_item_to_db(&self, item: &SensorInitialization) -> Result<Self::PrimaryKeyType> {
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for SensorInitialization: {}", e))?;
+        Ok(item.dev_eui.clone())
+    }
+
+    fn get_serialization_callback(&self, _item: &Self::ItemType) -> Self::SerializationCallbackType {
+        unimplemented!()
+    }
+
+    fn delete_item_in_db(&self, _key: &Self::PrimaryKeyType) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+unsafe impl Send for SensorInitializationDaoManager {}
+unsafe impl Sync for SensorInitializationDaoManager {}
+
+pub type SensorInitializationDataStore = DaoDataStore<SensorInitializationDaoManager>;
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::dao::sensor_initialization::tests  --features dao
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEV_EUI: &str = "4711";
+
+    #[test]
+    fn test_sensor_initialization_state_persistence() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let dao_manager = SensorInitializationDaoManager::new(options);
+        dao_manager.init_db_schema().unwrap();
+
+        let mut initialization = SensorInitialization::new(DEV_EUI);
+        dao_manager.write_item_to_db(&initialization).unwrap();
+        assert_eq!(
+            dao_manager.get_item_from_db(&DEV_EUI.to_string()).unwrap().get_state().unwrap(),
+            SensorInitializationState::Handshake
+        );
+
+        initialization.streams_channel_id = "channel-id".to_string();
+        initialization.set_state(SensorInitializationState::Announced);
+        dao_manager.write_item_to_db(&initialization).unwrap();
+
+        let stored = dao_manager.get_item_from_db(&DEV_EUI.to_string()).unwrap();
+        assert_eq!(stored.get_state().unwrap(), SensorInitializationState::Announced);
+        assert_eq!(stored.streams_channel_id, "channel-id");
+        assert!(!stored.is_done());
+        assert!(!stored.lorawan_node_update_pending);
+
+        let (all_items, cnt_total) = dao_manager.find_all("", None).unwrap();
+        assert_eq!(cnt_total, 1);
+        assert_eq!(all_items[0], stored);
+    }
+
+    #[test]
+    fn test_sensor_initialization_state_order() {
+        for state in [
+            SensorInitializationState::Handshake,
+            SensorInitializationState::Announced,
+            SensorInitializationState::Subscribed,
+            SensorInitializationState::KeyloadSent,
+            SensorInitializationState::Done,
+        ] {
+            assert_eq!(SensorInitializationState::from_str(state.as_str()).unwrap(), state);
+        }
+        assert!(SensorInitializationState::Announced < SensorInitializationState::KeyloadSent);
+        assert!(SensorInitializationState::from_str("Unknown").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureString,
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DaoDataStore,
+        DbFileBasedDaoManagerOptions,
+        DbSchemaVersionType,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        filter_items,
+        get_item_from_db,
+        find_all_items_in_db,
+        escape_sql_string,
+        update_db_schema_to_current_version,
+    },
+    dao_migration::{
+        Migration,
+        MigrationStep,
+    },
+};
+
+// Progress of the synchronization of a channel, used to sync channels incrementally.
+//
+// last_msg_id:                 Message id (hex encoded relative address) of the latest message
+//                              received by a sync. Empty if no message has been received yet.
+//                              Informational only: A sync always resumes from the Streams client
+//                              state of the channel, not from this message id.
+// last_msg_received_at_secs:   Time when a sync received a new message for the last time.
+//                              Used to prioritize recently active channels.
+// last_synced_at_secs:         Time of the last finished sync
+// synced_msgs_cnt:             Number of new messages received by all syncs
+// pruned_before_secs:          Cached messages having a timestamp_secs before this time may have
+//                              been deleted by the MessageRetentionPolicy (see message_retention.rs).
+//                              0 if no messages have been deleted.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct SyncCursor {
+    pub streams_channel_id: String,
+    pub last_msg_id: String,
+    pub last_msg_received_at_secs: i64,
+    pub last_synced_at_secs: i64,
+    pub synced_msgs_cnt: i64,
+    #[serde(default)]
+    pub pruned_before_secs: i64,
+}
+
+impl SyncCursor {
+    pub fn new(streams_channel_id: &str) -> Self {
+        SyncCursor {
+            streams_channel_id: streams_channel_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // Moves the cursor to the latest message received by a finished sync.
+    // last_msg_id is None if the sync did not receive new messages.
+    pub fn set_synced_now(&mut self, last_msg_id: Option<String>, new_msgs_cnt: u32) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+        if let Some(last_msg_id) = last_msg_id {
+            self.last_msg_id = last_msg_id;
+            self.last_msg_received_at_secs = now;
+        }
+        self.last_synced_at_secs = now;
+        self.synced_msgs_cnt += new_msgs_cnt as i64;
+    }
+
+    pub fn has_been_synced(&self) -> bool {
+        self.last_synced_at_secs > 0
+    }
+
+    pub fn set_pruned_before(&mut self, pruned_before_secs: i64) {
+        self.pruned_before_secs = self.pruned_before_secs.max(pruned_before_secs);
+    }
+}
+
+pub struct SyncCursorDaoManager {
+    connection: DbConnection,
+    options: DbFileBasedDaoManagerOptions,
+}
+
+impl Clone for SyncCursorDaoManager {
+    fn clone(&self) -> Self {
+        SyncCursorDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl DaoManager for SyncCursorDaoManager {
+    type ItemType = SyncCursor;
+    type PrimaryKeyType = String;
+    type SerializationCallbackType = SerializationCallbackRefToClosureString;
+    type OptionsType = DbFileBasedDaoManagerOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "SyncCursor";
+    const DAO_MANAGER_NAME: &'static str = "SyncCursorDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "streams_channel_id";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 2;
+    const MIGRATIONS: &'static [Migration] = &[
+        Migration {
+            version: 2,
+            description: "Add pruned_before_secs column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("pruned_before_secs", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "pruned_before_secs" },
+            ],
+        },
+    ];
+
+    fn new(options: DbFileBasedDaoManagerOptions) -> Self {
+        SyncCursorDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn get_table_name(&self) -> String { "sync_cursor".to_string() }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("last_msg_id", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("last_msg_received_at_secs", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("last_synced_at_secs", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("synced_msgs_cnt", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("pruned_before_secs", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for SyncCursor: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, channel_id: &Self::PrimaryKeyType) -> Result<SyncCursor> {
+        get_item_from_db(self, channel_id, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, channel_starts_with: &str) -> Result<SyncCursor>{
+        get_item_from_db(self, &channel_starts_with.to_string(), MatchType::StartsWith)
+    }
+
+    fn find_all(&self, channel_starts_with: &str, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        find_all_items_in_db(self, &channel_starts_with.to_string(), limit)
+    }
+
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
+    }
+
+    fn write_item_to_db(&self, item: &SyncCursor) -> Result<Self::PrimaryKeyType> {
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for SyncCursor: {}", e))?;
+        Ok(item.streams_channel_id.clone())
+    }
+
+    fn get_serialization_callback(&self, _item: &Self::ItemType) -> Self::SerializationCallbackType {
+        unimplemented!()
+    }
+
+    fn delete_item_in_db(&self, key: &Self::PrimaryKeyType) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "DELETE FROM \"{}\" WHERE {} = '{}'",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                escape_sql_string(key)
+            ).as_str()
+        )?;
+        Ok(())
+    }
+}
+
+unsafe impl Send for SyncCursorDaoManager {}
+unsafe impl Sync for SyncCursorDaoManager {}
+
+pub type SyncCursorDataStore = DaoDataStore<SyncCursorDaoManager>;
@@ -1,17 +1,31 @@
-use anyhow::Result;
+use std::{
+    fmt,
+    str::FromStr,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
 
 use serde::{
     Deserialize,
     Serialize
 };
 
-use rusqlite::Connection;
-use serde_rusqlite::to_params_named;
-
 use crate::{
     helpers::SerializationCallbackRefToClosureString,
     dao_helpers::{
         DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
         DaoDataStore,
         DbFileBasedDaoManagerOptions,
         DbSchemaVersionType,
@@ -22,21 +36,151 @@ use crate::{
         filter_items,
         get_item_from_db,
         find_all_items_in_db,
+        escape_sql_string,
         update_db_schema_to_current_version,
-    }
+    },
+    dao_migration::{
+        Migration,
+        MigrationStep,
+    },
 };
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+// Lifecycle of a Streams channel:
+//
+//      Active   -> The channel is used by its sensor
+//      Retired  -> The sensor has been moved to another channel (e.g. after a re-initialization)
+//                  or has been decommissioned. Messages are still synchronized by the
+//                  Message Explorer because the sensor may have sent messages before
+//                  it has been moved.
+//      Archived -> The channel is kept for its messages only. It is not synchronized anymore.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChannelStatus {
+    Active,
+    Retired,
+    Archived,
+}
+
+impl ChannelStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelStatus::Active => "active",
+            ChannelStatus::Retired => "retired",
+            ChannelStatus::Archived => "archived",
+        }
+    }
+}
+
+impl fmt::Display for ChannelStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ChannelStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "active" => Ok(ChannelStatus::Active),
+            "retired" => Ok(ChannelStatus::Retired),
+            "archived" => Ok(ChannelStatus::Archived),
+            _ => bail!("Unknown ChannelStatus '{}'. Possible values are: active, retired, archived", s),
+        }
+    }
+}
+
+fn get_default_channel_status() -> String {
+    ChannelStatus::Active.to_string()
+}
+
+// The status is stored as string (see ChannelStatus::as_str()).
+// retired_at_secs is 0 and successor_channel_id is empty for active channels.
+// The serde defaults are used for users contained in backup archives of older versions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct User {
     pub streams_channel_id: String,
     pub streams_client_state: Vec<u8>,
     pub seed_derivation_phrase: String,
     pub name: String,
     pub external_id: String,
+    #[serde(default = "get_default_channel_status")]
+    pub status: String,
+    #[serde(default)]
+    pub retired_at_secs: i64,
+    // Channel replacing this channel. Empty if the channel has no successor.
+    #[serde(default)]
+    pub successor_channel_id: String,
+    // Name of the payload decoder used by the Message Explorer to decode the messages
+    // of the channel. Empty if no decoder has been assigned.
+    #[serde(default)]
+    pub payload_decoder: String,
+    // Incremented each time the streams_client_state is written by the serialization callback.
+    // Used to find out which of two different client states of a channel is the newer one.
+    // Is 0 for users that have not been written since the column has been added.
+    #[serde(default)]
+    pub client_state_sequence: i64,
+    // Identifier of the sensor used by external systems (e.g. an asset management system).
+    // In contrast to the external_id, which is the DevEUI of the sensor, the asset_id
+    // is not used by the Management Console. Empty if no asset_id has been assigned.
+    #[serde(default)]
+    pub asset_id: String,
+}
+
+impl Default for User {
+    fn default() -> Self {
+        User {
+            streams_channel_id: String::default(),
+            streams_client_state: Vec::default(),
+            seed_derivation_phrase: String::default(),
+            name: String::default(),
+            external_id: String::default(),
+            status: get_default_channel_status(),
+            retired_at_secs: 0,
+            successor_channel_id: String::default(),
+            payload_decoder: String::default(),
+            client_state_sequence: 0,
+            asset_id: String::default(),
+        }
+    }
+}
+
+impl User {
+    pub fn get_status(&self) -> Result<ChannelStatus> {
+        ChannelStatus::from_str(self.status.as_str())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == ChannelStatus::Active.as_str()
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.status == ChannelStatus::Archived.as_str()
+    }
+
+    // Sets the status to Retired. The retirement timestamp of an already retired or
+    // archived channel is kept.
+    pub fn retire(&mut self, successor_channel_id: Option<&str>) {
+        if self.is_active() {
+            self.retired_at_secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default();
+            self.status = ChannelStatus::Retired.to_string();
+        }
+        if let Some(successor) = successor_channel_id {
+            self.successor_channel_id = successor.to_string();
+        }
+    }
+
+    // Active channels are retired before they are archived
+    pub fn archive(&mut self) {
+        self.retire(None);
+        self.status = ChannelStatus::Archived.to_string();
+    }
 }
 
 pub struct UserDaoManager{
-    connection: Connection,
+    connection: DbConnection,
     options: DbFileBasedDaoManagerOptions,
 }
 
@@ -58,7 +202,53 @@ impl DaoManager for UserDaoManager {
     const ITEM_TYPE_NAME: &'static str = "User";
     const DAO_MANAGER_NAME: &'static str = "UserDaoManager";
     const PRIMARY_KEY_COLUMN_NAME: &'static str = "streams_channel_id";
-    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 5;
+    const MIGRATIONS: &'static [Migration] = &[
+        Migration {
+            version: 2,
+            description: "Add channel lifecycle columns",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("status", ColumnType::Text, "NOT NULL DEFAULT 'active'")),
+                MigrationStep::AddColumn(ColumnDefinition::new("retired_at_secs", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+                MigrationStep::AddColumn(ColumnDefinition::new("successor_channel_id", ColumnType::Text, "NOT NULL DEFAULT ''")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "successor_channel_id" },
+                MigrationStep::DropColumn { column_name: "retired_at_secs" },
+                MigrationStep::DropColumn { column_name: "status" },
+            ],
+        },
+        Migration {
+            version: 3,
+            description: "Add payload_decoder column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("payload_decoder", ColumnType::Text, "NOT NULL DEFAULT ''")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "payload_decoder" },
+            ],
+        },
+        Migration {
+            version: 4,
+            description: "Add client_state_sequence column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("client_state_sequence", ColumnType::Integer, "NOT NULL DEFAULT 0")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "client_state_sequence" },
+            ],
+        },
+        Migration {
+            version: 5,
+            description: "Add asset_id column",
+            up: &[
+                MigrationStep::AddColumn(ColumnDefinition::new("asset_id", ColumnType::Text, "NOT NULL DEFAULT ''")),
+            ],
+            down: &[
+                MigrationStep::DropColumn { column_name: "asset_id" },
+            ],
+        },
+    ];
 
     fn new(options: DbFileBasedDaoManagerOptions) -> Self {
         UserDaoManager{
@@ -67,7 +257,7 @@ impl DaoManager for UserDaoManager {
         }
     }
 
-    fn get_connection(&self) -> &Connection {
+    fn get_connection(&self) -> &DbConnection {
         &self.connection
     }
 
@@ -78,15 +268,20 @@ impl DaoManager for UserDaoManager {
     }
 
     fn init_db_schema(&self) -> Result<()> {
-        self.connection.execute(format!("CREATE TABLE {} (\
-            {} TEXT NOT NULL PRIMARY KEY,\
-            streams_client_state BLOB NOT NULL,\
-            seed_derivation_phrase TEXT NOT NULL,\
-            name TEXT,\
-            external_id TEXT\
-            )
-            ", self.get_table_name(), Self::PRIMARY_KEY_COLUMN_NAME).as_str(), [])
-            .expect("Error on executing 'CREATE TABLE' for User");
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::Text, "NOT NULL PRIMARY KEY"),
+            ColumnDefinition::new("streams_client_state", ColumnType::Blob, "NOT NULL"),
+            ColumnDefinition::new("seed_derivation_phrase", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("name", ColumnType::Text, ""),
+            ColumnDefinition::new("external_id", ColumnType::Text, ""),
+            ColumnDefinition::new("status", ColumnType::Text, "NOT NULL DEFAULT 'active'"),
+            ColumnDefinition::new("retired_at_secs", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("successor_channel_id", ColumnType::Text, "NOT NULL DEFAULT ''"),
+            ColumnDefinition::new("payload_decoder", ColumnType::Text, "NOT NULL DEFAULT ''"),
+            ColumnDefinition::new("client_state_sequence", ColumnType::Integer, "NOT NULL DEFAULT 0"),
+            ColumnDefinition::new("asset_id", ColumnType::Text, "NOT NULL DEFAULT ''"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for User: {}", e))?;
         Ok(())
     }
 
@@ -107,16 +302,8 @@ impl DaoManager for UserDaoManager {
     }
 
     fn write_item_to_db(&self, item: &User) -> Result<Self::PrimaryKeyType> {
-        let _rows = self.connection.execute(format!(
-            "INSERT OR REPLACE INTO {} (streams_channel_id, streams_client_state, seed_derivation_phrase, name, external_id) VALUES (\
-                                :streams_channel_id,\
-                                :streams_client_state,\
-                                :seed_derivation_phrase,\
-                                :name,\
-                                :external_id\
-            )", self.get_table_name()).as_str(),
-                                           to_params_named(item).unwrap().to_slice().as_slice())
-            .expect("Error on executing 'INSERT INTO' for User");
+        let _rows = self.connection.upsert_item(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for User: {}", e))?;
         Ok(item.streams_channel_id.clone())
     }
 
@@ -128,6 +315,17 @@ impl DaoManager for UserDaoManager {
         Box::new( move |streams_channel_id: String, streams_client_state: Vec<u8>| -> Result<usize> {
             let mut new_user = User::default();
             let ret_val = streams_client_state.len();
+            // The channel lifecycle fields, the payload_decoder and the asset_id may have been changed by
+            // another process (e.g. a channel is retired while the Message Explorer syncs the
+            // channel) so they are taken from the database
+            if let Ok(stored_user) = this.get_item_from_db(&streams_channel_id) {
+                new_user.status = stored_user.status;
+                new_user.retired_at_secs = stored_user.retired_at_secs;
+                new_user.successor_channel_id = stored_user.successor_channel_id;
+                new_user.payload_decoder = stored_user.payload_decoder;
+                new_user.client_state_sequence = stored_user.client_state_sequence + 1;
+                new_user.asset_id = stored_user.asset_id;
+            }
             new_user.streams_client_state = streams_client_state;
             new_user.streams_channel_id = streams_channel_id;
             new_user.seed_derivation_phrase = seed_derive_phrase.clone();
@@ -138,8 +336,16 @@ impl DaoManager for UserDaoManager {
         })
     }
 
-    fn delete_item_in_db(&self, _key: &Self::PrimaryKeyType) -> Result<()> {
-        unimplemented!();
+    fn delete_item_in_db(&self, key: &Self::PrimaryKeyType) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "DELETE FROM \"{}\" WHERE {} = '{}'",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                escape_sql_string(key)
+            ).as_str()
+        )?;
+        Ok(())
     }
 }
 
@@ -147,3 +353,76 @@ unsafe impl Send for UserDaoManager {}
 unsafe impl Sync for UserDaoManager {}
 
 pub type UserDataStore = DaoDataStore<UserDaoManager>;
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::dao::user::tests  --features dao
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_user(channel_id: &str) -> User {
+        User {
+            streams_channel_id: channel_id.to_string(),
+            streams_client_state: vec![1, 2, 3],
+            seed_derivation_phrase: "phrase".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_channel_lifecycle_persistence() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let dao_manager = UserDaoManager::new(options);
+        dao_manager.init_db_schema().unwrap();
+
+        let mut user = get_test_user("old-channel");
+        assert_eq!(user.get_status().unwrap(), ChannelStatus::Active);
+        dao_manager.write_item_to_db(&user).unwrap();
+
+        user.retire(Some("new-channel"));
+        assert!(user.retired_at_secs > 0);
+        dao_manager.write_item_to_db(&user).unwrap();
+        let stored = dao_manager.get_item_from_db(&"old-channel".to_string()).unwrap();
+        assert_eq!(stored.get_status().unwrap(), ChannelStatus::Retired);
+        assert_eq!(stored.successor_channel_id, "new-channel");
+        assert_eq!(stored, user);
+
+        let retired_at_secs = user.retired_at_secs;
+        user.archive();
+        assert_eq!(user.get_status().unwrap(), ChannelStatus::Archived);
+        assert_eq!(user.retired_at_secs, retired_at_secs);
+        assert_eq!(user.successor_channel_id, "new-channel");
+
+        dao_manager.delete_item_in_db(&"old-channel".to_string()).unwrap();
+        assert!(dao_manager.get_item_from_db(&"old-channel".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_add_lifecycle_columns_to_legacy_table() {
+        let options = DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() };
+        let dao_manager = UserDaoManager::new(options);
+        dao_manager.get_connection().execute("CREATE TABLE \"user\" (\
+            streams_channel_id TEXT NOT NULL PRIMARY KEY,\
+            streams_client_state BLOB NOT NULL,\
+            seed_derivation_phrase TEXT NOT NULL,\
+            name TEXT,\
+            external_id TEXT\
+            )").unwrap();
+        dao_manager.get_connection().execute("INSERT INTO \"user\" VALUES ('legacy-channel', X'010203', 'phrase', 'name', '4711')").unwrap();
+
+        dao_manager.update_db_schema_to_current_version().unwrap();
+        let stored = dao_manager.get_item_from_db(&"legacy-channel".to_string()).unwrap();
+        assert!(stored.is_active());
+        assert_eq!(stored.retired_at_secs, 0);
+        assert_eq!(stored.successor_channel_id, "");
+    }
+
+    #[test]
+    fn test_channel_status_from_str() {
+        for status in [ChannelStatus::Active, ChannelStatus::Retired, ChannelStatus::Archived] {
+            assert_eq!(ChannelStatus::from_str(status.as_str()).unwrap(), status);
+        }
+        assert!(ChannelStatus::from_str("deleted").is_err());
+    }
+}
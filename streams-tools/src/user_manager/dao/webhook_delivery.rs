@@ -0,0 +1,148 @@
+use anyhow::{
+    Result,
+    anyhow,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    helpers::SerializationCallbackRefToClosureI64,
+    dao_helpers::{
+        DaoManager,
+        DbConnection,
+        StorageBackend,
+        ColumnDefinition,
+        ColumnType,
+        DbSchemaVersionType,
+        DaoDataStore,
+        DbFileBasedDaoManagerOptions,
+        DbFileBasedDaoManagerOpt,
+        Limit,
+        MatchType,
+        Condition,
+        get_item_from_db,
+        update_db_schema_to_current_version,
+        filter_items,
+    }
+};
+
+// Webhook notification that could not be delivered and needs to be retried
+// (see webhook::WebhookClient::retry_queued_deliveries()).
+//
+// message_id:              Streams message id of the notified message, used for logging
+// body:                    The json serialized NewMessageNotification
+// attempts:                Number of failed delivery attempts
+// next_attempt_at_secs:    The delivery will not be retried before this time
+// created_at_secs:         Time of the first delivery attempt
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct WebhookDelivery {
+    pub id: Option<i64>,
+    pub message_id: String,
+    pub body: String,
+    pub attempts: i64,
+    pub next_attempt_at_secs: i64,
+    pub created_at_secs: i64,
+}
+
+pub struct WebhookDeliveryDaoManager {
+    connection: DbConnection,
+    options: DbFileBasedDaoManagerOptions,
+}
+
+impl Clone for WebhookDeliveryDaoManager {
+    fn clone(&self) -> Self {
+        WebhookDeliveryDaoManager{
+            connection: self.options.get_new_connection(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl DaoManager for WebhookDeliveryDaoManager {
+    type ItemType = WebhookDelivery;
+    type PrimaryKeyType = i64;
+    type SerializationCallbackType = SerializationCallbackRefToClosureI64;
+    type OptionsType = DbFileBasedDaoManagerOptions;
+
+    const ITEM_TYPE_NAME: &'static str = "WebhookDelivery";
+    const DAO_MANAGER_NAME: &'static str = "WebhookDeliveryDaoManager";
+    const PRIMARY_KEY_COLUMN_NAME: &'static str = "id";
+    const DB_SCHEMA_VERSION: DbSchemaVersionType = 1;
+
+    fn new(options: DbFileBasedDaoManagerOptions) -> Self {
+        WebhookDeliveryDaoManager{
+            connection: options.get_new_connection(),
+            options,
+        }
+    }
+
+    fn get_connection(&self) -> &DbConnection {
+        &self.connection
+    }
+
+    fn get_table_name(&self) -> String { "webhook_delivery".to_string() }
+
+    fn update_db_schema_to_current_version(&self) -> Result<()> {
+        update_db_schema_to_current_version(self)
+    }
+
+    fn init_db_schema(&self) -> Result<()> {
+        self.connection.create_table(self.get_table_name().as_str(), &[
+            ColumnDefinition::new(Self::PRIMARY_KEY_COLUMN_NAME, ColumnType::GeneratedIntegerKey, ""),
+            ColumnDefinition::new("message_id", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("body", ColumnType::Text, "NOT NULL"),
+            ColumnDefinition::new("attempts", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("next_attempt_at_secs", ColumnType::Integer, "NOT NULL"),
+            ColumnDefinition::new("created_at_secs", ColumnType::Integer, "NOT NULL"),
+        ])
+            .map_err(|e| anyhow!("Error on executing 'CREATE TABLE' for WebhookDelivery: {}", e))?;
+        Ok(())
+    }
+
+    fn get_item_from_db(&self, id: &Self::PrimaryKeyType) -> Result<WebhookDelivery> {
+        get_item_from_db(self, id, MatchType::ExactMatch)
+    }
+
+    fn search_item(&self, _id_starts_with: &str) -> Result<WebhookDelivery>{
+        unimplemented!()
+    }
+
+    fn find_all(&self, _id_starts_with: &str, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &Vec::new(), limit)
+    }
+
+    fn filter(&self, conditions: Vec<Condition>, limit: Option<Limit>) -> Result<(Vec<Self::ItemType>, usize)> {
+        filter_items(self, &conditions, limit)
+    }
+
+    // Inserts a new delivery if item.id is None, otherwise the existing delivery is updated
+    fn write_item_to_db(&self, item: &WebhookDelivery) -> Result<Self::PrimaryKeyType> {
+        let key = self.connection.insert_item_returning_key(self.get_table_name().as_str(), Self::PRIMARY_KEY_COLUMN_NAME, item)
+            .map_err(|e| anyhow!("Error on executing 'INSERT INTO' for WebhookDelivery: {}", e))?;
+        Ok(key)
+    }
+
+    fn get_serialization_callback(&self, _item: &Self::ItemType) -> Self::SerializationCallbackType {
+        unimplemented!()
+    }
+
+    fn delete_item_in_db(&self, id: &Self::PrimaryKeyType) -> Result<()> {
+        let _rows = self.connection.execute(
+            format!(
+                "DELETE FROM \"{}\" WHERE {} = {}",
+                self.get_table_name(),
+                Self::PRIMARY_KEY_COLUMN_NAME,
+                id
+            ).as_str()
+        )?;
+        Ok(())
+    }
+}
+
+unsafe impl Send for WebhookDeliveryDaoManager {}
+unsafe impl Sync for WebhookDeliveryDaoManager {}
+
+pub type WebhookDeliveryDataStore = DaoDataStore<WebhookDeliveryDaoManager>;
@@ -0,0 +1,251 @@
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use tokio::sync::broadcast::{
+    self,
+    error::RecvError,
+};
+
+use streams::Message as StreamsMessage;
+
+// Number of events that can be buffered for a slow subscriber. If a subscriber
+// lags behind more than this, the oldest events are skipped for this subscriber.
+const MESSAGE_FEED_CAPACITY: usize = 1024;
+
+// A new sensor message that has been received while a channel was synchronized.
+// public_payload and masked_payload contain the payloads as being received
+// (the masked_payload is decrypted).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageFeedEvent {
+    pub external_id: String,
+    pub channel_id: String,
+    pub message_id: String,
+    pub msg_index: String,
+    pub public_payload: Vec<u8>,
+    pub masked_payload: Vec<u8>,
+    pub received_at_secs: i64,
+}
+
+impl MessageFeedEvent {
+    // Returns None if the message is not a sensor message (e.g. Announcement, Keyload, ...)
+    pub fn new_from_streams_msg(external_id: &str, streams_msg: &StreamsMessage) -> Option<Self> {
+        let public_payload = streams_msg.public_payload().unwrap_or(&[]);
+        let masked_payload = streams_msg.masked_payload().unwrap_or(&[]);
+        if public_payload.is_empty() && masked_payload.is_empty() {
+            return None;
+        }
+        Some(MessageFeedEvent {
+            external_id: external_id.to_string(),
+            channel_id: streams_msg.address.base().to_string(),
+            message_id: streams_msg.address.to_string(),
+            msg_index: hex::encode(streams_msg.address.to_msg_index()),
+            public_payload: public_payload.to_vec(),
+            masked_payload: masked_payload.to_vec(),
+            received_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+// Specifies the events a MessageFeedSubscription receives
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageFeedFilter {
+    All,
+    ChannelId(String),
+    ExternalId(String),
+    // Used to restrict clients to the nodes having an external_id that starts with the prefix
+    ExternalIdPrefix(String),
+}
+
+impl MessageFeedFilter {
+    pub fn matches(&self, event: &MessageFeedEvent) -> bool {
+        match self {
+            MessageFeedFilter::All => true,
+            MessageFeedFilter::ChannelId(channel_id) => event.channel_id == *channel_id,
+            MessageFeedFilter::ExternalId(external_id) => event.external_id == *external_id,
+            MessageFeedFilter::ExternalIdPrefix(prefix) => event.external_id.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ChannelActivity {
+    // Number of subscriptions watching the channel
+    watched: HashMap<String, usize>,
+    // Time of the last event of each channel
+    last_event: HashMap<String, Instant>,
+}
+
+// Distributes MessageFeedEvents to all subscribers (e.g. the /messages/stream endpoint
+// of the Message Explorer). Also keeps track of the channels that are watched by subscribers
+// and the channels that recently received messages so that these channels can be
+// synchronized more frequently than others.
+#[derive(Clone)]
+pub struct MessageFeed {
+    sender: broadcast::Sender<MessageFeedEvent>,
+    activity: Arc<Mutex<ChannelActivity>>,
+}
+
+impl MessageFeed {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(MESSAGE_FEED_CAPACITY);
+        MessageFeed {
+            sender,
+            activity: Arc::new(Mutex::new(ChannelActivity::default())),
+        }
+    }
+
+    pub fn publish(&self, event: MessageFeedEvent) {
+        self.activity.lock().unwrap().last_event.insert(event.channel_id.clone(), Instant::now());
+        // send() fails if there are no subscribers which is fine
+        let _ = self.sender.send(event);
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    // watched_channel_ids are synchronized frequently as long as the subscription exists
+    pub fn subscribe(&self, filter: MessageFeedFilter, watched_channel_ids: Vec<String>) -> MessageFeedSubscription {
+        {
+            let mut activity = self.activity.lock().unwrap();
+            for channel_id in watched_channel_ids.iter() {
+                *activity.watched.entry(channel_id.clone()).or_insert(0) += 1;
+            }
+        }
+        MessageFeedSubscription {
+            receiver: self.sender.subscribe(),
+            filter,
+            watched_channel_ids,
+            activity: self.activity.clone(),
+        }
+    }
+
+    // Returns the channels watched by subscribers and the channels that received
+    // messages during the last recent_activity_period
+    pub fn get_active_channel_ids(&self, recent_activity_period: Duration) -> Vec<String> {
+        let mut activity = self.activity.lock().unwrap();
+        activity.last_event.retain(|_, last_event| last_event.elapsed() <= recent_activity_period);
+        let mut ret_val: HashSet<String> = activity.watched.keys().cloned().collect();
+        ret_val.extend(activity.last_event.keys().cloned());
+        ret_val.into_iter().collect()
+    }
+}
+
+impl Default for MessageFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct MessageFeedSubscription {
+    receiver: broadcast::Receiver<MessageFeedEvent>,
+    filter: MessageFeedFilter,
+    watched_channel_ids: Vec<String>,
+    activity: Arc<Mutex<ChannelActivity>>,
+}
+
+impl MessageFeedSubscription {
+    // Returns the next event matching the filter of the subscription.
+    // Returns None if the MessageFeed has been dropped.
+    pub async fn recv(&mut self) -> Option<MessageFeedEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => {
+                    if self.filter.matches(&event) {
+                        return Some(event);
+                    }
+                },
+                Err(RecvError::Lagged(num_skipped)) => {
+                    log::warn!("[fn recv()] Subscriber lagged behind. Skipped {} messages", num_skipped);
+                },
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for MessageFeedSubscription {
+    fn drop(&mut self) {
+        let mut activity = self.activity.lock().unwrap();
+        for channel_id in self.watched_channel_ids.iter() {
+            let remove = match activity.watched.get_mut(channel_id) {
+                Some(cnt) => {
+                    *cnt -= 1;
+                    *cnt == 0
+                },
+                None => false,
+            };
+            if remove {
+                activity.watched.remove(channel_id);
+            }
+        }
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::message_feed::tests --features explorer
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_event(channel_id: &str, external_id: &str) -> MessageFeedEvent {
+        MessageFeedEvent {
+            external_id: external_id.to_string(),
+            channel_id: channel_id.to_string(),
+            message_id: format!("{}:84d48c0cc279564b467f7e74", channel_id),
+            msg_index: "00".to_string(),
+            public_payload: Vec::new(),
+            masked_payload: vec![1, 2, 3],
+            received_at_secs: 1677628800,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscription_filter() {
+        let feed = MessageFeed::new();
+        let mut by_channel = feed.subscribe(MessageFeedFilter::ChannelId("chan_b".to_string()), vec!["chan_b".to_string()]);
+        let mut by_external_id = feed.subscribe(MessageFeedFilter::ExternalId("dev_eui_a".to_string()), Vec::new());
+        let mut by_prefix = feed.subscribe(MessageFeedFilter::ExternalIdPrefix("dev_eui_b".to_string()), Vec::new());
+        assert!(feed.has_subscribers());
+
+        feed.publish(get_event("chan_a", "dev_eui_a"));
+        feed.publish(get_event("chan_b", "dev_eui_b"));
+
+        assert_eq!(by_channel.recv().await.unwrap().channel_id, "chan_b");
+        assert_eq!(by_external_id.recv().await.unwrap().channel_id, "chan_a");
+        assert_eq!(by_prefix.recv().await.unwrap().channel_id, "chan_b");
+    }
+
+    #[test]
+    fn test_active_channel_ids() {
+        let feed = MessageFeed::new();
+        let subscription = feed.subscribe(MessageFeedFilter::All, vec!["chan_a".to_string()]);
+        feed.publish(get_event("chan_b", "dev_eui_b"));
+
+        let mut active = feed.get_active_channel_ids(Duration::from_secs(60));
+        active.sort();
+        assert_eq!(active, vec!["chan_a".to_string(), "chan_b".to_string()]);
+
+        drop(subscription);
+        assert_eq!(feed.get_active_channel_ids(Duration::from_secs(60)), vec!["chan_b".to_string()]);
+        assert!(!feed.has_subscribers());
+    }
+}
@@ -121,7 +121,7 @@ impl EndpointUris {
 
 impl MessageIndexer {
     // utf8 encoded bytes for 'susee-'
-    const TAG_PREFIX: [u8; 6] = [115, 117, 115, 101, 101, 45];
+    pub(crate) const TAG_PREFIX: [u8; 6] = [115, 117, 115, 101, 101, 45];
 
     pub fn new(options: MessageIndexerOptions) -> MessageIndexer {
         let mut local_message_data_store: Option<MessageDataStore> = None;
@@ -245,11 +245,16 @@ impl MessageIndex for MessageIndexer {
                 Ok(messages) => {
                     if let Some(message) = messages.first() {
                         if let Some(local_data_store) = self.local_message_data_store.as_ref() {
-                            local_data_store.write_item_to_db(
-                                &DaoMessage{
-                                    message_id: msg_id_hex_str.clone(),
-                                    wrapped_binary: message.body().clone()
+                            // The wrapped_binary of a cached message may have been dropped by the
+                            // retention policy of the message cache. Its timestamps and link info
+                            // must not be lost when the wrapped_binary is cached again.
+                            let cached_message = local_data_store.get_item_read_only(&msg_id_hex_str)
+                                .map(|mut cached| {
+                                    cached.wrapped_binary = message.body().clone();
+                                    cached
                                 })
+                                .unwrap_or_else(|_| DaoMessage::new_received_now(msg_id_hex_str.clone(), message.body().clone()));
+                            local_data_store.write_item_to_db(&cached_message)
                                 .map_err(|e| LetsError::External(e))?;
                         }
 
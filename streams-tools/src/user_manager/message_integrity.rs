@@ -0,0 +1,455 @@
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use super::dao::message::Message as DaoMessage;
+
+// A message that links to a message that is not known, e.g. because it has been
+// dropped by the IOTA-Bridge or has never been sent by the sensor.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MissingLink {
+    pub message_id: String,
+    pub linked_msg_id: String,
+}
+
+// Messages of a publisher have consecutive sequence numbers.
+// A SequenceGap describes sequence numbers of a publisher that have not been found.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SequenceGap {
+    pub publisher: String,
+    pub last_sequence_before_gap: i64,
+    pub first_sequence_after_gap: i64,
+}
+
+// A message that has the same publisher and sequence number as another message or that
+// links to a message that is already linked by another message (the branch has been forked).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DuplicateMessage {
+    pub message_id: String,
+    pub duplicate_of: String,
+    pub reason: String,
+}
+
+// A publisher that is neither the channel author nor the sensor of the channel
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UnexpectedPublisher {
+    pub publisher: String,
+    pub message_ids: Vec<String>,
+}
+
+// Range of the checked sequence numbers of a publisher
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SequenceRange {
+    pub first: i64,
+    pub last: i64,
+}
+
+// Result of check_message_chain() resp. check_new_messages().
+// Messages without link info (see DaoMessage::has_link_info()) can not be checked
+// and are only counted in unchecked_messages_cnt.
+// sequence_ranges is needed to check new messages incrementally. It is empty for reports
+// created by older versions, which therefore need to be recreated by a full check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
+pub struct ChannelIntegrityReport {
+    pub checked_messages_cnt: usize,
+    pub unchecked_messages_cnt: usize,
+    pub author_publisher: String,
+    pub sensor_publisher: String,
+    pub missing_links: Vec<MissingLink>,
+    pub sequence_gaps: Vec<SequenceGap>,
+    pub duplicates: Vec<DuplicateMessage>,
+    pub unexpected_publishers: Vec<UnexpectedPublisher>,
+    #[serde(default)]
+    pub sequence_ranges: BTreeMap<String, SequenceRange>,
+}
+
+impl ChannelIntegrityReport {
+    pub fn new(author_publisher: &str) -> Self {
+        ChannelIntegrityReport {
+            author_publisher: author_publisher.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn issues_cnt(&self) -> usize {
+        self.missing_links.len()
+            + self.sequence_gaps.len()
+            + self.duplicates.len()
+            + self.unexpected_publishers.len()
+    }
+
+    pub fn is_intact(&self) -> bool {
+        self.issues_cnt() == 0
+    }
+
+    // Reports created by older versions can not be used to check new messages incrementally
+    pub fn can_be_continued(&self) -> bool {
+        self.checked_messages_cnt == 0 || !self.sequence_ranges.is_empty()
+    }
+
+    fn set_sensor_publisher(&mut self, sensor_publisher: &str) {
+        self.sensor_publisher = sensor_publisher.to_string();
+        self.unexpected_publishers.retain(|unexpected| unexpected.publisher != sensor_publisher);
+    }
+
+    fn add_unexpected_publisher_msg(&mut self, publisher: &str, message_id: &str) {
+        match self.unexpected_publishers.iter_mut().find(|unexpected| unexpected.publisher == publisher) {
+            Some(unexpected) => unexpected.message_ids.push(message_id.to_string()),
+            None => {
+                self.unexpected_publishers.push(UnexpectedPublisher {
+                    publisher: publisher.to_string(),
+                    message_ids: vec![message_id.to_string()],
+                });
+                self.unexpected_publishers.sort_by(|a, b| a.publisher.cmp(&b.publisher));
+            }
+        }
+    }
+
+    // Extends the SequenceRange of the publisher by the sequence and updates the sequence gaps.
+    // A sequence inside of a reported gap splits the gap.
+    fn add_sequence(&mut self, publisher: &str, sequence: i64) {
+        let range = match self.sequence_ranges.get_mut(publisher) {
+            Some(range) => range,
+            None => {
+                self.sequence_ranges.insert(publisher.to_string(), SequenceRange { first: sequence, last: sequence });
+                return;
+            }
+        };
+        if sequence > range.last {
+            if sequence > range.last + 1 {
+                self.sequence_gaps.push(SequenceGap {
+                    publisher: publisher.to_string(),
+                    last_sequence_before_gap: range.last,
+                    first_sequence_after_gap: sequence,
+                });
+            }
+            range.last = sequence;
+        } else if sequence < range.first {
+            if sequence + 1 < range.first {
+                self.sequence_gaps.push(SequenceGap {
+                    publisher: publisher.to_string(),
+                    last_sequence_before_gap: sequence,
+                    first_sequence_after_gap: range.first,
+                });
+            }
+            range.first = sequence;
+        } else if let Some(idx) = self.sequence_gaps.iter().position(|gap| gap.publisher == publisher
+            && gap.last_sequence_before_gap < sequence && sequence < gap.first_sequence_after_gap) {
+            let gap = self.sequence_gaps.remove(idx);
+            if sequence > gap.last_sequence_before_gap + 1 {
+                self.sequence_gaps.push(SequenceGap { first_sequence_after_gap: sequence, ..gap.clone() });
+            }
+            if sequence + 1 < gap.first_sequence_after_gap {
+                self.sequence_gaps.push(SequenceGap { last_sequence_before_gap: sequence, ..gap });
+            }
+        }
+        self.sequence_gaps.sort_by(|a, b| (&a.publisher, a.last_sequence_before_gap).cmp(&(&b.publisher, b.last_sequence_before_gap)));
+    }
+}
+
+// Describes the messages of a channel that may have been deleted by the MessageRetentionPolicy
+// of the message cache (see message_retention.rs). Deleted messages must not be reported as
+// integrity issues.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct PrunedChainInfo {
+    // Messages having a timestamp_secs before pruned_before_secs may have been deleted.
+    // 0 if no messages have been deleted.
+    pub pruned_before_secs: i64,
+    // The sensor publisher found by the previous check of the channel. Needed if the sensor
+    // message linking to a message of the author has been deleted.
+    pub sensor_publisher: String,
+}
+
+// A message can only link to a deleted message if it has been sent shortly after the deleted
+// messages. Messages having a timestamp_secs later than pruned_before_secs + this tolerance
+// are expected to link to an existing message.
+pub const PRUNED_LINK_TOLERANCE_SECS: i64 = 24 * 3600;
+
+impl PrunedChainInfo {
+    pub fn has_been_pruned(&self) -> bool {
+        self.pruned_before_secs > 0
+    }
+
+    // Returns true if the linked message of the message may have been deleted
+    pub fn may_link_to_pruned_msg(&self, message: &DaoMessage) -> bool {
+        self.has_been_pruned()
+            && message.timestamp_secs <= self.pruned_before_secs.saturating_add(PRUNED_LINK_TOLERANCE_SECS)
+    }
+}
+
+// Lookups into the messages of a channel used by check_new_messages(). This way the already
+// checked messages of a channel do not need to be read again to check new messages.
+pub trait CheckedMessages {
+    // Returns the publisher of the message if the message is known and has link info
+    fn get_publisher(&self, message_id: &str) -> Option<String>;
+    // Returns the id of an already checked message linking to linked_msg_id
+    fn find_successor(&self, linked_msg_id: &str) -> Option<String>;
+    // Returns the id of an already checked message having the publisher and sequence
+    fn find_by_sequence(&self, publisher: &str, publisher_sequence: i64) -> Option<String>;
+    // Called after the message has been checked
+    fn mark_checked(&mut self, message: &DaoMessage) -> anyhow::Result<()>;
+}
+
+// CheckedMessages for messages that are completely held in memory
+struct CheckedMessagesInMemory<'a> {
+    known: HashMap<&'a str, &'a str>,
+    successor_of: HashMap<String, String>,
+    sequences: HashMap<(String, i64), String>,
+}
+
+impl<'a> CheckedMessagesInMemory<'a> {
+    fn new(linked: &[&'a DaoMessage]) -> Self {
+        CheckedMessagesInMemory {
+            known: linked.iter().map(|msg| (msg.message_id.as_str(), msg.publisher.as_str())).collect(),
+            successor_of: HashMap::new(),
+            sequences: HashMap::new(),
+        }
+    }
+}
+
+impl<'a> CheckedMessages for CheckedMessagesInMemory<'a> {
+    fn get_publisher(&self, message_id: &str) -> Option<String> {
+        self.known.get(message_id).map(|publisher| publisher.to_string())
+    }
+
+    fn find_successor(&self, linked_msg_id: &str) -> Option<String> {
+        self.successor_of.get(linked_msg_id).cloned()
+    }
+
+    fn find_by_sequence(&self, publisher: &str, publisher_sequence: i64) -> Option<String> {
+        self.sequences.get(&(publisher.to_string(), publisher_sequence)).cloned()
+    }
+
+    fn mark_checked(&mut self, message: &DaoMessage) -> anyhow::Result<()> {
+        self.successor_of.entry(message.linked_msg_id.clone()).or_insert_with(|| message.message_id.clone());
+        self.sequences.entry((message.publisher.clone(), message.publisher_sequence)).or_insert_with(|| message.message_id.clone());
+        Ok(())
+    }
+}
+
+// Checks the linked branch of a sensor channel, starting with the keyload that links to
+// the announcement and ending with the latest sensor message.
+// All messages of the channel are checked. See check_new_messages() for the parameters.
+pub fn check_message_chain(messages: &[DaoMessage], author_publisher: &str, announcement_msg_id: &str, pruned: &PrunedChainInfo) -> ChannelIntegrityReport {
+    let mut ret_val = ChannelIntegrityReport::new(author_publisher);
+    let mut linked: Vec<&DaoMessage> = messages.iter().filter(|msg| msg.has_link_info()).collect();
+    ret_val.unchecked_messages_cnt = messages.len() - linked.len();
+    sort_messages(&mut linked);
+    let mut checked = CheckedMessagesInMemory::new(&linked);
+    check_new_messages(&mut ret_val, &linked, &mut checked, announcement_msg_id, pruned)
+        .expect("CheckedMessagesInMemory::mark_checked() never fails");
+    ret_val
+}
+
+// Sorts the messages in the order expected by check_new_messages()
+pub fn sort_messages(messages: &mut Vec<&DaoMessage>) {
+    // Use a deterministic order so that the first message of duplicates is always the same
+    messages.sort_by(|a, b| (a.timestamp_secs, &a.message_id).cmp(&(b.timestamp_secs, &b.message_id)));
+}
+
+// Adds the issues of new messages to the report of the previously checked messages.
+// The new messages must have link info and must be sorted using sort_messages().
+// After a message has been checked, CheckedMessages::mark_checked() is called.
+//
+// announcement_msg_id:     Message id of the announcement. The announcement is normally not
+//                          cached and therefore is not reported as missing link.
+//                          Several messages (keyloads, subscriptions) may link to the announcement
+//                          which is not reported as duplicate.
+//
+// pruned:                  If messages have been deleted by the retention policy, the first
+//                          remaining message of each publisher links to a deleted message
+//                          which is not reported as missing link, if the message has been
+//                          sent shortly after the deleted messages (see PRUNED_LINK_TOLERANCE_SECS).
+//
+// The sensor publisher is the publisher of the first message that links to a message
+// of the author. All other publishers are reported as unexpected publishers.
+// Each message is reported at most once as duplicate, so a forked message that links to an
+// already linked message using an already used sequence number is only reported once.
+pub fn check_new_messages<C: CheckedMessages>(
+    report: &mut ChannelIntegrityReport,
+    new_messages: &[&DaoMessage],
+    checked: &mut C,
+    announcement_msg_id: &str,
+    pruned: &PrunedChainInfo
+) -> anyhow::Result<()> {
+    if report.sensor_publisher.is_empty() && pruned.has_been_pruned() {
+        report.sensor_publisher = pruned.sensor_publisher.clone();
+    }
+
+    for msg in new_messages.iter() {
+        let publisher = msg.publisher.as_str();
+        let linked_msg_id = msg.linked_msg_id.as_str();
+        let mut is_duplicate = false;
+        if !linked_msg_id.is_empty() && linked_msg_id != announcement_msg_id {
+            let linked_publisher = checked.get_publisher(linked_msg_id);
+            if report.sensor_publisher.is_empty() && publisher != report.author_publisher
+                && linked_publisher.as_deref() == Some(report.author_publisher.as_str()) {
+                report.set_sensor_publisher(publisher);
+            }
+            let links_to_pruned_msg = pruned.may_link_to_pruned_msg(msg) && report.sequence_ranges.get(publisher)
+                .map_or(true, |range| msg.publisher_sequence < range.first);
+            if linked_publisher.is_none() && !links_to_pruned_msg {
+                report.missing_links.push(MissingLink {
+                    message_id: msg.message_id.clone(),
+                    linked_msg_id: msg.linked_msg_id.clone(),
+                });
+            }
+            if let Some(first_successor) = checked.find_successor(linked_msg_id) {
+                is_duplicate = true;
+                report.duplicates.push(DuplicateMessage {
+                    message_id: msg.message_id.clone(),
+                    duplicate_of: first_successor,
+                    reason: format!("Links to the same message {}", linked_msg_id),
+                });
+            }
+        }
+
+        match checked.find_by_sequence(publisher, msg.publisher_sequence) {
+            Some(first_msg_id) => if !is_duplicate {
+                report.duplicates.push(DuplicateMessage {
+                    message_id: msg.message_id.clone(),
+                    duplicate_of: first_msg_id,
+                    reason: format!("Same publisher and sequence number {}", msg.publisher_sequence),
+                });
+            },
+            None => report.add_sequence(publisher, msg.publisher_sequence),
+        }
+
+        if publisher != report.author_publisher && publisher != report.sensor_publisher {
+            report.add_unexpected_publisher_msg(publisher, msg.message_id.as_str());
+        }
+
+        // A message received after the messages linking to it resolves their missing links
+        report.missing_links.retain(|link| link.linked_msg_id != msg.message_id);
+        report.checked_messages_cnt += 1;
+        checked.mark_checked(msg)?;
+    }
+    Ok(())
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::message_integrity::tests --features dao
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTHOR: &str = "aaaa";
+    const SENSOR: &str = "bbbb";
+    const ANNOUNCEMENT: &str = "0000";
+
+    fn get_msg(message_id: &str, publisher: &str, publisher_sequence: i64, linked_msg_id: &str, timestamp_secs: i64) -> DaoMessage {
+        let mut ret_val = DaoMessage {
+            message_id: message_id.to_string(),
+            timestamp_secs,
+            ..Default::default()
+        };
+        ret_val.set_link_info(publisher.to_string(), publisher_sequence, linked_msg_id.to_string());
+        ret_val
+    }
+
+    fn get_intact_chain() -> Vec<DaoMessage> {
+        vec![
+            get_msg("1111", AUTHOR, 2, ANNOUNCEMENT, 10),
+            get_msg("2222", SENSOR, 2, "1111", 20),
+            get_msg("3333", SENSOR, 3, "2222", 30),
+            get_msg("4444", SENSOR, 4, "3333", 40),
+        ]
+    }
+
+    #[test]
+    fn test_intact_chain() {
+        let report = check_message_chain(&get_intact_chain(), AUTHOR, ANNOUNCEMENT, &PrunedChainInfo::default());
+        assert!(report.is_intact());
+        assert_eq!(report.sensor_publisher, SENSOR);
+        assert_eq!(report.checked_messages_cnt, 4);
+    }
+
+    #[test]
+    fn test_missing_message() {
+        let mut messages = get_intact_chain();
+        messages.remove(2);
+        let report = check_message_chain(&messages, AUTHOR, ANNOUNCEMENT, &PrunedChainInfo::default());
+        assert_eq!(report.missing_links, vec![MissingLink { message_id: "4444".to_string(), linked_msg_id: "3333".to_string() }]);
+        assert_eq!(report.sequence_gaps, vec![SequenceGap {
+            publisher: SENSOR.to_string(),
+            last_sequence_before_gap: 2,
+            first_sequence_after_gap: 4,
+        }]);
+        assert_eq!(report.issues_cnt(), 2);
+    }
+
+    #[test]
+    fn test_pruned_chain() {
+        let mut messages = get_intact_chain();
+        // The keyload of the author is kept, the first sensor messages have been deleted
+        messages.remove(1);
+        messages.remove(1);
+        let report = check_message_chain(&messages, AUTHOR, ANNOUNCEMENT, &PrunedChainInfo::default());
+        assert_eq!(report.missing_links.len(), 1);
+        assert_eq!(report.unexpected_publishers.len(), 1);
+
+        let pruned = PrunedChainInfo {
+            pruned_before_secs: 35,
+            sensor_publisher: SENSOR.to_string(),
+        };
+        let report = check_message_chain(&messages, AUTHOR, ANNOUNCEMENT, &pruned);
+        assert!(report.is_intact());
+        assert_eq!(report.sensor_publisher, SENSOR);
+        assert_eq!(report.checked_messages_cnt, 2);
+
+        // A message sent long after the deleted messages must link to an existing message
+        messages[1].timestamp_secs = pruned.pruned_before_secs + PRUNED_LINK_TOLERANCE_SECS + 1;
+        let report = check_message_chain(&messages, AUTHOR, ANNOUNCEMENT, &pruned);
+        assert_eq!(report.missing_links, vec![MissingLink { message_id: "4444".to_string(), linked_msg_id: "3333".to_string() }]);
+        assert_eq!(report.sensor_publisher, SENSOR);
+    }
+
+    #[test]
+    fn test_duplicates_and_unexpected_publishers() {
+        let mut messages = get_intact_chain();
+        messages.push(get_msg("5555", SENSOR, 4, "3333", 50));
+        messages.push(get_msg("6666", "cccc", 2, "4444", 60));
+        messages.push(DaoMessage { message_id: "7777".to_string(), ..Default::default() });
+        let report = check_message_chain(&messages, AUTHOR, ANNOUNCEMENT, &PrunedChainInfo::default());
+        // The fork links to the same message and uses the same sequence number but is only reported once
+        assert_eq!(report.duplicates.len(), 1);
+        assert!(report.duplicates.iter().all(|dup| dup.message_id == "5555" && dup.duplicate_of == "4444"));
+        assert_eq!(report.unexpected_publishers, vec![UnexpectedPublisher {
+            publisher: "cccc".to_string(),
+            message_ids: vec!["6666".to_string()],
+        }]);
+        assert_eq!(report.unchecked_messages_cnt, 1);
+        assert!(report.missing_links.is_empty());
+    }
+
+    #[test]
+    fn test_check_new_messages_incrementally() {
+        let messages = get_intact_chain();
+        let refs: Vec<&DaoMessage> = messages.iter().collect();
+        // Message 3333 is received after message 4444
+        let mut checked = CheckedMessagesInMemory::new(&[refs[0], refs[1], refs[3]]);
+        let mut report = ChannelIntegrityReport::new(AUTHOR);
+        check_new_messages(&mut report, &[refs[0], refs[1], refs[3]], &mut checked, ANNOUNCEMENT, &PrunedChainInfo::default()).unwrap();
+        assert_eq!(report.missing_links.len(), 1);
+        assert_eq!(report.sequence_gaps.len(), 1);
+        assert!(report.can_be_continued());
+
+        checked.known.insert(refs[2].message_id.as_str(), refs[2].publisher.as_str());
+        check_new_messages(&mut report, &[refs[2]], &mut checked, ANNOUNCEMENT, &PrunedChainInfo::default()).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.checked_messages_cnt, 4);
+        assert_eq!(report.sequence_ranges[SENSOR], SequenceRange { first: 2, last: 4 });
+        assert_eq!(report, check_message_chain(&messages, AUTHOR, ANNOUNCEMENT, &PrunedChainInfo::default()));
+
+        // Reports of older versions have no sequence ranges
+        let legacy_report = ChannelIntegrityReport { checked_messages_cnt: 4, ..Default::default() };
+        assert!(!legacy_report.can_be_continued());
+    }
+}
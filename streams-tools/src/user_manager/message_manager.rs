@@ -1,69 +1,567 @@
-use anyhow::Result;
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+};
 use futures::TryStreamExt;
 use lets::message::TransportMessage;
 
 use streams::{
     User,
+    Message as StreamsMessage,
     transport::Transport,
 };
 
 use crate::{
-    dao_helpers::Limit,
+    dao_helpers::{
+        Limit,
+        Condition,
+        Conditions,
+        MatchType,
+        OrderBy,
+        SortOrder,
+        DbFileBasedDaoManagerOptions,
+    },
+    helpers::get_tangle_address_from_strings,
 };
 
-use super::dao::message::{
-    MessageDataStore,
-    MessageDataStoreOptions,
-    Message as DaoMessage
+use super::{
+    dao::{
+        message::{
+            MessageDataStore,
+            MessageDataStoreOptions,
+            MessageTimeFilter,
+            Message as DaoMessage
+        },
+        poi::{
+            PoiDataStore,
+            Poi,
+        },
+        channel_integrity::{
+            ChannelIntegrityDataStore,
+            ChannelIntegrity,
+        },
+        sync_cursor::{
+            SyncCursorDataStore,
+            SyncCursor,
+        },
+        webhook_delivery::WebhookDeliveryDataStore,
+    },
+    proof_of_inclusion::{
+        PoiClient,
+        PoiClientOptions,
+    },
+    webhook::{
+        WebhookClient,
+        WebhookOptions,
+        NewMessageNotification,
+    },
+    message_feed::{
+        MessageFeed,
+        MessageFeedEvent,
+    },
+    message_integrity::{
+        ChannelIntegrityReport,
+        CheckedMessages,
+        PrunedChainInfo,
+        check_new_messages,
+        sort_messages,
+    },
+    subscriber_manager::get_identifier_str,
 };
 
+const POI_SYNC_PAGE_SIZE: usize = 1000;
+const INTEGRITY_CHECK_PAGE_SIZE: usize = 1000;
+// Messages not referenced by a milestone yet are retried by later syncs as long as they
+// have been cached during this time span
+const MILESTONE_TIMESTAMP_SYNC_WINDOW_SECS: i64 = 24 * 3600;
+
 pub struct MessageManager<'a, TransT> {
     user: &'a mut User<TransT>,
     message_data_store: MessageDataStore,
+    poi_data_store: PoiDataStore,
+    integrity_data_store: ChannelIntegrityDataStore,
+    sync_cursor_data_store: SyncCursorDataStore,
+    webhook_delivery_data_store: WebhookDeliveryDataStore,
+    poi_client: Option<PoiClient>,
+    milestone_timestamp_client: Option<PoiClient>,
+    webhook_client: Option<WebhookClient>,
+    message_feed: Option<MessageFeed>,
+    dev_eui: String,
     streams_channel_id: String,
 }
 
 impl<'a, TransT> MessageManager<'a, TransT> {
     pub fn new(user: &'a mut User<TransT>, channel_id: String, db_file_name: String) -> Self {
+        let db_file_opt = DbFileBasedDaoManagerOptions {
+            file_path_and_name: db_file_name.clone(),
+        };
+        let integrity_data_store = ChannelIntegrityDataStore::new(db_file_opt.clone());
+        let sync_cursor_data_store = SyncCursorDataStore::new(db_file_opt.clone());
+        let webhook_delivery_data_store = WebhookDeliveryDataStore::new(db_file_opt);
         let msg_data_store_opt = MessageDataStoreOptions {
             file_path_and_name: db_file_name,
             streams_channel_id: channel_id.clone()
         };
         log::debug!("[fn new()] Creating new MessageManager using MessageDataStoreOptions {}", msg_data_store_opt);
-        let message_data_store = MessageDataStore::new(msg_data_store_opt);
+        let message_data_store = MessageDataStore::new(msg_data_store_opt.clone());
+        let poi_data_store = PoiDataStore::new(msg_data_store_opt);
         MessageManager {
             user,
             message_data_store,
+            poi_data_store,
+            integrity_data_store,
+            sync_cursor_data_store,
+            webhook_delivery_data_store,
+            poi_client: None,
+            milestone_timestamp_client: None,
+            webhook_client: None,
+            message_feed: None,
+            dev_eui: String::new(),
             streams_channel_id: channel_id,
         }
     }
 
+    // If PoiClientOptions are set, sync() will fetch the Proof of Inclusion for each
+    // cached message that has no stored Proof of Inclusion yet.
+    pub fn set_poi_client_options(&mut self, options: PoiClientOptions) {
+        self.poi_client = Some(PoiClient::new(options));
+    }
+
+    // If no PoiClientOptions are set, sync() will fetch the milestone timestamp of each
+    // cached message from the IOTA node using these options. Otherwise the milestone timestamp
+    // is taken from the Proof of Inclusion.
+    pub fn set_milestone_timestamp_client_options(&mut self, options: PoiClientOptions) {
+        self.milestone_timestamp_client = Some(PoiClient::new(options));
+    }
+
+    // If WebhookOptions are set, sync() will send a webhook notification for each new sensor
+    // message. The dev_eui is included in the notification to identify the sensor.
+    // Notifications that can not be delivered are queued in the WebhookDeliveryDataStore
+    // and are retried by the explorer::webhook_retry_loop.
+    pub fn set_webhook_options(&mut self, options: WebhookOptions, dev_eui: &str) {
+        self.webhook_client = Some(WebhookClient::new(options));
+        self.dev_eui = dev_eui.to_string();
+    }
+
+    // If a MessageFeed is set, sync() will publish each new sensor message to the feed.
+    // The dev_eui is used as external_id of the published MessageFeedEvents.
+    pub fn set_message_feed(&mut self, message_feed: MessageFeed, dev_eui: &str) {
+        self.message_feed = Some(message_feed);
+        self.dev_eui = dev_eui.to_string();
+    }
+
     pub fn index(&self, limit: Option<Limit>) -> Result<(Vec<DaoMessage>, usize)> {
         self.message_data_store.find_all("", limit)
     }
 
+    // Lists the cached messages having a timestamp_secs in the time range of the time_filter
+    pub fn index_by_time(&self, time_filter: &MessageTimeFilter, limit: Option<Limit>) -> Result<(Vec<DaoMessage>, usize)> {
+        self.message_data_store.filter_ordered(time_filter.get_conditions(), time_filter.get_order_by(), limit)
+    }
+
     pub fn get(&self, message_id: &str) -> Result<DaoMessage> {
         self.message_data_store.get_item_read_only(&message_id.to_string())
     }
+
+    pub fn get_poi(&self, message_id: &str) -> Result<Poi> {
+        self.poi_data_store.get_item_read_only(&message_id.to_string())
+    }
+
+    // Returns a new SyncCursor if the channel has not been synced yet
+    pub fn get_sync_cursor(&self) -> SyncCursor {
+        self.sync_cursor_data_store.get_item_read_only(&self.streams_channel_id)
+            .unwrap_or_else(|_| SyncCursor::new(self.streams_channel_id.as_str()))
+    }
+
+    async fn sync_proofs_of_inclusion(&self, poi_client: &PoiClient) -> Result<u32> {
+        let mut num_pois_stored = 0;
+        let mut offset = 0;
+        loop {
+            let (messages, _) = self.message_data_store.find_all("", Some(Limit { limit: POI_SYNC_PAGE_SIZE, offset }))?;
+            if messages.is_empty() {
+                break;
+            }
+            offset += messages.len();
+            for message in messages {
+                if let Ok(poi) = self.poi_data_store.get_item_read_only(&message.message_id) {
+                    // Messages cached before the message timestamps have been introduced
+                    // need to get the milestone_timestamp from their stored Proof of Inclusion
+                    if message.milestone_timestamp == 0 {
+                        self.store_milestone_timestamp(message, poi.milestone_timestamp)?;
+                    }
+                    continue;
+                }
+                match self.fetch_and_store_poi(poi_client, message.message_id.as_str()).await {
+                    Ok(_) => num_pois_stored += 1,
+                    Err(e) => log::warn!("[fn sync_proofs_of_inclusion()] Could not fetch Proof of Inclusion for message {}. Error: {}",
+                        message.message_id, e),
+                }
+            }
+        }
+        Ok(num_pois_stored)
+    }
+
+    async fn fetch_and_store_poi(&self, poi_client: &PoiClient, message_id: &str) -> Result<()> {
+        let address = get_tangle_address_from_strings(self.streams_channel_id.as_str(), message_id)
+            .map_err(|e| anyhow!("Error on creating tangle address for message {}. Error: {}", message_id, e))?;
+        let block_id = poi_client.get_block_id(address.to_msg_index()).await?;
+        let proof_of_inclusion = poi_client.create_poi(block_id.as_str()).await?;
+        let is_verified = proof_of_inclusion.verify(block_id.as_str())?;
+        if !is_verified {
+            log::warn!("[fn fetch_and_store_poi()] Proof of Inclusion for message {} could not be verified", message_id);
+        }
+        self.poi_data_store.write_item_to_db(&Poi {
+            message_id: message_id.to_string(),
+            milestone_index: proof_of_inclusion.milestone_index(),
+            milestone_timestamp: proof_of_inclusion.milestone_timestamp(),
+            is_verified,
+            proof_of_inclusion: proof_of_inclusion.to_json_string()?,
+            block_id,
+        })?;
+        let message = self.message_data_store.get_item_read_only(&message_id.to_string())?;
+        self.store_milestone_timestamp(message, proof_of_inclusion.milestone_timestamp())
+    }
+
+    // Fetches the milestone timestamp of the recently cached messages that have no milestone
+    // timestamp yet. Messages cached before MILESTONE_TIMESTAMP_SYNC_WINDOW_SECS are not
+    // retried, e.g. because their block has been pruned by the IOTA node, and keep their
+    // received_at_secs as timestamp_secs.
+    async fn sync_milestone_timestamps(&self, client: &PoiClient) -> Result<u32> {
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+        let mut condition_vec = Vec::<Condition>::new();
+        let mut conditions = Conditions(&mut condition_vec);
+        conditions.add(Some("0".to_string()), "milestone_timestamp", MatchType::ExactMatch);
+        conditions.add(Some((now_secs - MILESTONE_TIMESTAMP_SYNC_WINDOW_SECS).to_string()), "received_at_secs", MatchType::GreaterOrEqual);
+        let messages = self.message_data_store.filter_unlimited(condition_vec)?;
+
+        let mut num_timestamps_stored = 0;
+        for message in messages {
+            match self.fetch_milestone_timestamp(client, message.message_id.as_str()).await {
+                Ok(Some(milestone_timestamp)) => {
+                    self.store_milestone_timestamp(message, milestone_timestamp)?;
+                    num_timestamps_stored += 1;
+                },
+                Ok(None) => log::debug!("[fn sync_milestone_timestamps()] Message {} has not been referenced by a milestone yet",
+                    message.message_id),
+                Err(e) => log::warn!("[fn sync_milestone_timestamps()] Could not fetch milestone timestamp for message {}. Error: {}",
+                    message.message_id, e),
+            }
+        }
+        Ok(num_timestamps_stored)
+    }
+
+    async fn fetch_milestone_timestamp(&self, client: &PoiClient, message_id: &str) -> Result<Option<u32>> {
+        let address = get_tangle_address_from_strings(self.streams_channel_id.as_str(), message_id)
+            .map_err(|e| anyhow!("Error on creating tangle address for message {}. Error: {}", message_id, e))?;
+        let block_id = client.get_block_id(address.to_msg_index()).await?;
+        client.get_milestone_timestamp(block_id.as_str()).await
+    }
+
+    fn store_milestone_timestamp(&self, mut message: DaoMessage, milestone_timestamp: u32) -> Result<()> {
+        message.set_milestone_timestamp(milestone_timestamp as i64);
+        self.message_data_store.write_item_to_db(&message)?;
+        Ok(())
+    }
+}
+
+// CheckedMessages of check_new_messages() that are looked up in the MessageDataStore
+struct CheckedMessagesInDataStore<'a> {
+    message_data_store: &'a MessageDataStore,
+}
+
+impl<'a> CheckedMessagesInDataStore<'a> {
+    fn find_checked(&self, mut conditions: Vec<Condition>) -> Option<String> {
+        Conditions(&mut conditions).add(Some("1".to_string()), "integrity_checked", MatchType::ExactMatch);
+        self.message_data_store.filter(conditions, Some(Limit { limit: 1, offset: 0 }))
+            .ok()
+            .and_then(|(messages, _)| messages.into_iter().next())
+            .map(|message| message.message_id)
+    }
+}
+
+impl<'a> CheckedMessages for CheckedMessagesInDataStore<'a> {
+    fn get_publisher(&self, message_id: &str) -> Option<String> {
+        self.message_data_store.get_item_read_only(&message_id.to_string()).ok()
+            .filter(|message| message.has_link_info())
+            .map(|message| message.publisher)
+    }
+
+    fn find_successor(&self, linked_msg_id: &str) -> Option<String> {
+        let mut conditions = Vec::<Condition>::new();
+        Conditions(&mut conditions).add(Some(linked_msg_id.to_string()), "linked_msg_id", MatchType::ExactMatch);
+        self.find_checked(conditions)
+    }
+
+    fn find_by_sequence(&self, publisher: &str, publisher_sequence: i64) -> Option<String> {
+        let mut conditions = Vec::<Condition>::new();
+        let mut cond = Conditions(&mut conditions);
+        cond.add(Some(publisher.to_string()), "publisher", MatchType::ExactMatch);
+        cond.add(Some(publisher_sequence.to_string()), "publisher_sequence", MatchType::ExactMatch);
+        self.find_checked(conditions)
+    }
+
+    fn mark_checked(&mut self, message: &DaoMessage) -> Result<()> {
+        // The wrapped_binary of the checked message has been dropped
+        let mut stored = self.message_data_store.get_item_read_only(&message.message_id)?;
+        stored.integrity_checked = true;
+        self.message_data_store.write_item_to_db(&stored)?;
+        Ok(())
+    }
+}
+
+// Messages are cached by the MessageIndexer when they are fetched, but their link info
+// is stored by MessageManager::sync(). So a cached message having link info has already been synced.
+fn is_already_synced(message_data_store: &MessageDataStore, msg_id: &str) -> bool {
+    message_data_store.get_item_read_only(&msg_id.to_string())
+        .map(|message| message.has_link_info())
+        .unwrap_or(false)
+}
+
+// Stores the publisher, sequence number and linked message of the streams_msg in the cached message
+fn store_link_info(message_data_store: &MessageDataStore, streams_msg: &StreamsMessage) -> Result<()> {
+    let message_id = hex::encode(streams_msg.address.relative());
+    let mut message = message_data_store.get_item_read_only(&message_id)?;
+    set_link_info(&mut message, streams_msg);
+    message_data_store.write_item_to_db(&message)?;
+    Ok(())
+}
+
+fn set_link_info(message: &mut DaoMessage, streams_msg: &StreamsMessage) {
+    let header = &streams_msg.header;
+    message.set_link_info(
+        get_identifier_str(header.publisher()),
+        header.sequence() as i64,
+        header.linked_msg_address().map(hex::encode).unwrap_or_default(),
+    );
 }
 
 impl<'a, TransT> MessageManager<'a, TransT>
 where
     TransT: for<'b> Transport<'b, Msg = TransportMessage>
 {
+    // Fetches the messages that have been published since the last sync, starting at the cursors
+    // of the Streams client state. Each new message is cached by the MessageIndexer and is
+    // notified via webhook and message feed. Messages that have already been synced before
+    // (e.g. if an outdated Streams client state has been restored) are skipped so that they are
+    // not notified twice. Afterwards the SyncCursor of the channel is updated.
+    //
+    // The expensive integrity check of the whole message chain is only done if new messages
+    // have been received or if the channel has never been checked.
+    //
+    // Returns the number of new messages.
     pub async fn sync(&mut self) -> Result<u32> {
+        let mut sync_cursor = self.get_sync_cursor();
+        let message_data_store = &self.message_data_store;
         let mut messages = self.user.messages();
 
-        let mut num_messages_stored = 0;
-        log::debug!("[fn sync()] Starting to sync addresses for channel {}", self.streams_channel_id);
+        let mut num_new_messages = 0;
+        let mut num_skipped_messages = 0;
+        let mut last_msg_id: Option<String> = None;
+        log::debug!("[fn sync()] Starting to sync addresses for channel {} after message '{}'",
+            self.streams_channel_id,
+            sync_cursor.last_msg_id
+        );
         while let Some(msg) = messages.try_next().await? {
-            num_messages_stored += 1;
-            log::debug!("[fn sync()] Fetched message {} to trigger MessageIndexer message caching", msg.address.relative().to_string());
+            let msg_id = hex::encode(msg.address.relative());
+            if is_already_synced(message_data_store, msg_id.as_str()) {
+                log::debug!("[fn sync()] Skipping already synced message {}", msg_id);
+                num_skipped_messages += 1;
+                continue;
+            }
+            num_new_messages += 1;
+            log::debug!("[fn sync()] Fetched message {} to trigger MessageIndexer message caching", msg_id);
+            if let Err(e) = store_link_info(message_data_store, &msg) {
+                log::warn!("[fn sync()] Could not store link info of message {}. Error: {}", msg_id, e);
+            }
+            if let Some(webhook_client) = &self.webhook_client {
+                if let Some(notification) = NewMessageNotification::new_from_streams_msg(
+                    self.dev_eui.as_str(), &msg, webhook_client.include_decrypted_payload()
+                ) {
+                    if let Err(e) = webhook_client.notify(&notification, &self.webhook_delivery_data_store).await {
+                        log::error!("[fn sync()] {}", e);
+                    }
+                }
+            }
+            if let Some(message_feed) = &self.message_feed {
+                if let Some(event) = MessageFeedEvent::new_from_streams_msg(self.dev_eui.as_str(), &msg) {
+                    message_feed.publish(event);
+                }
+            }
+            last_msg_id = Some(msg_id);
         }
-        log::info!("[fn sync()] Fetched {} messages to trigger MessageIndexer message caching in channel: {}",
-            num_messages_stored,
-            self.streams_channel_id
+        drop(messages);
+        log::info!("[fn sync()] Fetched {} new messages in channel: {}. Skipped {} already synced messages",
+            num_new_messages,
+            self.streams_channel_id,
+            num_skipped_messages,
         );
-        Ok(num_messages_stored)
+        let needs_integrity_check = num_new_messages > 0 || self.integrity_data_store
+            .get_item_read_only(&self.streams_channel_id).is_err();
+        if needs_integrity_check {
+            match self.check_integrity().await {
+                Ok(report) => if !report.is_intact() {
+                    log::warn!("[fn sync()] Found {} integrity issues in channel {}: {} missing links, {} sequence gaps, {} duplicates, {} unexpected publishers",
+                        report.issues_cnt(),
+                        self.streams_channel_id,
+                        report.missing_links.len(),
+                        report.sequence_gaps.len(),
+                        report.duplicates.len(),
+                        report.unexpected_publishers.len(),
+                    );
+                },
+                Err(e) => log::warn!("[fn sync()] Could not check integrity of channel {}. Error: {}", self.streams_channel_id, e),
+            }
+        }
+        if let Some(poi_client) = &self.poi_client {
+            let num_pois_stored = self.sync_proofs_of_inclusion(poi_client).await?;
+            log::info!("[fn sync()] Stored {} new Proofs of Inclusion for channel: {}",
+                num_pois_stored,
+                self.streams_channel_id
+            );
+        } else if let Some(milestone_timestamp_client) = &self.milestone_timestamp_client {
+            let num_timestamps_stored = self.sync_milestone_timestamps(milestone_timestamp_client).await?;
+            log::info!("[fn sync()] Stored {} new milestone timestamps for channel: {}",
+                num_timestamps_stored,
+                self.streams_channel_id
+            );
+        }
+        sync_cursor.set_synced_now(last_msg_id, num_new_messages);
+        self.sync_cursor_data_store.write_item_to_db(&sync_cursor)?;
+        Ok(num_new_messages)
+    }
+
+    // Checks the messages of the channel that have not been checked by previous integrity checks
+    // (see check_new_messages()) and stores the resulting report in the ChannelIntegrityDataStore.
+    // The integrity_checked flag of the cached messages is used as cursor, so the already checked
+    // part of the message chain is not read again.
+    // Messages cached by older versions have no link info, which is backfilled by receiving the
+    // message again. Messages whose link info can not be backfilled are not retried.
+    // Messages deleted by the retention policy of the message cache are not reported as issues.
+    async fn check_integrity(&mut self) -> Result<ChannelIntegrityReport> {
+        let author_publisher = self.user.identifier().map(get_identifier_str).unwrap_or_default();
+        let announcement_msg_id = self.user.stream_address()
+            .map(|address| hex::encode(address.relative()))
+            .unwrap_or_default();
+        let pruned = self.get_pruned_chain_info();
+        let previous_report = self.integrity_data_store.get_item_read_only(&self.streams_channel_id)
+            .and_then(|integrity| integrity.get_report())
+            .ok()
+            .filter(|report| report.can_be_continued() && report.author_publisher == author_publisher);
+        let mut report = match previous_report {
+            Some(report) => report,
+            None => {
+                log::info!("[fn check_integrity()] Checking all messages of channel {}", self.streams_channel_id);
+                // Messages that have been marked as checked for a report that can not be
+                // continued need to be checked again
+                self.reset_integrity_checked_flags()?;
+                ChannelIntegrityReport::new(author_publisher.as_str())
+            }
+        };
+
+        let mut unchecked_conditions = Vec::<Condition>::new();
+        Conditions(&mut unchecked_conditions).add(Some("0".to_string()), "integrity_checked", MatchType::ExactMatch);
+        let order_by = Some(OrderBy::new("timestamp_secs", SortOrder::Ascending));
+        loop {
+            // Checked messages are marked, so the next page always starts at offset 0
+            let (messages, _) = self.message_data_store.filter_ordered(
+                unchecked_conditions.clone(),
+                order_by.clone(),
+                Some(Limit { limit: INTEGRITY_CHECK_PAGE_SIZE, offset: 0 })
+            )?;
+            if messages.is_empty() {
+                break;
+            }
+            let mut linked = Vec::with_capacity(messages.len());
+            for mut message in messages {
+                if !message.has_link_info() && !self.backfill_link_info(&mut message).await {
+                    report.unchecked_messages_cnt += 1;
+                    self.mark_integrity_checked(&mut message)?;
+                    continue;
+                }
+                // The wrapped_binary is not needed to check the chain
+                message.wrapped_binary = Vec::new();
+                linked.push(message);
+            }
+            let mut sorted: Vec<&DaoMessage> = linked.iter().collect();
+            sort_messages(&mut sorted);
+            let mut checked = CheckedMessagesInDataStore {
+                message_data_store: &self.message_data_store,
+            };
+            check_new_messages(&mut report, &sorted, &mut checked, announcement_msg_id.as_str(), &pruned)?;
+        }
+
+        self.integrity_data_store.write_item_to_db(
+            &ChannelIntegrity::new_checked_now(self.streams_channel_id.as_str(), &report)?
+        )?;
+        Ok(report)
+    }
+
+    fn mark_integrity_checked(&self, message: &mut DaoMessage) -> Result<()> {
+        // The wrapped_binary of the stored message must not be overwritten
+        let mut stored = self.message_data_store.get_item_read_only(&message.message_id)?;
+        stored.integrity_checked = true;
+        self.message_data_store.write_item_to_db(&stored)?;
+        message.integrity_checked = true;
+        Ok(())
+    }
+
+    fn reset_integrity_checked_flags(&self) -> Result<()> {
+        let mut checked_conditions = Vec::<Condition>::new();
+        Conditions(&mut checked_conditions).add(Some("1".to_string()), "integrity_checked", MatchType::ExactMatch);
+        loop {
+            let (messages, _) = self.message_data_store.filter(
+                checked_conditions.clone(),
+                Some(Limit { limit: INTEGRITY_CHECK_PAGE_SIZE, offset: 0 })
+            )?;
+            if messages.is_empty() {
+                return Ok(());
+            }
+            for mut message in messages {
+                message.integrity_checked = false;
+                self.message_data_store.write_item_to_db(&message)?;
+            }
+        }
+    }
+
+    fn get_pruned_chain_info(&self) -> PrunedChainInfo {
+        let previous_report = self.integrity_data_store.get_item_read_only(&self.streams_channel_id)
+            .and_then(|integrity| integrity.get_report())
+            .unwrap_or_default();
+        PrunedChainInfo {
+            pruned_before_secs: self.get_sync_cursor().pruned_before_secs,
+            sensor_publisher: previous_report.sensor_publisher,
+        }
+    }
+
+    // Returns false if the link info could not be backfilled
+    async fn backfill_link_info(&mut self, message: &mut DaoMessage) -> bool {
+        let address = match get_tangle_address_from_strings(self.streams_channel_id.as_str(), message.message_id.as_str()) {
+            Ok(address) => address,
+            Err(e) => {
+                log::warn!("[fn backfill_link_info()] Error on creating tangle address for message {}. Error: {}", message.message_id, e);
+                return false;
+            }
+        };
+        match self.user.receive_message(address).await {
+            Ok(streams_msg) => {
+                set_link_info(message, &streams_msg);
+                if let Err(e) = self.message_data_store.write_item_to_db(message) {
+                    log::warn!("[fn backfill_link_info()] Could not store link info of message {}. Error: {}", message.message_id, e);
+                }
+                true
+            },
+            Err(e) => {
+                log::warn!("[fn backfill_link_info()] Could not receive message {}. The message will not be checked. Error: {}", message.message_id, e);
+                false
+            }
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,289 @@
+use std::fmt;
+
+use anyhow::Result;
+
+use crate::{
+    dao_helpers::{
+        DbFileBasedDaoManagerOptions,
+        Limit,
+        SortOrder,
+    },
+    user_manager::dao::{
+        message::{
+            Message,
+            MessageDataStore,
+            MessageDataStoreOptions,
+            MessageTimeFilter,
+        },
+        poi::PoiDataStore,
+        channel_integrity::ChannelIntegrityDataStore,
+        sync_cursor::{
+            SyncCursor,
+            SyncCursorDataStore,
+        },
+    },
+};
+
+const PRUNE_PAGE_SIZE: usize = 1000;
+
+pub const SECS_PER_DAY: u64 = 24 * 3600;
+// Retention periods specified in months are converted using 30 days per month
+pub const SECS_PER_MONTH: u64 = 30 * SECS_PER_DAY;
+
+// Retention policy for the messages cached in the message_{channel_id} tables of the
+// message cache database (see MessageIndexerOptions.message_data_store).
+//
+// message_retention_secs:  Cached messages having a timestamp_secs older than this are deleted
+//                          together with their Proof of Inclusion, so that they are not listed
+//                          by the Message Explorer anymore. Messages of the channel author
+//                          (keyloads) are kept because they are needed to check the integrity
+//                          of the channel. Channels that have never been checked are not pruned.
+// binary_retention_secs:   The wrapped_binary of cached messages older than this is dropped if a
+//                          Proof of Inclusion has been stored for the message. The binary is
+//                          fetched again from the inx-collector if the message is requested.
+//
+// Messages cached by older versions have no timestamps and are never pruned.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageRetentionPolicy {
+    pub message_retention_secs: Option<u64>,
+    pub binary_retention_secs: Option<u64>,
+}
+
+impl MessageRetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.message_retention_secs.is_some() || self.binary_retention_secs.is_some()
+    }
+}
+
+impl fmt::Display for MessageRetentionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = |secs: Option<u64>| secs.map_or("unlimited".to_string(), |secs| format!("{} days", secs / SECS_PER_DAY));
+        write!(f, "MessageRetentionPolicy: messages: {}, wrapped binaries: {}",
+               days(self.message_retention_secs),
+               days(self.binary_retention_secs),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PruneResult {
+    pub deleted_messages_cnt: usize,
+    pub deleted_pois_cnt: usize,
+    pub dropped_binaries_cnt: usize,
+}
+
+impl PruneResult {
+    pub fn has_changes(&self) -> bool {
+        self.deleted_messages_cnt > 0 || self.deleted_pois_cnt > 0 || self.dropped_binaries_cnt > 0
+    }
+
+    pub fn add(&mut self, other: &PruneResult) {
+        self.deleted_messages_cnt += other.deleted_messages_cnt;
+        self.deleted_pois_cnt += other.deleted_pois_cnt;
+        self.dropped_binaries_cnt += other.dropped_binaries_cnt;
+    }
+}
+
+impl fmt::Display for PruneResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} deleted messages, {} deleted Proofs of Inclusion, {} dropped wrapped binaries",
+               self.deleted_messages_cnt,
+               self.deleted_pois_cnt,
+               self.dropped_binaries_cnt,
+        )
+    }
+}
+
+// Applies the policy to the cached messages of the channel.
+// The channel must not be synchronized while it is pruned, otherwise the SyncCursor
+// updated here could be overwritten by the sync.
+pub fn prune_channel_messages(db_file_name: &str, channel_id: &str, policy: &MessageRetentionPolicy, now_secs: i64) -> Result<PruneResult> {
+    let mut ret_val = PruneResult::default();
+    let db_file_opt = DbFileBasedDaoManagerOptions {
+        file_path_and_name: db_file_name.to_string(),
+    };
+    let msg_data_store_opt = MessageDataStoreOptions {
+        file_path_and_name: db_file_name.to_string(),
+        streams_channel_id: channel_id.to_string(),
+    };
+    let message_data_store = MessageDataStore::new(msg_data_store_opt.clone());
+    let poi_data_store = PoiDataStore::new(msg_data_store_opt);
+
+    if let Some(message_retention_secs) = policy.message_retention_secs {
+        let author_publisher = ChannelIntegrityDataStore::new(db_file_opt.clone())
+            .get_item_read_only(&channel_id.to_string())
+            .and_then(|integrity| integrity.get_report())
+            .map(|report| report.author_publisher)
+            .unwrap_or_default();
+        if author_publisher.is_empty() {
+            log::debug!("[fn prune_channel_messages()] Integrity of channel {} has not been checked yet. Messages will not be deleted.", channel_id);
+        } else {
+            let pruned_before_secs = now_secs - message_retention_secs as i64;
+            let (deleted_messages_cnt, deleted_pois_cnt) = delete_expired_messages(
+                &message_data_store, &poi_data_store, author_publisher.as_str(), pruned_before_secs)?;
+            if deleted_messages_cnt > 0 {
+                let sync_cursor_data_store = SyncCursorDataStore::new(db_file_opt);
+                let mut sync_cursor = sync_cursor_data_store.get_item_read_only(&channel_id.to_string())
+                    .unwrap_or_else(|_| SyncCursor::new(channel_id));
+                sync_cursor.set_pruned_before(pruned_before_secs);
+                sync_cursor_data_store.write_item_to_db(&sync_cursor)?;
+            }
+            ret_val.deleted_messages_cnt = deleted_messages_cnt;
+            ret_val.deleted_pois_cnt = deleted_pois_cnt;
+        }
+    }
+
+    if let Some(binary_retention_secs) = policy.binary_retention_secs {
+        ret_val.dropped_binaries_cnt = drop_expired_binaries(
+            &message_data_store, &poi_data_store, now_secs - binary_retention_secs as i64)?;
+    }
+    Ok(ret_val)
+}
+
+// Returns the messages having a timestamp_secs between 1 and older_than_secs
+fn get_expired_messages_page(message_data_store: &MessageDataStore, older_than_secs: i64, offset: usize) -> Result<Vec<Message>> {
+    let time_filter = MessageTimeFilter {
+        from_secs: Some(1),
+        to_secs: Some(older_than_secs - 1),
+        sort_order: Some(SortOrder::Ascending),
+    };
+    let (messages, _) = message_data_store.filter_ordered(
+        time_filter.get_conditions(),
+        time_filter.get_order_by(),
+        Some(Limit { limit: PRUNE_PAGE_SIZE, offset })
+    )?;
+    Ok(messages)
+}
+
+// Returns the number of deleted messages and Proofs of Inclusion
+fn delete_expired_messages(message_data_store: &MessageDataStore, poi_data_store: &PoiDataStore, author_publisher: &str, older_than_secs: i64) -> Result<(usize, usize)> {
+    // The ids are collected first as deleting messages while paging would skip messages
+    let mut expired_msg_ids = Vec::<String>::new();
+    let mut offset = 0;
+    loop {
+        let messages = get_expired_messages_page(message_data_store, older_than_secs, offset)?;
+        if messages.is_empty() {
+            break;
+        }
+        offset += messages.len();
+        expired_msg_ids.extend(messages.into_iter()
+            .filter(|msg| msg.publisher != author_publisher)
+            .map(|msg| msg.message_id)
+        );
+    }
+
+    let mut deleted_pois_cnt = 0;
+    for message_id in expired_msg_ids.iter() {
+        if poi_data_store.get_item_read_only(message_id).is_ok() {
+            poi_data_store.delete_item_in_db(message_id)?;
+            deleted_pois_cnt += 1;
+        }
+        message_data_store.delete_item_in_db(message_id)?;
+    }
+    Ok((expired_msg_ids.len(), deleted_pois_cnt))
+}
+
+// Returns the number of dropped wrapped binaries
+fn drop_expired_binaries(message_data_store: &MessageDataStore, poi_data_store: &PoiDataStore, older_than_secs: i64) -> Result<usize> {
+    let mut ret_val = 0;
+    let mut offset = 0;
+    loop {
+        let messages = get_expired_messages_page(message_data_store, older_than_secs, offset)?;
+        if messages.is_empty() {
+            break;
+        }
+        offset += messages.len();
+        for mut message in messages {
+            if message.wrapped_binary.is_empty() || poi_data_store.get_item_read_only(&message.message_id).is_err() {
+                continue;
+            }
+            message.wrapped_binary = Vec::new();
+            message_data_store.write_item_to_db(&message)?;
+            ret_val += 1;
+        }
+    }
+    Ok(ret_val)
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::message_retention::tests --features dao
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_manager::dao::poi::Poi;
+
+    const AUTHOR: &str = "aaaa";
+    const SENSOR: &str = "bbbb";
+
+    fn get_data_stores() -> (MessageDataStore, PoiDataStore) {
+        let msg_data_store_opt = MessageDataStoreOptions {
+            file_path_and_name: ":memory:".to_string(),
+            streams_channel_id: "a1b2c3".to_string(),
+        };
+        (MessageDataStore::new(msg_data_store_opt.clone()), PoiDataStore::new(msg_data_store_opt))
+    }
+
+    fn write_message(message_data_store: &MessageDataStore, message_id: &str, publisher: &str, timestamp_secs: i64) {
+        let mut message = Message {
+            message_id: message_id.to_string(),
+            wrapped_binary: vec![1, 2, 3],
+            timestamp_secs,
+            received_at_secs: timestamp_secs,
+            ..Default::default()
+        };
+        message.set_link_info(publisher.to_string(), 2, String::new());
+        message_data_store.write_item_to_db(&message).unwrap();
+    }
+
+    fn write_poi(poi_data_store: &PoiDataStore, message_id: &str) {
+        poi_data_store.write_item_to_db(&Poi {
+            message_id: message_id.to_string(),
+            ..Default::default()
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_delete_expired_messages() {
+        let (message_data_store, poi_data_store) = get_data_stores();
+        write_message(&message_data_store, "keyload", AUTHOR, 100);
+        write_message(&message_data_store, "msg-1", SENSOR, 200);
+        write_message(&message_data_store, "msg-2", SENSOR, 300);
+        write_message(&message_data_store, "msg-3", SENSOR, 400);
+        // Cached by an older version
+        write_message(&message_data_store, "msg-0", SENSOR, 0);
+        write_poi(&poi_data_store, "msg-1");
+        write_poi(&poi_data_store, "msg-3");
+
+        let deleted = delete_expired_messages(&message_data_store, &poi_data_store, AUTHOR, 350).unwrap();
+        assert_eq!(deleted, (2, 1));
+        let (messages, _) = message_data_store.find_all("", None).unwrap();
+        let mut message_ids: Vec<String> = messages.into_iter().map(|msg| msg.message_id).collect();
+        message_ids.sort();
+        assert_eq!(message_ids, vec!["keyload", "msg-0", "msg-3"]);
+        assert!(poi_data_store.get_item_read_only(&"msg-1".to_string()).is_err());
+        assert!(poi_data_store.get_item_read_only(&"msg-3".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_drop_expired_binaries() {
+        let (message_data_store, poi_data_store) = get_data_stores();
+        write_message(&message_data_store, "msg-1", SENSOR, 200);
+        write_message(&message_data_store, "msg-2", SENSOR, 300);
+        write_message(&message_data_store, "msg-3", SENSOR, 400);
+        write_poi(&poi_data_store, "msg-1");
+        write_poi(&poi_data_store, "msg-3");
+
+        assert_eq!(drop_expired_binaries(&message_data_store, &poi_data_store, 350).unwrap(), 1);
+        // Already dropped binaries are not counted again
+        assert_eq!(drop_expired_binaries(&message_data_store, &poi_data_store, 350).unwrap(), 0);
+        let get_binary_len = |message_id: &str| message_data_store.get_item_read_only(&message_id.to_string())
+            .unwrap()
+            .wrapped_binary
+            .len();
+        assert_eq!(get_binary_len("msg-1"), 0);
+        assert_eq!(get_binary_len("msg-2"), 3);
+        assert_eq!(get_binary_len("msg-3"), 3);
+        assert!(message_data_store.get_item_read_only(&"msg-1".to_string()).unwrap().has_link_info());
+    }
+}
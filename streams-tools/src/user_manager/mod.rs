@@ -9,6 +9,18 @@ pub mod multi_channel_management;
 pub mod message_manager;
 #[cfg(feature = "std")]
 pub(crate) mod message_indexer;
+#[cfg(feature = "dao")]
+pub mod proof_of_inclusion;
+#[cfg(feature = "dao")]
+pub mod webhook;
+#[cfg(feature = "dao")]
+pub mod message_feed;
+#[cfg(feature = "dao")]
+pub mod message_integrity;
+#[cfg(feature = "dao")]
+pub mod message_retention;
+#[cfg(feature = "dao")]
+pub mod backup;
 
 #[cfg(feature = "dao")]
 pub mod dao;
@@ -25,6 +37,7 @@ pub use {
     channel_manager::{
         ChannelManager,
         ChannelManagerPlainTextWallet,
+        ChannelManagerMultiChannelWallet,
     },
     subscriber_manager::{
         SubscriberManagerPlainTextWallet
@@ -9,6 +9,7 @@ use std::{
 
 use anyhow::{
     Result,
+    anyhow,
     bail,
 };
 
@@ -20,16 +21,31 @@ use crate::{
     helpers::{
         SerializationCallbackRefToClosureString
     },
-    PlainTextWallet,
     UserDataStore,
+    wallet::{
+        MultiChannelWallet,
+        MultiChannelWalletType,
+    },
     user_manager::dao::{
         User,
-        message::MessageDataStoreOptions,
+        message::{
+            MessageDataStoreOptions,
+            MessageDaoManager,
+        },
+        poi::PoiDaoManager,
+        user::UserDaoManager,
+        channel_integrity::ChannelIntegrityDaoManager,
+        sync_cursor::SyncCursorDaoManager,
     },
     dao_helpers::{
+        DaoManager,
+        DbConnection,
+        DbFileBasedDaoManagerOptions,
         Condition,
         Conditions,
-        MatchType
+        MatchType,
+        drop_table_if_exists,
+        delete_item_if_table_exists,
     }
 };
 
@@ -37,7 +53,8 @@ use crate::{
 pub struct MultiChannelManagerOptions {
     pub iota_node: String,
     pub wallet_filename: String,
-    //TODO: Needs to be managed by stronghold
+    pub wallet_type: MultiChannelWalletType,
+    // Used to encrypt the serialized Streams Client States (User.streams_client_state)
     pub streams_user_serialization_password: String,
     pub message_data_store_for_msg_caching: Option<MessageDataStoreOptions>,
     pub inx_collector_access_throttle_sleep_time_millisecs: Option<u64>,
@@ -91,7 +108,109 @@ async fn manage_existing_users_with_conflicting_external_id(user_store: &UserDat
     Ok(())
 }
 
-pub async fn get_initial_channel_manager<'a>(user_store: &UserDataStore, options: &MultiChannelManagerOptions, external_user_id: Option<String>) -> Result<ChannelManager<PlainTextWallet>> {
+// Returns the user having exactly the specified external_id if it exists.
+pub fn get_user_for_external_id(user_store: &UserDataStore, external_id: &str) -> Result<Option<User>> {
+    let exact_match_conditions = create_conditions_for_external_id_filter(external_id, MatchType::ExactMatch);
+    let (exact_match_users, _) = user_store.filter(exact_match_conditions, None)?;
+    Ok(exact_match_users.into_iter().next())
+}
+
+// Returns the user that has been replaced most recently by a new user with the same external_id.
+// Following the external_id disambiguation described above, this is the user having the
+// external_id "EXTERNAL_ID_VALUE-(###)" with the highest ### integer counter.
+pub fn get_predecessor_user_for_external_id(user_store: &UserDataStore, external_id: &str) -> Result<Option<User>> {
+    let search_string = external_id.to_string() + "-(";
+    let disambiguated_users_cond = create_conditions_for_external_id_filter(search_string.as_str(), MatchType::StartsWith);
+    let (disambiguated_users, _) = user_store.filter(disambiguated_users_cond, None)?;
+    let ret_val = disambiguated_users.into_iter()
+        .filter_map(|user| {
+            user.external_id[search_string.len()..]
+                .strip_suffix(")")
+                .and_then(|counter| counter.parse::<usize>().ok())
+                .map(|counter| (counter, user))
+        })
+        .max_by_key(|(counter, _)| *counter)
+        .map(|(_, user)| user);
+    Ok(ret_val)
+}
+
+// Sets the status of the channel to retired. If a successor_channel_id is specified,
+// the successor channel must exist.
+pub fn retire_channel(user_store: &UserDataStore, channel_id: &str, successor_channel_id: Option<&str>) -> Result<User> {
+    let mut user = user_store.get_item_read_only(&channel_id.to_string())
+        .map_err(|_| anyhow!("Channel {} does not exist", channel_id))?;
+    if let Some(successor) = successor_channel_id {
+        if successor == channel_id {
+            bail!("Channel {} can not be its own successor", channel_id);
+        }
+        user_store.get_item_read_only(&successor.to_string())
+            .map_err(|_| anyhow!("Successor channel {} does not exist", successor))?;
+    }
+    user.retire(successor_channel_id);
+    user_store.write_item_to_db(&user)?;
+    log::info!("[fn retire_channel()] Channel {} is now {}. Successor channel: '{}'", channel_id, user.status, user.successor_channel_id);
+    Ok(user)
+}
+
+// Sets the status of the channel to archived. Archived channels are not synchronized by the
+// Message Explorer anymore.
+pub fn archive_channel(user_store: &UserDataStore, channel_id: &str) -> Result<User> {
+    let mut user = user_store.get_item_read_only(&channel_id.to_string())
+        .map_err(|_| anyhow!("Channel {} does not exist", channel_id))?;
+    user.archive();
+    user_store.write_item_to_db(&user)?;
+    log::info!("[fn archive_channel()] Channel {} is now {}", channel_id, user.status);
+    Ok(user)
+}
+
+// Deletes the user of the channel and the cached messages, proofs of inclusion, integrity
+// data and sync cursor of the channel stored in the database message_db_file_path_and_name.
+// Everything is deleted in one transaction, starting with the user. As the streams_client_state
+// of the channel is lost, only channels that are not active can be deleted.
+pub fn delete_channel(user_store: &UserDataStore, channel_id: &str, message_db_file_path_and_name: &str) -> Result<()> {
+    let user = user_store.get_item_read_only(&channel_id.to_string())
+        .map_err(|_| anyhow!("Channel {} does not exist", channel_id))?;
+    if user.is_active() {
+        bail!("Channel {} is active. Only retired or archived channels can be deleted", channel_id);
+    }
+    let msg_data_store_opt = MessageDataStoreOptions {
+        file_path_and_name: message_db_file_path_and_name.to_string(),
+        streams_channel_id: channel_id.to_string(),
+    };
+    let db_file_opt = DbFileBasedDaoManagerOptions {
+        file_path_and_name: message_db_file_path_and_name.to_string(),
+    };
+    let users = UserDaoManager::new(db_file_opt.clone());
+    let messages = MessageDaoManager::new(msg_data_store_opt.clone());
+    let pois = PoiDaoManager::new(msg_data_store_opt);
+    let channel_integrities = ChannelIntegrityDaoManager::new(db_file_opt.clone());
+    let sync_cursors = SyncCursorDaoManager::new(db_file_opt);
+    // All statements need to be executed using the same connection to be part of the transaction
+    let connection = DbConnection::open(message_db_file_path_and_name)?;
+    connection.run_in_transaction(|| {
+        delete_item_if_table_exists(&connection, &users, channel_id)?;
+        drop_table_if_exists(&connection, &messages)?;
+        drop_table_if_exists(&connection, &pois)?;
+        delete_item_if_table_exists(&connection, &channel_integrities, channel_id)?;
+        delete_item_if_table_exists(&connection, &sync_cursors, channel_id)
+    })?;
+    log::info!("[fn delete_channel()] Deleted channel {}", channel_id);
+    Ok(())
+}
+
+// Retires the predecessor user (see get_predecessor_user_for_external_id()) of a new channel
+// having the external_id and links it to the new channel. Predecessors that already have a
+// successor channel are not changed.
+pub fn retire_predecessor_channel(user_store: &UserDataStore, external_id: &str, successor_channel_id: &str) -> Result<Option<User>> {
+    match get_predecessor_user_for_external_id(user_store, external_id)? {
+        Some(predecessor) if predecessor.successor_channel_id.is_empty() => {
+            retire_channel(user_store, predecessor.streams_channel_id.as_str(), Some(successor_channel_id)).map(Some)
+        },
+        _ => Ok(None),
+    }
+}
+
+pub async fn get_initial_channel_manager<'a>(user_store: &UserDataStore, options: &MultiChannelManagerOptions, external_user_id: Option<String>) -> Result<ChannelManager<MultiChannelWallet>> {
     let mut new_opt = ChannelManagerOptions::default();
     new_opt.throttle_sleep_time_millisecs = options.inx_collector_access_throttle_sleep_time_millisecs;
     let wallet = get_wallet(options, None)?;
@@ -100,7 +219,7 @@ pub async fn get_initial_channel_manager<'a>(user_store: &UserDataStore, options
         manage_existing_users_with_conflicting_external_id(user_store,external_id.as_str()).await?;
         initial_user_having_seed_derivation_phrase.external_id = external_id;
     }
-    initial_user_having_seed_derivation_phrase.seed_derivation_phrase = wallet.seed_derivation_phrase.as_ref().unwrap().clone();
+    initial_user_having_seed_derivation_phrase.seed_derivation_phrase = wallet.get_seed_derivation_phrase().unwrap().clone();
     new_opt.serialize_streams_client_state_callback = Some(
         user_store.get_serialization_callback(&initial_user_having_seed_derivation_phrase)
     );
@@ -112,7 +231,7 @@ pub async fn get_initial_channel_manager<'a>(user_store: &UserDataStore, options
     ).await)
 }
 
-pub async fn get_channel_manager_for_channel_starts_with(channel_starts_with: &str, user_store: &mut UserDataStore, options: &MultiChannelManagerOptions, update_user_on_exit: bool) -> Result<ChannelManager<PlainTextWallet>> {
+pub async fn get_channel_manager_for_channel_starts_with(channel_starts_with: &str, user_store: &mut UserDataStore, options: &MultiChannelManagerOptions, update_user_on_exit: bool) -> Result<ChannelManager<MultiChannelWallet>> {
     if let Ok((user_dao, serialize_streams_client_state_callback)) = user_store.search_item(channel_starts_with) {
         let wallet = get_wallet(options, Some(&user_dao))?;
         let client_state_callback = if update_user_on_exit {
@@ -133,7 +252,7 @@ pub async fn get_channel_manager_for_channel_starts_with(channel_starts_with: &s
     }
 }
 
-pub async fn get_channel_manager_for_channel_id<'a>(channel_id: &str, user_store: &UserDataStore, options: &MultiChannelManagerOptions) -> Result<ChannelManager<PlainTextWallet>> {
+pub async fn get_channel_manager_for_channel_id<'a>(channel_id: &str, user_store: &UserDataStore, options: &MultiChannelManagerOptions) -> Result<ChannelManager<MultiChannelWallet>> {
     let (user_dao, serialize_streams_client_state_callback) =
         user_store.get_item(&channel_id.to_string())?;
     let wallet = get_wallet(options, Some(&user_dao))?;
@@ -147,7 +266,23 @@ pub async fn get_channel_manager_for_channel_id<'a>(channel_id: &str, user_store
     ).await
 }
 
-pub async fn get_channel_manager_for_external_id<'a>(external_id: &str, user_store: &UserDataStore, options: &MultiChannelManagerOptions) -> Result<ChannelManager<PlainTextWallet>> {
+// Same as get_channel_manager_for_channel_id() but the Streams client state is not stored
+// when the returned ChannelManager is dropped. Changes of the client state made by the
+// ChannelManager, e.g. by receiving messages, are lost.
+pub async fn get_read_only_channel_manager_for_channel_id<'a>(channel_id: &str, user_store: &UserDataStore, options: &MultiChannelManagerOptions) -> Result<ChannelManager<MultiChannelWallet>> {
+    let user_dao = user_store.get_item_read_only(&channel_id.to_string())?;
+    let wallet = get_wallet(options, Some(&user_dao))?;
+    get_channel_manager_by_user_dao(
+        user_dao,
+        wallet,
+        options.iota_node.as_str(),
+        None,
+        options.message_data_store_for_msg_caching.clone(),
+        options.inx_collector_access_throttle_sleep_time_millisecs,
+    ).await
+}
+
+pub async fn get_channel_manager_for_external_id<'a>(external_id: &str, user_store: &UserDataStore, options: &MultiChannelManagerOptions) -> Result<ChannelManager<MultiChannelWallet>> {
     let conditions_buffer = create_conditions_for_external_id_filter(external_id, MatchType::ExactMatch);
     if let Some(user) = user_store.get_first_filtered_item(conditions_buffer) {
         get_channel_manager_for_channel_id(user.streams_channel_id.as_str(), user_store, options).await
@@ -156,6 +291,63 @@ pub async fn get_channel_manager_for_external_id<'a>(external_id: &str, user_sto
     }
 }
 
+// Re-encrypts the streams_client_state of the users using the new_serialization_password.
+// The currently used password is taken from the options.
+// Users without streams_client_state are returned unchanged.
+pub async fn reencrypt_streams_client_states(users: Vec<User>, options: &MultiChannelManagerOptions, new_serialization_password: &str) -> Result<Vec<User>> {
+    let mut ret_val = Vec::<User>::with_capacity(users.len());
+    for user in users {
+        if user.streams_client_state.is_empty() {
+            ret_val.push(user);
+            continue;
+        }
+        let wallet = get_wallet(options, Some(&user))?;
+        let mut channel_manager = get_channel_manager_by_user_dao(
+            user.clone(),
+            wallet,
+            options.iota_node.as_str(),
+            None,
+            None,
+            options.inx_collector_access_throttle_sleep_time_millisecs,
+        ).await?;
+        if channel_manager.user.is_none() {
+            bail!("Could not decrypt the Streams Client State of channel {}", user.streams_channel_id);
+        }
+        let mut reencrypted_user = user;
+        reencrypted_user.streams_client_state = channel_manager.backup_streams_client_state(new_serialization_password).await?;
+        ret_val.push(reencrypted_user);
+    }
+    Ok(ret_val)
+}
+
+// Returns all users having a non empty streams_client_state. In contrast to find_all() the
+// number of returned users is not limited.
+pub fn find_users_having_streams_client_state(user_store: &UserDataStore) -> Result<Vec<User>> {
+    Ok(user_store.find_all_unlimited()?
+        .into_iter()
+        .filter(|user| !user.streams_client_state.is_empty())
+        .collect())
+}
+
+// Re-encrypts the streams_client_state of all users stored in the user_store using the
+// new_serialization_password. The currently used password is taken from the options.
+// All Streams Client States are decrypted before any user is written to the database, so that
+// a wrong current password (which lets the decryption fail) does not result in a partially
+// rotated database. All users are written in one transaction.
+// Returns the number of re-encrypted Streams Client States.
+pub async fn rotate_streams_client_state_password(user_store: &UserDataStore, options: &MultiChannelManagerOptions, new_serialization_password: &str) -> Result<usize> {
+    let users = find_users_having_streams_client_state(user_store)?;
+    let rotated_users = reencrypt_streams_client_states(users, options, new_serialization_password).await?;
+    user_store.run_in_transaction(|| {
+        for user in rotated_users.iter() {
+            user_store.write_item_to_db(user)?;
+            log::debug!("[fn rotate_streams_client_state_password()] Re-encrypted Streams Client State of channel {}", user.streams_channel_id);
+        }
+        Ok(())
+    })?;
+    Ok(rotated_users.len())
+}
+
 fn create_conditions_for_external_id_filter(external_id: &str, match_type: MatchType) -> Vec<Condition> {
     let mut conditions_buffer = Vec::<Condition>::new();
     let mut conditions = Conditions(&mut conditions_buffer);
@@ -165,12 +357,12 @@ fn create_conditions_for_external_id_filter(external_id: &str, match_type: Match
 
 async fn get_channel_manager_by_user_dao(
     user_dao: User,
-    wallet: PlainTextWallet,
+    wallet: MultiChannelWallet,
     node: &str,
     serialize_streams_client_state_callback: Option<SerializationCallbackRefToClosureString>,
     message_data_store_for_msg_caching: Option<MessageDataStoreOptions>,
     throttle_sleep_time_millisecs: Option<u64>
-) -> Result<ChannelManager<PlainTextWallet>>{
+) -> Result<ChannelManager<MultiChannelWallet>>{
     let mut new_opt = ChannelManagerOptions::default();
     new_opt.throttle_sleep_time_millisecs = throttle_sleep_time_millisecs;
     new_opt.streams_client_state = Some(user_dao.streams_client_state.clone());
@@ -183,7 +375,7 @@ async fn get_channel_manager_by_user_dao(
     ).await)
 }
 
-fn get_wallet(options: &MultiChannelManagerOptions, user_dao: Option<&User>) -> Result<PlainTextWallet>{
+fn get_wallet(options: &MultiChannelManagerOptions, user_dao: Option<&User>) -> Result<MultiChannelWallet>{
     let seed_derivation_phrase = if let Some(user) = user_dao {
         user.seed_derivation_phrase.clone()
     } else {
@@ -192,9 +384,40 @@ fn get_wallet(options: &MultiChannelManagerOptions, user_dao: Option<&User>) ->
         format!("{}", hasher.finish())
     };
 
-    Ok(PlainTextWallet::new(
+    MultiChannelWallet::new(
+        &options.wallet_type,
         options.streams_user_serialization_password.as_str(),
-        Some(options.wallet_filename.as_str()),
+        options.wallet_filename.as_str(),
         Some(seed_derivation_phrase),
-    ))
-}
\ No newline at end of file
+    )
+}
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::multi_channel_management::tests  --features dao
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_users_having_streams_client_state() {
+        let user_store = UserDataStore::new(DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() });
+        let users_cnt = 1001;
+        for idx in 0..users_cnt {
+            user_store.write_item_to_db(&User {
+                streams_channel_id: format!("channel-{:05}", idx),
+                streams_client_state: vec![1, 2, 3],
+                seed_derivation_phrase: "phrase".to_string(),
+                ..Default::default()
+            }).unwrap();
+        }
+        user_store.write_item_to_db(&User {
+            streams_channel_id: "channel-without-state".to_string(),
+            seed_derivation_phrase: "phrase".to_string(),
+            ..Default::default()
+        }).unwrap();
+
+        let users = find_users_having_streams_client_state(&user_store).unwrap();
+        assert_eq!(users.len(), users_cnt);
+        assert!(users.iter().all(|user| user.streams_client_state == vec![1, 2, 3]));
+    }
+}
@@ -0,0 +1,381 @@
+#![allow(non_snake_case)]
+
+use std::fmt;
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use serde_json::Value as JsonValue;
+
+use hyper::{
+    Body,
+    Client as HyperClient,
+    client::HttpConnector,
+};
+
+use crypto::hashes::{
+    blake2b::Blake2b256,
+    Digest,
+};
+
+use crate::{
+    helpers::get_iota_node_url,
+    http::http_tools::{
+        RequestBuilderTools,
+        get_string_from_response_body,
+    },
+    streams_transport::streams_transport::{
+        STREAMS_TOOLS_CONST_INX_COLLECTOR_PORT,
+        STREAMS_TOOLS_CONST_INX_POI_PORT,
+    },
+};
+
+use super::message_indexer::MessageIndexer;
+
+// Hash prefixes used by inx-poi to distinguish leaf hashes from node hashes
+const LEAF_HASH_PREFIX: u8 = 0;
+const NODE_HASH_PREFIX: u8 = 1;
+
+pub type MerkleHash = [u8; 32];
+
+fn decode_prefixed_hex(hex_str: &str) -> Result<Vec<u8>> {
+    hex::decode(hex_str.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Error on hex decoding '{}'. Error: {}", hex_str, e))
+}
+
+fn decode_merkle_hash(hex_str: &str) -> Result<MerkleHash> {
+    let bytes = decode_prefixed_hex(hex_str)?;
+    if bytes.len() != 32 {
+        bail!("Merkle hash '{}' has length {} but 32 bytes are expected", hex_str, bytes.len())
+    }
+    let mut ret_val: MerkleHash = [0; 32];
+    ret_val.copy_from_slice(bytes.as_slice());
+    Ok(ret_val)
+}
+
+// A node of the Merkle proof as been serialized by inx-poi:
+// * {"l": ..., "r": ...}   Inner node having a left and right child
+// * {"h": "0x..."}         Already hashed sub tree
+// * {"value": "0x..."}     Leaf containing the block id that is proven to be included
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum MerkleProofNode {
+    Node {
+        l: Box<MerkleProofNode>,
+        r: Box<MerkleProofNode>,
+    },
+    Hash {
+        h: String,
+    },
+    Value {
+        value: String,
+    },
+}
+
+impl MerkleProofNode {
+    pub fn hash(&self) -> Result<MerkleHash> {
+        let mut hasher = Blake2b256::new();
+        match self {
+            MerkleProofNode::Node { l, r } => {
+                hasher.update([NODE_HASH_PREFIX]);
+                hasher.update(l.hash()?);
+                hasher.update(r.hash()?);
+            },
+            MerkleProofNode::Hash { h } => {
+                return decode_merkle_hash(h.as_str());
+            },
+            MerkleProofNode::Value { value } => {
+                hasher.update([LEAF_HASH_PREFIX]);
+                hasher.update(decode_prefixed_hex(value.as_str())?);
+            },
+        }
+        let mut ret_val: MerkleHash = [0; 32];
+        ret_val.copy_from_slice(hasher.finalize().as_slice());
+        Ok(ret_val)
+    }
+
+    pub fn contains_value(&self, value_bytes: &[u8]) -> bool {
+        match self {
+            MerkleProofNode::Node { l, r } => l.contains_value(value_bytes) || r.contains_value(value_bytes),
+            MerkleProofNode::Hash { .. } => false,
+            MerkleProofNode::Value { value } => {
+                decode_prefixed_hex(value.as_str())
+                    .map(|bytes| bytes.as_slice() == value_bytes)
+                    .unwrap_or(false)
+            },
+        }
+    }
+}
+
+// Proof of Inclusion as been created by the 'create' endpoint of inx-poi.
+// The same JSON document can be used with the 'validate' endpoint of inx-poi.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProofOfInclusion {
+    pub milestone: JsonValue,
+    pub block: JsonValue,
+    pub proof: MerkleProofNode,
+}
+
+impl ProofOfInclusion {
+    pub fn from_json_str(json_str: &str) -> Result<Self> {
+        serde_json::from_str(json_str)
+            .map_err(|e| anyhow!("Error on parsing ProofOfInclusion from JSON. Error: {}", e))
+    }
+
+    pub fn to_json_string(&self) -> Result<String> {
+        serde_json::to_string(self)
+            .map_err(|e| anyhow!("Error on serializing ProofOfInclusion to JSON. Error: {}", e))
+    }
+
+    pub fn milestone_index(&self) -> u32 {
+        self.milestone["index"].as_u64().unwrap_or(0) as u32
+    }
+
+    pub fn milestone_timestamp(&self) -> u32 {
+        self.milestone["timestamp"].as_u64().unwrap_or(0) as u32
+    }
+
+    pub fn inclusion_merkle_root(&self) -> Result<MerkleHash> {
+        if let Some(root_hex_str) = self.milestone["inclusionMerkleRoot"].as_str() {
+            decode_merkle_hash(root_hex_str)
+        } else {
+            bail!("The milestone of the ProofOfInclusion does not contain an 'inclusionMerkleRoot'")
+        }
+    }
+
+    // Verifies locally that the Merkle proof contains the specified block_id and that the
+    // proof hashes to the 'inclusionMerkleRoot' of the milestone.
+    // The milestone signatures are not verified. Use the 'validate' endpoint of inx-poi
+    // to validate the milestone against the tangle.
+    pub fn verify(&self, block_id: &str) -> Result<bool> {
+        let block_id_bytes = decode_prefixed_hex(block_id)?;
+        if !self.proof.contains_value(block_id_bytes.as_slice()) {
+            log::debug!("[fn verify()] Proof does not contain block_id {}", block_id);
+            return Ok(false);
+        }
+        Ok(self.proof.hash()? == self.inclusion_merkle_root()?)
+    }
+}
+
+// node_api_url is the url of the core REST api of the IOTA node, which is used to fetch
+// the milestone timestamp of a block without creating a Proof of Inclusion.
+#[derive(Clone)]
+pub struct PoiClientOptions {
+    pub inx_collector_url: String,
+    pub poi_url: String,
+    pub node_api_url: String,
+}
+
+impl PoiClientOptions {
+    pub fn new(iota_node: &str) -> Self {
+        Self {
+            inx_collector_url: format!("http://{}:{}", iota_node, STREAMS_TOOLS_CONST_INX_COLLECTOR_PORT),
+            poi_url: format!("http://{}:{}{}", iota_node, STREAMS_TOOLS_CONST_INX_POI_PORT, EndpointUris::POI_API_ROUTE),
+            node_api_url: get_iota_node_url(iota_node),
+        }
+    }
+}
+
+impl fmt::Display for PoiClientOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PoiClientOptions:\n     inx_collector_url: {}\n     poi_url: {}\n     node_api_url: {}",
+               self.inx_collector_url, self.poi_url, self.node_api_url
+        )
+    }
+}
+
+struct EndpointUris {}
+
+impl EndpointUris {
+    pub const POI_API_ROUTE: &'static str = "/api/poi/v1";
+    pub const GET_BLOCK: &'static str = "/block";
+    pub const CREATE_POI: &'static str = "/create";
+    pub const CORE_API_ROUTE: &'static str = "/api/core/v2";
+
+    pub fn get_uri___get_block(tag_hex_str: &str) -> String {
+        format!("{}/{}", Self::GET_BLOCK, tag_hex_str)
+    }
+
+    pub fn get_uri___create_poi(block_id: &str) -> String {
+        format!("{}/{}", Self::CREATE_POI, block_id)
+    }
+
+    pub fn get_uri___block_metadata(block_id: &str) -> String {
+        format!("{}/blocks/{}/metadata", Self::CORE_API_ROUTE, block_id)
+    }
+
+    pub fn get_uri___milestone_by_index(milestone_index: u64) -> String {
+        format!("{}/milestones/by-index/{}", Self::CORE_API_ROUTE, milestone_index)
+    }
+}
+
+// Fetches Proofs of Inclusion from an inx-poi compatible endpoint.
+// The block id of a Streams message is read from the 'blockId' field of the
+// inx-collector response for the message tag.
+#[derive(Clone)]
+pub struct PoiClient {
+    hyper_client: HyperClient<HttpConnector, Body>,
+    options: PoiClientOptions,
+}
+
+impl PoiClient {
+    pub fn new(options: PoiClientOptions) -> Self {
+        Self {
+            hyper_client: HyperClient::new(),
+            options,
+        }
+    }
+
+    pub async fn get_block_id(&self, msg_index: [u8; 32]) -> Result<String> {
+        let mut tag = Vec::with_capacity(msg_index.len() + MessageIndexer::TAG_PREFIX.len());
+        tag.extend_from_slice(&MessageIndexer::TAG_PREFIX);
+        tag.extend_from_slice(&msg_index);
+        let url = format!("{}{}", self.options.inx_collector_url, EndpointUris::get_uri___get_block(&hex::encode(tag)));
+        let block_json = self.get_json(url.clone()).await?;
+        if let Some(block_id) = block_json["blockId"].as_str() {
+            Ok(block_id.to_string())
+        } else {
+            bail!("The inx-collector response for '{}' does not contain a 'blockId'", url)
+        }
+    }
+
+    pub async fn create_poi(&self, block_id: &str) -> Result<ProofOfInclusion> {
+        let url = format!("{}{}", self.options.poi_url, EndpointUris::get_uri___create_poi(block_id));
+        let poi_json = self.get_json(url).await?;
+        serde_json::from_value(poi_json)
+            .map_err(|e| anyhow!("Error on parsing ProofOfInclusion for block '{}'. Error: {}", block_id, e))
+    }
+
+    // Returns the timestamp of the milestone referencing the block, fetched from the core api
+    // of the IOTA node. Returns None if the block has not been referenced by a milestone yet.
+    pub async fn get_milestone_timestamp(&self, block_id: &str) -> Result<Option<u32>> {
+        let url = format!("{}{}", self.options.node_api_url, EndpointUris::get_uri___block_metadata(block_id));
+        let metadata_json = self.get_json(url).await?;
+        let milestone_index = match metadata_json["referencedByMilestoneIndex"].as_u64() {
+            Some(milestone_index) => milestone_index,
+            None => return Ok(None),
+        };
+        let url = format!("{}{}", self.options.node_api_url, EndpointUris::get_uri___milestone_by_index(milestone_index));
+        let milestone_json = self.get_json(url.clone()).await?;
+        match milestone_json["timestamp"].as_u64() {
+            Some(timestamp) => Ok(Some(timestamp as u32)),
+            None => bail!("The response of '{}' does not contain a milestone 'timestamp'", url),
+        }
+    }
+
+    async fn get_json(&self, url: String) -> Result<JsonValue> {
+        log::debug!("[fn get_json()] Sending get request: {}", url);
+        let request = RequestBuilderTools::get_request_builder()
+            .method("GET")
+            .uri(url.clone())
+            .body(Body::empty())?;
+        let response = self.hyper_client.request(request).await
+            .map_err(|e| anyhow!("Error on sending request '{}'. Error: {}", url, e))?;
+        if !response.status().is_success() {
+            bail!("Request '{}' responded with http status {}", url, response.status())
+        }
+        let json_str = get_string_from_response_body(response).await?;
+        serde_json::from_str(json_str.as_str())
+            .map_err(|e| anyhow!("Error on parsing the response of '{}' as JSON. Error: {}", url, e))
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::proof_of_inclusion::tests
+//
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use hyper::{
+        Server,
+        Response,
+        service::{
+            make_service_fn,
+            service_fn,
+        },
+    };
+    use super::*;
+
+    const POI_FIXTURE: &str = include_str!("../../../test/poi/poi_fixture.json");
+    const INX_COLLECTOR_BLOCK_FIXTURE: &str = include_str!("../../../test/poi/inx_collector_block.json");
+    const BLOCK_ID: &str = "0x27ecd0a598e76f8a2fd264d427df0a119903e8eae384e478902541756f089dd1";
+    const BLOCK_METADATA_FIXTURE: &str = r#"{"blockId":"0x27ecd0a598e76f8a2fd264d427df0a119903e8eae384e478902541756f089dd1","referencedByMilestoneIndex":4711,"ledgerInclusionState":"noTransaction"}"#;
+    const MILESTONE_FIXTURE: &str = r#"{"type":7,"index":4711,"timestamp":1700000000,"protocolVersion":2}"#;
+
+    #[test]
+    fn test_verify_poi_fixture() {
+        let poi = ProofOfInclusion::from_json_str(POI_FIXTURE).unwrap();
+        assert_eq!(poi.milestone_index(), 4711);
+        assert_eq!(poi.milestone_timestamp(), 1700000000);
+        assert!(poi.verify(BLOCK_ID).unwrap());
+    }
+
+    #[test]
+    fn test_verify_poi_fixture_with_other_block_id() {
+        let poi = ProofOfInclusion::from_json_str(POI_FIXTURE).unwrap();
+        let other_block_id = "0x".to_string() + "ab".repeat(32).as_str();
+        assert!(!poi.verify(other_block_id.as_str()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_tampered_poi_fixture() {
+        let mut poi = ProofOfInclusion::from_json_str(POI_FIXTURE).unwrap();
+        if let MerkleProofNode::Node { r, .. } = &mut poi.proof {
+            *r = Box::new(MerkleProofNode::Hash { h: "0x".to_string() + "00".repeat(32).as_str() });
+        }
+        assert!(!poi.verify(BLOCK_ID).unwrap());
+    }
+
+    #[test]
+    fn test_poi_json_roundtrip() {
+        let poi = ProofOfInclusion::from_json_str(POI_FIXTURE).unwrap();
+        let json_str = poi.to_json_string().unwrap();
+        assert_eq!(ProofOfInclusion::from_json_str(json_str.as_str()).unwrap(), poi);
+    }
+
+    async fn run_stand_in_endpoint() -> SocketAddr {
+        let make_service = make_service_fn(|_conn| async {
+            Ok::<_, hyper::Error>(service_fn(|req: hyper::Request<Body>| async move {
+                let path = req.uri().path();
+                let body = if path.starts_with(EndpointUris::GET_BLOCK) {
+                    INX_COLLECTOR_BLOCK_FIXTURE
+                } else if path.ends_with("/metadata") {
+                    BLOCK_METADATA_FIXTURE
+                } else if path.starts_with(EndpointUris::CORE_API_ROUTE) {
+                    MILESTONE_FIXTURE
+                } else {
+                    POI_FIXTURE
+                };
+                Ok::<_, hyper::Error>(Response::new(Body::from(body)))
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_poi_client_with_stand_in_endpoint() {
+        let addr = run_stand_in_endpoint().await;
+        let poi_client = PoiClient::new(PoiClientOptions {
+            inx_collector_url: format!("http://{}", addr),
+            poi_url: format!("http://{}{}", addr, EndpointUris::POI_API_ROUTE),
+            node_api_url: format!("http://{}", addr),
+        });
+        let block_id = poi_client.get_block_id([1; 32]).await.unwrap();
+        assert_eq!(block_id.as_str(), BLOCK_ID);
+        let poi = poi_client.create_poi(block_id.as_str()).await.unwrap();
+        assert!(poi.verify(block_id.as_str()).unwrap());
+        let milestone_timestamp = poi_client.get_milestone_timestamp(block_id.as_str()).await.unwrap();
+        assert_eq!(milestone_timestamp, Some(poi.milestone_timestamp()));
+    }
+}
@@ -31,6 +31,9 @@ use crate::{
             TANGLE_ADDRESS_BYTE_LEN,
         },
         INITIALIZATION_CNT_MAX_VALUE,
+        InitializationEpoch,
+        get_next_initialization_cnt,
+        get_full_initialization_cnt,
     },
     compressed_state::{
         CompressedStateListen,
@@ -117,20 +120,35 @@ where
         }
     }
 
+    // Clears the Streams Client State. If the sensor has been subscribed to a channel before,
+    // the initialization_cnt is incremented so that the next subscription is detected as
+    // re-initialization by the iota-bridge.
     pub async fn clear_client_state(&mut self) -> Result<()> {
-        log::debug!("[fn clear_client_state()] START");
+        let has_been_subscribed = self.user.is_some();
+        self.reset_client_state().await?;
+        if has_been_subscribed {
+            self.wallet.increment_initialization_cnt()?;
+            self.transport.set_initialization_cnt(self.wallet.get_initialization_cnt());
+            log::info!("[fn clear_client_state()] Initialization count has been incremented to {} (epoch {})",
+                self.wallet.get_initialization_cnt(), self.wallet.get_initialization_epoch());
+        }
+        Ok(())
+    }
+
+    async fn reset_client_state(&mut self) -> Result<()> {
+        log::debug!("[fn reset_client_state()] START");
 
-        log::debug!("[fn clear_client_state()] Calling subscriber_persistence.clear_client_state()");
+        log::debug!("[fn reset_client_state()] Calling subscriber_persistence.clear_client_state()");
         self.subscriber_persistence.borrow_mut().clear_client_state()?;
 
-        log::debug!("[fn clear_client_state()] Setting all links and user to None");
+        log::debug!("[fn reset_client_state()] Setting all links and user to None");
         self.prev_msg_link = None;
         self.subscription_link = None;
         self.user = None;
         self.transport.set_initial_use_compressed_msg_state(false);
         self.transport.remove_listener(self.compressed_subscription_handle);
 
-        log::debug!("[fn clear_client_state()] Ok");
+        log::debug!("[fn reset_client_state()] Ok");
         Ok(())
     }
 }
@@ -178,27 +196,24 @@ impl<TSR, TransportT, WalletT: SimpleWallet> SubscriberManager<TransportT, Walle
     }
 
     async fn subscribe_with_dirty_client_state(&mut self, ann_address: Address, initialization_cnt: u8) -> Result<()> {
-        if initialization_cnt < INITIALIZATION_CNT_MAX_VALUE {
-            log::info!("[fn subscribe_with_dirty_client_state()]\n\
-                                ------------------------------------------------------------------\n\
-                                An already existing subscription will be replaced by a new one.\n\
-                                Initialization count will be incremented from {} to {}\n\
-                                Maximum Initialization count is {}\n\
-                                ------------------------------------------------------------------\n",
-                     initialization_cnt,
-                     initialization_cnt + 1,
-                     INITIALIZATION_CNT_MAX_VALUE,
-            );
-            self.clear_client_state().await?;
-            self.subscribe_with_cleared_client_state(ann_address).await?;
-            self.wallet.increment_initialization_cnt()?;
-
-            if initialization_cnt == INITIALIZATION_CNT_MAX_VALUE {
-                println_maximum_initialization_cnt_reached_warning("SubscriberManager.subscribe_with_dirty_client_state()", initialization_cnt);
-            }
-        } else {
-            bail!("[SubscriberManager.subscribe_with_dirty_client_state())] Maximum number of channel initializations has been reached. Initialization count is {}",
-                    initialization_cnt)
+        let (next_initialization_cnt, next_initialization_epoch) =
+            get_next_initialization_cnt(initialization_cnt, self.wallet.get_initialization_epoch());
+        log::info!("[fn subscribe_with_dirty_client_state()]\n\
+                            ------------------------------------------------------------------\n\
+                            An already existing subscription will be replaced by a new one.\n\
+                            Initialization count will be incremented from {} to {}\n\
+                            Initialization epoch is {}\n\
+                            ------------------------------------------------------------------\n",
+                 initialization_cnt,
+                 next_initialization_cnt,
+                 next_initialization_epoch,
+        );
+        self.reset_client_state().await?;
+        self.subscribe_with_cleared_client_state(ann_address).await?;
+        self.wallet.increment_initialization_cnt()?;
+
+        if initialization_cnt == INITIALIZATION_CNT_MAX_VALUE {
+            println_initialization_cnt_wrapped_around_info("SubscriberManager.subscribe_with_dirty_client_state()", next_initialization_epoch);
         }
         Ok(())
     }
@@ -377,6 +392,10 @@ impl<TransportT, WalletT: SimpleWallet> SubscriberManager<TransportT, WalletT>
         self.wallet.get_initialization_cnt()
     }
 
+    pub fn get_initialization_epoch(&self) -> InitializationEpoch {
+        self.wallet.get_initialization_epoch()
+    }
+
     async fn export_to_subscriber_persistence(&mut self) -> Result<()> {
         log::debug!("[fn export_to_subscriber_persistence()] START");
         if self.user.is_some() {
@@ -445,18 +464,16 @@ impl<TransportT, WalletT: SimpleWallet> SubscriberManager<TransportT, WalletT>
     }
 }
 
-pub fn println_maximum_initialization_cnt_reached_warning(fn_name: &str, current_initialization_cnt: u8) {
-    log::info!("\n\n[{}] Warning maximum number of initializations reached:\n\n\
-                                ---------------------------------------------------------------\n\
-                                ---------------------- W A R N I N G --------------------------\n\
+pub fn println_initialization_cnt_wrapped_around_info(fn_name: &str, current_initialization_epoch: InitializationEpoch) {
+    log::info!("\n\n[{}] The initialization count has wrapped around:\n\n\
                                 ---------------------------------------------------------------\n\
-                                The maximum number of initializations has been reached.\n\
-                                The initialization count for this sensor now is {}.\n\
-                                To reset the initialization count, the flash of the sensor\n\
-                                needs to be erased.\n\
+                                The initialization count has been reset to 0 and the\n\
+                                initialization epoch has been incremented to {}.\n\
+                                The full initialization count of this sensor now is {}.\n\
                                 ---------------------------------------------------------------\n",
              fn_name,
-             current_initialization_cnt,
+             current_initialization_epoch,
+             get_full_initialization_cnt(0, current_initialization_epoch),
     );
 }
 
@@ -528,19 +545,22 @@ fn read_optional_tangle_address_from_bytes(
 }
 
 pub fn get_public_key_str<'a, TransportT: StreamsTransport>(user: &User<TransportT>) -> String {
-    let mut own_public_key_str = "None".to_string();
-    if let Some(identifier) = user.identifier() {
-        match identifier {
-            Identifier::Ed25519(public_key) => {
-                own_public_key_str = hex::encode(public_key.to_bytes().as_slice());
-            },
-            // The following line is commented out because using our Streams feature set Identifier
-            // only contains Ed25519. Otherwise the line would result in a "warning: unreachable pattern".
-            // _ => {}
-        }
+    match user.identifier() {
+        Some(identifier) => get_identifier_str(identifier),
+        None => "None".to_string(),
+    }
+}
 
+// Returns the hex encoded public key of the identifier
+pub fn get_identifier_str(identifier: &Identifier) -> String {
+    match identifier {
+        Identifier::Ed25519(public_key) => {
+            hex::encode(public_key.to_bytes().as_slice())
+        },
+        // The following line is commented out because using our Streams feature set Identifier
+        // only contains Ed25519. Otherwise the line would result in a "warning: unreachable pattern".
+        // _ => {}
     }
-    own_public_key_str
 }
 
 impl<TransportT, WalletT: SimpleWallet> CompressedStateListen for SubscriberManager<TransportT, WalletT>
@@ -0,0 +1,452 @@
+use std::{
+    fmt,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use hyper::{
+    Body,
+    Client as HyperClient,
+    client::HttpConnector,
+};
+
+use crypto::macs::hmac::HMAC_SHA256;
+
+use streams::Message as StreamsMessage;
+
+use crate::{
+    http::http_tools::RequestBuilderTools,
+    dao_helpers::{
+        Condition,
+        Conditions,
+        Limit,
+        MatchType,
+        OrderBy,
+        SortOrder,
+    },
+};
+
+use super::dao::webhook_delivery::{
+    WebhookDelivery,
+    WebhookDeliveryDataStore,
+};
+
+pub static WEBHOOK_SIGNATURE_HEADER: &str = "X-Susee-Signature";
+pub static WEBHOOK_TIMESTAMP_HEADER: &str = "X-Susee-Timestamp";
+
+// Number of queued deliveries fetched per query by WebhookClient::retry_queued_deliveries()
+const RETRY_PAGE_SIZE: usize = 100;
+
+// Payload of the webhook http POST request that is send for each new sensor message.
+// Binary data are provided as hex encoded strings.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct NewMessageNotification {
+    pub dev_eui: String,
+    pub channel_id: String,
+    pub message_id: String,
+    pub msg_index: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_payload: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decrypted_payload: Option<String>,
+}
+
+impl NewMessageNotification {
+    // Returns None if the message is not a sensor message (e.g. Announcement, Keyload, ...).
+    // The decrypted_payload will only be set if include_decrypted_payload is true and the masked
+    // payload of the message could be decrypted by the user that has received the message.
+    pub fn new_from_streams_msg(dev_eui: &str, streams_msg: &StreamsMessage, include_decrypted_payload: bool) -> Option<Self> {
+        let public_payload = streams_msg.public_payload().filter(|data| !data.is_empty());
+        let masked_payload = streams_msg.masked_payload().filter(|data| !data.is_empty());
+        if public_payload.is_none() && masked_payload.is_none() {
+            return None;
+        }
+        Some(NewMessageNotification {
+            dev_eui: dev_eui.to_string(),
+            channel_id: streams_msg.address.base().to_string(),
+            message_id: streams_msg.address.to_string(),
+            msg_index: hex::encode(streams_msg.address.to_msg_index()),
+            public_payload: public_payload.map(|data| hex::encode(data)),
+            decrypted_payload: if include_decrypted_payload {
+                masked_payload.map(|data| hex::encode(data))
+            } else {
+                None
+            },
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookOptions {
+    // http url the notifications are POSTed to
+    pub url: String,
+    // If specified, the request is signed using HMAC-SHA256 and the hex encoded
+    // signature is provided in the X-Susee-Signature header: "sha256=<hex signature>"
+    // (see get_hmac_signature()).
+    pub secret: Option<String>,
+    pub include_decrypted_payload: bool,
+    // Delay before the first retry of a failed notification. The delay is doubled
+    // for each further retry but will not exceed max_retry_delay_secs.
+    pub retry_delay_secs: u64,
+    pub max_retry_delay_secs: u64,
+}
+
+impl WebhookOptions {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            secret: None,
+            include_decrypted_payload: false,
+            retry_delay_secs: 10,
+            max_retry_delay_secs: 3600,
+        }
+    }
+}
+
+impl fmt::Display for WebhookOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WebhookOptions:\n     url: {}\n     secret is set: {}\n     include_decrypted_payload: {}\n     retry_delay_secs: {}\n     max_retry_delay_secs: {}",
+               self.url, self.secret.is_some(), self.include_decrypted_payload, self.retry_delay_secs, self.max_retry_delay_secs
+        )
+    }
+}
+
+fn get_hmac_sha256(secret: &str, data: &[u8]) -> String {
+    let mut mac = [0u8; 32];
+    HMAC_SHA256(data, secret.as_bytes(), &mut mac);
+    format!("sha256={}", hex::encode(mac))
+}
+
+// The signature is calculated over "<timestamp>.<body>" where timestamp is the value of the
+// X-Susee-Timestamp header. This way receivers can reject replayed requests having an
+// outdated timestamp.
+pub fn get_hmac_signature(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut data = Vec::<u8>::with_capacity(timestamp.len() + 1 + body.len());
+    data.extend_from_slice(timestamp.as_bytes());
+    data.push(b'.');
+    data.extend_from_slice(body);
+    get_hmac_sha256(secret, data.as_slice())
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Clone)]
+pub struct WebhookClient {
+    hyper_client: HyperClient<HttpConnector, Body>,
+    options: WebhookOptions,
+}
+
+impl WebhookClient {
+    pub fn new(options: WebhookOptions) -> Self {
+        Self {
+            hyper_client: HyperClient::new(),
+            options,
+        }
+    }
+
+    pub fn include_decrypted_payload(&self) -> bool {
+        self.options.include_decrypted_payload
+    }
+
+    // Sends the notification once. If the notification can not be delivered it is stored in the
+    // delivery_queue and will be retried by retry_queued_deliveries(), so that the caller
+    // (e.g. a channel sync) is not blocked by an unavailable webhook receiver.
+    // Returns an error if the notification could neither be delivered nor be queued.
+    pub async fn notify(&self, notification: &NewMessageNotification, delivery_queue: &WebhookDeliveryDataStore) -> Result<()> {
+        let body = serde_json::to_string(notification)
+            .map_err(|e| anyhow!("Error on serializing NewMessageNotification. Error: {}", e))?;
+        if let Err(e) = self.send(body.as_bytes()).await {
+            log::warn!("[fn notify()] Webhook notification for message {} failed. Retrying in the background. Error: {}",
+                notification.message_id, e);
+            let now = now_secs();
+            delivery_queue.write_item_to_db(&WebhookDelivery {
+                id: None,
+                message_id: notification.message_id.clone(),
+                body,
+                attempts: 1,
+                next_attempt_at_secs: now + self.get_retry_delay_secs(1),
+                created_at_secs: now,
+            }).map_err(|e| anyhow!("Could not queue webhook notification for message {}. Error: {}",
+                notification.message_id, e))?;
+        }
+        Ok(())
+    }
+
+    // Retries the queued deliveries that are due in the order they have been queued.
+    // Delivered notifications are removed from the delivery_queue. Notifications are kept
+    // until they have been delivered.
+    // If a delivery fails again its next attempt is postponed and the remaining deliveries
+    // are left for the next call, because the webhook receiver is most probably not available.
+    //
+    // Returns the number of delivered notifications.
+    pub async fn retry_queued_deliveries(&self, delivery_queue: &WebhookDeliveryDataStore) -> Result<usize> {
+        let mut due_conditions = Vec::<Condition>::new();
+        Conditions(&mut due_conditions).add(Some(now_secs().to_string()), "next_attempt_at_secs", MatchType::LessOrEqual);
+        let order_by = Some(OrderBy::new("id", SortOrder::Ascending));
+        let mut num_delivered = 0;
+        loop {
+            // Delivered notifications are deleted and failed ones are postponed,
+            // so the next page always starts at offset 0
+            let (deliveries, _) = delivery_queue.filter_ordered(
+                due_conditions.clone(),
+                order_by.clone(),
+                Some(Limit { limit: RETRY_PAGE_SIZE, offset: 0 })
+            )?;
+            if deliveries.is_empty() {
+                return Ok(num_delivered);
+            }
+            for mut delivery in deliveries {
+                let id = delivery.id
+                    .ok_or_else(|| anyhow!("Queued webhook delivery for message {} has no id", delivery.message_id))?;
+                match self.send(delivery.body.as_bytes()).await {
+                    Ok(_) => {
+                        delivery_queue.delete_item_in_db(&id)?;
+                        num_delivered += 1;
+                    },
+                    Err(e) => {
+                        delivery.attempts += 1;
+                        delivery.next_attempt_at_secs = now_secs() + self.get_retry_delay_secs(delivery.attempts);
+                        log::warn!("[fn retry_queued_deliveries()] Webhook notification for message {} failed {} times. Next retry in {} secs. Error: {}",
+                            delivery.message_id, delivery.attempts, self.get_retry_delay_secs(delivery.attempts), e);
+                        delivery_queue.write_item_to_db(&delivery)?;
+                        return Ok(num_delivered);
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns the delay before the next attempt of a notification that has failed 'attempts' times
+    fn get_retry_delay_secs(&self, attempts: i64) -> i64 {
+        let doublings = (attempts - 1).clamp(0, 31) as u32;
+        self.options.retry_delay_secs
+            .saturating_mul(1u64 << doublings)
+            .min(self.options.max_retry_delay_secs) as i64
+    }
+
+    async fn send(&self, body: &[u8]) -> Result<()> {
+        let timestamp = now_secs().to_string();
+        let mut builder = RequestBuilderTools::get_request_builder()
+            .method("POST")
+            .uri(self.options.url.clone())
+            .header("Content-Type", "application/json")
+            .header(WEBHOOK_TIMESTAMP_HEADER, timestamp.as_str());
+        if let Some(secret) = self.options.secret.as_ref() {
+            builder = builder.header(WEBHOOK_SIGNATURE_HEADER, get_hmac_signature(secret.as_str(), timestamp.as_str(), body));
+        }
+        let response = self.hyper_client.request(builder.body(Body::from(body.to_vec()))?).await
+            .map_err(|e| anyhow!("Error on sending request to '{}'. Error: {}", self.options.url, e))?;
+        if !response.status().is_success() {
+            bail!("Webhook '{}' responded with http status {}", self.options.url, response.status())
+        }
+        Ok(())
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib user_manager::webhook::tests
+//
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::SocketAddr,
+        sync::{
+            Arc,
+            atomic::{
+                AtomicUsize,
+                Ordering,
+            },
+        },
+    };
+    use hyper::{
+        Server,
+        Response,
+        StatusCode,
+        service::{
+            make_service_fn,
+            service_fn,
+        },
+    };
+    use super::*;
+    use crate::dao_helpers::DbFileBasedDaoManagerOptions;
+
+    const SECRET: &str = "webhook-secret";
+
+    fn get_notification() -> NewMessageNotification {
+        NewMessageNotification {
+            dev_eui: "4711".to_string(),
+            channel_id: "cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000".to_string(),
+            message_id: "cbd12e732e3c6df93c6fc189bf0d0553c2219d644402bae7caa8968aa5ba15dc0000000000000000:84d48c0cc279564b467f7e74".to_string(),
+            msg_index: "00".repeat(32),
+            public_payload: None,
+            decrypted_payload: Some("0102".to_string()),
+        }
+    }
+
+    // Responds with http status 500 for the first 'num_failures' requests.
+    // Successful requests are only counted if the signature and the timestamp are valid.
+    async fn run_webhook_receiver(num_failures: usize, num_valid_requests: Arc<AtomicUsize>) -> SocketAddr {
+        let num_requests = Arc::new(AtomicUsize::new(0));
+        let make_service = make_service_fn(move |_conn| {
+            let num_requests = num_requests.clone();
+            let num_valid_requests = num_valid_requests.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |req: hyper::Request<Body>| {
+                    let num_requests = num_requests.clone();
+                    let num_valid_requests = num_valid_requests.clone();
+                    async move {
+                        let get_header = |name: &str| req.headers().get(name)
+                            .map(|value| value.to_str().unwrap().to_string());
+                        let signature = get_header(WEBHOOK_SIGNATURE_HEADER);
+                        let timestamp = get_header(WEBHOOK_TIMESTAMP_HEADER).unwrap_or_default();
+                        let body = hyper::body::to_bytes(req.into_body()).await?;
+                        if num_requests.fetch_add(1, Ordering::SeqCst) < num_failures {
+                            let mut response = Response::new(Body::empty());
+                            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                            return Ok::<_, hyper::Error>(response);
+                        }
+                        let is_recent = timestamp.parse::<i64>()
+                            .map(|secs| (now_secs() - secs).abs() <= 300)
+                            .unwrap_or(false);
+                        if is_recent && signature == Some(get_hmac_signature(SECRET, timestamp.as_str(), body.as_ref())) {
+                            num_valid_requests.fetch_add(1, Ordering::SeqCst);
+                        }
+                        Ok::<_, hyper::Error>(Response::new(Body::empty()))
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    fn get_webhook_client(addr: SocketAddr) -> WebhookClient {
+        let mut options = WebhookOptions::new(format!("http://{}/", addr).as_str());
+        options.secret = Some(SECRET.to_string());
+        options.retry_delay_secs = 60;
+        options.max_retry_delay_secs = 100;
+        WebhookClient::new(options)
+    }
+
+    fn get_delivery_queue() -> WebhookDeliveryDataStore {
+        WebhookDeliveryDataStore::new(DbFileBasedDaoManagerOptions { file_path_and_name: "not used".to_string() })
+    }
+
+    // Lets the queued deliveries be retried by the next retry_queued_deliveries() call
+    fn set_all_deliveries_due(delivery_queue: &WebhookDeliveryDataStore) {
+        for mut delivery in delivery_queue.find_all_unlimited().unwrap() {
+            delivery.next_attempt_at_secs = 0;
+            delivery_queue.write_item_to_db(&delivery).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            get_hmac_sha256("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_hmac_signature_includes_timestamp() {
+        assert_eq!(
+            get_hmac_signature(SECRET, "1700000000", b"{}"),
+            get_hmac_sha256(SECRET, b"1700000000.{}")
+        );
+        assert_ne!(
+            get_hmac_signature(SECRET, "1700000000", b"{}"),
+            get_hmac_signature(SECRET, "1700000001", b"{}")
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let webhook_client = get_webhook_client(([127, 0, 0, 1], 0).into());
+        assert_eq!(webhook_client.get_retry_delay_secs(1), 60);
+        assert_eq!(webhook_client.get_retry_delay_secs(2), 100);
+        assert_eq!(webhook_client.get_retry_delay_secs(1000), 100);
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let num_valid_requests = Arc::new(AtomicUsize::new(0));
+        let addr = run_webhook_receiver(0, num_valid_requests.clone()).await;
+        let webhook_client = get_webhook_client(addr);
+        let delivery_queue = get_delivery_queue();
+        webhook_client.notify(&get_notification(), &delivery_queue).await.unwrap();
+        assert_eq!(num_valid_requests.load(Ordering::SeqCst), 1);
+        assert!(delivery_queue.find_all_unlimited().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_notification_is_queued_and_retried() {
+        let num_valid_requests = Arc::new(AtomicUsize::new(0));
+        let addr = run_webhook_receiver(1, num_valid_requests.clone()).await;
+        let webhook_client = get_webhook_client(addr);
+        let delivery_queue = get_delivery_queue();
+        let notification = get_notification();
+        webhook_client.notify(&notification, &delivery_queue).await.unwrap();
+        assert_eq!(num_valid_requests.load(Ordering::SeqCst), 0);
+
+        let deliveries = delivery_queue.find_all_unlimited().unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].message_id, notification.message_id);
+        assert_eq!(deliveries[0].attempts, 1);
+        assert!(deliveries[0].next_attempt_at_secs > now_secs());
+
+        // The delivery is not due yet
+        assert_eq!(webhook_client.retry_queued_deliveries(&delivery_queue).await.unwrap(), 0);
+        assert_eq!(num_valid_requests.load(Ordering::SeqCst), 0);
+
+        set_all_deliveries_due(&delivery_queue);
+        assert_eq!(webhook_client.retry_queued_deliveries(&delivery_queue).await.unwrap(), 1);
+        assert_eq!(num_valid_requests.load(Ordering::SeqCst), 1);
+        assert!(delivery_queue.find_all_unlimited().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_retry_postpones_delivery() {
+        let num_valid_requests = Arc::new(AtomicUsize::new(0));
+        let addr = run_webhook_receiver(3, num_valid_requests.clone()).await;
+        let webhook_client = get_webhook_client(addr);
+        let delivery_queue = get_delivery_queue();
+        webhook_client.notify(&get_notification(), &delivery_queue).await.unwrap();
+        webhook_client.notify(&get_notification(), &delivery_queue).await.unwrap();
+
+        // The first retry fails, so the second delivery is left for the next call
+        set_all_deliveries_due(&delivery_queue);
+        assert_eq!(webhook_client.retry_queued_deliveries(&delivery_queue).await.unwrap(), 0);
+        let deliveries = delivery_queue.find_all_unlimited().unwrap();
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].attempts, 2);
+        assert!(deliveries[0].next_attempt_at_secs > now_secs());
+        assert_eq!(deliveries[1].attempts, 1);
+
+        set_all_deliveries_due(&delivery_queue);
+        assert_eq!(webhook_client.retry_queued_deliveries(&delivery_queue).await.unwrap(), 2);
+        assert_eq!(num_valid_requests.load(Ordering::SeqCst), 2);
+        assert!(delivery_queue.find_all_unlimited().unwrap().is_empty());
+    }
+}
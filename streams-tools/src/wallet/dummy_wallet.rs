@@ -2,9 +2,15 @@ use super::simple_wallet::SimpleWallet;
 
 use anyhow::Result;
 
+use crate::binary_persist::{
+    InitializationEpoch,
+    get_next_initialization_cnt,
+};
+
 #[derive(Clone, Default)]
 pub struct DummyWallet {
-    initialization_cnt: u8
+    initialization_cnt: u8,
+    initialization_epoch: InitializationEpoch,
 }
 
 const SEED: &'static str = "--- This is a dummy seed used by a dummy wallet --- This is a dummy seed used ...";
@@ -30,8 +36,15 @@ impl SimpleWallet for DummyWallet {
         self.initialization_cnt
     }
 
+    fn get_initialization_epoch(&self) -> InitializationEpoch {
+        self.initialization_epoch
+    }
+
     fn increment_initialization_cnt(&mut self) -> Result<u8> {
-        self.initialization_cnt += 1;
+        let (initialization_cnt, initialization_epoch) =
+            get_next_initialization_cnt(self.initialization_cnt, self.initialization_epoch);
+        self.initialization_cnt = initialization_cnt;
+        self.initialization_epoch = initialization_epoch;
         Ok(self.initialization_cnt)
     }
 }
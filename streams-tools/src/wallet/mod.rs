@@ -1,12 +1,28 @@
 pub mod simple_wallet;
 pub mod dummy_wallet;
 pub mod plain_text_wallet;
+pub mod multi_channel_wallet;
+#[cfg(feature = "stronghold")]
+pub mod stronghold_wallet;
 
 pub use {
     simple_wallet::SimpleWallet,
     dummy_wallet::DummyWallet,
+    multi_channel_wallet::{
+        MultiChannelWallet,
+        MultiChannelWalletType,
+    },
 };
 
 pub use {
     plain_text_wallet::*
+};
+
+#[cfg(feature = "stronghold")]
+pub use {
+    stronghold_wallet::{
+        StrongholdWallet,
+        StrongholdUnlock,
+        migrate_plain_text_wallet,
+    }
 };
\ No newline at end of file
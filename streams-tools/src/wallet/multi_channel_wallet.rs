@@ -0,0 +1,109 @@
+use anyhow::Result;
+
+use crate::binary_persist::InitializationEpoch;
+
+use super::{
+    simple_wallet::SimpleWallet,
+    plain_text_wallet::PlainTextWallet,
+};
+
+#[cfg(feature = "stronghold")]
+use super::stronghold_wallet::{
+    StrongholdWallet,
+    StrongholdUnlock,
+};
+
+// Defines which wallet implementation is used to manage the seeds of multiple Streams channels
+// (see user_manager::multi_channel_management)
+#[derive(Clone)]
+pub enum MultiChannelWalletType {
+    // DO NOT USE FOR PRODUCTION PURPOSES - The seed is stored in cleartext
+    PlainText,
+    #[cfg(feature = "stronghold")]
+    Stronghold(StrongholdUnlock),
+}
+
+impl Default for MultiChannelWalletType {
+    fn default() -> Self {
+        MultiChannelWalletType::PlainText
+    }
+}
+
+// Allows to choose the wallet implementation at runtime while ChannelManager instances
+// are typed using one single wallet type.
+#[derive(Clone)]
+pub enum MultiChannelWallet {
+    PlainText(PlainTextWallet),
+    #[cfg(feature = "stronghold")]
+    Stronghold(StrongholdWallet),
+}
+
+impl MultiChannelWallet {
+    pub fn new(wallet_type: &MultiChannelWalletType, serialization_password: &str, file_path_name: &str, seed_derivation_phrase: Option<String>) -> Result<Self> {
+        match wallet_type {
+            MultiChannelWalletType::PlainText => Ok(MultiChannelWallet::PlainText(
+                PlainTextWallet::new(serialization_password, Some(file_path_name), seed_derivation_phrase)
+            )),
+            #[cfg(feature = "stronghold")]
+            MultiChannelWalletType::Stronghold(unlock) => Ok(MultiChannelWallet::Stronghold(
+                StrongholdWallet::new(serialization_password, Some(file_path_name), seed_derivation_phrase, unlock.clone())?
+            )),
+        }
+    }
+
+    pub fn get_seed_derivation_phrase(&self) -> Option<&String> {
+        match self {
+            MultiChannelWallet::PlainText(wallet) => wallet.seed_derivation_phrase.as_ref(),
+            #[cfg(feature = "stronghold")]
+            MultiChannelWallet::Stronghold(wallet) => wallet.seed_derivation_phrase.as_ref(),
+        }
+    }
+}
+
+impl SimpleWallet for MultiChannelWallet {
+    const IS_USABLE_WALLET: bool = true;
+
+    fn new(file_path_name: &str) -> Self {
+        MultiChannelWallet::PlainText(<PlainTextWallet as SimpleWallet>::new(file_path_name))
+    }
+
+    fn get_seed(&self) -> &str {
+        match self {
+            MultiChannelWallet::PlainText(wallet) => wallet.get_seed(),
+            #[cfg(feature = "stronghold")]
+            MultiChannelWallet::Stronghold(wallet) => wallet.get_seed(),
+        }
+    }
+
+    fn get_serialization_password(&self) -> &str {
+        match self {
+            MultiChannelWallet::PlainText(wallet) => wallet.get_serialization_password(),
+            #[cfg(feature = "stronghold")]
+            MultiChannelWallet::Stronghold(wallet) => wallet.get_serialization_password(),
+        }
+    }
+
+    fn get_initialization_cnt(&self) -> u8 {
+        match self {
+            MultiChannelWallet::PlainText(wallet) => wallet.get_initialization_cnt(),
+            #[cfg(feature = "stronghold")]
+            MultiChannelWallet::Stronghold(wallet) => wallet.get_initialization_cnt(),
+        }
+    }
+
+    fn get_initialization_epoch(&self) -> InitializationEpoch {
+        match self {
+            MultiChannelWallet::PlainText(wallet) => wallet.get_initialization_epoch(),
+            #[cfg(feature = "stronghold")]
+            MultiChannelWallet::Stronghold(wallet) => wallet.get_initialization_epoch(),
+        }
+    }
+
+    fn increment_initialization_cnt(&mut self) -> Result<u8> {
+        match self {
+            MultiChannelWallet::PlainText(wallet) => wallet.increment_initialization_cnt(),
+            #[cfg(feature = "stronghold")]
+            MultiChannelWallet::Stronghold(wallet) => wallet.increment_initialization_cnt(),
+        }
+    }
+}
@@ -12,8 +12,11 @@ use crate::binary_persist::{
     BinaryPersist,
     USIZE_LEN,
     RangeIterator,
+    InitializationEpoch,
     serialize_string,
-    deserialize_string
+    deserialize_string,
+    deserialize_trailing_u32,
+    get_next_initialization_cnt,
 };
 
 use super::simple_wallet::SimpleWallet;
@@ -42,6 +45,8 @@ pub struct PtwPersist {
     pub initialization_cnt: u8,
     pub seed: String,
     pub misc_other_data: String,
+    // Is appended to the persisted data. Persistence files of older versions don't contain it.
+    pub initialization_epoch: InitializationEpoch,
 }
 
 impl BinaryPersist for PtwPersist {
@@ -50,6 +55,7 @@ impl BinaryPersist for PtwPersist {
         ret_val += 2 * USIZE_LEN;       // Length of 2 Strings: seed + misc_other_data
         ret_val += self.seed.len();
         ret_val += self.misc_other_data.len();
+        ret_val += 4;                   // initialization_epoch u32
         ret_val
     }
 
@@ -65,6 +71,9 @@ impl BinaryPersist for PtwPersist {
         serialize_string(&self.seed, buffer, &mut range)?;
         // MISC_OTHER_DATA
         serialize_string(&self.misc_other_data, buffer, &mut range)?;
+        // INITIALIZATION_EPOCH
+        range.increment(4);
+        self.initialization_epoch.to_bytes(&mut buffer[range.clone()])?;
 
         Ok(range.end)
     }
@@ -77,8 +86,11 @@ impl BinaryPersist for PtwPersist {
         let seed= deserialize_string(buffer, & mut range)?;
         // MISC_OTHER_DATA
         let misc_other_data= deserialize_string(buffer, & mut range)?;
+        // INITIALIZATION_EPOCH - Is missing in persistence files of older versions
+        let initialization_epoch: InitializationEpoch = deserialize_trailing_u32("initialization_epoch", buffer, &mut range)?
+            .unwrap_or_default();
 
-        Ok(PtwPersist {initialization_cnt, seed, misc_other_data})
+        Ok(PtwPersist {initialization_cnt, seed, misc_other_data, initialization_epoch})
     }
 }
 
@@ -103,7 +115,7 @@ pub fn create_seed() -> String {
     String::from(seed)
 }
 
-fn create_seed_from_derivation_phrase(master_seed: &str, seed_derivation_phrase: &str) -> String {
+pub(crate) fn create_seed_from_derivation_phrase(master_seed: &str, seed_derivation_phrase: &str) -> String {
     let mut hasher = DefaultHasher::new();
     hasher.write_str(master_seed);
     hasher.write_str(seed_derivation_phrase);
@@ -129,6 +141,7 @@ fn create_persistence_file(file_name: &str) -> Result<PtwPersist>{
         initialization_cnt: 0,
         seed: create_seed(),
         misc_other_data: String::default(),
+        initialization_epoch: 0,
     };
     write_persistence_file(file_name, &persist)?;
     log::debug!("[fn create_persistence_file()] Wrote seed {} into persistence file '{}'", persist.seed, file_name);
@@ -143,7 +156,7 @@ fn write_persistence_file(file_name: &str, persist: &PtwPersist) -> Result<()>{
     Ok(())
 }
 
-fn read_persistence_file(file_name: &str) -> Result<PtwPersist> {
+pub(crate) fn read_persistence_file(file_name: &str) -> Result<PtwPersist> {
     let buffer = fs_read(file_name).expect(format!("Could not open persistence file '{}'", file_name).as_str());
     PtwPersist::try_from_bytes(buffer.as_slice())
 }
@@ -206,8 +219,15 @@ impl SimpleWallet for PlainTextWallet {
         self.persist.initialization_cnt
     }
 
+    fn get_initialization_epoch(&self) -> InitializationEpoch {
+        self.persist.initialization_epoch
+    }
+
     fn increment_initialization_cnt(&mut self) -> Result<u8> {
-        self.persist.initialization_cnt += 1;
+        let (initialization_cnt, initialization_epoch) =
+            get_next_initialization_cnt(self.persist.initialization_cnt, self.persist.initialization_epoch);
+        self.persist.initialization_cnt = initialization_cnt;
+        self.persist.initialization_epoch = initialization_epoch;
         self.persist.seed = create_seed();
         self.write_wallet_file();
         Ok(self.persist.initialization_cnt)
@@ -223,6 +243,7 @@ impl SimpleWallet for PlainTextWallet {
 mod tests {
     use super::*;
     use std::fs::remove_file as fs_remove_file;
+    use crate::binary_persist::INITIALIZATION_CNT_MAX_VALUE;
 
     const SERIALIZATION_PASSWD: &'static str = "DO NOT USE THIS IN PRODUCTION";
 
@@ -231,6 +252,7 @@ mod tests {
             initialization_cnt,
             seed: seed.clone(),
             misc_other_data: other_data.to_string(),
+            initialization_epoch: 0,
         };
         write_persistence_file(file_name, &persist).expect("Error on writing persistence file");
         persist
@@ -249,6 +271,27 @@ mod tests {
         assert_eq!(seed.len(), 81);
         assert!(seed.chars().all(|c| ALPH9.contains(c)));
     }
+
+    #[test]
+    fn test_persist_truncated_initialization_epoch() {
+        let persist = PtwPersist {
+            initialization_cnt: 1,
+            seed: create_seed(),
+            misc_other_data: "Some other data".to_string(),
+            initialization_epoch: 4711,
+        };
+        let buffer = persist.as_vecu8().unwrap();
+        assert_eq!(PtwPersist::try_from_bytes(buffer.as_slice()).unwrap(), persist);
+        // Persistence files of older versions don't contain the initialization_epoch
+        let legacy = PtwPersist::try_from_bytes(&buffer[..buffer.len() - 4]).unwrap();
+        assert_eq!(legacy.initialization_cnt, persist.initialization_cnt);
+        assert_eq!(legacy.misc_other_data, persist.misc_other_data);
+        assert_eq!(legacy.initialization_epoch, 0);
+        for trailing_bytes in 1..4 {
+            assert!(PtwPersist::try_from_bytes(&buffer[..buffer.len() - 4 + trailing_bytes]).is_err());
+        }
+    }
+
     #[test]
     fn test_write_persistence_file() {
         let file_name = "test_persistence.ptw";
@@ -293,4 +336,20 @@ mod tests {
         assert_eq!(SERIALIZATION_PASSWD, ptw.get_serialization_password());
         fs_remove_file(file_name).expect("Error on removing persistence file");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_initialization_cnt_wrap_around() {
+        let file_name = "test_persistence_wrap_around.ptw";
+        let seed = create_seed();
+        prepare_persisted_file(file_name, INITIALIZATION_CNT_MAX_VALUE, &seed, "");
+        let mut ptw = PlainTextWallet::new(SERIALIZATION_PASSWD, Some(file_name), None);
+        assert_eq!(ptw.get_initialization_epoch(), 0);
+        assert_eq!(ptw.increment_initialization_cnt().unwrap(), 0);
+        assert_eq!(ptw.get_initialization_epoch(), 1);
+
+        let reopened = PlainTextWallet::new(SERIALIZATION_PASSWD, Some(file_name), None);
+        assert_eq!(reopened.get_initialization_cnt(), 0);
+        assert_eq!(reopened.get_initialization_epoch(), 1);
+        fs_remove_file(file_name).expect("Error on removing persistence file");
+    }
+}
@@ -1,5 +1,7 @@
 use anyhow::Result;
 
+use crate::binary_persist::InitializationEpoch;
+
 pub trait SimpleWallet {
     const IS_USABLE_WALLET: bool;
 
@@ -8,5 +10,10 @@ pub trait SimpleWallet {
     fn get_serialization_password(&self) -> &str;
 
     fn get_initialization_cnt(&self) -> u8;
+    // Counts how often the initialization_cnt has wrapped around.
+    // See binary_persist::InitializationEpoch for more details.
+    fn get_initialization_epoch(&self) -> InitializationEpoch;
+    // Wraps around to 0 after INITIALIZATION_CNT_MAX_VALUE has been reached and increments
+    // the initialization_epoch in this case.
     fn increment_initialization_cnt(&mut self) -> Result<u8>;
 }
\ No newline at end of file
@@ -0,0 +1,320 @@
+use std::{
+    env,
+    fs::read as fs_read,
+    path::Path,
+};
+
+use iota_stronghold::{
+    KeyProvider,
+    SnapshotPath,
+    Stronghold,
+};
+
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use crate::binary_persist::{
+    BinaryPersist,
+    InitializationEpoch,
+    get_next_initialization_cnt,
+};
+
+use super::{
+    simple_wallet::SimpleWallet,
+    plain_text_wallet::{
+        PtwPersist,
+        create_seed,
+        create_seed_from_derivation_phrase,
+        read_persistence_file,
+    },
+};
+
+// Environment variables used by SimpleWallet::new() to unlock the stronghold snapshot file
+pub static STRONGHOLD_PASSWORD_ENV_VAR: &str = "SUSEE_STRONGHOLD_PASSWORD";
+pub static STRONGHOLD_KEYFILE_ENV_VAR: &str = "SUSEE_STRONGHOLD_KEYFILE";
+
+static DEFAULT_FILE_NAME: &str = "channel-seed.stronghold";
+
+// The stronghold snapshot contains one client storing the PtwPersist data of the wallet
+// in one store record. Contrary to the PlainTextWallet the data are encrypted.
+static STRONGHOLD_CLIENT_PATH: &[u8] = b"susee-wallet";
+static STRONGHOLD_PERSIST_RECORD_KEY: &[u8] = b"persist";
+
+// Defines how the stronghold snapshot file is unlocked.
+// In case of a KeyFile the content of the file is used as passphrase.
+#[derive(Clone)]
+pub enum StrongholdUnlock {
+    Password(String),
+    KeyFile(String),
+}
+
+impl StrongholdUnlock {
+    // Uses the environment variable SUSEE_STRONGHOLD_KEYFILE if set,
+    // otherwise SUSEE_STRONGHOLD_PASSWORD
+    pub fn from_env() -> Result<Self> {
+        if let Ok(key_file) = env::var(STRONGHOLD_KEYFILE_ENV_VAR) {
+            Ok(StrongholdUnlock::KeyFile(key_file))
+        } else if let Ok(password) = env::var(STRONGHOLD_PASSWORD_ENV_VAR) {
+            Ok(StrongholdUnlock::Password(password))
+        } else {
+            bail!("Neither environment variable {} nor {} is set. Can not unlock the stronghold wallet.",
+                STRONGHOLD_KEYFILE_ENV_VAR, STRONGHOLD_PASSWORD_ENV_VAR)
+        }
+    }
+
+    fn get_passphrase(&self) -> Result<Vec<u8>> {
+        let passphrase = match self {
+            StrongholdUnlock::Password(password) => password.as_bytes().to_vec(),
+            StrongholdUnlock::KeyFile(key_file) => fs_read(key_file)
+                .map_err(|e| anyhow!("Could not read stronghold keyfile '{}'. Error: {}", key_file, e))?,
+        };
+        if passphrase.is_empty() {
+            bail!("The stronghold password or keyfile must not be empty");
+        }
+        Ok(passphrase)
+    }
+
+    fn get_key_provider(&self) -> Result<KeyProvider> {
+        KeyProvider::with_passphrase_hashed_blake2b(self.get_passphrase()?)
+            .map_err(|e| anyhow!("Could not create stronghold KeyProvider. Error: {:?}", e))
+    }
+}
+
+fn read_snapshot_file(file_name: &str, unlock: &StrongholdUnlock) -> Result<PtwPersist> {
+    let stronghold = Stronghold::default();
+    let client = stronghold.load_client_from_snapshot(
+        STRONGHOLD_CLIENT_PATH,
+        &unlock.get_key_provider()?,
+        &SnapshotPath::from_path(file_name)
+    ).map_err(|e| anyhow!("Could not unlock stronghold snapshot file '{}'. Wrong password or keyfile? Error: {:?}", file_name, e))?;
+    let buffer = client.store().get(STRONGHOLD_PERSIST_RECORD_KEY)
+        .map_err(|e| anyhow!("Could not read wallet data from stronghold snapshot file '{}'. Error: {:?}", file_name, e))?
+        .ok_or_else(|| anyhow!("Stronghold snapshot file '{}' does not contain wallet data", file_name))?;
+    PtwPersist::try_from_bytes(buffer.as_slice())
+}
+
+fn write_snapshot_file(file_name: &str, unlock: &StrongholdUnlock, persist: &PtwPersist) -> Result<()> {
+    let mut buffer = vec![0_u8; persist.needed_size()];
+    let _data_len = persist.to_bytes(&mut buffer)?;
+
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(STRONGHOLD_CLIENT_PATH)
+        .map_err(|e| anyhow!("Could not create stronghold client. Error: {:?}", e))?;
+    let _old_value = client.store().insert(STRONGHOLD_PERSIST_RECORD_KEY.to_vec(), buffer, None)
+        .map_err(|e| anyhow!("Could not insert wallet data into stronghold store. Error: {:?}", e))?;
+    stronghold.write_client(STRONGHOLD_CLIENT_PATH)
+        .map_err(|e| anyhow!("Could not write stronghold client. Error: {:?}", e))?;
+    log::debug!("[fn write_snapshot_file()] Committing stronghold snapshot file '{}'", file_name);
+    stronghold.commit_with_keyprovider(&SnapshotPath::from_path(file_name), &unlock.get_key_provider()?)
+        .map_err(|e| anyhow!("Could not write stronghold snapshot file '{}'. Error: {:?}", file_name, e))?;
+    Ok(())
+}
+
+// Converts an existing PlainTextWallet file (e.g. 'wallet-management-console.txt') into
+// a stronghold snapshot file. Seed and initialization_cnt are kept so that all seeds
+// derived using a seed_derivation_phrase stay the same.
+// The plain text wallet file is not deleted. Please delete it in a secure way after
+// the migration has been verified.
+pub fn migrate_plain_text_wallet(plain_text_file_name: &str, snapshot_file_name: &str, unlock: &StrongholdUnlock) -> Result<()> {
+    if !Path::new(plain_text_file_name).exists() {
+        bail!("Plain text wallet file '{}' does not exist", plain_text_file_name);
+    }
+    if Path::new(snapshot_file_name).exists() {
+        bail!("Stronghold snapshot file '{}' already exists. Will not overwrite it.", snapshot_file_name);
+    }
+    let persist = read_persistence_file(plain_text_file_name)?;
+    write_snapshot_file(snapshot_file_name, unlock, &persist)?;
+    if read_snapshot_file(snapshot_file_name, unlock)? != persist {
+        bail!("Verification of the migrated stronghold snapshot file '{}' failed", snapshot_file_name);
+    }
+    log::info!("[fn migrate_plain_text_wallet()] Migrated plain text wallet '{}' into stronghold snapshot file '{}'",
+        plain_text_file_name, snapshot_file_name);
+    Ok(())
+}
+
+// SimpleWallet implementation storing the wallet data in an encrypted stronghold snapshot file.
+// Seeds are derived from the master seed using the seed_derivation_phrase exactly like
+// the PlainTextWallet does, so a migrated PlainTextWallet results in the same channel seeds.
+#[derive(Clone)]
+pub struct StrongholdWallet {
+    pub file_name: String,
+    pub persist: PtwPersist,
+    pub serialization_password: String,
+    pub derived_seed: Option<String>,
+    pub seed_derivation_phrase: Option<String>,
+    unlock: StrongholdUnlock,
+}
+
+impl StrongholdWallet {
+    pub fn new(serialization_password: &str, file_path_name: Option<&str>, seed_derivation_phrase: Option<String>, unlock: StrongholdUnlock) -> Result<Self> {
+        let file_name = file_path_name.unwrap_or(DEFAULT_FILE_NAME);
+        log::debug!("[fn new()] file_name: '{}'", file_name);
+        let persist = if Path::new(file_name).exists() {
+            read_snapshot_file(file_name, &unlock)?
+        } else {
+            let persist = PtwPersist {
+                initialization_cnt: 0,
+                seed: create_seed(),
+                misc_other_data: String::default(),
+                initialization_epoch: 0,
+            };
+            write_snapshot_file(file_name, &unlock, &persist)?;
+            log::info!("[fn new()] Created new stronghold snapshot file '{}'", file_name);
+            persist
+        };
+        let derived_seed = seed_derivation_phrase.as_ref().map(|derivation_phrase|
+            create_seed_from_derivation_phrase(persist.seed.as_str(), derivation_phrase.as_str())
+        );
+        Ok(Self {
+            file_name: file_name.to_string(),
+            persist,
+            serialization_password: serialization_password.to_string(),
+            derived_seed,
+            seed_derivation_phrase,
+            unlock,
+        })
+    }
+
+    pub fn write_wallet_file(&self) -> Result<()> {
+        write_snapshot_file(self.file_name.as_str(), &self.unlock, &self.persist)
+    }
+
+    // Returns true if the snapshot file contains a password used to encrypt the Streams Client States
+    pub fn has_state_password(&self) -> bool {
+        !self.persist.misc_other_data.is_empty()
+    }
+
+    // Returns the password used to encrypt the Streams Client States which is stored
+    // in the misc_other_data of the snapshot file. If no password is stored yet,
+    // a new random password is created and stored.
+    pub fn get_or_create_state_password(&mut self) -> Result<String> {
+        if self.persist.misc_other_data.is_empty() {
+            self.persist.misc_other_data = create_seed();
+            self.write_wallet_file()?;
+            log::info!("[fn get_or_create_state_password()] Stored new state password in stronghold snapshot file '{}'", self.file_name);
+        }
+        Ok(self.persist.misc_other_data.clone())
+    }
+
+    // Replaces the stored password used to encrypt the Streams Client States
+    pub fn set_state_password(&mut self, state_password: &str) -> Result<()> {
+        self.persist.misc_other_data = state_password.to_string();
+        self.write_wallet_file()
+    }
+}
+
+impl SimpleWallet for StrongholdWallet {
+    const IS_USABLE_WALLET: bool = true;
+
+    // The snapshot file is unlocked using the environment variables
+    // SUSEE_STRONGHOLD_KEYFILE or SUSEE_STRONGHOLD_PASSWORD.
+    // The state password stored in the snapshot file is used as serialization password
+    // (see get_or_create_state_password()).
+    fn new(file_path_name: &str) -> Self {
+        let unlock = StrongholdUnlock::from_env()
+            .expect("Could not get the unlock method for the stronghold wallet");
+        let mut wallet = Self::new("", Some(file_path_name), None, unlock)
+            .expect(format!("Error while processing the stronghold snapshot file '{}'", file_path_name).as_str());
+        wallet.serialization_password = wallet.get_or_create_state_password()
+            .expect(format!("Error while storing the state password in the stronghold snapshot file '{}'", file_path_name).as_str());
+        wallet
+    }
+
+    fn get_seed(&self) -> &str {
+        if let Some(derived_seed) = self.derived_seed.as_ref() {
+            derived_seed.as_str()
+        } else {
+            self.persist.seed.as_str()
+        }
+    }
+
+    fn get_serialization_password(&self) -> &str {
+        self.serialization_password.as_str()
+    }
+
+    fn get_initialization_cnt(&self) -> u8 {
+        self.persist.initialization_cnt
+    }
+
+    fn get_initialization_epoch(&self) -> InitializationEpoch {
+        self.persist.initialization_epoch
+    }
+
+    fn increment_initialization_cnt(&mut self) -> Result<u8> {
+        let (initialization_cnt, initialization_epoch) =
+            get_next_initialization_cnt(self.persist.initialization_cnt, self.persist.initialization_epoch);
+        self.persist.initialization_cnt = initialization_cnt;
+        self.persist.initialization_epoch = initialization_epoch;
+        self.persist.seed = create_seed();
+        self.write_wallet_file()?;
+        Ok(self.persist.initialization_cnt)
+    }
+}
+
+// These tests need to be started as follows:
+//      > cargo test --package streams-tools --lib wallet::stronghold_wallet::tests --features stronghold -- --test-threads=1
+//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::remove_file as fs_remove_file;
+    use crate::PlainTextWallet;
+
+    const SERIALIZATION_PASSWD: &'static str = "DO NOT USE THIS IN PRODUCTION";
+    const STRONGHOLD_PASSWD: &'static str = "test-stronghold-password";
+
+    #[test]
+    fn test_stronghold_wallet_persistence() {
+        let file_name = "test_wallet.stronghold";
+        let unlock = StrongholdUnlock::Password(STRONGHOLD_PASSWD.to_string());
+        let mut wallet = StrongholdWallet::new(SERIALIZATION_PASSWD, Some(file_name), None, unlock.clone()).unwrap();
+        assert_eq!(wallet.get_initialization_cnt(), 0);
+        assert_eq!(wallet.increment_initialization_cnt().unwrap(), 1);
+
+        let reopened = StrongholdWallet::new(SERIALIZATION_PASSWD, Some(file_name), None, unlock).unwrap();
+        assert_eq!(reopened.get_initialization_cnt(), 1);
+        assert_eq!(reopened.get_seed(), wallet.get_seed());
+
+        let wrong_unlock = StrongholdUnlock::Password("wrong-password".to_string());
+        assert!(StrongholdWallet::new(SERIALIZATION_PASSWD, Some(file_name), None, wrong_unlock).is_err());
+        fs_remove_file(file_name).expect("Error on removing snapshot file");
+    }
+
+    #[test]
+    fn test_simple_wallet_uses_state_password() {
+        let file_name = "test_simple_wallet.stronghold";
+        env::remove_var(STRONGHOLD_KEYFILE_ENV_VAR);
+        env::set_var(STRONGHOLD_PASSWORD_ENV_VAR, STRONGHOLD_PASSWD);
+        let wallet = <StrongholdWallet as SimpleWallet>::new(file_name);
+        env::remove_var(STRONGHOLD_PASSWORD_ENV_VAR);
+        assert!(wallet.has_state_password());
+        assert_ne!(wallet.get_serialization_password(), STRONGHOLD_PASSWD);
+        assert_eq!(wallet.get_serialization_password(), wallet.persist.misc_other_data.as_str());
+
+        let unlock = StrongholdUnlock::Password(STRONGHOLD_PASSWD.to_string());
+        let mut reopened = StrongholdWallet::new(SERIALIZATION_PASSWD, Some(file_name), None, unlock).unwrap();
+        assert_eq!(reopened.get_or_create_state_password().unwrap(), wallet.get_serialization_password());
+        fs_remove_file(file_name).expect("Error on removing snapshot file");
+    }
+
+    #[test]
+    fn test_migrate_plain_text_wallet() {
+        let plain_text_file_name = "test_migration.ptw";
+        let snapshot_file_name = "test_migration.stronghold";
+        let seed_derivation_phrase = "B".repeat(81);
+        let ptw = PlainTextWallet::new(SERIALIZATION_PASSWD, Some(plain_text_file_name), Some(seed_derivation_phrase.clone()));
+
+        let unlock = StrongholdUnlock::Password(STRONGHOLD_PASSWD.to_string());
+        migrate_plain_text_wallet(plain_text_file_name, snapshot_file_name, &unlock).unwrap();
+        assert!(migrate_plain_text_wallet(plain_text_file_name, snapshot_file_name, &unlock).is_err());
+
+        let wallet = StrongholdWallet::new(SERIALIZATION_PASSWD, Some(snapshot_file_name), Some(seed_derivation_phrase), unlock).unwrap();
+        assert_eq!(wallet.get_seed(), ptw.get_seed());
+        assert_eq!(wallet.persist, ptw.persist);
+        fs_remove_file(plain_text_file_name).expect("Error on removing persistence file");
+        fs_remove_file(snapshot_file_name).expect("Error on removing snapshot file");
+    }
+}
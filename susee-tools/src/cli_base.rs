@@ -29,6 +29,16 @@ If this option is not used:
   'wallet-{}.txt'.
 ";
 
+static STATE_PASSWORD_FILE_ABOUT_FMT_STR: &str = "Specifies a keyfile containing the password used to encrypt the
+Streams client states.
+The password can also be provided using the environment variable
+SUSEE_STATE_PASSWORD which takes precedence over this argument.
+If neither is used, the password is read from the file
+'state-password-{}.key' in the --data-dir folder.
+If this file does not exist, a random password is created and written
+into this file.
+";
+
 pub static DATA_DIR_ABOUT: &str = "The folder where all data files are stored.
 This also applies for the location of the default wallet file (--wallet-file
 argument is not used).
@@ -38,17 +48,20 @@ Examples:
 ";
 
 static mut WALLET_FILE_ABOUT: String = String::new();
+static mut STATE_PASSWORD_FILE_ABOUT: String = String::new();
 static mut DATA_DIR_DEFAULT_VALUE: String = String::new();
 
 pub struct BaseArgKeys {
     pub node: &'static str,
     pub wallet_file: &'static str,
+    pub state_password_file: &'static str,
     pub data_dir: &'static str,
 }
 
 pub static BASE_ARG_KEYS: BaseArgKeys = BaseArgKeys {
     node: "node",
     wallet_file: "wallet-file",
+    state_password_file: "state-password-file",
     data_dir: "data-dir",
 };
 
@@ -160,6 +173,12 @@ impl<'a, ArgKeysT> Cli<'a, ArgKeysT> {
                     .value_name("WALLET_FILE_PATH_AND_NAME")
                     .help(WALLET_FILE_ABOUT.as_str())
                 );
+                STATE_PASSWORD_FILE_ABOUT = String::from(STATE_PASSWORD_FILE_ABOUT_FMT_STR).replace("{}", app_name_lowercase.as_str());
+                ret_val = ret_val.arg(Arg::new(BASE_ARG_KEYS.state_password_file)
+                    .long(BASE_ARG_KEYS.state_password_file)
+                    .value_name("STATE_PASSWORD_FILE")
+                    .help(STATE_PASSWORD_FILE_ABOUT.as_str())
+                );
             }
         }
 
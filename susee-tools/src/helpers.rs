@@ -5,7 +5,16 @@ use std::{
 };
 
 use clap::ArgMatches;
-use anyhow::Result;
+use anyhow::{
+    Result,
+    anyhow,
+    bail,
+};
+
+use streams_tools::wallet::create_seed;
+
+// Environment variable to provide the password used to encrypt the Streams Client States
+pub static SUSEE_STATE_PASSWORD_ENV_VAR: &str = "SUSEE_STATE_PASSWORD";
 
 pub fn get_wallet_filename(arg_matches: &ArgMatches, wallet_file_arg_name: &str, data_dir: &String, default_wallet_file_name: &str) -> Result<String>{
     if arg_matches.is_present(wallet_file_arg_name) {
@@ -15,6 +24,72 @@ pub fn get_wallet_filename(arg_matches: &ArgMatches, wallet_file_arg_name: &str,
     }
 }
 
+// Returns the password used to encrypt the Streams Client States if it is provided by the
+// environment variable SUSEE_STATE_PASSWORD or by the keyfile specified with the
+// state_password_file CLI argument (in this order). Otherwise None is returned.
+pub fn get_state_password_from_env_or_keyfile(arg_matches: &ArgMatches, state_password_file_arg_name: &str) -> Result<Option<String>> {
+    if let Ok(password) = env::var(SUSEE_STATE_PASSWORD_ENV_VAR) {
+        if password.is_empty() {
+            bail!("Environment variable {} must not be empty", SUSEE_STATE_PASSWORD_ENV_VAR);
+        }
+        return Ok(Some(password));
+    }
+    if let Some(keyfile) = arg_matches.value_of(state_password_file_arg_name) {
+        return Ok(Some(read_state_password_file(keyfile)?));
+    }
+    Ok(None)
+}
+
+pub fn read_state_password_file(keyfile: &str) -> Result<String> {
+    let password = fs::read_to_string(keyfile)
+        .map_err(|e| anyhow!("Could not read state password file '{}'. Error: {}", keyfile, e))?
+        .trim()
+        .to_string();
+    if password.is_empty() {
+        bail!("State password file '{}' is empty", keyfile);
+    }
+    Ok(password)
+}
+
+// Reads the password from the keyfile. If the keyfile does not exist, a new random
+// password is created and written into the keyfile.
+pub fn read_or_create_state_password_file(keyfile: &str) -> Result<String> {
+    if Path::new(keyfile).exists() {
+        read_state_password_file(keyfile)
+    } else {
+        let password = create_seed();
+        write_state_password_file(keyfile, password.as_str())?;
+        log::info!("Created new state password file '{}'", keyfile);
+        Ok(password)
+    }
+}
+
+// Writes the password into the keyfile which is only readable by the owner (on unix systems)
+pub fn write_state_password_file(keyfile: &str, password: &str) -> Result<()> {
+    fs::write(keyfile, password)
+        .map_err(|e| anyhow!("Could not write state password file '{}'. Error: {}", keyfile, e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(keyfile, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+// Returns the password used to encrypt the Streams Client States.
+// The password is taken from (in this order):
+// * the environment variable SUSEE_STATE_PASSWORD
+// * the keyfile specified by the state_password_file CLI argument
+// * the default keyfile in the data_dir. This keyfile is created using a random
+//   password on first use, so that every installation uses its own password.
+pub fn get_state_password(arg_matches: &ArgMatches, state_password_file_arg_name: &str, data_dir: &String, default_keyfile_name: &str) -> Result<String> {
+    if let Some(password) = get_state_password_from_env_or_keyfile(arg_matches, state_password_file_arg_name)? {
+        Ok(password)
+    } else {
+        read_or_create_state_password_file(format!("{}/{}", data_dir, default_keyfile_name).as_str())
+    }
+}
+
 pub fn assert_data_dir_existence(data_dir: &String) -> Result<()>{
     if !Path::new(data_dir).exists() {
         fs::create_dir_all(data_dir)?;
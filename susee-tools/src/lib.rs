@@ -16,6 +16,5 @@ pub use {
     helpers::*,
 };
 
-pub static SUSEE_CONST_SECRET_PASSWORD: &str = "SUSEE";
 pub static SUSEE_CONST_COMMAND_CONFIRM_FETCH_WAIT_SEC: u32 = 3;
 pub static SUSEE_CONST_SEND_MESSAGE_REPETITION_WAIT_SEC: u32 = 3;
\ No newline at end of file
@@ -0,0 +1,30 @@
+-- Tables of an iota-bridge database created by the first release
+-- (schema version 1 of all tables)
+CREATE TABLE lora_wan_node (
+    dev_eui TEXT NOT NULL PRIMARY KEY,
+    initialization_cnt INTEGER NOT NULL,
+    streams_channel_id  TEXT NOT NULL
+);
+
+CREATE INDEX idx_lora_wan_node_streams_channel_id ON lora_wan_node (
+    streams_channel_id
+);
+
+INSERT INTO lora_wan_node (dev_eui, initialization_cnt, streams_channel_id) VALUES ('1122334455667788', 3, 'c0ffee');
+
+CREATE TABLE pending_request (
+    request_key INTEGER NOT NULL PRIMARY KEY,
+    dev_eui TEXT NOT NULL,
+    msg_id BLOB NOT NULL,
+    initialization_cnt INTEGER NOT NULL,
+    streams_api_request BLOB NOT NULL
+);
+
+INSERT INTO pending_request (request_key, dev_eui, msg_id, initialization_cnt, streams_api_request)
+    VALUES (1, '1122334455667788', X'0102', 3, X'030405');
+
+CREATE TABLE buffered_message (
+    id INTEGER NOT NULL PRIMARY KEY,
+    link TEXT NOT NULL,
+    body BLOB NOT NULL
+);
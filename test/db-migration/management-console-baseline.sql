@@ -0,0 +1,20 @@
+-- Tables of a management-console database created by the first release
+-- (schema version 1 of all tables)
+CREATE TABLE "user" (
+    streams_channel_id TEXT NOT NULL PRIMARY KEY,
+    streams_client_state BLOB NOT NULL,
+    seed_derivation_phrase TEXT NOT NULL,
+    name TEXT,
+    external_id TEXT
+);
+
+INSERT INTO "user" (streams_channel_id, streams_client_state, seed_derivation_phrase, name, external_id)
+    VALUES ('c0ffee', X'0A0B0C', 'some seed derivation phrase', 'Sensor 1', 'meter-1');
+
+CREATE TABLE message_a1b2c3 (
+    message_id TEXT NOT NULL PRIMARY KEY,
+    wrapped_binary BLOB NOT NULL
+);
+
+INSERT INTO message_a1b2c3 (message_id, wrapped_binary) VALUES ('msg-1', X'010203');
+INSERT INTO message_a1b2c3 (message_id, wrapped_binary) VALUES ('msg-2', X'040506');
@@ -0,0 +1,16 @@
+-- User table that has been updated by a management-console release using ad-hoc column checks
+-- instead of the schema_migration table. The table contains the channel lifecycle columns
+-- (schema version 2) but the migration has not been recorded.
+CREATE TABLE "user" (
+    streams_channel_id TEXT NOT NULL PRIMARY KEY,
+    streams_client_state BLOB NOT NULL,
+    seed_derivation_phrase TEXT NOT NULL,
+    name TEXT,
+    external_id TEXT,
+    status TEXT NOT NULL DEFAULT 'active',
+    retired_at_secs INTEGER NOT NULL DEFAULT 0,
+    successor_channel_id TEXT NOT NULL DEFAULT ''
+);
+
+INSERT INTO "user" (streams_channel_id, streams_client_state, seed_derivation_phrase, name, external_id, status, retired_at_secs, successor_channel_id)
+    VALUES ('c0ffee', X'0A0B0C', 'some seed derivation phrase', 'Sensor 1', 'meter-1', 'retired', 1700000000, 'd00d');